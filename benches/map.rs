@@ -0,0 +1,129 @@
+use criterion::{BatchSize, BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use travl::map::TravlMap;
+use travl::small::SmallTravlMap;
+
+const SIZES: [u64; 3] = [1_000, 10_000, 100_000];
+
+/// Spreads consecutive indices over the key space, so insertions don't all happen on the same branch
+fn scattered_key(index: u64) -> u64 {
+    index.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+fn filled_map(size: u64) -> TravlMap<'static, u64, u64> {
+    let mut map = TravlMap::new();
+    for index in 0..size {
+        map.insert(scattered_key(index), scattered_key(index));
+    }
+    map
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map/insert");
+
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| filled_map(black_box(size)));
+        });
+    }
+
+    group.finish();
+}
+
+fn remove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map/remove");
+
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(
+                || filled_map(size),
+                |mut map| {
+                    for index in 0..size {
+                        map.remove(&scattered_key(index));
+                    }
+                    map
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+/// Compares inserting a single entry along its search path with rebuilding the tree through a batch, which was
+/// the only way to add an entry to a built map before
+fn insert_one(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map/insert_one");
+
+    for size in SIZES {
+        let key = scattered_key(size);
+
+        group.bench_with_input(BenchmarkId::new("path", size), &size, |b, &size| {
+            b.iter_batched_ref(|| filled_map(size), |map| map.insert(key, key), BatchSize::LargeInput);
+        });
+        group.bench_with_input(BenchmarkId::new("rebuild", size), &size, |b, &size| {
+            b.iter_batched_ref(|| filled_map(size), |map| map.batch().insert(key, key), BatchSize::LargeInput);
+        });
+    }
+
+    group.finish();
+}
+
+/// Compares removing a single entry along its search path with rebuilding the tree out of the remaining entries
+/// through a batch, which was the only way to remove an entry from a built map before
+fn remove_one(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map/remove_one");
+
+    for size in SIZES {
+        let key = scattered_key(size / 2);
+
+        group.bench_with_input(BenchmarkId::new("path", size), &size, |b, &size| {
+            b.iter_batched_ref(|| filled_map(size), |map| map.remove(&key), BatchSize::LargeInput);
+        });
+        group.bench_with_input(BenchmarkId::new("rebuild", size), &size, |b, &size| {
+            b.iter_batched(
+                || filled_map(size),
+                |map| {
+                    let mut rebuilt = TravlMap::new();
+                    let mut batch = rebuilt.batch();
+                    for (&entry_key, &value) in map.iter().filter(|(entry_key, _)| **entry_key != key) {
+                        batch.insert(entry_key, value);
+                    }
+                    batch.finish();
+                    rebuilt
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+/// Compares filling and looking up tiny maps held in a tree with the same maps held inline
+fn small(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map/small");
+
+    for size in [4_u64, 8, 16] {
+        group.bench_with_input(BenchmarkId::new("tree", size), &size, |b, &size| {
+            b.iter(|| {
+                let map = filled_map(black_box(size));
+                (0..size).filter_map(|index| map.get(&scattered_key(index))).count()
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("inline", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut map = SmallTravlMap::<u64, u64>::new();
+                for index in 0..black_box(size) {
+                    map.insert(scattered_key(index), scattered_key(index));
+                }
+                (0..size).filter_map(|index| map.get(&scattered_key(index))).count()
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, insert, remove, insert_one, remove_one, small);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+use criterion::{BatchSize, BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use travl::persistent::TravlMapPersistent;
+
+const SIZES: [u64; 3] = [1_000, 10_000, 100_000];
+
+/// Spreads consecutive indices over the key space, so insertions don't all happen on the same branch
+fn scattered_key(index: u64) -> u64 {
+    index.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+fn filled_map(size: u64) -> TravlMapPersistent<u64, u64> {
+    (0..size).map(|index| (scattered_key(index), index)).collect()
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("persistent/insert");
+
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| filled_map(black_box(size)));
+        });
+    }
+
+    group.finish();
+}
+
+fn remove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("persistent/remove");
+
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(
+                || filled_map(size),
+                |mut map| {
+                    for index in 0..size {
+                        map = map.remove(&scattered_key(index)).0;
+                    }
+                    map
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, insert, remove);
+criterion_main!(benches);
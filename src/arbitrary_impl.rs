@@ -0,0 +1,64 @@
+//! Support for [`arbitrary`], to generate maps within fuzz targets
+//!
+//! Maps are generated by replaying a random sequence of [`MapOperation`]s instead of being built
+//! from random contents only, so that the generated maps cover the shapes reachable through
+//! successive operations. Fuzz targets can also generate the operations themselves to apply them
+//! on a map along with a reference model.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::map::{TravlMap, TravlMapConfig};
+
+/// Largest imbalance factor given to generated maps, as larger ones make balancing trivial
+const MAX_ARBITRARY_IMBALANCE_FACTOR: u64 = 3;
+
+/// Operation on a map, generated by fuzz targets
+#[derive(Arbitrary, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapOperation<K, V> {
+    /// Inserts the entry, replacing the value of an existing key
+    Insert(K, V),
+    /// Removes the entry of the key, if it exists
+    Remove(K),
+    /// Removes every entry
+    Clear,
+}
+
+impl<K, V> MapOperation<K, V>
+where
+    K: Eq
+{
+    /// Applies the operation on a list of entries, used as a reference model of a map
+    pub fn apply_to(self, entries: &mut Vec<(K, V)>) {
+        match self {
+            Self::Insert(key, value) => match entries.iter_mut().find(|(existing, _)| *existing == key) {
+                Some((_, existing)) => *existing = value,
+                None => entries.push((key, value)),
+            },
+            Self::Remove(key) => entries.retain(|(existing, _)| *existing != key),
+            Self::Clear => entries.clear(),
+        }
+    }
+}
+
+impl<'a, 'u, K, V> Arbitrary<'u> for TravlMap<'a, K, V>
+where
+    K: Arbitrary<'u> + Hash + Eq + Clone,
+    V: Arbitrary<'u> + Ord + 'a
+{
+    fn arbitrary(u: &mut Unstructured<'u>) -> Result<Self> {
+        let imbalance_factor = u.int_in_range(0..=MAX_ARBITRARY_IMBALANCE_FACTOR)?;
+
+        // Entries are kept in a list rather than a hash map so that generation is deterministic
+        let mut entries = Vec::new();
+        for operation in u.arbitrary_iter::<MapOperation<K, V>>()? {
+            operation?.apply_to(&mut entries);
+        }
+
+        let config = TravlMapConfig::new(imbalance_factor);
+        Ok(Self::from_entries(config, entries, Box::new(|x| x), Box::new(Ord::cmp)))
+    }
+}
@@ -0,0 +1,153 @@
+//! Bulk loading of maps out of sorted runs fed concurrently by multiple threads
+//!
+//! Unlike collecting a parallel iterator, bulk loading suits input that arrives incrementally,
+//! for example entries read from several files or sockets at once. Each thread feeds its entries
+//! through a [`RunFeeder`], which sends them over a channel in runs. Once every feeder is dropped,
+//! [`BulkLoader::finish`] merges the runs and builds a balanced tree in one go.
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::mem;
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::map::TravlMap;
+
+/// Number of entries a [`RunFeeder`] buffers before sending them as a run, by default
+pub const DEFAULT_RUN_LEN: usize = 4096;
+
+/// Loader building a map out of runs of entries fed by multiple threads, see the
+/// [module-level documentation](self)
+#[derive(Debug)]
+pub struct BulkLoader<K, V> {
+    sender: Sender<Vec<(K, V)>>,
+    receiver: Receiver<Vec<(K, V)>>,
+}
+
+impl<K, V> BulkLoader<K, V> {
+    /// Creates a loader without any feeder
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    /// Creates a feeder sending runs of [`DEFAULT_RUN_LEN`] entries to this loader
+    #[must_use]
+    pub fn feeder(&self) -> RunFeeder<K, V> {
+        RunFeeder {
+            sender: self.sender.clone(),
+            buffer: Vec::new(),
+            run_len: DEFAULT_RUN_LEN,
+        }
+    }
+
+    /// Waits for every feeder to be dropped, then merges their runs into the given map, replacing its
+    /// current entries
+    ///
+    /// Runs should be sorted according to the map's property getter and ordering function, and are sorted
+    /// otherwise. When a key is fed multiple times, only the value received last is kept. Entries with
+    /// equal properties keep the order in which their runs were received.
+    ///
+    /// Feeders owned by the calling thread must be dropped beforehand, otherwise this never returns.
+    #[must_use]
+    pub fn finish<P>(self, mut map: TravlMap<'_, K, V, P>) -> TravlMap<'_, K, V, P>
+    where
+        K: Hash + Eq + Clone
+    {
+        let Self { sender, receiver } = self;
+        drop(sender);
+
+        let mut runs: Vec<Vec<(K, V)>> = receiver.into_iter().filter(|run| !run.is_empty()).collect();
+
+        // Runs are deduplicated from the last received entry backwards, so that the last value wins
+        let mut keys = HashSet::new();
+        for run in runs.iter_mut().rev() {
+            let entries = mem::take(run);
+            *run = entries.into_iter().rev().filter(|(key, _)| keys.insert(key.clone())).collect();
+            run.reverse();
+        }
+
+        map.rebuild_from_runs(runs);
+        map
+    }
+}
+
+impl<K, V> Default for BulkLoader<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Feeder of a [`BulkLoader`], which can be sent to another thread
+///
+/// Entries pushed one by one are buffered and sent as a run once enough of them are buffered,
+/// or when the feeder is [flushed](Self::flush) or dropped. Pushed entries should be sorted,
+/// each run being sorted on its own.
+#[derive(Debug)]
+pub struct RunFeeder<K, V> {
+    sender: Sender<Vec<(K, V)>>,
+    buffer: Vec<(K, V)>,
+    run_len: usize,
+}
+
+impl<K, V> RunFeeder<K, V> {
+    /// Sets the number of entries buffered before sending them as a run
+    ///
+    /// # Panics
+    ///
+    /// Panics if `run_len` is 0.
+    #[must_use]
+    pub fn with_run_len(mut self, run_len: usize) -> Self {
+        assert!(run_len != 0, "run length must be non-zero");
+
+        self.run_len = run_len;
+        self
+    }
+
+    /// Buffers an entry, sending the buffered entries as a run if there are enough of them
+    pub fn push(&mut self, key: K, value: V) {
+        self.buffer.push((key, value));
+
+        if self.buffer.len() >= self.run_len {
+            self.flush();
+        }
+    }
+
+    /// Sends a whole run of entries, after the buffered ones
+    pub fn send_run(&mut self, run: Vec<(K, V)>) {
+        self.flush();
+        self.send(run);
+    }
+
+    /// Sends the buffered entries as a run
+    pub fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            let run = mem::replace(&mut self.buffer, Vec::with_capacity(self.run_len));
+            self.send(run);
+        }
+    }
+
+    /// Sends a run to the loader
+    fn send(&self, run: Vec<(K, V)>) {
+        // Sending only fails if the loader was dropped without finishing, the run is then discarded
+        let _ = self.sender.send(run);
+    }
+}
+
+impl<K, V> Clone for RunFeeder<K, V> {
+    /// Creates another feeder of the same loader, without the buffered entries
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            buffer: Vec::new(),
+            run_len: self.run_len,
+        }
+    }
+}
+
+impl<K, V> Drop for RunFeeder<K, V> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
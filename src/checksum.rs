@@ -0,0 +1,117 @@
+//! Stable digests of trees, comparable across processes
+//!
+//! [`TravlMap::structural_hash`](crate::map::TravlMap::structural_hash) and
+//! [`TravlMapPersistent::structural_hash`](crate::persistent::TravlMapPersistent::structural_hash) digest the
+//! entries of a tree along with its shape, so that two processes, for example replicas or runs of a
+//! deterministic simulation, can check they hold identical trees by exchanging a single number.
+//!
+//! Unlike [`DefaultHasher`](std::hash::DefaultHasher), whose algorithm may change between Rust releases,
+//! digests are computed with [`StableHasher`], which never changes and writes integers in little-endian
+//! order, so they only depend on the [`Hash`] implementations of keys and values.
+
+use core::hash::{Hash, Hasher};
+
+/// Offset basis of the 64-bit FNV-1a hash
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Prime of the 64-bit FNV-1a hash
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Hasher computing the 64-bit FNV-1a hash, whose output doesn't depend on the Rust release nor the platform
+///
+/// Integers are hashed as little-endian bytes, and `usize` and `isize` as 64-bit integers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    /// Creates a hasher which hasn't hashed anything yet
+    #[must_use]
+    pub fn new() -> Self {
+        Self { state: FNV_OFFSET_BASIS }
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state = (self.state ^ u64::from(*byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+/// Digests a tree out of its nodes in pre-order, given as their entries, [`None`] standing for missing children
+///
+/// Missing children are hashed too, so that trees holding the same entries in different shapes differ.
+pub(crate) fn structural_hash<'n, K, V, I>(nodes: I) -> u64
+where
+    K: Hash + 'n,
+    V: Hash + 'n,
+    I: IntoIterator<Item = Option<(&'n K, &'n V)>>
+{
+    let mut hasher = StableHasher::new();
+
+    for node in nodes {
+        match node {
+            Some((key, value)) => {
+                hasher.write_u8(1);
+                key.hash(&mut hasher);
+                value.hash(&mut hasher);
+            },
+            None => hasher.write_u8(0),
+        }
+    }
+
+    hasher.finish()
+}
@@ -0,0 +1,468 @@
+//! Map shared between threads, for indexes read and written by many threads at once
+//!
+//! A [`ConcurrentTravlMap`] holds the current version of a [`TravlMapPersistent`] behind a lock.
+//! Since versions are never mutated, the lock is only held long enough to clone the current version,
+//! in O(1), or to replace it by a new one: reads run on their own version without blocking writers,
+//! and always observe a consistent map even if writes happen in the meantime.
+//!
+//! When a single writer applies batches of changes, a [`Publisher`] lets it work on a private
+//! draft and make all of its changes visible at once with [`publish`](Publisher::publish).
+//! [`Reader`]s then only ever see published versions.
+//!
+//! When many threads write at once, a [`ShardedTravlMap`] splits the keys into ranges held by
+//! independent maps, so that writes to different ranges don't contend on the same lock.
+
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::ops::{Bound, RangeBounds};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::persistent::TravlMapPersistent;
+
+/// Locks a version for reading
+///
+/// Versions are replaced in a single assignment, so a panicking writer can't leave a
+/// partially written map behind and poisoning is ignored.
+fn read_version<K, V>(version: &RwLock<TravlMapPersistent<K, V>>) -> RwLockReadGuard<'_, TravlMapPersistent<K, V>> {
+    version.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks a version for writing, see [`read_version`] about poisoning
+fn write_version<K, V>(version: &RwLock<TravlMapPersistent<K, V>>) -> RwLockWriteGuard<'_, TravlMapPersistent<K, V>> {
+    version.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Map ordered by key that can be read and written from multiple threads, see the
+/// [module-level documentation](self)
+pub struct ConcurrentTravlMap<K, V> {
+    current: RwLock<TravlMapPersistent<K, V>>,
+}
+
+impl<K, V> ConcurrentTravlMap<K, V> {
+    /// Creates an empty map
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_imbalance_factor(0)
+    }
+
+    /// Creates an empty map using the given imbalance factor
+    #[must_use]
+    pub fn with_imbalance_factor(imbalance_factor: u64) -> Self {
+        Self::from_persistent(TravlMapPersistent::with_imbalance_factor(imbalance_factor))
+    }
+
+    /// Creates a map whose current version is the given persistent map
+    #[must_use]
+    pub fn from_persistent(map: TravlMapPersistent<K, V>) -> Self {
+        Self { current: RwLock::new(map) }
+    }
+
+    /// Returns the current version of the map, which isn't affected by later writes
+    #[must_use]
+    pub fn snapshot(&self) -> TravlMapPersistent<K, V> {
+        self.read().clone()
+    }
+
+    /// Returns the number of entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns whether the map is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Consumes the map, returning its current version
+    #[must_use]
+    pub fn into_persistent(self) -> TravlMapPersistent<K, V> {
+        self.current.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the current version for reading
+    fn read(&self) -> RwLockReadGuard<'_, TravlMapPersistent<K, V>> {
+        read_version(&self.current)
+    }
+
+    /// Locks the current version for writing
+    fn write(&self) -> RwLockWriteGuard<'_, TravlMapPersistent<K, V>> {
+        write_version(&self.current)
+    }
+}
+
+impl<K, V> ConcurrentTravlMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone
+{
+    /// Returns a copy of the value associated to the given key, if it exists
+    #[must_use]
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.snapshot().get(key).cloned()
+    }
+
+    /// Returns whether the map contains the given key
+    #[must_use]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.snapshot().contains_key(key)
+    }
+
+    /// Returns a copy of the entries whose keys are within the given range, ordered by key
+    ///
+    /// The entries all come from the same version of the map. The lock is released before
+    /// they are copied, so large ranges don't block writers.
+    #[must_use]
+    pub fn range<Q, R>(&self, range: R) -> Vec<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>
+    {
+        let snapshot = self.snapshot();
+        snapshot.range(range).map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+
+    /// Inserts an entry, returning the previous value of the key if it already existed
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let mut current = self.write();
+        let previous = current.get(&key).cloned();
+        *current = current.insert(key, value);
+        previous
+    }
+
+    /// Removes the entry of the given key, returning its value if it existed
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        let mut current = self.write();
+        let (next, removed) = current.remove(key);
+        *current = next;
+        removed
+    }
+}
+
+impl<K, V> Default for ConcurrentTravlMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Debug for ConcurrentTravlMap<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.snapshot().fmt(f)
+    }
+}
+
+impl<K, V> From<TravlMapPersistent<K, V>> for ConcurrentTravlMap<K, V> {
+    fn from(map: TravlMapPersistent<K, V>) -> Self {
+        Self::from_persistent(map)
+    }
+}
+
+/// Writer of a map whose changes are made visible to [`Reader`]s all at once, see the
+/// [module-level documentation](self)
+pub struct Publisher<K, V> {
+    draft: TravlMapPersistent<K, V>,
+    published: Arc<RwLock<TravlMapPersistent<K, V>>>,
+}
+
+impl<K, V> Publisher<K, V> {
+    /// Creates a publisher of an empty map
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_persistent(TravlMapPersistent::new())
+    }
+
+    /// Creates a publisher whose draft and published version are the given persistent map
+    #[must_use]
+    pub fn from_persistent(map: TravlMapPersistent<K, V>) -> Self {
+        Self {
+            draft: map.clone(),
+            published: Arc::new(RwLock::new(map)),
+        }
+    }
+
+    /// Returns the draft, holding the changes made since the last publication
+    #[must_use]
+    pub fn draft(&self) -> &TravlMapPersistent<K, V> {
+        &self.draft
+    }
+
+    /// Creates a reader of the published versions
+    #[must_use]
+    pub fn reader(&self) -> Reader<K, V> {
+        Reader {
+            published: Arc::clone(&self.published),
+        }
+    }
+
+    /// Makes the draft visible to readers, in O(1)
+    ///
+    /// Readers that already loaded the previous version keep it until they load again.
+    pub fn publish(&mut self) {
+        *write_version(&self.published) = self.draft.clone();
+    }
+}
+
+impl<K, V> Publisher<K, V>
+where
+    K: Ord + Clone,
+    V: Clone
+{
+    /// Inserts an entry into the draft, returning the previous value of the key if it already existed
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = self.draft.get(&key).cloned();
+        self.draft = self.draft.insert(key, value);
+        previous
+    }
+
+    /// Removes the entry of the given key from the draft, returning its value if it existed
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        let (draft, removed) = self.draft.remove(key);
+        self.draft = draft;
+        removed
+    }
+}
+
+impl<K, V> Default for Publisher<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Debug for Publisher<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Publisher").field("draft", &self.draft).finish_non_exhaustive()
+    }
+}
+
+/// Reader of the versions published by a [`Publisher`]
+///
+/// Readers are cheap to clone and can be sent to other threads.
+pub struct Reader<K, V> {
+    published: Arc<RwLock<TravlMapPersistent<K, V>>>,
+}
+
+impl<K, V> Reader<K, V> {
+    /// Returns the latest published version, which isn't affected by later publications
+    ///
+    /// The lock is only held to clone the version, in O(1), and is never held while the
+    /// publisher changes its draft, so loading never waits on writes.
+    #[must_use]
+    pub fn load(&self) -> TravlMapPersistent<K, V> {
+        read_version(&self.published).clone()
+    }
+}
+
+impl<K, V> Clone for Reader<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            published: Arc::clone(&self.published),
+        }
+    }
+}
+
+impl<K, V> Debug for Reader<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader").field("published", &self.load()).finish()
+    }
+}
+
+/// Map ordered by key split into shards holding consecutive ranges of keys, see the
+/// [module-level documentation](self)
+///
+/// Each shard is a [`ConcurrentTravlMap`] with its own lock. Queries spanning multiple shards
+/// read each shard's current version in turn, so they aren't atomic with respect to writes
+/// happening meanwhile on the shards.
+pub struct ShardedTravlMap<K, V> {
+    /// Smallest key of every shard but the first one, in ascending order
+    boundaries: Vec<K>,
+    shards: Vec<ConcurrentTravlMap<K, V>>,
+}
+
+impl<K, V> ShardedTravlMap<K, V>
+where
+    K: Ord
+{
+    /// Creates an empty map split at the given keys
+    ///
+    /// Each boundary is the smallest key of a shard, so `n` distinct boundaries give `n + 1` shards.
+    #[must_use]
+    pub fn with_boundaries(boundaries: impl IntoIterator<Item = K>) -> Self {
+        let mut boundaries: Vec<K> = boundaries.into_iter().collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let shards = core::iter::repeat_with(ConcurrentTravlMap::new).take(boundaries.len() + 1).collect();
+        Self { boundaries, shards }
+    }
+
+    /// Returns the number of shards
+    #[must_use]
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns the shards, ordered by their range of keys
+    #[must_use]
+    pub fn shards(&self) -> &[ConcurrentTravlMap<K, V>] {
+        &self.shards
+    }
+
+    /// Returns the number of entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(ConcurrentTravlMap::len).sum()
+    }
+
+    /// Returns whether the map is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(ConcurrentTravlMap::is_empty)
+    }
+
+    /// Returns the index of the shard holding the given key
+    fn shard_index<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.boundaries.partition_point(|boundary| boundary.borrow() <= key)
+    }
+
+    /// Returns the shard holding the given key
+    fn shard<Q>(&self, key: &Q) -> &ConcurrentTravlMap<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        &self.shards[self.shard_index(key)]
+    }
+}
+
+impl<K, V> ShardedTravlMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone
+{
+    /// Returns a copy of the value associated to the given key, if it exists
+    #[must_use]
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.shard(key).get(key)
+    }
+
+    /// Returns whether the map contains the given key
+    #[must_use]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.shard(key).contains_key(key)
+    }
+
+    /// Inserts an entry, returning the previous value of the key if it already existed
+    ///
+    /// Only the shard holding the key is locked.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.shard(&key).insert(key, value)
+    }
+
+    /// Removes the entry of the given key, returning its value if it existed
+    ///
+    /// Only the shard holding the key is locked.
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.shard(key).remove(key)
+    }
+
+    /// Returns a copy of the entries whose keys are within the given range, ordered by key
+    ///
+    /// Only the shards overlapping the range are read.
+    #[must_use]
+    pub fn range<Q, R>(&self, range: R) -> Vec<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>
+    {
+        let bounds = (range.start_bound(), range.end_bound());
+        let first = match bounds.0 {
+            Bound::Included(start) | Bound::Excluded(start) => self.shard_index(start),
+            Bound::Unbounded => 0,
+        };
+        let last = match bounds.1 {
+            Bound::Included(end) | Bound::Excluded(end) => self.shard_index(end),
+            Bound::Unbounded => self.shards.len() - 1,
+        };
+
+        // Shards hold consecutive ranges of keys, so concatenating their entries keeps them ordered
+        self.shards
+            .get(first..=last)
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|shard| shard.range(bounds))
+            .collect()
+    }
+
+    /// Returns the number of entries whose keys are ordered before the given key
+    ///
+    /// Shards before the one holding the key are counted in O(1), and the entries of that shard in O(log n).
+    #[must_use]
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        let index = self.shard_index(key);
+        let before: usize = self.shards[..index].iter().map(ConcurrentTravlMap::len).sum();
+
+        before + self.shards[index].snapshot().rank(key)
+    }
+}
+
+impl<K, V> Debug for ShardedTravlMap<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedTravlMap").field("shards", &self.shards).finish_non_exhaustive()
+    }
+}
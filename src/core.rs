@@ -18,6 +18,11 @@
 //! For example, if you want to allow balance factors up to ±5, you need to set
 //! the imbalance factor to `4` (read as 4 more than the usual balance range)
 //! 
+//! Maps can also loosen it for writes only, through a [relaxed balancing strategy](crate::map::Balancing).
+//! 
+//! The imbalance factor can't exceed [`MAX_IMBALANCE_FACTOR`], and trees can't grow higher than
+//! [`MAX_HEIGHT`], so that the arithmetic on heights never overflows.
+//! 
 //! # Custom ordering
 //! 
 //! In order not to rely on [`Ord`], which is used for describing how _entire instances_ should be
@@ -26,14 +31,55 @@
 //! This enables two things:
 //! 
 //! 1. Having entire instances (or references to such instances) stored within the tree and being able
-//!     to sort them using an inner property/field
+//!    to sort them using an inner property/field
 //! 2. Reordering the tree whenever you want - Not usually useful, but on special occasions where an outside
-//!     factor may change what you calculate to order your instances, it can be useful
+//!    factor may change what you calculate to order your instances, it can be useful
+
+use core::cmp::Ordering;
+
+use crate::error::TravlError;
+#[cfg(feature = "metrics")]
+use crate::metrics::TreeMetrics;
+#[cfg(feature = "serde")]
+pub use crate::serde_impl::{IndexedNode, NodeRecord};
+use crate::storage::NodeStorage;
+
+/// Largest imbalance factor a tree accepts
+pub const MAX_IMBALANCE_FACTOR: u64 = u64::MAX >> 32;
+
+/// Largest height a tree can reach
+///
+/// Trees only get this high with pathological imbalance factors, long before exhausting memory.
+pub const MAX_HEIGHT: u64 = u64::MAX >> 32;
+
+/// Returns the given imbalance factor if it doesn't exceed [`MAX_IMBALANCE_FACTOR`]
+///
+/// # Errors
+///
+/// Returns [`TravlError::ImbalanceFactorTooLarge`] if the imbalance factor exceeds [`MAX_IMBALANCE_FACTOR`].
+pub fn check_imbalance_factor(imbalance_factor: u64) -> Result<u64, TravlError> {
+    if imbalance_factor > MAX_IMBALANCE_FACTOR {
+        return Err(TravlError::ImbalanceFactorTooLarge);
+    }
+
+    Ok(imbalance_factor)
+}
+
+/// Returns the given height if it doesn't exceed [`MAX_HEIGHT`]
+///
+/// # Errors
+///
+/// Returns [`TravlError::HeightLimitExceeded`] if the height exceeds [`MAX_HEIGHT`].
+pub fn check_height(height: u64) -> Result<u64, TravlError> {
+    if height > MAX_HEIGHT {
+        return Err(TravlError::HeightLimitExceeded);
+    }
 
-use std::cmp::Ordering;
+    Ok(height)
+}
 
 /// Balance factor
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum BalanceFactor {
     /// Left and right nodes are of same height
     Balanced,
@@ -47,6 +93,53 @@ pub enum BalanceFactor {
     TooRightHeavy,
 }
 
+impl BalanceFactor {
+    /// Computes the [`BalanceFactor`] of a node out of the heights of its subtrees, given an imbalance factor
+    #[must_use]
+    pub(crate) fn from_heights(left_height: u64, right_height: u64, imbalance_factor: u64) -> Self {
+        // Differences are compared rather than sums, which can't overflow nor saturate into false matches
+        let exceeding = |higher: u64, lower: u64| higher.checked_sub(lower).and_then(|diff| diff.checked_sub(1));
+
+        // left - right - 1 = unbalance_factor
+        if exceeding(left_height, right_height) == Some(imbalance_factor) {
+            return Self::TooLeftHeavy;
+        }
+
+        // right - left - 1 = unbalance_factor
+        if exceeding(right_height, left_height) == Some(imbalance_factor) {
+            return Self::TooRightHeavy;
+        }
+
+        match left_height.cmp(&right_height) {
+            Ordering::Equal => Self::Balanced,
+            Ordering::Greater => Self::LeftHeavy,
+            Ordering::Less => Self::RightHeavy,
+        }
+    }
+}
+
+/// Counters of the operations of a map, which only count when the `metrics` feature is enabled
+#[cfg(feature = "metrics")]
+pub(crate) type Metrics = TreeMetrics;
+/// Counters of the operations of a map, which only count when the `metrics` feature is enabled
+#[cfg(not(feature = "metrics"))]
+pub(crate) type Metrics = NoMetrics;
+
+/// Stand-in for [`TreeMetrics`](crate::metrics::TreeMetrics) when the `metrics` feature is disabled
+#[cfg(not(feature = "metrics"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct NoMetrics;
+
+#[cfg(not(feature = "metrics"))]
+#[allow(clippy::unused_self, reason = "mirrors the recording methods of `TreeMetrics`")]
+impl NoMetrics {
+    pub(crate) fn record_rotation(&mut self, _rotation: AVLRotation) {}
+
+    pub(crate) fn record_comparison(&mut self) {}
+
+    pub(crate) fn record_height_recomputation(&mut self) {}
+}
+
 /// AVL tree rotation
 /// 
 /// See [Tree rotation](https://en.wikipedia.org/wiki/Tree_rotation) for more details
@@ -65,23 +158,32 @@ pub enum AVLRotation {
 }
 
 /// Represents an AVL node
+///
+/// Nodes link to their parent and children by key, the linked nodes being looked up in the
+/// [`NodeStorage`] holding the tree.
 #[derive(Clone, Debug)]
-pub struct TravlNode<'a, K, V> {
+pub struct TravlNode<K, V> {
     key: K,
     value: V,
     height: u64,
-    parent: Option<&'a Self>,
-    left: Option<&'a Self>,
-    right: Option<&'a Self>,
+    /// Number of nodes of the subtree of the node, including itself
+    size: usize,
+    /// Rank of the node in a [rank-balanced](crate::map::Balancing::Rank) tree, equal to its height otherwise
+    rank: u64,
+    parent: Option<K>,
+    left: Option<K>,
+    right: Option<K>,
 }
 
-impl<'a, K, V> TravlNode<'a, K, V> {
+impl<K, V> TravlNode<K, V> {
     /// Creates a new instance
     pub fn new(key: K, value: V) -> Self {
         Self {
             key,
             value,
             height: 0,
+            size: 1,
+            rank: 0,
             parent: None,
             left: None,
             right: None,
@@ -106,6 +208,12 @@ impl<'a, K, V> TravlNode<'a, K, V> {
         &mut self.value
     }
 
+    /// Consumes the node, returning its key and value
+    #[must_use]
+    pub fn into_entry(self) -> (K, V) {
+        (self.key, self.value)
+    }
+
     /// Returns a value from the node's value using a custom getter function
     #[must_use]
     pub fn prop<P, F>(&self, f: F) -> &P
@@ -130,22 +238,48 @@ impl<'a, K, V> TravlNode<'a, K, V> {
         self.height
     }
 
-    /// Returns the node's parent
+    /// Sets the node's height
+    pub(crate) fn set_height(&mut self, height: u64) {
+        self.height = height;
+    }
+
+    /// Returns the number of nodes of the node's subtree, including itself
     #[must_use]
-    pub fn parent(&self) -> Option<&Self> {
-        self.parent
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Sets the number of nodes of the node's subtree
+    pub(crate) fn set_size(&mut self, size: usize) {
+        self.size = size;
     }
 
-    /// Returns the node's left child
+    /// Returns the node's rank in a [rank-balanced](crate::map::Balancing::Rank) tree
+    pub(crate) fn rank(&self) -> u64 {
+        self.rank
+    }
+
+    /// Sets the node's rank in a rank-balanced tree
+    pub(crate) fn set_rank(&mut self, rank: u64) {
+        self.rank = rank;
+    }
+
+    /// Returns the key of the node's parent
+    #[must_use]
+    pub fn parent(&self) -> Option<&K> {
+        self.parent.as_ref()
+    }
+
+    /// Returns the key of the node's left child
     #[must_use]
-    pub fn left(&self) -> Option<&Self> {
-        self.left
+    pub fn left(&self) -> Option<&K> {
+        self.left.as_ref()
     }
 
-    /// Returns the node's right child
+    /// Returns the key of the node's right child
     #[must_use]
-    pub fn right(&self) -> Option<&Self> {
-        self.right
+    pub fn right(&self) -> Option<&K> {
+        self.right.as_ref()
     }
 
     /// Returns whether is alone: no parents, no children
@@ -172,70 +306,57 @@ impl<'a, K, V> TravlNode<'a, K, V> {
         self.height() == 0
     }
 
-    /// Computes the [`BalanceFactor`] given an imbalance factor
+    /// Computes the [`BalanceFactor`] given an imbalance factor, looking the node's children up in the given storage
     #[must_use]
-    pub fn balance_factor(&self, imbalance_factor: u64) -> BalanceFactor {
-        let left_height = self.left().map_or(0, Self::height);
-        let right_height = self.right().map_or(0, Self::height);
-
-        // right - left = -unbalance_factor - 1
-        // => right + unbalance_factor + 1 = left
-        if right_height.saturating_add(imbalance_factor.saturating_add(1)) == left_height {
-            return BalanceFactor::TooLeftHeavy;
-        }
-
-        // right - left = unbalance_factor + 1
-        // => left + unbalance_factor + 1 = right
-        if left_height.saturating_add(imbalance_factor.saturating_add(1)) == right_height {
-            return BalanceFactor::TooRightHeavy;
-        }
+    pub fn balance_factor<S>(&self, nodes: &S, imbalance_factor: u64) -> BalanceFactor
+    where
+        S: NodeStorage<K, V>
+    {
+        // Heights are counted in levels so that a leaf child weighs more than a missing one
+        let levels = |child: Option<&Self>| child.map_or(0, |child| child.height() + 1);
 
-        match left_height.cmp(&right_height) {
-            Ordering::Equal => BalanceFactor::Balanced,
-            Ordering::Greater => BalanceFactor::LeftHeavy,
-            Ordering::Less => BalanceFactor::RightHeavy,
-        }
+        BalanceFactor::from_heights(levels(nodes.left_of(self)), levels(nodes.right_of(self)), imbalance_factor)
     }
 
     /// Replaces the node's parent and returns the old value
     #[must_use]
-    pub fn link_parent(&mut self, parent: &'a Self) -> Option<&Self> {
+    pub fn link_parent(&mut self, parent: K) -> Option<K> {
         self.parent.replace(parent)
     }
 
     /// Removes the node's parent and returns the old value
     #[must_use]
-    pub fn unlink_parent(&mut self) -> Option<&Self> {
+    pub fn unlink_parent(&mut self) -> Option<K> {
         self.parent.take()
     }
 
     /// Replaces the node's left child and returns the old value
     #[must_use]
-    pub fn link_left(&mut self, left: &'a Self) -> Option<&Self> {
+    pub fn link_left(&mut self, left: K) -> Option<K> {
         self.left.replace(left)
     }
 
     /// Removes the node's left child and returns the old value
     #[must_use]
-    pub fn unlink_left(&mut self) -> Option<&Self> {
+    pub fn unlink_left(&mut self) -> Option<K> {
         self.left.take()
     }
 
     /// Replaces the node's right child and returns the old value
     #[must_use]
-    pub fn link_right(&mut self, right: &'a Self) -> Option<&Self> {
+    pub fn link_right(&mut self, right: K) -> Option<K> {
         self.right.replace(right)
     }
 
     /// Removes the node's right child and returns the old value
     #[must_use]
-    pub fn unlink_right(&mut self) -> Option<&Self> {
+    pub fn unlink_right(&mut self) -> Option<K> {
         self.right.take()
     }
 
     /// Replaces both children of the node and returns the old values
     #[must_use]
-    pub fn link_children(&mut self, children: (Option<&'a Self>, Option<&'a Self>)) -> (Option<&Self>, Option<&Self>) {
+    pub fn link_children(&mut self, children: (Option<K>, Option<K>)) -> (Option<K>, Option<K>) {
         let mut old_left = None;
         let mut old_right = None;
 
@@ -252,7 +373,7 @@ impl<'a, K, V> TravlNode<'a, K, V> {
 
     /// Removes the node's children and returns the old value
     #[must_use]
-    pub fn unlink_children(&mut self) -> (Option<&Self>, Option<&Self>) {
+    pub fn unlink_children(&mut self) -> (Option<K>, Option<K>) {
         (self.left.take(), self.right.take())
     }
 }
@@ -0,0 +1,309 @@
+//! Cursors over the nodes of a tree
+//!
+//! Similarly to the cursors of [`BTreeMap`](alloc::collections::BTreeMap), a cursor points to
+//! a node of the tree and can be moved around, either following the structure of the tree
+//! (parent, left and right children) or following the order of the tree (next and previous nodes).
+//!
+//! Moving to the next or previous node relies on the nodes' parent links.
+//!
+//! A [`CursorMut`] can also insert entries right next to the node it points to and remove that node, which
+//! rebalances the tree without invalidating the cursor, since it tracks its node by key.
+
+use core::fmt::Debug;
+
+use crate::core::TravlNode;
+use crate::error::TravlError;
+use crate::map::{NodeMap, RejectedEntry, TravlMap};
+use crate::storage::NodeStorage;
+use crate::traversal::{predecessor, successor};
+
+/// Read-only cursor pointing to a node of a tree
+pub struct Cursor<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    current: &'n TravlNode<K, V>,
+}
+
+impl<K, V, S> Debug for Cursor<'_, K, V, S>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Cursor").field("current", &self.current).finish_non_exhaustive()
+    }
+}
+
+impl<K, V, S> Clone for Cursor<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V, S> Copy for Cursor<'_, K, V, S> {}
+
+impl<'n, K, V, S> Cursor<'n, K, V, S>
+where
+    S: NodeStorage<K, V>
+{
+    /// Creates a cursor pointing to the given node, whose linked nodes are stored in the given storage
+    #[must_use]
+    pub fn new(nodes: &'n S, node: &'n TravlNode<K, V>) -> Self {
+        Self { nodes, current: node }
+    }
+
+    /// Returns the node the cursor points to
+    #[must_use]
+    pub fn current(&self) -> &'n TravlNode<K, V> {
+        self.current
+    }
+
+    /// Returns the key of the node the cursor points to
+    #[must_use]
+    pub fn key(&self) -> &'n K {
+        self.current.key()
+    }
+
+    /// Returns the value of the node the cursor points to
+    #[must_use]
+    pub fn value(&self) -> &'n V {
+        self.current.value()
+    }
+
+    /// Returns the parent of the current node without moving the cursor
+    #[must_use]
+    pub fn peek_parent(&self) -> Option<&'n TravlNode<K, V>> {
+        self.nodes.parent_of(self.current)
+    }
+
+    /// Returns the in-order successor of the current node without moving the cursor
+    #[must_use]
+    pub fn peek_next(&self) -> Option<&'n TravlNode<K, V>> {
+        successor(self.nodes, self.current)
+    }
+
+    /// Returns the in-order predecessor of the current node without moving the cursor
+    #[must_use]
+    pub fn peek_prev(&self) -> Option<&'n TravlNode<K, V>> {
+        predecessor(self.nodes, self.current)
+    }
+
+    /// Moves the cursor to the parent of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node has no parent.
+    pub fn move_to_parent(&mut self) -> Option<&'n TravlNode<K, V>> {
+        self.move_to(self.nodes.parent_of(self.current))
+    }
+
+    /// Moves the cursor to the left child of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node has no left child.
+    pub fn move_to_left(&mut self) -> Option<&'n TravlNode<K, V>> {
+        self.move_to(self.nodes.left_of(self.current))
+    }
+
+    /// Moves the cursor to the right child of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node has no right child.
+    pub fn move_to_right(&mut self) -> Option<&'n TravlNode<K, V>> {
+        self.move_to(self.nodes.right_of(self.current))
+    }
+
+    /// Moves the cursor to the in-order successor of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node is the last one.
+    pub fn move_next(&mut self) -> Option<&'n TravlNode<K, V>> {
+        self.move_to(successor(self.nodes, self.current))
+    }
+
+    /// Moves the cursor to the in-order predecessor of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node is the first one.
+    pub fn move_prev(&mut self) -> Option<&'n TravlNode<K, V>> {
+        self.move_to(predecessor(self.nodes, self.current))
+    }
+
+    /// Moves the cursor to the given node, if there is one
+    fn move_to(&mut self, node: Option<&'n TravlNode<K, V>>) -> Option<&'n TravlNode<K, V>> {
+        if let Some(node) = node {
+            self.current = node;
+        }
+
+        node
+    }
+}
+
+/// Cursor pointing to a node of a map, allowing to mutate the values it points to
+///
+/// Since nodes are stored in the map by key, the cursor keeps track of the current key
+/// and looks nodes up when it is moved around.
+pub struct CursorMut<'m, 'a, K, V, P = V, S = NodeMap<K, V>>
+where
+    V: 'a
+{
+    map: &'m mut TravlMap<'a, K, V, P, S>,
+    current: K,
+}
+
+impl<'m, 'a, K, V, P, S> CursorMut<'m, 'a, K, V, P, S>
+where
+    K: Clone,
+    S: NodeStorage<K, V>
+{
+    /// Creates a cursor pointing to the node associated to the given key
+    ///
+    /// Returns [`None`] if the map doesn't contain the key.
+    #[must_use]
+    pub fn new(map: &'m mut TravlMap<'a, K, V, P, S>, key: &K) -> Option<Self> {
+        if !map.contains_key(key) {
+            return None;
+        }
+
+        Some(Self { map, current: key.clone() })
+    }
+
+    /// Returns the node the cursor points to
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor followed a link to a node which isn't part of the map,
+    /// see [`try_current`](Self::try_current)
+    #[must_use]
+    pub fn current(&self) -> &TravlNode<K, V> {
+        self.try_current().expect("cursor should point to a node of the map")
+    }
+
+    /// Returns the node the cursor points to
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::BrokenLink`] if the cursor followed a link to a node which isn't part of the map.
+    pub fn try_current(&self) -> Result<&TravlNode<K, V>, TravlError> {
+        self.map.get(&self.current).ok_or(TravlError::BrokenLink)
+    }
+
+    /// Returns the key of the node the cursor points to
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.current
+    }
+
+    /// Returns a mutable pointer to the value of the node the cursor points to
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map no longer contains the current key, which cannot happen
+    /// as long as the cursor mutably borrows the map
+    #[must_use]
+    pub fn value_mut(&mut self) -> &mut V {
+        self.map
+            .get_mut(&self.current)
+            .expect("cursor should point to a node of the map")
+            .value_mut()
+    }
+
+    /// Returns a read-only cursor pointing to the same node
+    #[must_use]
+    pub fn as_cursor(&self) -> Cursor<'_, K, V, S> {
+        Cursor::new(self.map.nodes(), self.current())
+    }
+
+    /// Moves the cursor to the parent of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node has no parent.
+    pub fn move_to_parent(&mut self) -> Option<&TravlNode<K, V>> {
+        self.move_with(S::parent_of)
+    }
+
+    /// Moves the cursor to the left child of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node has no left child.
+    pub fn move_to_left(&mut self) -> Option<&TravlNode<K, V>> {
+        self.move_with(S::left_of)
+    }
+
+    /// Moves the cursor to the right child of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node has no right child.
+    pub fn move_to_right(&mut self) -> Option<&TravlNode<K, V>> {
+        self.move_with(S::right_of)
+    }
+
+    /// Moves the cursor to the in-order successor of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node is the last one.
+    pub fn move_next(&mut self) -> Option<&TravlNode<K, V>> {
+        self.move_with(successor)
+    }
+
+    /// Moves the cursor to the in-order predecessor of the current node
+    ///
+    /// Returns the new current node, or [`None`] without moving if the current node is the first one.
+    pub fn move_prev(&mut self) -> Option<&TravlNode<K, V>> {
+        self.move_with(predecessor)
+    }
+
+    /// Inserts an entry right after the current node in the order of the map, rebalancing the tree
+    ///
+    /// The cursor keeps pointing to the current node.
+    ///
+    /// # Errors
+    ///
+    /// Returns the entry back if its key is already in the map, or if its property isn't ordered between the
+    /// properties of the current node and of its in-order successor.
+    pub fn insert_after(&mut self, key: K, value: V) -> Result<(), RejectedEntry<K, V>>
+    where
+        K: Eq
+    {
+        self.map.insert_next_to(&self.current, key, value, true)
+    }
+
+    /// Inserts an entry right before the current node in the order of the map, rebalancing the tree
+    ///
+    /// The cursor keeps pointing to the current node.
+    ///
+    /// # Errors
+    ///
+    /// Returns the entry back if its key is already in the map, or if its property isn't ordered between the
+    /// properties of the in-order predecessor of the current node and of the current node.
+    pub fn insert_before(&mut self, key: K, value: V) -> Result<(), RejectedEntry<K, V>>
+    where
+        K: Eq
+    {
+        self.map.insert_next_to(&self.current, key, value, false)
+    }
+
+    /// Removes the current node from the map, rebalancing the tree, and returns its entry
+    ///
+    /// Also returns a cursor pointing to the in-order successor of the removed node, or to its predecessor if it
+    /// was the last node, and [`None`] if the map is now empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map no longer contains the current key, which cannot happen
+    /// as long as the cursor mutably borrows the map
+    pub fn remove_current(self) -> ((K, V), Option<Self>)
+    where
+        K: Eq
+    {
+        let current = self.current();
+        let next = successor(self.map.nodes(), current)
+            .or_else(|| predecessor(self.map.nodes(), current))
+            .map(|node| node.key().clone());
+
+        let value = self.map.remove(&self.current).expect("cursor should point to a node of the map");
+        let entry = (self.current, value);
+
+        (entry, next.map(|current| Self { map: self.map, current }))
+    }
+
+    /// Moves the cursor to the node returned by the given step function, if there is one
+    fn move_with<F>(&mut self, step: F) -> Option<&TravlNode<K, V>>
+    where
+        F: for<'c> FnOnce(&'c S, &'c TravlNode<K, V>) -> Option<&'c TravlNode<K, V>>,
+    {
+        let key = step(self.map.nodes(), self.current())?.key().clone();
+
+        self.current = key;
+        self.map.get(&self.current)
+    }
+}
@@ -0,0 +1,59 @@
+//! Errors of the fallible operations on trees
+//!
+//! Operations which can fail because of the state of a tree, or because of their arguments, return a
+//! [`TravlError`] rather than panicking within the tree code, so that callers can recover from them.
+
+use core::error::Error;
+use core::fmt::{self, Display};
+
+use crate::map::{RejectedEntry, RejectionReason};
+
+/// Error of a fallible operation on a tree
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TravlError {
+    /// The map holds nodes, but none of them is its root
+    MissingRoot,
+    /// A node links to a node which isn't part of the map
+    BrokenLink,
+    /// The key is already part of the map
+    DuplicateKey,
+    /// The entry's property is ordered before the one of the entry it should follow
+    OutOfOrder,
+    /// Two values can't be compared with each other, such as a float and NaN
+    Incomparable,
+    /// The imbalance factor exceeds [`MAX_IMBALANCE_FACTOR`](crate::core::MAX_IMBALANCE_FACTOR)
+    ImbalanceFactorTooLarge,
+    /// The tree would grow higher than [`MAX_HEIGHT`](crate::core::MAX_HEIGHT)
+    HeightLimitExceeded,
+}
+
+impl Display for TravlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRoot => f.write_str("the map holds nodes but has no root"),
+            Self::BrokenLink => f.write_str("a node links to a node which isn't part of the map"),
+            Self::DuplicateKey => f.write_str("the key is already part of the map"),
+            Self::OutOfOrder => f.write_str("the entry is ordered before the previous entry"),
+            Self::Incomparable => f.write_str("the values can't be compared with each other"),
+            Self::ImbalanceFactorTooLarge => f.write_str("the imbalance factor exceeds the maximum imbalance factor"),
+            Self::HeightLimitExceeded => f.write_str("the tree would grow higher than the maximum height"),
+        }
+    }
+}
+
+impl Error for TravlError {}
+
+impl From<RejectionReason> for TravlError {
+    fn from(reason: RejectionReason) -> Self {
+        match reason {
+            RejectionReason::OutOfOrder => Self::OutOfOrder,
+            RejectionReason::DuplicateKey => Self::DuplicateKey,
+        }
+    }
+}
+
+impl<K, V> From<RejectedEntry<K, V>> for TravlError {
+    fn from(rejected: RejectedEntry<K, V>) -> Self {
+        rejected.reason().into()
+    }
+}
@@ -0,0 +1,157 @@
+//! Map whose entries expire at a deadline, for caches with time-based eviction and timer wheels
+//!
+//! Besides the entries ordered by key, a [`TravlExpiringMap`] indexes keys by deadline in a
+//! [`TravlHeap`], so that [`expire_until`](TravlExpiringMap::expire_until) removes every due entry in
+//! O(m log n) for m due entries, without scanning the others. Deadlines can be of any ordered type,
+//! typically [`Instant`](std::time::Instant) or a tick count.
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+
+use crate::heap::TravlHeap;
+use crate::persistent::{PersistentIter, TravlMapPersistent};
+
+/// Map whose entries expire at a deadline, see the [module-level documentation](self)
+///
+/// Entries aren't removed on their own once their deadline is reached: they stay visible until
+/// [`expire_until`](Self::expire_until) is called.
+pub struct TravlExpiringMap<K, V, T> {
+    entries: TravlMapPersistent<K, V>,
+    deadlines: TravlHeap<K, T>,
+}
+
+impl<K, V, T> TravlExpiringMap<K, V, T> {
+    /// Creates an empty map
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            entries: TravlMapPersistent::new(),
+            deadlines: TravlHeap::new(),
+        }
+    }
+
+    /// Returns the number of entries in the map, including due entries that weren't expired yet
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map contains no entry
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the earliest deadline of the entries along with its key
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<(&K, &T)> {
+        self.deadlines.peek()
+    }
+
+    /// Returns an iterator over the entries, ordered by key
+    #[must_use]
+    pub fn iter(&self) -> PersistentIter<'_, K, V> {
+        self.entries.iter()
+    }
+}
+
+impl<K, V, T> TravlExpiringMap<K, V, T>
+where
+    K: Ord + Clone,
+    V: Clone,
+    T: Ord + Clone
+{
+    /// Returns the value associated to the given key, if it exists
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    /// Returns `true` if the map contains the given key
+    #[must_use]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the deadline of the given key, if it exists
+    #[must_use]
+    pub fn deadline(&self, key: &K) -> Option<&T> {
+        self.deadlines.priority(key)
+    }
+
+    /// Inserts an entry expiring at the given deadline, returning the previous value of the key
+    ///
+    /// The deadline of an existing key is replaced along with its value.
+    pub fn insert(&mut self, key: K, value: V, deadline: T) -> Option<V> {
+        self.deadlines.push(key.clone(), deadline);
+        self.entries.insert_in_place(key, value)
+    }
+
+    /// Changes the deadline of an existing key, returning its previous deadline
+    pub fn set_deadline(&mut self, key: &K, deadline: T) -> Option<T> {
+        self.deadlines.change_priority(key, deadline)
+    }
+
+    /// Removes an entry before its deadline, returning its value if it existed
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.deadlines.remove_by_key(key);
+        self.entries.remove_in_place(key)
+    }
+
+    /// Removes the entries whose deadline is `now` or earlier, returning them ordered by deadline
+    pub fn expire_until(&mut self, now: &T) -> Vec<(K, V)> {
+        let mut expired = Vec::new();
+
+        while self.deadlines.peek().is_some_and(|(_, deadline)| deadline <= now) {
+            let Some((key, _)) = self.deadlines.pop() else {
+                break;
+            };
+
+            expired.extend(self.entries.remove_in_place(&key).map(|value| (key, value)));
+        }
+
+        expired
+    }
+}
+
+impl<K, V, T> Clone for TravlExpiringMap<K, V, T>
+where
+    K: Clone,
+    T: Clone
+{
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            deadlines: self.deadlines.clone(),
+        }
+    }
+}
+
+impl<K, V, T> Default for TravlExpiringMap<K, V, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'m, K, V, T> IntoIterator for &'m TravlExpiringMap<K, V, T> {
+    type IntoIter = PersistentIter<'m, K, V>;
+    type Item = (&'m K, &'m V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, T> Debug for TravlExpiringMap<K, V, T>
+where
+    K: Debug,
+    V: Debug,
+    T: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TravlExpiringMap")
+            .field("entries", &self.entries)
+            .field("deadlines", &self.deadlines)
+            .finish()
+    }
+}
@@ -0,0 +1,219 @@
+//! Priority queue whose entries are addressed by key, for schedulers and graph searches
+//!
+//! Unlike [`BinaryHeap`](alloc::collections::BinaryHeap), a [`TravlHeap`] associates each priority to a key,
+//! so the priority of a key can be changed and a key can be removed wherever it is in the queue, both in
+//! O(log n). It is built on two [`TravlMapPersistent`]s, one ordering the keys by priority and the other
+//! holding the priority of each key, which makes cloning a queue O(1). The key of lowest priority is cached,
+//! so [peeking](TravlHeap::peek) runs in O(1).
+
+use core::fmt::{self, Debug};
+use core::iter::FusedIterator;
+
+use crate::persistent::{PersistentIter, TravlMapPersistent};
+
+/// Priority queue addressed by key, see the [module-level documentation](self)
+///
+/// Keys are popped from the lowest priority to the greatest one, wrap priorities in
+/// [`Reverse`](core::cmp::Reverse) to pop the greatest priorities first. Keys of equal priorities are
+/// popped in key order.
+pub struct TravlHeap<K, P> {
+    queue: TravlMapPersistent<(P, K), ()>,
+    priorities: TravlMapPersistent<K, P>,
+    /// Priority and key of the first entry of `queue`
+    first: Option<(P, K)>,
+}
+
+impl<K, P> TravlHeap<K, P> {
+    /// Creates an empty queue
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            queue: TravlMapPersistent::new(),
+            priorities: TravlMapPersistent::new(),
+            first: None,
+        }
+    }
+
+    /// Returns the number of keys in the queue
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.priorities.len()
+    }
+
+    /// Returns `true` if the queue contains no key
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.priorities.is_empty()
+    }
+
+    /// Returns the key of lowest priority along with its priority, without removing it, in O(1)
+    #[must_use]
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.first.as_ref().map(|(priority, key)| (key, priority))
+    }
+
+    /// Returns an iterator over the keys and their priorities, from the lowest priority to the greatest one
+    #[must_use]
+    pub fn iter(&self) -> HeapIter<'_, K, P> {
+        HeapIter {
+            inner: self.queue.iter(),
+        }
+    }
+}
+
+impl<K, P> TravlHeap<K, P>
+where
+    K: Ord + Clone,
+    P: Ord + Clone
+{
+    /// Returns the priority of the given key, if it is in the queue
+    #[must_use]
+    pub fn priority(&self, key: &K) -> Option<&P> {
+        self.priorities.get(key)
+    }
+
+    /// Returns `true` if the given key is in the queue
+    #[must_use]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.priorities.contains_key(key)
+    }
+
+    /// Adds a key to the queue with the given priority, returning its previous priority if it was already
+    /// in the queue
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        let previous = self.priorities.insert_in_place(key.clone(), priority.clone());
+        let entry = (priority, key);
+
+        let mut was_first = false;
+        if let Some(previous) = &previous {
+            let stale = (previous.clone(), entry.1.clone());
+            self.queue.remove_in_place(&stale);
+            was_first = self.first.as_ref() == Some(&stale);
+        }
+        self.queue.insert_in_place(entry.clone(), ());
+
+        if was_first {
+            self.first = self.queue.first_key_value().map(|(first, ())| first.clone());
+        } else if self.first.as_ref().is_none_or(|first| entry < *first) {
+            self.first = Some(entry);
+        }
+        previous
+    }
+
+    /// Removes the key of lowest priority from the queue, returning it along with its priority
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        let (priority, key) = self.first.clone()?;
+
+        self.remove_by_key(&key);
+        Some((key, priority))
+    }
+
+    /// Changes the priority of a key already in the queue, returning its previous priority
+    ///
+    /// Keys that aren't in the queue are left out, see [`push`](Self::push) to add them.
+    pub fn change_priority(&mut self, key: &K, priority: P) -> Option<P> {
+        if !self.priorities.contains_key(key) {
+            return None;
+        }
+        self.push(key.clone(), priority)
+    }
+
+    /// Removes a key from the queue, returning its priority if it was in the queue
+    pub fn remove_by_key(&mut self, key: &K) -> Option<P> {
+        let priority = self.priorities.remove_in_place(key)?;
+
+        let entry = (priority, key.clone());
+        self.queue.remove_in_place(&entry);
+        if self.first.as_ref() == Some(&entry) {
+            self.first = self.queue.first_key_value().map(|(first, ())| first.clone());
+        }
+        Some(entry.0)
+    }
+
+    /// Adds the keys of another queue to this one, the priorities of the other queue replacing those of
+    /// keys in both queues
+    ///
+    /// Runs in O(m log(n + m)) for m keys in the other queue, which is left untouched.
+    pub fn append(&mut self, other: &Self) {
+        for (key, priority) in &other.priorities {
+            self.push(key.clone(), priority.clone());
+        }
+    }
+}
+
+impl<K, P> Clone for TravlHeap<K, P>
+where
+    K: Clone,
+    P: Clone
+{
+    fn clone(&self) -> Self {
+        Self {
+            queue: self.queue.clone(),
+            priorities: self.priorities.clone(),
+            first: self.first.clone(),
+        }
+    }
+}
+
+impl<K, P> Default for TravlHeap<K, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, P> Debug for TravlHeap<K, P>
+where
+    K: Debug,
+    P: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, P> FromIterator<(K, P)> for TravlHeap<K, P>
+where
+    K: Ord + Clone,
+    P: Ord + Clone
+{
+    fn from_iter<I: IntoIterator<Item = (K, P)>>(entries: I) -> Self {
+        let mut heap = Self::new();
+        for (key, priority) in entries {
+            heap.push(key, priority);
+        }
+        heap
+    }
+}
+
+impl<'h, K, P> IntoIterator for &'h TravlHeap<K, P> {
+    type IntoIter = HeapIter<'h, K, P>;
+    type Item = (&'h K, &'h P);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the keys of a [`TravlHeap`] and their priorities, from the lowest priority to the
+/// greatest one
+///
+/// Created by [`TravlHeap::iter`].
+pub struct HeapIter<'h, K, P> {
+    inner: PersistentIter<'h, (P, K), ()>,
+}
+
+impl<'h, K, P> Iterator for HeapIter<'h, K, P> {
+    type Item = (&'h K, &'h P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|((priority, key), ())| (key, priority))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, P> ExactSizeIterator for HeapIter<'_, K, P> {}
+
+impl<K, P> FusedIterator for HeapIter<'_, K, P> {}
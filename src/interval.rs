@@ -0,0 +1,440 @@
+//! Interval map, associating values to half-open intervals and finding the intervals overlapping a point
+//! or another interval
+//!
+//! [`TravlIntervalMap`] is a [`TravlMapPersistent`] ordered by interval start, where every subtree is
+//! [augmented](Augmentation) with the greatest end of its intervals. Queries skip the subtrees ending
+//! before the queried point or interval, as well as the subtrees starting after it, and run in
+//! O(log n + m) for m matching intervals.
+//!
+//! A [`SweepLine`] walks through the starts and ends of the intervals in order while keeping track of the
+//! intervals open at each point, which suits scheduling problems such as detecting conflicts or allocating
+//! rooms.
+
+use alloc::collections::{BTreeMap, btree_map};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug};
+use core::iter::{FusedIterator, Peekable};
+use core::ops::{Bound, Range, RangeBounds};
+
+use crate::persistent::{Augmentation, PersistentIter, PersistentNode, TravlMapPersistent};
+
+/// Interval used as a key, ordered by start then by end
+#[derive(Clone, PartialEq, Eq)]
+struct Interval<T>(Range<T>);
+
+impl<T> PartialOrd for Interval<T>
+where
+    T: Ord
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Interval<T>
+where
+    T: Ord
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.start.cmp(&other.0.start).then_with(|| self.0.end.cmp(&other.0.end))
+    }
+}
+
+/// Greatest end of the intervals of a subtree
+#[derive(Clone)]
+struct MaxEnd<T>(T);
+
+impl<T, V> Augmentation<Interval<T>, V> for MaxEnd<T>
+where
+    T: Ord + Clone
+{
+    fn from_entry(key: &Interval<T>, _value: &V) -> Self {
+        Self(key.0.end.clone())
+    }
+
+    fn combine(&self, next: &Self) -> Self {
+        if next.0 > self.0 { next.clone() } else { self.clone() }
+    }
+}
+
+/// Map of half-open intervals to values, see the [module-level documentation](self)
+///
+/// Like [`TravlMapPersistent`], cloning an interval map is O(1).
+pub struct TravlIntervalMap<T, V> {
+    map: TravlMapPersistent<Interval<T>, V, MaxEnd<T>>,
+}
+
+impl<T, V> TravlIntervalMap<T, V> {
+    /// Creates an empty map
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            map: TravlMapPersistent::new_augmented(),
+        }
+    }
+
+    /// Returns the number of intervals in the map
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no interval
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the intervals and their values, ordered by start then by end
+    #[must_use]
+    pub fn iter(&self) -> IntervalIter<'_, T, V> {
+        IntervalIter {
+            inner: self.map.iter(),
+        }
+    }
+
+    /// Returns a sweep line over the starts and ends of the intervals, see [`SweepLine`]
+    #[must_use]
+    pub fn sweep(&self) -> SweepLine<'_, T, V> {
+        SweepLine {
+            starts: self.iter().peekable(),
+            active: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T, V> TravlIntervalMap<T, V>
+where
+    T: Ord + Clone,
+    V: Clone
+{
+    /// Returns a reference to the value associated to the given interval
+    #[must_use]
+    pub fn get(&self, interval: &Range<T>) -> Option<&V> {
+        self.map.get(&Interval(interval.clone()))
+    }
+
+    /// Associates a value to an interval, returning the value it was previously associated to
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is empty, its start not being lower than its end.
+    pub fn insert(&mut self, interval: Range<T>, value: V) -> Option<V> {
+        assert!(interval.start < interval.end, "interval must not be empty");
+
+        let key = Interval(interval);
+        let previous = self.map.get(&key).cloned();
+        self.map = self.map.insert(key, value);
+        previous
+    }
+
+    /// Removes an interval, returning its value if it was in the map
+    pub fn remove(&mut self, interval: &Range<T>) -> Option<V> {
+        let (map, removed) = self.map.remove(&Interval(interval.clone()));
+        self.map = map;
+        removed
+    }
+
+    /// Returns an iterator over the intervals containing the given point, ordered by start then by end
+    #[must_use]
+    pub fn query_point(&self, point: &T) -> Overlapping<'_, T, V> {
+        Overlapping::new(
+            self.map.root(),
+            (Bound::Included(point.clone()), Bound::Included(point.clone())),
+        )
+    }
+
+    /// Returns an iterator over the intervals overlapping the given range, ordered by start then by end
+    ///
+    /// Intervals only touching the range, ending where the range starts, don't overlap it.
+    #[must_use]
+    pub fn query_overlapping<R>(&self, range: R) -> Overlapping<'_, T, V>
+    where
+        R: RangeBounds<T>
+    {
+        Overlapping::new(
+            self.map.root(),
+            (range.start_bound().cloned(), range.end_bound().cloned()),
+        )
+    }
+}
+
+impl<T, V> Clone for TravlIntervalMap<T, V> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<T, V> Default for TravlIntervalMap<T, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, V> Debug for TravlIntervalMap<T, V>
+where
+    T: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T, V> FromIterator<(Range<T>, V)> for TravlIntervalMap<T, V>
+where
+    T: Ord + Clone,
+    V: Clone
+{
+    fn from_iter<I: IntoIterator<Item = (Range<T>, V)>>(entries: I) -> Self {
+        let mut map = Self::new();
+        for (interval, value) in entries {
+            map.insert(interval, value);
+        }
+        map
+    }
+}
+
+impl<'m, T, V> IntoIterator for &'m TravlIntervalMap<T, V> {
+    type IntoIter = IntervalIter<'m, T, V>;
+    type Item = (&'m Range<T>, &'m V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the intervals of a [`TravlIntervalMap`] and their values
+///
+/// Created by [`TravlIntervalMap::iter`].
+pub struct IntervalIter<'m, T, V> {
+    inner: PersistentIter<'m, Interval<T>, V, MaxEnd<T>>,
+}
+
+impl<'m, T, V> Iterator for IntervalIter<'m, T, V> {
+    type Item = (&'m Range<T>, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(interval, value)| (&interval.0, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, V> ExactSizeIterator for IntervalIter<'_, T, V> {}
+
+impl<T, V> FusedIterator for IntervalIter<'_, T, V> {}
+
+/// Iterator over the intervals of a [`TravlIntervalMap`] overlapping a point or a range, and their values
+///
+/// Created by [`TravlIntervalMap::query_point`] and [`TravlIntervalMap::query_overlapping`].
+pub struct Overlapping<'m, T, V> {
+    /// Nodes whose left subtree was visited, and which remain to be visited with their right subtree
+    stack: Vec<&'m PersistentNode<Interval<T>, V, MaxEnd<T>>>,
+    bounds: (Bound<T>, Bound<T>),
+}
+
+impl<'m, T, V> Overlapping<'m, T, V>
+where
+    T: Ord
+{
+    /// Creates an iterator over the intervals of the given subtree overlapping the given bounds
+    fn new(root: Option<&'m PersistentNode<Interval<T>, V, MaxEnd<T>>>, bounds: (Bound<T>, Bound<T>)) -> Self {
+        let mut overlapping = Self {
+            stack: Vec::new(),
+            bounds,
+        };
+        overlapping.push_left_branch(root);
+        overlapping
+    }
+
+    /// Returns `true` if an interval ending at `end` ends after the start of the bounds
+    fn ends_after_start(&self, end: &T) -> bool {
+        match &self.bounds.0 {
+            Bound::Included(start) | Bound::Excluded(start) => end > start,
+            Bound::Unbounded => true,
+        }
+    }
+
+    /// Returns `true` if an interval starting at `start` starts before the end of the bounds
+    fn starts_before_end(&self, start: &T) -> bool {
+        match &self.bounds.1 {
+            Bound::Included(end) => start <= end,
+            Bound::Excluded(end) => start < end,
+            Bound::Unbounded => true,
+        }
+    }
+
+    /// Pushes the given node and its chain of left children onto the stack, stopping at the first subtree
+    /// whose intervals all end before the start of the bounds
+    fn push_left_branch(&mut self, mut node: Option<&'m PersistentNode<Interval<T>, V, MaxEnd<T>>>) {
+        while let Some(current) = node {
+            if !self.ends_after_start(&current.augmentation.0) {
+                break;
+            }
+
+            self.stack.push(current);
+            node = current.left.as_deref();
+        }
+    }
+}
+
+impl<'m, T, V> Iterator for Overlapping<'m, T, V>
+where
+    T: Ord
+{
+    type Item = (&'m Range<T>, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            let interval = &node.key.0;
+
+            // The remaining nodes and their right subtrees start even later
+            if !self.starts_before_end(&interval.start) {
+                self.stack.clear();
+                return None;
+            }
+
+            self.push_left_branch(node.right.as_deref());
+
+            if self.ends_after_start(&interval.end) {
+                return Some((interval, &node.value));
+            }
+        }
+
+        None
+    }
+}
+
+impl<T, V> FusedIterator for Overlapping<'_, T, V> where T: Ord {}
+
+/// Start or end of an interval, produced by a [`SweepLine`]
+#[derive(Debug)]
+pub enum SweepEvent<'m, T, V> {
+    /// The interval starts, and is now active
+    Start(&'m Range<T>, &'m V),
+    /// The interval ends, and is no longer active
+    End(&'m Range<T>, &'m V),
+}
+
+impl<'m, T, V> SweepEvent<'m, T, V> {
+    /// Returns the point where the event happens
+    #[must_use]
+    pub fn point(&self) -> &'m T {
+        match self {
+            Self::Start(interval, _) => &interval.start,
+            Self::End(interval, _) => &interval.end,
+        }
+    }
+
+    /// Returns the interval starting or ending
+    #[must_use]
+    pub fn interval(&self) -> &'m Range<T> {
+        match self {
+            Self::Start(interval, _) | Self::End(interval, _) => interval,
+        }
+    }
+
+    /// Returns the value of the interval starting or ending
+    #[must_use]
+    pub fn value(&self) -> &'m V {
+        match self {
+            Self::Start(_, value) | Self::End(_, value) => value,
+        }
+    }
+}
+
+impl<T, V> Clone for SweepEvent<'_, T, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, V> Copy for SweepEvent<'_, T, V> {}
+
+/// Iterator over the starts and ends of the intervals of a [`TravlIntervalMap`], ordered by point, which
+/// keeps track of the active intervals
+///
+/// Ends come before starts at the same point, since intervals are half-open: an interval ending where
+/// another starts never overlaps it. After each event, [`active`](Self::active) gives the intervals that
+/// started but didn't end yet.
+///
+/// Created by [`TravlIntervalMap::sweep`].
+pub struct SweepLine<'m, T, V> {
+    starts: Peekable<IntervalIter<'m, T, V>>,
+    /// Intervals started but not ended yet, ordered by end
+    active: BTreeMap<(&'m T, &'m T), (&'m Range<T>, &'m V)>,
+}
+
+impl<'m, T, V> SweepLine<'m, T, V>
+where
+    T: Ord
+{
+    /// Returns the number of active intervals
+    #[must_use]
+    pub fn active_len(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Returns an iterator over the active intervals and their values, ordered by end
+    #[must_use]
+    pub fn active(&self) -> ActiveIntervals<'_, 'm, T, V> {
+        ActiveIntervals {
+            inner: self.active.values(),
+        }
+    }
+}
+
+impl<'m, T, V> Iterator for SweepLine<'m, T, V>
+where
+    T: Ord
+{
+    type Item = SweepEvent<'m, T, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next_end = self.active.first_key_value().map(|((end, _), _)| *end);
+        let ends_first = match (next_end, self.starts.peek()) {
+            (Some(end), Some((interval, _))) => *end <= interval.start,
+            (next_end, _) => next_end.is_some(),
+        };
+
+        if ends_first {
+            let (_, (interval, value)) = self.active.pop_first()?;
+            return Some(SweepEvent::End(interval, value));
+        }
+
+        let (interval, value) = self.starts.next()?;
+        self.active.insert((&interval.end, &interval.start), (interval, value));
+        Some(SweepEvent::Start(interval, value))
+    }
+}
+
+impl<T, V> FusedIterator for SweepLine<'_, T, V> where T: Ord {}
+
+/// Iterator over the active intervals of a [`SweepLine`] and their values, ordered by end
+///
+/// Created by [`SweepLine::active`].
+pub struct ActiveIntervals<'s, 'm, T, V> {
+    inner: btree_map::Values<'s, (&'m T, &'m T), (&'m Range<T>, &'m V)>,
+}
+
+impl<'m, T, V> Iterator for ActiveIntervals<'_, 'm, T, V> {
+    type Item = (&'m Range<T>, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().copied()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, V> ExactSizeIterator for ActiveIntervals<'_, '_, T, V> {}
+
+impl<T, V> FusedIterator for ActiveIntervals<'_, '_, T, V> {}
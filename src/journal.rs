@@ -0,0 +1,194 @@
+//! Map journaling its changes, whose insertions and removals can be undone and redone
+//!
+//! A [`JournaledMap`] records the inverse of each insertion and removal, which restores the key to its
+//! previous state: its previous value, or its absence. Undoing a change applies its inverse and records
+//! the inverse of that in turn, so that the change can be redone.
+//!
+//! Only the logical contents of the map are restored: a map whose changes were undone holds the same
+//! entries as before, but its tree may be shaped differently.
+
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+
+use crate::persistent::TravlMapPersistent;
+
+/// Change of a single key
+#[derive(Clone, Debug)]
+enum Edit<K, V> {
+    /// Associates the value to the key
+    Set(K, V),
+    /// Removes the key
+    Remove(K),
+}
+
+/// Map whose changes can be undone and redone, see the [module-level documentation](self)
+pub struct JournaledMap<K, V> {
+    map: TravlMapPersistent<K, V>,
+    /// Inverses of the changes that can be undone, the latest last
+    undo: Vec<Edit<K, V>>,
+    /// Inverses of the undone changes that can be redone, the latest undone last
+    redo: Vec<Edit<K, V>>,
+}
+
+impl<K, V> JournaledMap<K, V> {
+    /// Creates an empty map without history
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_persistent(TravlMapPersistent::new())
+    }
+
+    /// Creates a map holding the entries of the given map, without history
+    #[must_use]
+    pub fn from_persistent(map: TravlMapPersistent<K, V>) -> Self {
+        Self {
+            map,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Returns the current version of the map, which isn't affected by later changes
+    #[must_use]
+    pub fn snapshot(&self) -> TravlMapPersistent<K, V> {
+        self.map.clone()
+    }
+
+    /// Returns the number of entries in the map
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entry
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of changes that can be undone
+    #[must_use]
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Returns the number of undone changes that can be redone
+    #[must_use]
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Forgets the history of the map, keeping its entries
+    pub fn clear_history(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+impl<K, V> JournaledMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone
+{
+    /// Returns the value associated to the given key, if it exists
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Inserts an entry, returning the previous value of the key
+    ///
+    /// The change can be undone, and the changes undone until now can no longer be redone.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = self.map.get(&key).cloned();
+        let inverse = self.apply(Edit::Set(key, value));
+
+        self.undo.push(inverse);
+        self.redo.clear();
+        previous
+    }
+
+    /// Removes the entry of the given key, returning its value if it existed
+    ///
+    /// Removing a missing key doesn't change the map and isn't recorded.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = self.map.get(key).cloned()?;
+        let inverse = self.apply(Edit::Remove(key.clone()));
+
+        self.undo.push(inverse);
+        self.redo.clear();
+        Some(removed)
+    }
+
+    /// Undoes the latest `count` changes not undone yet, returning how many were undone
+    pub fn undo(&mut self, count: usize) -> usize {
+        let mut undone = 0;
+
+        while undone < count {
+            let Some(edit) = self.undo.pop() else {
+                break;
+            };
+
+            let inverse = self.apply(edit);
+            self.redo.push(inverse);
+            undone += 1;
+        }
+
+        undone
+    }
+
+    /// Redoes the latest `count` undone changes, returning how many were redone
+    pub fn redo(&mut self, count: usize) -> usize {
+        let mut redone = 0;
+
+        while redone < count {
+            let Some(edit) = self.redo.pop() else {
+                break;
+            };
+
+            let inverse = self.apply(edit);
+            self.undo.push(inverse);
+            redone += 1;
+        }
+
+        redone
+    }
+
+    /// Applies a change to the map, returning its inverse
+    fn apply(&mut self, edit: Edit<K, V>) -> Edit<K, V> {
+        let key = match &edit {
+            Edit::Set(key, _) | Edit::Remove(key) => key.clone(),
+        };
+
+        let inverse = match self.map.get(&key) {
+            Some(previous) => Edit::Set(key, previous.clone()),
+            None => Edit::Remove(key),
+        };
+
+        self.map = match edit {
+            Edit::Set(key, value) => self.map.insert(key, value),
+            Edit::Remove(key) => self.map.remove(&key).0,
+        };
+
+        inverse
+    }
+}
+
+impl<K, V> Default for JournaledMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Debug for JournaledMap<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JournaledMap")
+            .field("map", &self.map)
+            .field("undo_len", &self.undo.len())
+            .field("redo_len", &self.redo.len())
+            .finish()
+    }
+}
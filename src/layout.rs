@@ -0,0 +1,147 @@
+//! Breadth-first packed layout of a map's entries, for read-only binary searches
+//!
+//! The entries are stored in a single `Vec` following the breadth-first order of a complete binary tree:
+//! the children of the entry at index `i` are at indices `2i + 1` and `2i + 2`. Searches only move
+//! forward in the `Vec`, and the first levels of the tree share the same few cache lines, which makes
+//! this layout well suited for read-mostly indexes built once and queried many times.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::Hash;
+
+use crate::map::{NodeMap, OrdFn, PropFn, TravlMap, TravlMapConfig};
+use crate::storage::NodeStorage;
+
+/// Entries of a map packed in breadth-first order, with implicit child indices
+///
+/// Layouts are created by [`TravlMap::to_bfs_layout`] and turned back into maps by
+/// [`rebuild`](Self::rebuild).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BfsLayout<K, V> {
+    config: TravlMapConfig,
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> BfsLayout<K, V> {
+    /// Packs entries sorted according to a map's ordering
+    pub(crate) fn from_sorted(config: TravlMapConfig, sorted: Vec<(K, V)>) -> Self {
+        let mut slots: Vec<Option<(K, V)>> = core::iter::repeat_with(|| None).take(sorted.len()).collect();
+
+        for (index, entry) in in_order_indices(sorted.len()).zip(sorted) {
+            slots[index] = Some(entry);
+        }
+
+        Self {
+            config,
+            entries: slots.into_iter().flatten().collect(),
+        }
+    }
+
+    /// Returns the configuration of the map
+    #[must_use]
+    pub fn config(&self) -> TravlMapConfig {
+        self.config
+    }
+
+    /// Returns the entries in breadth-first order
+    #[must_use]
+    pub fn entries(&self) -> &[(K, V)] {
+        &self.entries
+    }
+
+    /// Returns the number of entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the layout contains no entries
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the index of the left child of the entry at the given index, if any
+    #[must_use]
+    pub fn left_child(&self, index: usize) -> Option<usize> {
+        Some(2 * index + 1).filter(|&child| child < self.len())
+    }
+
+    /// Returns the index of the right child of the entry at the given index, if any
+    #[must_use]
+    pub fn right_child(&self, index: usize) -> Option<usize> {
+        Some(2 * index + 2).filter(|&child| child < self.len())
+    }
+
+    /// Binary searches the layout with a comparator function, like [`slice::binary_search_by`]
+    ///
+    /// The comparator returns the ordering of the given entry relative to the target, following the
+    /// ordering of the map the layout was created from. If several entries match, any of them may be returned.
+    pub fn binary_search_by<F>(&self, mut f: F) -> Option<&(K, V)>
+    where
+        F: FnMut(&K, &V) -> Ordering
+    {
+        let mut index = 0;
+
+        while let Some(entry @ (key, value)) = self.entries.get(index) {
+            index = match f(key, value) {
+                Ordering::Less => 2 * index + 2,
+                Ordering::Greater => 2 * index + 1,
+                Ordering::Equal => return Some(entry),
+            };
+        }
+
+        None
+    }
+
+    /// Consumes the layout, returning its entries in order
+    #[must_use]
+    pub fn into_sorted_vec(self) -> Vec<(K, V)> {
+        let mut slots: Vec<Option<(K, V)>> = self.entries.into_iter().map(Some).collect();
+        let len = slots.len();
+
+        in_order_indices(len).filter_map(|index| slots[index].take()).collect()
+    }
+
+    /// Rebuilds the map using the given property getter and ordering function
+    ///
+    /// The entries are sorted again, so the functions don't have to match the ones of the original map.
+    #[must_use]
+    pub fn rebuild<'a, P>(self, prop_fn: PropFn<'a, V, P>, ordering_fn: OrdFn<'a, P>) -> TravlMap<'a, K, V, P>
+    where
+        K: Clone,
+        NodeMap<K, V>: NodeStorage<K, V>
+    {
+        let config = self.config;
+        TravlMap::from_entries(config, self.into_sorted_vec(), prop_fn, ordering_fn)
+    }
+
+    /// Rebuilds the map using the default property getter and ordering function
+    #[must_use]
+    pub fn rebuild_default<'a>(self) -> TravlMap<'a, K, V>
+    where
+        K: Clone,
+        NodeMap<K, V>: NodeStorage<K, V>,
+        V: Ord + 'a
+    {
+        self.rebuild(Box::new(|x| x), Box::new(Ord::cmp))
+    }
+}
+
+/// Returns the indices of a breadth-first packed tree of the given length, in in-order traversal order
+fn in_order_indices(len: usize) -> impl Iterator<Item = usize> {
+    let mut stack = Vec::new();
+    let mut index = 0;
+
+    core::iter::from_fn(move || {
+        while index < len {
+            stack.push(index);
+            index = 2 * index + 1;
+        }
+
+        let current = stack.pop()?;
+        index = 2 * current + 2;
+        Some(current)
+    })
+}
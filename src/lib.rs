@@ -1,9 +1,59 @@
 //! A library for AVL trees and related operations
+//!
+//! The crate only needs [`alloc`] when the default `std` feature is disabled, for `no_std` targets. Modules
+//! relying on I/O, threads or locks aren't available then, and hash maps and sets come from `hashbrown` if
+//! its feature is enabled. Without it, maps store their nodes in a [`SlabStorage`](storage::SlabStorage),
+//! whose keys must implement [`Ord`], and the [`tombstone`] module and the balance factors of
+//! [`TreeStats`](stats::TreeStats) aren't available.
 
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+pub mod bulk;
+pub mod checksum;
+#[cfg(feature = "std")]
+pub mod concurrent;
 pub mod core;
+pub mod cursor;
+pub mod error;
+pub mod expiry;
+pub mod heap;
+pub mod interval;
+pub mod journal;
+pub mod layout;
+pub mod list;
+pub mod lru;
 pub mod map;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod multiset;
+pub mod observer;
+pub mod partial;
+pub mod persistent;
+pub mod render;
 pub mod set;
+pub mod small;
+#[cfg(feature = "std")]
+pub mod snapshot;
+pub mod stats;
+pub mod storage;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub mod tombstone;
 pub mod traversal;
+pub mod validation;
+pub mod versioned;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+#[cfg(feature = "proptest")]
+mod proptest_impl;
+#[cfg(feature = "rkyv")]
+mod rkyv_impl;
 #[cfg(feature = "serde")]
 mod serde_impl;
+#[cfg(feature = "async")]
+mod stream_impl;
@@ -0,0 +1,397 @@
+//! Sequence indexed by position, whose insertions, removals, splits and concatenations run in O(log n)
+//!
+//! Unlike maps, a [`TravlList`] doesn't order its elements by key: elements keep the order they were
+//! inserted at, and are found by position. Every node of its AVL tree knows the number of elements of its
+//! subtree, which locates a position by descending from the root.
+//!
+//! Every operation changing the sequence is built on two primitives: splitting a tree at a position, and
+//! joining two trees with an element between them. Joining descends along the side of the higher tree
+//! until it meets a subtree as high as the other tree, so both run in O(log n).
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug};
+use core::iter::FusedIterator;
+
+/// Subtree of a list, [`None`] if empty
+type Tree<T> = Option<Box<ListNode<T>>>;
+
+/// Node of a [`TravlList`]
+#[derive(Clone)]
+struct ListNode<T> {
+    value: T,
+    /// Number of levels of the subtree of the node, 1 for leaves
+    height: u64,
+    /// Number of elements of the subtree of the node
+    size: usize,
+    left: Tree<T>,
+    right: Tree<T>,
+}
+
+impl<T> ListNode<T> {
+    /// Creates a subtree out of its root element and children, computing its height and size
+    #[allow(clippy::unnecessary_wraps, reason = "subtrees are always handled as `Tree`s")]
+    fn new(left: Tree<T>, value: T, right: Tree<T>) -> Tree<T> {
+        Some(Box::new(Self {
+            value,
+            height: height(left.as_deref()).max(height(right.as_deref())) + 1,
+            size: size(left.as_deref()) + size(right.as_deref()) + 1,
+            left,
+            right,
+        }))
+    }
+
+    /// Splits the node into its children and element
+    fn into_parts(self) -> (Tree<T>, T, Tree<T>) {
+        (self.left, self.value, self.right)
+    }
+}
+
+/// Returns the height of the given subtree, 0 if it is empty
+fn height<T>(tree: Option<&ListNode<T>>) -> u64 {
+    tree.map_or(0, |node| node.height)
+}
+
+/// Returns the number of elements of the given subtree
+fn size<T>(tree: Option<&ListNode<T>>) -> usize {
+    tree.map_or(0, |node| node.size)
+}
+
+/// Creates a subtree out of its root element and children whose heights differ by at most 2, rotating it if
+/// they differ by 2
+fn balance<T>(left: Tree<T>, value: T, right: Tree<T>) -> Tree<T> {
+    let (left_height, right_height) = (height(left.as_deref()), height(right.as_deref()));
+
+    match (left, right) {
+        (Some(left), right) if left_height > right_height + 1 => {
+            let (left_left, left_value, left_right) = left.into_parts();
+
+            if height(left_left.as_deref()) >= height(left_right.as_deref()) {
+                ListNode::new(left_left, left_value, ListNode::new(left_right, value, right))
+            } else {
+                let (middle_left, middle_value, middle_right) =
+                    left_right.map(|node| node.into_parts()).expect("higher subtrees aren't empty");
+
+                ListNode::new(
+                    ListNode::new(left_left, left_value, middle_left),
+                    middle_value,
+                    ListNode::new(middle_right, value, right),
+                )
+            }
+        },
+        (left, Some(right)) if right_height > left_height + 1 => {
+            let (right_left, right_value, right_right) = right.into_parts();
+
+            if height(right_right.as_deref()) >= height(right_left.as_deref()) {
+                ListNode::new(ListNode::new(left, value, right_left), right_value, right_right)
+            } else {
+                let (middle_left, middle_value, middle_right) =
+                    right_left.map(|node| node.into_parts()).expect("higher subtrees aren't empty");
+
+                ListNode::new(
+                    ListNode::new(left, value, middle_left),
+                    middle_value,
+                    ListNode::new(middle_right, right_value, right_right),
+                )
+            }
+        },
+        (left, right) => ListNode::new(left, value, right),
+    }
+}
+
+/// Joins two subtrees of any heights with an element between them
+fn join<T>(left: Tree<T>, value: T, right: Tree<T>) -> Tree<T> {
+    let (left_height, right_height) = (height(left.as_deref()), height(right.as_deref()));
+
+    match (left, right) {
+        (Some(left), right) if left_height > right_height + 1 => {
+            let (left_left, left_value, left_right) = left.into_parts();
+            let joined = join(left_right, value, right);
+            balance(left_left, left_value, joined)
+        },
+        (left, Some(right)) if right_height > left_height + 1 => {
+            let (right_left, right_value, right_right) = right.into_parts();
+            let joined = join(left, value, right_left);
+            balance(joined, right_value, right_right)
+        },
+        (left, right) => ListNode::new(left, value, right),
+    }
+}
+
+/// Splits a subtree into the elements before the given position and the others
+fn split<T>(tree: Tree<T>, index: usize) -> (Tree<T>, Tree<T>) {
+    let Some(node) = tree else {
+        return (None, None);
+    };
+
+    let left_size = size(node.left.as_deref());
+    let (left, value, right) = node.into_parts();
+
+    if index <= left_size {
+        let (before, after) = split(left, index);
+        (before, join(after, value, right))
+    } else {
+        let (before, after) = split(right, index - left_size - 1);
+        (join(left, value, before), after)
+    }
+}
+
+/// Removes the last element of a subtree, returning the remaining subtree and the element
+fn pop_last<T>(node: ListNode<T>) -> (Tree<T>, T) {
+    let (left, value, right) = node.into_parts();
+
+    match right {
+        Some(right) => {
+            let (right, last) = pop_last(*right);
+            (balance(left, value, right), last)
+        },
+        None => (left, value),
+    }
+}
+
+/// Concatenates two subtrees
+fn concat<T>(first: Tree<T>, second: Tree<T>) -> Tree<T> {
+    match (first, second) {
+        (Some(first), second) => {
+            let (first, last) = pop_last(*first);
+            join(first, last, second)
+        },
+        (None, second) => second,
+    }
+}
+
+/// Sequence indexed by position, see the [module-level documentation](self)
+pub struct TravlList<T> {
+    root: Tree<T>,
+}
+
+impl<T> TravlList<T> {
+    /// Creates an empty list
+    #[must_use]
+    pub fn new() -> Self {
+        Self { root: None }
+    }
+
+    /// Returns the number of elements in the list
+    #[must_use]
+    pub fn len(&self) -> usize {
+        size(self.root.as_deref())
+    }
+
+    /// Returns `true` if the list contains no element
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the height of the tree, 0 for an empty list
+    #[must_use]
+    pub fn height(&self) -> u64 {
+        height(self.root.as_deref())
+    }
+
+    /// Returns a reference to the element at the given position
+    #[must_use]
+    pub fn get(&self, mut index: usize) -> Option<&T> {
+        let mut current = self.root.as_deref();
+
+        while let Some(node) = current {
+            let left_size = size(node.left.as_deref());
+            current = match index.cmp(&left_size) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    node.right.as_deref()
+                },
+            };
+        }
+
+        None
+    }
+
+    /// Returns a mutable reference to the element at the given position
+    #[must_use]
+    pub fn get_mut(&mut self, mut index: usize) -> Option<&mut T> {
+        let mut current = self.root.as_deref_mut();
+
+        while let Some(node) = current {
+            let left_size = size(node.left.as_deref());
+            current = match index.cmp(&left_size) {
+                Ordering::Less => node.left.as_deref_mut(),
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    node.right.as_deref_mut()
+                },
+            };
+        }
+
+        None
+    }
+
+    /// Inserts an element at the given position, shifting the following elements
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length of the list.
+    pub fn insert_at(&mut self, index: usize, value: T) {
+        assert!(index <= self.len(), "insertion index (is {index}) should be <= len (is {})", self.len());
+
+        let (before, after) = split(self.root.take(), index);
+        self.root = join(before, value, after);
+    }
+
+    /// Removes the element at the given position and returns it, shifting the following elements
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_at(&mut self, index: usize) -> T {
+        assert!(index < self.len(), "removal index (is {index}) should be < len (is {})", self.len());
+
+        let (before, after) = split(self.root.take(), index);
+        let (removed, after) = split(after, 1);
+        self.root = concat(before, after);
+
+        removed.expect("the removed element is within bounds").value
+    }
+
+    /// Appends an element at the end of the list
+    pub fn push_back(&mut self, value: T) {
+        self.insert_at(self.len(), value);
+    }
+
+    /// Prepends an element at the start of the list
+    pub fn push_front(&mut self, value: T) {
+        self.insert_at(0, value);
+    }
+
+    /// Splits the list into the elements before the given position and the others
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length of the list.
+    #[must_use]
+    pub fn split_at(self, index: usize) -> (Self, Self) {
+        assert!(index <= self.len(), "split index (is {index}) should be <= len (is {})", self.len());
+
+        let (before, after) = split(self.root, index);
+        (Self { root: before }, Self { root: after })
+    }
+
+    /// Concatenates two lists, the elements of `other` following those of `self`
+    #[must_use]
+    pub fn concat(self, other: Self) -> Self {
+        Self {
+            root: concat(self.root, other.root),
+        }
+    }
+
+    /// Returns an iterator over the elements, in order
+    #[must_use]
+    pub fn iter(&self) -> ListIter<'_, T> {
+        let mut iter = ListIter {
+            stack: Vec::new(),
+            remaining: self.len(),
+        };
+        iter.push_left_branch(self.root.as_deref());
+        iter
+    }
+}
+
+impl<T> Clone for TravlList<T>
+where
+    T: Clone
+{
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<T> Default for TravlList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for TravlList<T>
+where
+    T: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> PartialEq for TravlList<T>
+where
+    T: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T> Eq for TravlList<T> where T: Eq {}
+
+impl<T> FromIterator<T> for TravlList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        let mut list = Self::new();
+        for value in values {
+            list.push_back(value);
+        }
+        list
+    }
+}
+
+impl<'l, T> IntoIterator for &'l TravlList<T> {
+    type IntoIter = ListIter<'l, T>;
+    type Item = &'l T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of a [`TravlList`], in order
+///
+/// Created by [`TravlList::iter`].
+pub struct ListIter<'l, T> {
+    /// Nodes whose left subtree was visited, and which remain to be visited with their right subtree
+    stack: Vec<&'l ListNode<T>>,
+    remaining: usize,
+}
+
+impl<'l, T> ListIter<'l, T> {
+    /// Pushes the given node and its chain of left children onto the stack
+    fn push_left_branch(&mut self, mut node: Option<&'l ListNode<T>>) {
+        while let Some(current) = node {
+            self.stack.push(current);
+            node = current.left.as_deref();
+        }
+    }
+}
+
+impl<'l, T> Iterator for ListIter<'l, T> {
+    type Item = &'l T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_branch(node.right.as_deref());
+        self.remaining -= 1;
+
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for ListIter<'_, T> {}
+
+impl<T> FusedIterator for ListIter<'_, T> {}
@@ -0,0 +1,199 @@
+//! Bounded cache evicting its least recently used entries, whose keys stay ordered
+//!
+//! A [`TravlLru`] pairs the entries ordered by key with a [`TravlHeap`] ordering keys by the time they were
+//! last used, counted in operations. Lookups, insertions and evictions run in O(log n), and unlike
+//! hash-based caches, the cached keys can be queried by range.
+
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::ops::RangeBounds;
+
+use crate::heap::TravlHeap;
+use crate::persistent::{PersistentIter, PersistentRange, TravlMapPersistent};
+
+/// Cache holding at most a given number of entries, see the [module-level documentation](self)
+pub struct TravlLru<K, V> {
+    entries: TravlMapPersistent<K, V>,
+    last_used: TravlHeap<K, u64>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl<K, V> TravlLru<K, V> {
+    /// Creates an empty cache holding at most `capacity` entries
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity != 0, "capacity must be non-zero");
+
+        Self {
+            entries: TravlMapPersistent::new(),
+            last_used: TravlHeap::new(),
+            capacity,
+            clock: 0,
+        }
+    }
+
+    /// Returns the maximum number of entries of the cache
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of entries in the cache
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the cache contains no entry
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the entries, ordered by key, without marking them as used
+    #[must_use]
+    pub fn iter(&self) -> PersistentIter<'_, K, V> {
+        self.entries.iter()
+    }
+
+    /// Returns an iterator over the entries whose keys are within the given range, ordered by key, without
+    /// marking them as used
+    #[must_use]
+    pub fn range<Q, R>(&self, range: R) -> PersistentRange<'_, K, V, Q, R>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>
+    {
+        self.entries.range(range)
+    }
+
+    /// Returns the next value of the clock, which orders the uses of the entries
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+impl<K, V> TravlLru<K, V>
+where
+    K: Ord + Clone,
+    V: Clone
+{
+    /// Returns the value associated to the given key and marks it as the most recently used entry
+    ///
+    /// The time of last use of the key is updated in place, without copying the nodes of the cache unless a
+    /// clone of it shares them.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.entries.contains_key(key) {
+            let now = self.tick();
+            self.last_used.change_priority(key, now);
+        }
+
+        self.entries.get(key)
+    }
+
+    /// Returns the value associated to the given key, without marking it as used
+    #[must_use]
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    /// Returns `true` if the cache contains the given key, without marking it as used
+    #[must_use]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the least recently used entry, without marking it as used
+    #[must_use]
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        let (key, _) = self.last_used.peek()?;
+        self.entries.get(key).map(|value| (key, value))
+    }
+
+    /// Inserts an entry and marks it as the most recently used one, returning the previous value of the key
+    ///
+    /// The least recently used entry is dropped if it has to be evicted to stay within the capacity, see
+    /// [`push`](Self::push) to get it back.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.put(key, value).0
+    }
+
+    /// Inserts an entry and marks it as the most recently used one, returning the least recently used entry
+    /// if it was evicted to stay within the capacity
+    ///
+    /// Nothing is evicted when the key was already cached, its previous value being dropped, see
+    /// [`insert`](Self::insert) to get it back.
+    pub fn push(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.put(key, value).1
+    }
+
+    /// Inserts an entry, returning the previous value of the key and the evicted entry
+    fn put(&mut self, key: K, value: V) -> (Option<V>, Option<(K, V)>) {
+        let now = self.tick();
+
+        self.last_used.push(key.clone(), now);
+        let previous = self.entries.insert_in_place(key, value);
+
+        let evicted = if self.entries.len() > self.capacity {
+            self.pop_lru()
+        } else {
+            None
+        };
+        (previous, evicted)
+    }
+
+    /// Removes an entry, returning its value if it was cached
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.last_used.remove_by_key(key);
+        self.entries.remove_in_place(key)
+    }
+
+    /// Removes the least recently used entry and returns it
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let (key, _) = self.last_used.pop()?;
+        self.entries.remove_in_place(&key).map(|value| (key, value))
+    }
+}
+
+impl<K, V> Clone for TravlLru<K, V>
+where
+    K: Clone
+{
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            last_used: self.last_used.clone(),
+            capacity: self.capacity,
+            clock: self.clock,
+        }
+    }
+}
+
+impl<'c, K, V> IntoIterator for &'c TravlLru<K, V> {
+    type IntoIter = PersistentIter<'c, K, V>;
+    type Item = (&'c K, &'c V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> Debug for TravlLru<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TravlLru")
+            .field("capacity", &self.capacity)
+            .field("entries", &self.entries)
+            .finish_non_exhaustive()
+    }
+}
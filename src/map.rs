@@ -1,17 +1,96 @@
-//! Map similar to [`BTreeMap`](std::collections::BTreeMap) and its operations
+//! Map similar to [`BTreeMap`](alloc::collections::BTreeMap) and its operations
 //! 
 //! Refer to the [`core`](crate::core) module for information about the inner workings
 //! of the AVL tree, its nodes and related operations.
 
-use std::cmp::Ordering;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Display};
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt::Debug;
-use std::hash::Hash;
+#[cfg(feature = "rayon")]
+use std::collections::HashSet;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
 
-use crate::core::TravlNode;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
-type PropFn<'a, V, P> = Box<dyn FnMut(&V) -> &P + 'a>;
-type OrdFn<'a, P> = Box<dyn FnMut(&P, &P) -> Ordering + 'a>;
+#[cfg(feature = "arbitrary")]
+pub use crate::arbitrary_impl::MapOperation;
+use crate::checksum;
+use crate::core::{MAX_IMBALANCE_FACTOR, Metrics, TravlNode, check_imbalance_factor};
+use crate::cursor::{Cursor, CursorMut};
+use crate::error::TravlError;
+use crate::layout::BfsLayout;
+#[cfg(feature = "metrics")]
+use crate::metrics::TreeMetrics;
+#[cfg(feature = "proptest")]
+pub use crate::proptest_impl::{TravlMapStrategy, TravlMapValueTree};
+use crate::render::{render_ascii, render_json};
+#[cfg(feature = "rkyv")]
+pub use crate::rkyv_impl::{ArchiveEntry, ArchivedArchiveEntry, ArchivedTravlMapArchive, TravlMapArchive};
+#[cfg(feature = "serde")]
+use crate::serde_impl::NodeRecord;
+#[cfg(feature = "serde")]
+pub use crate::serde_impl::{
+    DeserializedMap, FormattedMap, SERDE_FORMAT_VERSION, SerdeFormat, StreamingTravlMapSeed, TravlMapSeed,
+};
+#[cfg(feature = "std")]
+use crate::snapshot::{SnapshotCodec, invalid_data, read_header, write_header};
+use crate::stats::TreeStats;
+use crate::storage::NodeStorage;
+#[cfg(feature = "async")]
+pub use crate::stream_impl::{DEFAULT_YIELD_INTERVAL, IterStream};
+#[cfg(feature = "rayon")]
+use crate::traversal::ParNodeIter;
+use crate::traversal::{
+    BoundaryIter, InOrderIter, InternalIter, LeafIter, ParentLinkedInOrderIter, PostOrderIter, Traversal,
+    TraversalOrder, leftmost, predecessor, rightmost, successor, walk_with,
+};
+use crate::validation::Violation;
+
+/// Map storing the nodes of a [`TravlMap`] by key, hashing them with `H`
+///
+/// This is a [`hashbrown::HashMap`] when the `hashbrown` feature is enabled, and a std [`HashMap`] otherwise.
+/// The hasher can be chosen with [`TravlMapBuilder::hasher`].
+#[cfg(feature = "hashbrown")]
+pub type NodeMap<K, V, H = hashbrown::DefaultHashBuilder> = hashbrown::HashMap<K, TravlNode<K, V>, H>;
+/// Map storing the nodes of a [`TravlMap`] by key, hashing them with `H`
+///
+/// This is a `hashbrown::HashMap` when the `hashbrown` feature is enabled, and a std [`HashMap`] otherwise.
+/// The hasher can be chosen with [`TravlMapBuilder::hasher`].
+#[cfg(all(feature = "std", not(feature = "hashbrown")))]
+pub type NodeMap<K, V, H = std::hash::RandomState> = HashMap<K, TravlNode<K, V>, H>;
+/// Map storing the nodes of a [`TravlMap`] by key
+///
+/// This is a `hashbrown::HashMap` when the `hashbrown` feature is enabled, and a
+/// [`SlabStorage`](crate::storage::SlabStorage) without the `std` feature otherwise.
+#[cfg(not(any(feature = "std", feature = "hashbrown")))]
+pub type NodeMap<K, V> = crate::storage::SlabStorage<K, V>;
+
+/// Property getter of a [`TravlMap`]
+///
+/// The closure must be [`Send`] and [`Sync`] so that maps can be sent to and shared between threads, where
+/// lookups such as [`TravlMap::find`] may call it concurrently.
+pub(crate) type PropFn<'a, V, P> = Box<dyn Fn(&V) -> &P + Send + Sync + 'a>;
+/// Ordering function of a [`TravlMap`]
+///
+/// The closure must be [`Send`] and [`Sync`] so that maps can be sent to and shared between threads, where
+/// lookups such as [`TravlMap::find`] may call it concurrently.
+pub(crate) type OrdFn<'a, P> = Box<dyn Fn(&P, &P) -> Ordering + Send + Sync + 'a>;
 
 /// Search type when searching for a value in the tree
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
@@ -20,6 +99,10 @@ pub enum SearchType {
     #[default]
     Equality,
     /// Returns nearest value (rounding)
+    ///
+    /// Ordering functions only compare properties and can't tell which of two neighbors is nearer, so this rounds
+    /// up, and only rounds down when no value is ordered after the searched one.
+    #[deprecated = "ordering functions can't tell which neighbor is nearer, use `NearestToBottom` or `NearestToTop`"]
     Nearest,
     /// Returns nearest value from the bottom (rounding down)
     NearestToBottom,
@@ -27,46 +110,463 @@ pub enum SearchType {
     NearestToTop,
 }
 
-/// Map similar to [`BTreeMap`](std::collections::BTreeMap)
-pub struct TravlMap<'a, K, V, P = V> {
+/// Policy applied to entries with equal properties when merging maps
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EqualPropPolicy {
+    /// Yields all entries, entries from earlier maps coming first
+    #[default]
+    KeepAll,
+    /// Only yields the first entry, coming from the earliest map
+    KeepFirst,
+    /// Only yields the last entry, coming from the latest map
+    KeepLast,
+}
+
+/// Place given to inserted entries among the entries whose properties are equal to theirs
+///
+/// Only entries inserted one by one are placed using the policy. Bulk builds keep the relative order of the
+/// entries they are given.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TiePolicy {
+    /// Inserted entries come after the entries whose properties are equal to theirs
+    #[default]
+    After,
+    /// Inserted entries come before the entries whose properties are equal to theirs
+    Before,
+}
+
+/// Strategy keeping the tree of a map balanced as entries are inserted and removed one by one
+///
+/// Bulk builds, such as [`compact`](TravlMap::compact) or deserialization, build trees of minimal height
+/// whatever the strategy.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Balancing {
+    /// Rotates nodes as soon as the heights of their subtrees differ by more than the imbalance factor allows
+    #[default]
+    Strict,
+    /// Only rotates nodes once the heights of their subtrees differ by the given number of levels more than the
+    /// imbalance factor allows
+    ///
+    /// Writes rotate nodes far less often, at the cost of deeper lookups. The tree is brought back within the
+    /// imbalance factor by rebuilding it, for example through [`compact`](TravlMap::compact).
+    Relaxed(u64),
+    /// Never rotates nodes, but rebuilds the smallest imbalanced subtree on the path of a write at minimal height,
+    /// in O(n) for its n nodes
+    ///
+    /// Rebuilding a subtree balances all of its levels at once, so it rarely needs to be rebuilt again soon, which
+    /// some write-heavy workloads prefer over frequent rotations.
+    Scapegoat,
+    /// Rotates nodes once one of their subtrees holds more than [`WEIGHT_RATIO`] times as many nodes as the
+    /// other, counting one more node in each, rather than comparing their heights
+    ///
+    /// Heights aren't bounded by the imbalance factor, but remain logarithmic. Keeping subtree sizes balanced
+    /// makes positions, as used by [`TravlMap::select`] and [`TravlMap::rank`], evenly spread across the tree.
+    Weight,
+    /// Keeps the tree a weak AVL tree, whose nodes have a rank exceeding the ranks of their children by one or two,
+    /// leaves having rank 0
+    ///
+    /// Insertions rebalance the tree as an AVL tree would, but removals only ever rotate nodes once or twice,
+    /// ranks absorbing the rest of the imbalance, which suits removal-heavy workloads. Heights aren't bounded by
+    /// the imbalance factor, but never exceed twice the height of an AVL tree.
+    Rank,
+}
+
+/// Largest ratio between the weights of the subtrees of a node in a [weight-balanced](Balancing::Weight) tree, the
+/// weight of a subtree being its number of nodes plus one
+///
+/// Along with [`WEIGHT_ROTATION_RATIO`], it is one of the few pairs of parameters for which a single or double
+/// rotation always restores the balance of a node after an insertion or a removal.
+pub const WEIGHT_RATIO: usize = 3;
+
+/// Ratio between the weights of the inner and outer subtrees of the heavier child of an imbalanced node in a
+/// [weight-balanced](Balancing::Weight) tree from which a double rotation is needed rather than a single one
+pub const WEIGHT_ROTATION_RATIO: usize = 2;
+
+/// Difference between two maps, yielded by [`TravlMap::diff`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffItem<'n, K, V> {
+    /// Entry only present in the other map
+    Added(&'n K, &'n V),
+    /// Entry only present in this map
+    Removed(&'n K, &'n V),
+    /// Key present in both maps but with different values, the old value coming first
+    Changed(&'n K, &'n V, &'n V),
+}
+
+impl<K, V> Clone for DiffItem<'_, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for DiffItem<'_, K, V> {}
+
+/// Configuration of a map, independent from its property getter and ordering function
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TravlMapConfig {
     imbalance_factor: u64,
-    root_key: Option<&'a K>,
-    nodes: HashMap<K, TravlNode<'a, K, V>>,
+    tie_policy: TiePolicy,
+    balancing: Balancing,
+}
+
+impl TravlMapConfig {
+    /// Creates a configuration using the given imbalance factor
+    ///
+    /// # Panics
+    ///
+    /// Panics if the imbalance factor exceeds [`MAX_IMBALANCE_FACTOR`], see
+    /// [`try_new`](Self::try_new) for a fallible version.
+    #[must_use]
+    pub fn new(imbalance_factor: u64) -> Self {
+        Self::try_new(imbalance_factor).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates a configuration using the given imbalance factor, if it doesn't exceed
+    /// [`MAX_IMBALANCE_FACTOR`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::ImbalanceFactorTooLarge`] if the imbalance factor exceeds the maximum.
+    pub fn try_new(imbalance_factor: u64) -> Result<Self, TravlError> {
+        Ok(Self {
+            imbalance_factor: check_imbalance_factor(imbalance_factor)?,
+            tie_policy: TiePolicy::default(),
+            balancing: Balancing::default(),
+        })
+    }
+
+    /// Sets the tie policy
+    #[must_use]
+    pub fn with_tie_policy(mut self, tie_policy: TiePolicy) -> Self {
+        self.tie_policy = tie_policy;
+        self
+    }
+
+    /// Sets the balancing strategy
+    #[must_use]
+    pub fn with_balancing(mut self, balancing: Balancing) -> Self {
+        self.balancing = balancing;
+        self
+    }
+
+    /// Returns the imbalance factor
+    #[must_use]
+    pub fn imbalance_factor(&self) -> u64 {
+        self.imbalance_factor
+    }
+
+    /// Returns the tie policy
+    #[must_use]
+    pub fn tie_policy(&self) -> TiePolicy {
+        self.tie_policy
+    }
+
+    /// Returns the balancing strategy
+    #[must_use]
+    pub fn balancing(&self) -> Balancing {
+        self.balancing
+    }
+
+    /// Returns the imbalance factor writes keep the tree within, which a [relaxed](Balancing::Relaxed) strategy
+    /// loosens, capped at [`MAX_IMBALANCE_FACTOR`]
+    ///
+    /// Heights of [weight-balanced](Balancing::Weight) and [rank-balanced](Balancing::Rank) trees aren't bounded
+    /// by any imbalance factor, the maximum is returned for them.
+    #[must_use]
+    pub fn effective_imbalance_factor(&self) -> u64 {
+        match self.balancing {
+            Balancing::Strict | Balancing::Scapegoat => self.imbalance_factor,
+            Balancing::Relaxed(slack) => self.imbalance_factor.saturating_add(slack).min(MAX_IMBALANCE_FACTOR),
+            Balancing::Weight | Balancing::Rank => MAX_IMBALANCE_FACTOR,
+        }
+    }
+}
+
+/// Owned copy of a map's configuration and entries, without its property getter and ordering function
+///
+/// Closures can't be persisted, so snapshots are what gets serialized when the map's functions are
+/// provided again on load. Snapshots are created by [`TravlMap::snapshot`] and turned back into maps by
+/// [`rebuild`](Self::rebuild).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TravlMapSnapshot<K, V> {
+    pub(crate) config: TravlMapConfig,
+    pub(crate) entries: Vec<(K, V)>,
+}
+
+impl<K, V> TravlMapSnapshot<K, V> {
+    /// Returns the configuration of the map
+    #[must_use]
+    pub fn config(&self) -> TravlMapConfig {
+        self.config
+    }
+
+    /// Returns the entries of the map, in the order they had in the map
+    #[must_use]
+    pub fn entries(&self) -> &[(K, V)] {
+        &self.entries
+    }
+
+    /// Rebuilds the map using the given property getter and ordering function
+    ///
+    /// The entries are sorted again, so the functions don't have to match the ones of the original map.
+    #[must_use]
+    pub fn rebuild<'a, P>(self, prop_fn: PropFn<'a, V, P>, ordering_fn: OrdFn<'a, P>) -> TravlMap<'a, K, V, P>
+    where
+        K: Clone,
+        NodeMap<K, V>: NodeStorage<K, V>
+    {
+        TravlMap::from_entries(self.config, self.entries, prop_fn, ordering_fn)
+    }
+
+    /// Rebuilds the map using the default property getter and ordering function
+    #[must_use]
+    pub fn rebuild_default<'a>(self) -> TravlMap<'a, K, V>
+    where
+        K: Clone,
+        NodeMap<K, V>: NodeStorage<K, V>,
+        V: Ord + 'a
+    {
+        self.rebuild(Box::new(|x| x), Box::new(Ord::cmp))
+    }
+}
+
+/// Reason why an entry was rejected by a [`SortedEntriesBuilder`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+    /// The entry's property is ordered before the one of the previous entry
+    OutOfOrder,
+    /// The entry's key was already pushed
+    DuplicateKey,
+}
+
+/// Entry rejected by a [`SortedEntriesBuilder`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedEntry<K, V> {
+    key: K,
+    value: V,
+    reason: RejectionReason,
+}
+
+impl<K, V> RejectedEntry<K, V> {
+    /// Returns the rejected entry's key
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the rejected entry's value
+    #[must_use]
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// Returns why the entry was rejected
+    #[must_use]
+    pub fn reason(&self) -> RejectionReason {
+        self.reason
+    }
+
+    /// Returns the rejected entry
+    #[must_use]
+    pub fn into_entry(self) -> (K, V) {
+        (self.key, self.value)
+    }
+}
+
+impl<K, V> core::fmt::Display for RejectedEntry<K, V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.reason {
+            RejectionReason::OutOfOrder => f.write_str("entry is ordered before the previous entry"),
+            RejectionReason::DuplicateKey => f.write_str("entry has the same key as a previous entry"),
+        }
+    }
+}
+
+impl<K, V> core::error::Error for RejectedEntry<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+}
+
+/// Map similar to [`BTreeMap`](alloc::collections::BTreeMap)
+///
+/// Maps are [`Send`] and [`Sync`] as long as their keys and values are.
+///
+/// # Panic safety
+///
+/// The property getter and ordering function are only called before the tree is changed: entries are compared
+/// before being linked, so a panic in either of them leaves the tree as it was. Operations rebuilding the whole
+/// tree, such as [`compact`](Self::compact), [`repair`](Self::repair) or ending a [`Batch`], take the nodes out
+/// of the map before sorting them, and a panic while sorting loses the entries taken out. The map is then
+/// empty and [poisoned](Self::is_poisoned), like a [`Mutex`](std::sync::Mutex) whose guard was dropped by a
+/// panic, until a rebuild completes. The map is also poisoned when the closure given to [`apply`](Self::apply)
+/// panics, since it may have left its value out of order, and when the property getter or ordering function
+/// panic while `apply` looks for the new place of a node it unlinked, the node being lost.
+///
+/// Nodes are stored in a [`NodeMap`] by default, see the [`storage`](crate::storage) module to store them
+/// differently.
+pub struct TravlMap<'a, K, V, P = V, S = NodeMap<K, V>>
+where
+    V: 'a
+{
+    config: TravlMapConfig,
+    root_key: Option<K>,
+    nodes: S,
     prop_fn: PropFn<'a, V, P>,
     ordering_fn: OrdFn<'a, P>,
+    /// Whether a rebuild of the tree was interrupted by a panic, see [`TravlMap::is_poisoned`]
+    poisoned: bool,
+    metrics: Metrics,
 }
 
-impl<K, V> Debug for TravlMap<'_, K, V>
+impl<K, V, P, S> Debug for TravlMap<'_, K, V, P, S>
 where
     K: Debug,
-    V: Debug
+    V: Debug,
+    S: Debug
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("TravlMap")
-            .field("imbalance_factor", &self.imbalance_factor)
+            .field("config", &self.config)
             .field("root_key", &self.root_key)
             .field("nodes", &self.nodes)
+            .field("poisoned", &self.poisoned)
             // Once `.field_with()` is stable, use it to indicate the presence of
             // prop_fn and ordering_fn but replacing the function with just its signature as a string
             .finish_non_exhaustive()
     }
 }
 
+impl<K, V, P, S> PartialEq for TravlMap<'_, K, V, P, S>
+where
+    K: PartialEq,
+    V: PartialEq,
+    S: NodeStorage<K, V>
+{
+    /// Compares the entries of both maps in order, regardless of the shape of their trees
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K, V, P, S> Eq for TravlMap<'_, K, V, P, S>
+where
+    K: Eq,
+    V: Eq,
+    S: NodeStorage<K, V>
+{
+}
+
+impl<K, V, P, S> Hash for TravlMap<'_, K, V, P, S>
+where
+    K: Hash,
+    V: Hash,
+    S: NodeStorage<K, V>
+{
+    /// Hashes the entries in order, regardless of the shape of the tree, consistently with [`PartialEq`]
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+
+        for entry in self {
+            entry.hash(state);
+        }
+    }
+}
+
+impl<K, V, P, S> Display for TravlMap<'_, K, V, P, S>
+where
+    K: Display,
+    V: Display,
+    S: NodeStorage<K, V>
+{
+    /// Formats the entries in order, as `{k1: v1, k2: v2, ...}`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("{")?;
+
+        for (index, (key, value)) in self.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{key}: {value}")?;
+        }
+
+        f.write_str("}")
+    }
+}
+
 impl<'a, K, V> Default for TravlMap<'a, K, V>
 where
     V: Ord + 'a
 {
     fn default() -> Self {
         Self {
-            imbalance_factor: 0,
+            config: TravlMapConfig::default(),
             root_key: None,
-            nodes: HashMap::new(),
+            nodes: NodeMap::new(),
             prop_fn: Box::new(|x| x),
             ordering_fn: Box::new(Ord::cmp),
+            poisoned: false,
+            metrics: Metrics::default(),
         }
     }
 }
 
+impl<'a, K, V> From<BTreeMap<K, V>> for TravlMap<'a, K, V>
+where
+    K: Clone,
+    NodeMap<K, V>: NodeStorage<K, V>,
+    V: Ord + 'a
+{
+    /// Bulk-builds a map out of the entries of a [`BTreeMap`]
+    fn from(map: BTreeMap<K, V>) -> Self {
+        Self::from_entries(TravlMapConfig::default(), map.into_iter().collect(), Box::new(|x| x), Box::new(Ord::cmp))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, K, V, S> From<HashMap<K, V, S>> for TravlMap<'a, K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Ord + 'a
+{
+    /// Bulk-builds a map out of the entries of a [`HashMap`]
+    fn from(map: HashMap<K, V, S>) -> Self {
+        Self::from_entries(TravlMapConfig::default(), map.into_iter().collect(), Box::new(|x| x), Box::new(Ord::cmp))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, K, V> FromParallelIterator<(K, V)> for TravlMap<'a, K, V>
+where
+    K: Hash + Eq + Clone + Send + Sync,
+    V: Ord + Send + 'a
+{
+    /// Bulk-builds a map out of the entries of a parallel iterator
+    ///
+    /// See [`from_par_iter_with_prop_getter_and_ordering`](TravlMap::from_par_iter_with_prop_getter_and_ordering)
+    /// about the parallel build.
+    fn from_par_iter<I>(entries: I) -> Self
+    where
+        I: IntoParallelIterator<Item = (K, V)>
+    {
+        Self::from_par_iter_with_prop_getter_and_ordering(entries, |value| value, Ord::cmp)
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<'a, K, V, S> From<hashbrown::HashMap<K, V, S>> for TravlMap<'a, K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Ord + 'a
+{
+    /// Bulk-builds a map out of the entries of a [`hashbrown::HashMap`]
+    fn from(map: hashbrown::HashMap<K, V, S>) -> Self {
+        Self::from_entries(TravlMapConfig::default(), map.into_iter().collect(), Box::new(|x| x), Box::new(Ord::cmp))
+    }
+}
+
 impl<'a, K, V> TravlMap<'a, K, V>
 where
     V: Ord + 'a
@@ -76,20 +576,65 @@ where
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Restores a map from a snapshot written by [`write_snapshot`](Self::write_snapshot)
+    ///
+    /// The entries are expected to be in the order of the map's values, and the map uses the imbalance factor of
+    /// the given configuration. Wrapping the reader in a [`BufReader`](std::io::BufReader) is recommended.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, if the snapshot is invalid, or if its entries are out of order or
+    /// have duplicate keys.
+    #[cfg(feature = "std")]
+    pub fn read_snapshot<R>(reader: R, config: TravlMapConfig) -> io::Result<Self>
+    where
+        R: Read,
+        K: Hash + Eq + Clone + SnapshotCodec,
+        V: SnapshotCodec
+    {
+        Self::new().read_snapshot_into(reader, config)
+    }
 }
 
 impl<'a, K, V> TravlMap<'a, K, V> {
+    /// Returns a builder configuring a map step by step, see [`TravlMapBuilder`]
+    #[must_use]
+    pub fn builder() -> TravlMapBuilder<'a, K, V> {
+        TravlMapBuilder {
+            config: TravlMapConfig::default(),
+            capacity: 0,
+            prop_fn: Box::new(|x| x),
+            ordering_fn: (),
+            storage: None,
+            key_marker: PhantomData,
+        }
+    }
+
     /// Creates a map using a custom ordering function
     #[must_use]
     pub fn new_with_ordering(ordering_fn: OrdFn<'a, V>) -> Self {
         Self {
-            imbalance_factor: 0,
+            config: TravlMapConfig::default(),
             root_key: None,
-            nodes: HashMap::new(),
+            nodes: NodeMap::new(),
             prop_fn: Box::new(|x| x),
             ordering_fn,
+            poisoned: false,
+            metrics: Metrics::default(),
         }
     }
+
+    /// Creates a map using a custom ordering function, given as a closure or a function pointer
+    ///
+    /// The function is boxed by the map, which doesn't allocate for named functions and closures capturing nothing.
+    #[must_use]
+    pub fn with_ordering<F>(ordering_fn: F) -> Self
+    where
+        F: Fn(&V, &V) -> Ordering + Send + Sync + 'a
+    {
+        Self::new_with_ordering(Box::new(ordering_fn))
+    }
 }
 
 impl<'a, K, V, P> TravlMap<'a, K, V, P>
@@ -100,18 +645,31 @@ where
     #[must_use]
     pub fn new_with_prop_getter(prop_fn: PropFn<'a, V, P>) -> Self {
         Self {
-            imbalance_factor: 0,
+            config: TravlMapConfig::default(),
             root_key: None,
-            nodes: HashMap::new(),
+            nodes: NodeMap::new(),
             prop_fn,
             ordering_fn: Box::new(Ord::cmp),
+            poisoned: false,
+            metrics: Metrics::default(),
         }
     }
+
+    /// Creates a map using a custom property getter, given as a closure or a function pointer
+    ///
+    /// See [`with_ordering`](TravlMap::with_ordering) about boxing.
+    #[must_use]
+    pub fn with_prop_getter<F>(prop_fn: F) -> Self
+    where
+        F: Fn(&V) -> &P + Send + Sync + 'a
+    {
+        Self::new_with_prop_getter(Box::new(prop_fn))
+    }
 }
 
-impl<'a, K, V, P> TravlMap<'a, K, V, P>
+impl<'a, K, V, P, S> TravlMap<'a, K, V, P, S>
 where
-    K: Hash + Eq
+    S: NodeStorage<K, V>
 {
     /// Returns whether the map contains a given key
     #[must_use]
@@ -119,89 +677,2973 @@ where
         self.nodes.contains_key(key)
     }
 
-    /// Returns the node associated to the given key, if it exists
+    /// Returns the root node, if there is one
     #[must_use]
-    pub fn get(&self, key: &K) -> Option<&TravlNode<'a, K, V>> {
-        self.nodes.get(key)
+    pub fn root(&self) -> Option<&TravlNode<K, V>> {
+        self.root_key.as_ref().and_then(|key| self.nodes.get(key))
+    }
+
+    /// Returns the root node, [`None`] if the map is empty
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::MissingRoot`] if the map holds nodes but none of them is its root.
+    pub fn try_root(&self) -> Result<Option<&TravlNode<K, V>>, TravlError> {
+        match self.root() {
+            None if !self.nodes.is_empty() => Err(TravlError::MissingRoot),
+            root => Ok(root),
+        }
     }
 
-    /// Finds the value within the map
+    /// Gathers the structural statistics of the tree, see [`TreeStats`]
     #[must_use]
-    pub fn find(&self, val: &P, search_type: SearchType) -> Option<&TravlNode<'a, K, V>> {
-        todo!();
+    pub fn stats(&self) -> TreeStats {
+        TreeStats::collect(&self.nodes, self.root(), self.config.effective_imbalance_factor())
     }
 
-    /// Returns a mutable pointer to the node associate to the given key, if it exists
+    /// Returns a stable digest of the entries and shape of the tree, see the [`checksum`] module
+    ///
+    /// Maps holding the same entries in trees of the same shape have the same digest, including in other
+    /// processes, whatever their property getters and ordering functions.
     #[must_use]
-    pub fn get_mut(&mut self, key: &K) -> Option<&mut TravlNode<'a, K, V>> {
-        self.nodes.get_mut(key)
+    pub fn structural_hash(&self) -> u64
+    where
+        K: Hash,
+        V: Hash
+    {
+        let mut stack = vec![self.root()];
+
+        checksum::structural_hash(core::iter::from_fn(|| {
+            let node = stack.pop()?;
+            if let Some(node) = node {
+                stack.extend([self.nodes.right_of(node), self.nodes.left_of(node)]);
+            }
+
+            Some(node.map(|node| (node.key(), node.value())))
+        }))
     }
-}
 
-impl<'a, K, V, P> TravlMap<'a, K, V, P> {
-    /// Creates a map using a custom property getter and ordering function
+    /// Checks the invariants of the tree, returning every violation found, see [`Violation`]
+    ///
+    /// Checks that every node is reachable from the root and linked back to its parent, that the root has no
+    /// parent, that heights and sizes are correct, that heights are within the [effective imbalance
+    /// factor](TravlMapConfig::effective_imbalance_factor), that sizes are within the [weight ratio](WEIGHT_RATIO)
+    /// or that ranks are valid depending on the balancing strategy, that nodes are ordered by the current property
+    /// getter and ordering function, and that nodes are stored under their own key. The map is borrowed mutably to call
+    /// the property getter.
     #[must_use]
-    pub fn new_with_prop_getter_and_ordering(prop_fn: PropFn<'a, V, P>, ordering_fn: OrdFn<'a, P>) -> Self {
-        Self {
-            imbalance_factor: 0,
-            root_key: None,
-            nodes: HashMap::new(),
+    pub fn validate(&mut self) -> Vec<Violation<K>>
+    where
+        K: Eq + Clone
+    {
+        let Self {
+            config,
+            root_key,
+            nodes,
             prop_fn,
             ordering_fn,
+            ..
+        } = self;
+        let mut violations: Vec<_> = nodes
+            .iter()
+            .filter(|(key, node)| *key != node.key())
+            .map(|(key, _)| Violation::KeyMismatch(key.clone()))
+            .collect();
+
+        let Some(root) = root_key.as_ref().and_then(|key| nodes.get(key)) else {
+            if !nodes.is_empty() {
+                violations.push(Violation::MissingRoot);
+            }
+            return violations;
+        };
+
+        if root.has_parent() {
+            violations.push(Violation::RootHasParent(root.key().clone()));
+        }
+
+        let levels = |node: Option<&TravlNode<K, V>>| node.map_or(0, |node| node.height() + 1);
+        let sizes = |node: Option<&TravlNode<K, V>>| node.map_or(0, TravlNode::size);
+        // Reached nodes are told apart by address, so that keys don't need to be hashable. Nodes are only
+        // descended into once, so that links forming a cycle can't make the walk loop forever
+        let mut reached = BTreeSet::new();
+        let mut stack = Vec::new();
+        let mut current = Some(root);
+        let mut previous: Option<&TravlNode<K, V>> = None;
+
+        loop {
+            while let Some(node) = current.filter(|node| reached.insert(core::ptr::from_ref(*node))) {
+                stack.push(node);
+                current = nodes.left_of(node);
+            }
+
+            let Some(node) = stack.pop() else {
+                break;
+            };
+            current = nodes.right_of(node);
+            let key = node.key();
+
+            for child_key in [node.left(), node.right()].into_iter().flatten() {
+                match nodes.get(child_key) {
+                    None => violations.push(Violation::ForeignNode(child_key.clone())),
+                    Some(child) if child.parent() != Some(key) => {
+                        violations.push(Violation::ParentMismatch { parent: key.clone(), child: child_key.clone() });
+                    },
+                    Some(_) => {},
+                }
+            }
+
+            let (left_levels, right_levels) = (levels(nodes.left_of(node)), levels(nodes.right_of(node)));
+            let expected = left_levels.max(right_levels);
+            if node.height() != expected {
+                violations.push(Violation::WrongHeight { key: key.clone(), height: node.height(), expected });
+            }
+
+            let (left_size, right_size) = (sizes(nodes.left_of(node)), sizes(nodes.right_of(node)));
+            let expected = left_size.saturating_add(right_size).saturating_add(1);
+            if node.size() != expected {
+                violations.push(Violation::WrongSize { key: Some(key.clone()), size: node.size(), expected });
+            }
+
+            let difference = left_levels.abs_diff(right_levels);
+            if difference.checked_sub(1).is_some_and(|excess| excess > config.effective_imbalance_factor()) {
+                violations.push(Violation::Imbalanced { key: key.clone(), difference });
+            }
+
+            if config.balancing() == Balancing::Weight && !weights_balanced(left_size, right_size) {
+                violations.push(Violation::WeightImbalanced { key: key.clone(), left_size, right_size });
+            }
+
+            let rank_levels = node.rank() + 1;
+            let is_leaf = node.left().is_none() && node.right().is_none();
+            let ranks_balanced = [nodes.left_of(node), nodes.right_of(node)]
+                .map(|child| rank_levels.checked_sub(child.map_or(0, |child| child.rank() + 1)))
+                .iter()
+                .all(|difference| matches!(difference, Some(1 | 2)));
+            if config.balancing() == Balancing::Rank && (!ranks_balanced || is_leaf && node.rank() != 0) {
+                violations.push(Violation::WrongRank { key: key.clone(), rank: node.rank() });
+            }
+
+            if let Some(previous) = previous
+                && ordering_fn(prop_fn(previous.value()), prop_fn(node.value())) == Ordering::Greater
+            {
+                violations.push(Violation::OutOfOrder { previous: previous.key().clone(), key: key.clone() });
+            }
+            previous = Some(node);
         }
+
+        violations.extend(
+            nodes
+                .iter()
+                .filter(|(_, node)| !reached.contains(&core::ptr::from_ref(*node)))
+                .map(|(key, _)| Violation::Unreachable(key.clone()))
+        );
+        violations
     }
 
-    /// Returns the imbalance factor
+    /// Checks that every node is ordered after its in-order predecessor, returning the pairs of adjacent nodes
+    /// that aren't, see [`Violation::OutOfOrder`]
+    ///
+    /// Only compares the properties of adjacent nodes, which is much cheaper than [`validate`](Self::validate),
+    /// so it can be run periodically to detect values whose property changed since they were inserted, for
+    /// example through interior mutability. The map is borrowed mutably to call the property getter.
     #[must_use]
-    pub fn imbalance_factor(&self) -> u64 {
-        self.imbalance_factor
+    pub fn audit_ordering(&mut self) -> Vec<Violation<K>>
+    where
+        K: Clone
+    {
+        let root = self.root_key.as_ref().and_then(|key| self.nodes.get(key));
+        let mut nodes = InOrderIter::new(&self.nodes, root);
+        let mut violations = Vec::new();
+
+        let Some(mut previous) = nodes.next() else {
+            return violations;
+        };
+
+        for node in nodes {
+            let previous_prop = (self.prop_fn)(previous.value());
+            if (self.ordering_fn)(previous_prop, (self.prop_fn)(node.value())) == Ordering::Greater {
+                violations.push(Violation::OutOfOrder { previous: previous.key().clone(), key: node.key().clone() });
+            }
+
+            previous = node;
+        }
+
+        violations
     }
 
-    /// Returns the key of the root node, if there is one
+    /// Returns whether a rebuild of the tree was interrupted by a panic of the property getter or ordering
+    /// function, losing the entries it had taken out of the map
+    ///
+    /// The remaining tree is still valid. The map stays poisoned until a rebuild completes, for example through
+    /// [`repair`](Self::repair).
     #[must_use]
-    pub fn root_key(&self) -> Option<&K> {
-        self.root_key
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
     }
 
-    /// Returns the nodes' [`HashMap`]
+    /// Returns the counters of the work performed by the insertions and removals of the map
+    ///
+    /// Only the heights recomputed while rebalancing are counted so far, lookups aren't counted.
+    #[cfg(feature = "metrics")]
     #[must_use]
-    pub fn nodes(&self) -> &HashMap<K, TravlNode<'a, K, V>> {
-        &self.nodes
+    pub fn metrics(&self) -> &TreeMetrics {
+        &self.metrics
     }
 
-    /// Returns the property getter function
+    /// Rebuilds the tree out of its stored entries if it breaks any invariant, returning the violations found
+    ///
+    /// Every entry stored in the map is kept under the key it is stored under, including the nodes no longer
+    /// reachable from the root, and entries are sorted again with the current property getter and ordering
+    /// function, for example after a value was mutated through [`get_mut`](Self::get_mut) in a way that changed
+    /// its property. The map is left untouched if it is valid and isn't [poisoned](Self::is_poisoned).
+    pub fn repair(&mut self) -> Vec<Violation<K>>
+    where
+        K: Eq + Clone
+    {
+        let violations = self.validate();
+        if violations.is_empty() && !self.poisoned {
+            return violations;
+        }
+
+        self.poisoned = true;
+        let entries = self.nodes.drain().map(TravlNode::into_entry).collect();
+        self.rebuild_from(entries);
+
+        violations
+    }
+
+    /// Returns an iterator over the entries of the map, in order
     #[must_use]
-    pub fn prop_fn(&self) -> &PropFn<'a, V, P> {
-        &self.prop_fn
+    pub fn iter(&self) -> Iter<'_, K, V, S> {
+        Iter {
+            inner: InOrderIter::new(&self.nodes, self.root()),
+            remaining: self.len(),
+        }
     }
 
-    /// Returns the ordering function
+    /// Returns an iterator over the entries of the map, in order, grouped in batches of `chunk_size` entries
+    ///
+    /// The last batch may contain fewer entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
     #[must_use]
-    pub fn ordering_fn(&self) -> &OrdFn<'a, P> {
-        &self.ordering_fn
+    pub fn iter_chunks(&self, chunk_size: usize) -> Chunks<'_, K, V, S> {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+
+        Chunks {
+            inner: self.iter(),
+            chunk_size,
+        }
     }
 
-    /// Returns whether the map is empty
+    /// Returns an iterator over the keys of the map along with the property of their value, in order
+    ///
+    /// The properties are computed using the property getter of the map.
     #[must_use]
-    pub fn is_empty(&self) -> bool {
-        self.nodes.is_empty()
+    pub fn props(&mut self) -> Props<'_, 'a, K, V, P, S> {
+        Props {
+            inner: InOrderIter::new(&self.nodes, self.root_key.as_ref().and_then(|key| self.nodes.get(key))),
+            prop_fn: &mut self.prop_fn,
+        }
     }
 
-    /// Returns the number of nodes within the tree
+    /// Returns an iterator over the pairs of adjacent entries of the map, in order
+    ///
+    /// The iterator is empty if the map contains fewer than two entries.
     #[must_use]
-    pub fn len(&self) -> usize {
-        self.nodes.len()
+    pub fn iter_pairs(&self) -> Pairs<'_, K, V, S> {
+        let mut inner = self.iter();
+        let previous = inner.next();
+
+        Pairs { inner, previous }
     }
 
-    /// Replaces the property getter function and reorders the tree accordingly
-    pub fn replace_prop_fn(&mut self, prop_fn: PropFn<'a, V, P>) {
-        self.prop_fn = prop_fn;
-        todo!("Trigger reordering")
+    /// Returns an iterator over all the windows of `window_size` adjacent entries of the map, in order
+    ///
+    /// The iterator is empty if the map contains fewer than `window_size` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size` is 0.
+    #[must_use]
+    pub fn iter_windows(&self, window_size: usize) -> Windows<'_, K, V, S> {
+        assert!(window_size != 0, "window size must be non-zero");
+
+        Windows {
+            inner: self.iter(),
+            window: VecDeque::with_capacity(window_size),
+            window_size,
+        }
     }
 
-    /// Replaces the ordering function and reorders the tree accordingly
-    pub fn replace_ordering_fn(&mut self, ordering_fn: OrdFn<'a, P>) {
-        self.ordering_fn = ordering_fn;
-        todo!("Trigger reordering")
+    /// Returns an iterator merging the entries of this map and the given maps into a single sorted stream
+    ///
+    /// The property getter and ordering function of this map are used to compare the entries of all maps,
+    /// the other maps are therefore expected to be ordered the same way.
+    /// Entries are yielded along with the index of the map they come from, this map having index 0
+    /// and the other maps following in the given order.
+    #[must_use]
+    pub fn merge_iter<'m>(
+        &'m mut self,
+        others: &[&'m Self],
+        policy: EqualPropPolicy,
+    ) -> MergeIter<'m, 'a, K, V, P, S> {
+        let own_entries = Iter {
+            inner: InOrderIter::new(&self.nodes, self.root_key.as_ref().and_then(|key| self.nodes.get(key))),
+            remaining: self.nodes.len(),
+        };
+        let mut sources: Vec<_> = core::iter::once(own_entries).chain(others.iter().map(|map| map.iter())).collect();
+        let heads = sources.iter_mut().map(Iterator::next).collect();
+
+        MergeIter {
+            prop_fn: &mut self.prop_fn,
+            ordering_fn: &mut self.ordering_fn,
+            sources,
+            heads,
+            policy,
+        }
+    }
+
+    /// Returns an iterator over the differences between this map and the given one
+    ///
+    /// Both maps are walked in order simultaneously, so differences are yielded sorted by property
+    /// and computing them takes O(n + m). The property getter and ordering function of this map are used
+    /// to compare the entries of both maps, the other map is therefore expected to be ordered the same way.
+    #[must_use]
+    pub fn diff<'m>(&'m mut self, other: &'m Self) -> Diff<'m, 'a, K, V, P, S>
+    where
+        V: PartialEq
+    {
+        let mut old_entries = Iter {
+            inner: InOrderIter::new(&self.nodes, self.root_key.as_ref().and_then(|key| self.nodes.get(key))),
+            remaining: self.nodes.len(),
+        };
+        let mut new_entries = other.iter();
+
+        Diff {
+            prop_fn: &mut self.prop_fn,
+            ordering_fn: &mut self.ordering_fn,
+            old_nodes: &self.nodes,
+            new_nodes: &other.nodes,
+            old_head: old_entries.next(),
+            new_head: new_entries.next(),
+            old_entries,
+            new_entries,
+        }
+    }
+
+    /// Returns an iterator over the leaves of the tree, in order
+    #[must_use]
+    pub fn leaves(&self) -> LeafIter<'_, K, V, S> {
+        LeafIter::new(&self.nodes, self.root())
+    }
+
+    /// Returns an iterator over the internal nodes of the tree, in order
+    #[must_use]
+    pub fn internal_nodes(&self) -> InternalIter<'_, K, V, S> {
+        InternalIter::new(&self.nodes, self.root())
+    }
+
+    /// Returns an iterator over the boundary of the tree
+    ///
+    /// See [`BoundaryIter`] for details about the visiting order.
+    #[must_use]
+    pub fn boundary(&self) -> BoundaryIter<'_, K, V, S> {
+        BoundaryIter::new(&self.nodes, self.root())
+    }
+
+    /// Returns a traversal of the whole tree
+    ///
+    /// The traversal type is chosen by the caller, for example
+    /// `map.traverse::<PostOrderIter<_, _>>()`.
+    #[must_use]
+    pub fn traverse<'n, T>(&'n self) -> T
+    where
+        T: Traversal<'n, K, V, S>
+    {
+        T::from_root(&self.nodes, self.root())
+    }
+
+    /// Calls the given function on the nodes of the tree, following the given order
+    ///
+    /// The traversal stops as soon as the function returns [`ControlFlow::Break`], and the break value is returned.
+    pub fn walk_with<'n, B, F>(&'n self, order: TraversalOrder, f: F) -> ControlFlow<B>
+    where
+        F: FnMut(&'n TravlNode<K, V>) -> ControlFlow<B>
+    {
+        walk_with(&self.nodes, self.root(), order, f)
+    }
+
+    /// Returns a traversal of the subtree rooted at the node associated to the given key
+    ///
+    /// The traversal is empty if the map doesn't contain the key.
+    #[must_use]
+    pub fn traverse_subtree<'n, T>(&'n self, key: &K) -> T
+    where
+        T: Traversal<'n, K, V, S>
+    {
+        T::from_root(&self.nodes, self.get(key))
+    }
+
+    /// Returns a cursor pointing to the node associated to the given key, if it exists
+    #[must_use]
+    pub fn cursor(&self, key: &K) -> Option<Cursor<'_, K, V, S>> {
+        self.get(key).map(|node| Cursor::new(&self.nodes, node))
+    }
+
+    /// Returns a cursor allowing to mutate values, pointing to the node associated to the given key, if it exists
+    #[must_use]
+    pub fn cursor_mut(&mut self, key: &K) -> Option<CursorMut<'_, 'a, K, V, P, S>>
+    where
+        K: Clone
+    {
+        CursorMut::new(self, key)
     }
+
+    /// Returns an in-order iterator starting at the node associated to the given key
+    ///
+    /// The iterator is empty if the map doesn't contain the key.
+    /// Since it steps through the nodes using their parent links, starting the iteration
+    /// doesn't require walking the tree from its root.
+    #[must_use]
+    pub fn iter_from(&self, key: &K) -> ParentLinkedInOrderIter<'_, K, V, S> {
+        ParentLinkedInOrderIter::starting_at(&self.nodes, self.get(key))
+    }
+
+    /// Returns an in-order iterator starting at the first node whose property isn't ordered before the given one
+    ///
+    /// Finding the first node requires a single descent from the root, in O(log n).
+    #[must_use]
+    pub fn range_from(&mut self, prop: &P) -> ParentLinkedInOrderIter<'_, K, V, S> {
+        let root = self.root_key.as_ref().and_then(|key| self.nodes.get(key));
+        let first = lower_bound(&self.nodes, root, prop, &self.prop_fn, &self.ordering_fn);
+
+        ParentLinkedInOrderIter::starting_at(&self.nodes, first)
+    }
+
+    /// Returns a stream over the entries of the map, in order
+    ///
+    /// See [`IterStream`] for how the stream cooperates with the executor.
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub fn stream(&self) -> IterStream<Iter<'_, K, V, S>> {
+        IterStream::new(self.iter())
+    }
+
+    /// Returns a stream over the nodes of the map, in order, starting at the first node whose property
+    /// isn't ordered before the given one
+    ///
+    /// See [`range_from`](Self::range_from) and [`IterStream`].
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub fn stream_from(&mut self, prop: &P) -> IterStream<ParentLinkedInOrderIter<'_, K, V, S>> {
+        IterStream::new(self.range_from(prop))
+    }
+
+    /// Returns an in-order iterator over the subtree rooted at the node associated to the given key,
+    /// starting at the first node of that subtree whose property isn't ordered before the given one
+    ///
+    /// The iterator is empty if the map doesn't contain the key.
+    #[must_use]
+    pub fn range_from_in_subtree(&mut self, key: &K, prop: &P) -> ParentLinkedInOrderIter<'_, K, V, S> {
+        let subtree_root = self.nodes.get(key);
+        let first = lower_bound(&self.nodes, subtree_root, prop, &self.prop_fn, &self.ordering_fn);
+
+        ParentLinkedInOrderIter::starting_at_within(&self.nodes, subtree_root, first)
+    }
+
+    /// Applies the given function to every value of the map, in post-order
+    ///
+    /// Since the function may change the properties the tree is ordered by, the ordering of the tree
+    /// is checked once all values have been transformed.
+    ///
+    /// Returns whether the tree is still correctly ordered.
+    #[must_use]
+    pub fn transform_values<F>(&mut self, mut f: F) -> bool
+    where
+        K: Clone,
+        F: FnMut(&K, &mut V)
+    {
+        let keys: Vec<K> = PostOrderIter::new(&self.nodes, self.root()).map(|node| node.key().clone()).collect();
+
+        for key in &keys {
+            if let Some(node) = self.nodes.get_mut(key) {
+                f(key, node.value_mut());
+            }
+        }
+
+        self.is_ordered()
+    }
+
+    /// Returns whether every node's property is ordered after the one of its in-order predecessor
+    fn is_ordered(&mut self) -> bool {
+        let root = self.root_key.as_ref().and_then(|key| self.nodes.get(key));
+        let mut nodes = InOrderIter::new(&self.nodes, root);
+
+        let Some(mut previous) = nodes.next() else {
+            return true;
+        };
+
+        for node in nodes {
+            let previous_prop = (self.prop_fn)(previous.value());
+            if (self.ordering_fn)(previous_prop, (self.prop_fn)(node.value())) == Ordering::Greater {
+                return false;
+            }
+
+            previous = node;
+        }
+
+        true
+    }
+
+    /// Starts a batch of insertions, deferring all rebalancing until the batch ends
+    ///
+    /// Inserted entries are buffered without touching the tree. When the batch ends, the tree is
+    /// rebuilt once out of its entries and the buffered ones, which is much faster than rebalancing
+    /// after each insertion when loading many entries.
+    #[must_use]
+    pub fn batch(&mut self) -> Batch<'_, 'a, K, V, P, S>
+    where
+        K: Clone
+    {
+        Batch {
+            map: self,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Rebuilds the tree into a perfectly balanced one of minimal height, in O(n)
+    ///
+    /// Useful after heavy churn or with a large imbalance factor. Entries are moved in order into the
+    /// new tree without being sorted again, and the node storage keeps its capacity.
+    pub fn compact(&mut self)
+    where
+        K: Clone
+    {
+        let entries = self.take_entries();
+        self.build_balanced(entries);
+    }
+
+    /// Takes the entries out of the map in order, leaving it empty
+    pub(crate) fn take_ordered_entries(&mut self) -> Vec<(K, V)>
+    where
+        K: Clone
+    {
+        let entries = self.take_entries();
+        self.poisoned = false;
+        entries
+    }
+
+    /// Takes the entries out of the map in order, poisoning it until it is rebuilt
+    fn take_entries(&mut self) -> Vec<(K, V)>
+    where
+        K: Clone
+    {
+        let ordered_keys: Vec<K> = self.iter().map(|(key, _)| key.clone()).collect();
+        self.poisoned = true;
+        let entries = ordered_keys
+            .iter()
+            .filter_map(|key| self.nodes.remove(key))
+            .map(TravlNode::into_entry)
+            .collect();
+        self.root_key = None;
+        entries
+    }
+
+    /// Returns the node associated to the given key, if it exists
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&TravlNode<K, V>> {
+        self.nodes.get(key)
+    }
+
+    /// Finds the node whose property matches the given one according to the search type, in O(log n)
+    ///
+    /// Among nodes whose properties are equal to the given one, the first one is returned, except when rounding
+    /// down, which returns the last node whose property isn't ordered after the given one.
+    #[must_use]
+    pub fn find(&self, prop: &P, search_type: SearchType) -> Option<&TravlNode<K, V>> {
+        let Self { nodes, prop_fn, ordering_fn, .. } = self;
+        let root = self.root();
+
+        match search_type {
+            SearchType::Equality => lower_bound(nodes, root, prop, prop_fn, ordering_fn)
+                .filter(|node| ordering_fn(prop_fn(node.value()), prop) == Ordering::Equal),
+            #[allow(deprecated)]
+            SearchType::Nearest => lower_bound(nodes, root, prop, prop_fn, ordering_fn)
+                .or_else(|| floor(nodes, root, prop, prop_fn, ordering_fn)),
+            SearchType::NearestToBottom => floor(nodes, root, prop, prop_fn, ordering_fn),
+            SearchType::NearestToTop => lower_bound(nodes, root, prop, prop_fn, ordering_fn),
+        }
+    }
+
+    /// Returns a mutable pointer to the node associate to the given key, if it exists
+    #[must_use]
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut TravlNode<K, V>> {
+        self.nodes.get_mut(key)
+    }
+
+    /// Returns the node at the given position in the order of the map, the first node being at position 0
+    ///
+    /// Every node knows the number of nodes of its subtree, so this runs in O(log n).
+    #[must_use]
+    pub fn select(&self, mut rank: usize) -> Option<&TravlNode<K, V>> {
+        let mut current = self.root();
+
+        while let Some(node) = current {
+            let left_size = self.nodes.left_of(node).map_or(0, TravlNode::size);
+            current = match rank.cmp(&left_size) {
+                Ordering::Less => self.nodes.left_of(node),
+                Ordering::Equal => return Some(node),
+                Ordering::Greater => {
+                    rank -= left_size + 1;
+                    self.nodes.right_of(node)
+                },
+            };
+        }
+
+        None
+    }
+
+    /// Returns the position of the node of the given key in the order of the map, or [`None`] if the key isn't
+    /// in the map
+    ///
+    /// The nodes ordered before it are counted on the path from the node to the root, in O(log n), without calling
+    /// the property getter nor the ordering function.
+    #[must_use]
+    pub fn rank(&self, key: &K) -> Option<usize>
+    where
+        K: Eq
+    {
+        let mut node = self.nodes.get(key)?;
+        let mut rank = self.nodes.left_of(node).map_or(0, TravlNode::size);
+
+        while let Some(parent) = self.nodes.parent_of(node) {
+            if parent.right() == Some(node.key()) {
+                rank += parent.size() - node.size();
+            }
+            node = parent;
+        }
+
+        Some(rank)
+    }
+
+    /// Inserts an entry, returning the value previously associated to its key
+    ///
+    /// The entry is placed among the entries whose properties are equal to its own following the map's
+    /// [tie policy](TiePolicy), and the tree is rebalanced along the path leading to it in O(log n).
+    /// When the key is already in the map, its value is replaced and its node is moved where the new value belongs,
+    /// as [`apply`](Self::apply) would.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V>
+    where
+        K: Eq + Clone
+    {
+        if self.nodes.contains_key(&key) {
+            let mut replaced = None;
+            self.apply(&key, |old| replaced = Some(core::mem::replace(old, value)));
+            return replaced;
+        }
+
+        let slot = self.slot_of(&value);
+        self.attach(TravlNode::new(key, value), slot);
+        None
+    }
+
+    /// Removes the entry of the given key, returning its value
+    ///
+    /// The tree is rebalanced along the path leading to the removed node in O(log n), without calling the
+    /// property getter nor the ordering function.
+    pub fn remove(&mut self, key: &K) -> Option<V>
+    where
+        K: Eq + Clone
+    {
+        self.detach(key).map(|node| node.into_entry().1)
+    }
+
+    /// Applies a closure to the value associated to the given key, moving its node where it now belongs in the
+    /// order of the map
+    ///
+    /// Returns whether the node was moved, or [`None`] if the key isn't in the map. A node still ordered between
+    /// its in-order neighbors stays in place, otherwise it is unlinked and linked again following the map's
+    /// [tie policy](TiePolicy), rebalancing the tree along both paths in O(log n).
+    pub fn apply<F>(&mut self, key: &K, f: F) -> Option<bool>
+    where
+        K: Eq + Clone,
+        F: FnOnce(&mut V)
+    {
+        let node = self.nodes.get(key)?;
+        let previous_key = predecessor(&self.nodes, node).map(|previous| previous.key().clone());
+        let next_key = successor(&self.nodes, node).map(|next| next.key().clone());
+
+        let was_poisoned = self.poisoned;
+        self.poisoned = true;
+        f(self.nodes.get_mut(key)?.value_mut());
+
+        let Self { nodes, prop_fn, ordering_fn, .. } = self;
+        let value = nodes.get(key)?.value();
+        let after_previous = previous_key
+            .and_then(|previous_key| nodes.get(&previous_key))
+            .is_none_or(|previous| ordering_fn(prop_fn(previous.value()), prop_fn(value)) != Ordering::Greater);
+        let before_next = next_key
+            .and_then(|next_key| nodes.get(&next_key))
+            .is_none_or(|next| ordering_fn(prop_fn(value), prop_fn(next.value())) != Ordering::Greater);
+
+        if after_previous && before_next {
+            self.poisoned = was_poisoned;
+            return Some(false);
+        }
+
+        let node = self.detach(key)?;
+        let slot = self.slot_of(node.value());
+        self.attach(node, slot);
+
+        self.poisoned = was_poisoned;
+        Some(true)
+    }
+
+    /// Consumes the map, returning its entries in a [`BTreeMap`], ordered by key
+    #[must_use]
+    pub fn into_btree_map(self) -> BTreeMap<K, V>
+    where
+        K: Ord
+    {
+        self.nodes.into_values().map(TravlNode::into_entry).collect()
+    }
+
+    /// Returns a snapshot of the map's configuration and entries, which can be persisted and rebuilt later
+    ///
+    /// Unlike [`TravlMapPersistent::snapshot`](crate::persistent::TravlMapPersistent::snapshot), this copies
+    /// every entry in O(n), since the nodes of this map are mutated in place and can't be shared.
+    #[must_use]
+    pub fn snapshot(&self) -> TravlMapSnapshot<K, V>
+    where
+        K: Clone,
+        V: Clone
+    {
+        TravlMapSnapshot {
+            config: self.config(),
+            entries: self.to_sorted_vec(),
+        }
+    }
+
+    /// Returns a copy of the map's entries packed in breadth-first order, for read-only binary searches
+    ///
+    /// See the [`layout`](crate::layout) module for details about the layout.
+    #[must_use]
+    pub fn to_bfs_layout(&self) -> BfsLayout<K, V>
+    where
+        K: Clone,
+        V: Clone
+    {
+        BfsLayout::from_sorted(self.config(), self.to_sorted_vec())
+    }
+
+    /// Returns a copy of the map's entries, in order
+    #[must_use]
+    pub fn to_sorted_vec(&self) -> Vec<(K, V)>
+    where
+        K: Clone,
+        V: Clone
+    {
+        self.iter().map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, P, S> TravlMap<'_, K, V, P, S>
+where
+    K: SnapshotCodec,
+    V: SnapshotCodec,
+    S: NodeStorage<K, V>
+{
+    /// Writes a compact binary snapshot of the map, see the [`snapshot`](crate::snapshot) module for its layout
+    ///
+    /// The imbalance factor isn't part of the snapshot. Wrapping the writer in a
+    /// [`BufWriter`](std::io::BufWriter) is recommended.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn write_snapshot<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write
+    {
+        write_header(&mut writer, self.len())?;
+
+        for (key, value) in self {
+            key.encode(&mut writer)?;
+            value.encode(&mut writer)?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, K, V, P> TravlMap<'a, K, V, P>
+where
+    K: Hash + Eq + Clone + SnapshotCodec,
+    V: SnapshotCodec
+{
+    /// Restores a map from a snapshot using a custom property getter and ordering function
+    ///
+    /// See [`read_snapshot`](TravlMap::read_snapshot) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, if the snapshot is invalid, or if its entries are out of order or
+    /// have duplicate keys.
+    pub fn read_snapshot_with_prop_getter_and_ordering<R>(
+        reader: R,
+        config: TravlMapConfig,
+        prop_fn: PropFn<'a, V, P>,
+        ordering_fn: OrdFn<'a, P>,
+    ) -> io::Result<Self>
+    where
+        R: Read
+    {
+        Self::new_with_prop_getter_and_ordering(prop_fn, ordering_fn).read_snapshot_into(reader, config)
+    }
+
+    /// Replaces the content of the map by the entries of the snapshot
+    fn read_snapshot_into<R>(self, mut reader: R, config: TravlMapConfig) -> io::Result<Self>
+    where
+        R: Read
+    {
+        let len = read_header(&mut reader)?;
+        let mut builder = SortedEntriesBuilder::new(self);
+
+        for _ in 0..len {
+            let key = K::decode(&mut reader)?;
+            let value = V::decode(&mut reader)?;
+            builder.push(key, value).map_err(|rejected| invalid_data(rejected.to_string()))?;
+        }
+
+        let mut map = builder.finish();
+        map.set_config(config);
+        Ok(map)
+    }
+}
+
+impl<K, V, P, S> TravlMap<'_, K, V, P, S>
+where
+    K: Debug,
+    V: Debug,
+    S: NodeStorage<K, V>
+{
+    /// Renders the tree using box-drawing characters, with the balance factor of each node
+    ///
+    /// See [`render_ascii`] for details about the rendering.
+    #[must_use]
+    pub fn render_ascii(&self) -> String {
+        render_ascii(&self.nodes, self.root(), self.config.effective_imbalance_factor())
+    }
+
+    /// Prints the rendering of [`render_ascii`](Self::render_ascii) to the standard output
+    #[cfg(feature = "std")]
+    pub fn print_tree(&self) {
+        std::print!("{}", self.render_ascii());
+    }
+}
+
+impl<K, V, P, S> TravlMap<'_, K, V, P, S>
+where
+    K: Display,
+    V: Display,
+    S: NodeStorage<K, V>
+{
+    /// Renders the tree as a nested JSON object, for tree visualization tools
+    ///
+    /// See [`render_json`] for details about the rendering.
+    #[must_use]
+    pub fn render_json(&self) -> String {
+        render_json(&self.nodes, self.root())
+    }
+}
+
+impl<'a, K, V, P> TravlMap<'a, K, V, P> {
+    /// Creates a map using a custom property getter and ordering function
+    #[must_use]
+    pub fn new_with_prop_getter_and_ordering(prop_fn: PropFn<'a, V, P>, ordering_fn: OrdFn<'a, P>) -> Self {
+        Self {
+            config: TravlMapConfig::default(),
+            root_key: None,
+            nodes: NodeMap::new(),
+            prop_fn,
+            ordering_fn,
+            poisoned: false,
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// Creates a map using a custom property getter and ordering function, given as closures or function
+    /// pointers
+    ///
+    /// See [`with_ordering`](TravlMap::with_ordering) about boxing.
+    #[must_use]
+    pub fn with_prop_getter_and_ordering<G, F>(prop_fn: G, ordering_fn: F) -> Self
+    where
+        G: Fn(&V) -> &P + Send + Sync + 'a,
+        F: Fn(&P, &P) -> Ordering + Send + Sync + 'a
+    {
+        Self::new_with_prop_getter_and_ordering(Box::new(prop_fn), Box::new(ordering_fn))
+    }
+
+    /// Bulk-builds a map out of the entries of a parallel iterator, using a custom property getter and ordering
+    /// function
+    ///
+    /// Entries are collected, sorted and linked into a balanced tree on the [`rayon`] thread pool, only their
+    /// final insertion into the node storage being sequential. When a key appears multiple times, only its last
+    /// entry is kept. Unlike the closures of maps, the property getter and ordering function are [`Fn`] since the
+    /// sort calls them concurrently.
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub fn from_par_iter_with_prop_getter_and_ordering<I, G, F>(entries: I, prop_fn: G, ordering_fn: F) -> Self
+    where
+        K: Hash + Eq + Clone + Send + Sync,
+        V: Send,
+        I: IntoParallelIterator<Item = (K, V)>,
+        G: Fn(&V) -> &P + Send + Sync + 'a,
+        F: Fn(&P, &P) -> Ordering + Send + Sync + 'a
+    {
+        let mut entries: Vec<(K, V)> = entries.into_par_iter().collect();
+        entries.par_sort_by(|(_, a), (_, b)| ordering_fn(prop_fn(a), prop_fn(b)));
+
+        let mut map = Self::with_prop_getter_and_ordering(prop_fn, ordering_fn);
+        map.par_build_balanced(entries);
+        map
+    }
+
+    /// Creates a map from unordered entries, building a balanced tree
+    ///
+    /// The entries are sorted using the given property getter and ordering function beforehand.
+    pub(crate) fn from_entries(
+        config: TravlMapConfig,
+        entries: Vec<(K, V)>,
+        prop_fn: PropFn<'a, V, P>,
+        ordering_fn: OrdFn<'a, P>,
+    ) -> Self
+    where
+        K: Clone,
+        NodeMap<K, V>: NodeStorage<K, V>
+    {
+        let mut map = Self::new_with_prop_getter_and_ordering(prop_fn, ordering_fn);
+        map.config = config;
+        map.rebuild_from(entries);
+        map
+    }
+
+    /// Creates a map from node records, linking them following their recorded structure
+    ///
+    /// The first record, if any, is the root of the tree, and the links of the records must be indices of
+    /// records. The linked tree is validated, returning the first violation found if the records don't form a
+    /// valid tree, or [`Violation::WrongSize`] if several records share a key.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_structure(
+        config: TravlMapConfig,
+        records: Vec<NodeRecord<K, V>>,
+        prop_fn: PropFn<'a, V, P>,
+        ordering_fn: OrdFn<'a, P>,
+    ) -> Result<Self, Violation<K>>
+    where
+        K: Hash + Eq + Clone
+    {
+        let mut map = Self::new_with_prop_getter_and_ordering(prop_fn, ordering_fn);
+        map.config = config;
+
+        let keys: Vec<K> = records.iter().map(|record| record.key().clone()).collect();
+        map.root_key = keys.first().cloned();
+        map.nodes.reserve(keys.len());
+
+        for record in records {
+            let (height, links) = (record.height(), [record.parent(), record.left(), record.right()]);
+            let mut node = record.into_node();
+            node.set_height(height);
+
+            let [parent, left, right] = links.map(|link| link.map(|index| keys[index].clone()));
+            if let Some(parent) = parent {
+                let _ = node.link_parent(parent);
+            }
+            let _ = node.link_children((left, right));
+
+            let _ = map.nodes.insert(node.key().clone(), node);
+        }
+
+        if map.len() != keys.len() {
+            return Err(Violation::WrongSize { key: None, size: keys.len(), expected: map.len() });
+        }
+
+        // Records are in pre-order, so the children of a node come after it
+        for key in keys.iter().rev() {
+            let (left_size, right_size) = map.children_sizes(key);
+            if let Some(node) = map.nodes.get_mut(key) {
+                node.set_size(left_size.saturating_add(right_size).saturating_add(1));
+                node.set_rank(node.height());
+            }
+        }
+        if map.config.balancing() == Balancing::Rank {
+            map.assign_ranks(&keys);
+        }
+
+        match map.validate().into_iter().next() {
+            Some(violation) => Err(violation),
+            None => Ok(map),
+        }
+    }
+
+    /// Returns a wrapper serializing the map using the given format
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn with_serde_format(&self, format: SerdeFormat) -> FormattedMap<'_, 'a, K, V, P> {
+        FormattedMap::new(self, format)
+    }
+}
+
+impl<'a, K, V, P, S> TravlMap<'a, K, V, P, S>
+where
+    S: NodeStorage<K, V>
+{
+    /// Sorts unordered entries using the map's property getter and ordering function and builds a balanced tree
+    /// out of them, replacing the current tree
+    ///
+    /// The sort is stable, entries with equal properties keep their relative order.
+    pub(crate) fn rebuild_from(&mut self, mut entries: Vec<(K, V)>)
+    where
+        K: Clone
+    {
+        self.sort_entries(&mut entries);
+        self.build_balanced(entries);
+    }
+
+    /// Sorts unordered entries and builds a balanced tree out of them, replacing the current tree, keeping the
+    /// node of the given key as the root if possible, see [`build_balanced_around`](Self::build_balanced_around)
+    #[cfg(feature = "serde")]
+    pub(crate) fn rebuild_around(&mut self, mut entries: Vec<(K, V)>, root: Option<&K>)
+    where
+        K: Eq + Clone
+    {
+        self.sort_entries(&mut entries);
+        self.build_balanced_around(entries, root);
+    }
+
+    /// Compares two values following the map's property getter and ordering function
+    pub(crate) fn compare_values(&self, a: &V, b: &V) -> Ordering {
+        (self.ordering_fn)((self.prop_fn)(a), (self.prop_fn)(b))
+    }
+
+    /// Compares the property of a value to the given one following the map's ordering function
+    pub(crate) fn compare_to_prop(&self, value: &V, prop: &P) -> Ordering {
+        (self.ordering_fn)((self.prop_fn)(value), prop)
+    }
+
+    /// Stably sorts entries using the map's property getter and ordering function, poisoning the map until the
+    /// tree is rebuilt
+    fn sort_entries(&mut self, entries: &mut [(K, V)]) {
+        self.poisoned = true;
+        let prop_fn = &mut self.prop_fn;
+        let ordering_fn = &mut self.ordering_fn;
+        entries.sort_by(|(_, a), (_, b)| ordering_fn(prop_fn(a), prop_fn(b)));
+    }
+
+    /// Merges runs of entries, each sorted according to the map's ordering, into a balanced tree
+    /// replacing the current tree
+    ///
+    /// Runs are merged pairwise, in O(n log r) for r runs. Entries with equal properties keep the
+    /// order of their runs. Runs that turn out not to be sorted are sorted beforehand.
+    #[cfg(feature = "std")]
+    pub(crate) fn rebuild_from_runs(&mut self, mut runs: Vec<Vec<(K, V)>>)
+    where
+        K: Clone
+    {
+        self.poisoned = true;
+        let prop_fn = &mut self.prop_fn;
+        let ordering_fn = &mut self.ordering_fn;
+        let mut compare = |(_, a): &(K, V), (_, b): &(K, V)| ordering_fn(prop_fn(a), prop_fn(b));
+
+        for run in &mut runs {
+            if !run.is_sorted_by(|a, b| compare(a, b) != Ordering::Greater) {
+                run.sort_by(&mut compare);
+            }
+        }
+
+        while runs.len() > 1 {
+            let mut pairs = runs.into_iter();
+            runs = Vec::with_capacity(pairs.len().div_ceil(2));
+
+            while let Some(first) = pairs.next() {
+                runs.push(match pairs.next() {
+                    Some(second) => merge_runs(first, second, &mut compare),
+                    None => first,
+                });
+            }
+        }
+
+        self.build_balanced(runs.pop().unwrap_or_default());
+    }
+
+    /// Builds a balanced tree out of entries sorted according to the map's ordering, replacing the current tree
+    ///
+    /// When a key appears multiple times, only its last entry is kept.
+    pub(crate) fn build_balanced<I>(&mut self, entries: I)
+    where
+        K: Clone,
+        I: IntoIterator<Item = (K, V)>
+    {
+        let keys = self.store_entries(entries);
+        self.link_balanced(&keys, None);
+    }
+
+    /// Builds a balanced tree out of entries sorted according to the map's ordering, replacing the current tree,
+    /// with the node of the given key as its root
+    ///
+    /// The entries on either side of the root are linked into subtrees of minimal height. When the root isn't
+    /// among the entries, or when such subtrees would be too far apart in height for the map's imbalance factor,
+    /// the tree is built as [`build_balanced`](Self::build_balanced) would.
+    #[cfg(feature = "serde")]
+    pub(crate) fn build_balanced_around<I>(&mut self, entries: I, root: Option<&K>)
+    where
+        K: Eq + Clone,
+        I: IntoIterator<Item = (K, V)>
+    {
+        let keys = self.store_entries(entries);
+        let root = root.and_then(|root| self.root_position(&keys, root));
+        self.link_balanced(&keys, root);
+    }
+
+    /// Gives valid ranks to the nodes of the given keys, in pre-order, if there are any, so that a tree deserialized
+    /// using the [structural format](SerdeFormat::Structural) can be rank-balanced
+    ///
+    /// The ranks a subtree's root can take form a range, computed out of the ranges of its children, from the
+    /// leaves up. Ranks are then picked from the root down. Ranks are left as they are if the tree can't be
+    /// rank-balanced, which validation reports.
+    #[cfg(feature = "serde")]
+    fn assign_ranks(&mut self, keys: &[K])
+    where
+        K: Hash + Eq + Clone
+    {
+        // Ranges of ranks plus one, missing children having rank -1
+        let mut ranges: HashMap<K, (u64, u64)> = HashMap::with_capacity(keys.len());
+        let range_of = |ranges: &HashMap<K, (u64, u64)>, child: Option<&K>| {
+            child.map_or(Some((0, 0)), |child| ranges.get(child).copied())
+        };
+
+        for key in keys.iter().rev() {
+            let Some(node) = self.nodes.get(key) else {
+                return;
+            };
+            let (Some(left), Some(right)) = (range_of(&ranges, node.left()), range_of(&ranges, node.right())) else {
+                return;
+            };
+
+            let is_leaf = node.left().is_none() && node.right().is_none();
+            let range = if is_leaf { (1, 1) } else { (left.0.max(right.0) + 1, left.1.min(right.1) + 2) };
+            if range.0 > range.1 {
+                return;
+            }
+            let _ = ranges.insert(key.clone(), range);
+        }
+
+        for key in keys {
+            let Some(node) = self.nodes.get(key) else {
+                return;
+            };
+            let (low, _) = ranges[key];
+            let rank_levels = match node.parent() {
+                None => low,
+                Some(parent) => low.max(self.nodes.get(parent).map_or(0, |parent| parent.rank() + 1).saturating_sub(2)),
+            };
+
+            if let Some(node) = self.nodes.get_mut(key) {
+                node.set_rank(rank_levels - 1);
+            }
+        }
+    }
+
+    /// Returns the position of the given key among the given sorted keys if the subtrees on either side of it,
+    /// once linked by [`link_balanced`](Self::link_balanced), would respect the map's imbalance factor, or its
+    /// weight ratio for weight-balanced maps
+    #[cfg(feature = "serde")]
+    pub(crate) fn root_position(&self, keys: &[K], root: &K) -> Option<usize>
+    where
+        K: Eq
+    {
+        let position = keys.iter().position(|key| key == root)?;
+        let (left_size, right_size) = (position, keys.len() - position - 1);
+        let balanced = match self.config.balancing() {
+            Balancing::Weight => weights_balanced(left_size, right_size),
+            _ => levels(left_size).abs_diff(levels(right_size)) <= self.config.imbalance_factor().saturating_add(1),
+        };
+        balanced.then_some(position)
+    }
+
+    /// Stores the given sorted entries as unlinked nodes, replacing the current tree, and returns their keys in
+    /// order
+    ///
+    /// When a key appears multiple times, only its last entry is kept.
+    fn store_entries<I>(&mut self, entries: I) -> Vec<K>
+    where
+        K: Clone,
+        I: IntoIterator<Item = (K, V)>
+    {
+        self.root_key = None;
+        self.nodes.clear();
+        // Linking the entries doesn't call the property getter nor the ordering function
+        self.poisoned = false;
+
+        let entries: Vec<(K, V)> = entries.into_iter().collect();
+        self.nodes.reserve(entries.len());
+
+        // Storing the entries from the last one keeps the last entry of each key without requiring more of the
+        // keys than the storage does, the positions of the stored entries being collected separately
+        let mut keys = Vec::with_capacity(entries.len());
+        for (key, value) in entries.into_iter().rev() {
+            if !self.nodes.contains_key(&key) {
+                keys.push(key.clone());
+                let _ = self.nodes.insert(key.clone(), TravlNode::new(key, value));
+            }
+        }
+        keys.reverse();
+        keys
+    }
+
+    /// Builds a balanced tree out of entries sorted according to the map's ordering on the [`rayon`] thread pool,
+    /// replacing the current tree
+    ///
+    /// The tree has the same shape as one built by [`build_balanced`](Self::build_balanced), and when a key
+    /// appears multiple times, only its last entry is kept as well.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn par_build_balanced(&mut self, entries: Vec<(K, V)>)
+    where
+        K: Hash + Eq + Clone + Send + Sync,
+        V: Send
+    {
+        self.root_key = None;
+        self.nodes.clear();
+        self.poisoned = false;
+
+        // Keeping the last entry of each key, as `build_balanced` does
+        let mut seen = HashSet::with_capacity(entries.len());
+        let is_last: Vec<bool> = entries.iter().rev().map(|(key, _)| seen.insert(key)).collect();
+        let has_duplicates = seen.len() < entries.len();
+        drop(seen);
+
+        let entries: Vec<(K, V)> = if has_duplicates {
+            let is_last = is_last.into_iter().rev();
+            entries.into_iter().zip(is_last).filter_map(|(entry, kept)| kept.then_some(entry)).collect()
+        } else {
+            entries
+        };
+
+        let mut nodes: Vec<TravlNode<K, V>> =
+            entries.into_par_iter().map(|(key, value)| TravlNode::new(key, value)).collect();
+        par_link_balanced(&mut nodes, None);
+
+        self.root_key = nodes.get(nodes.len() / 2).map(|node| node.key().clone());
+        self.nodes.reserve(nodes.len());
+        for node in nodes {
+            let _ = self.nodes.insert(node.key().clone(), node);
+        }
+    }
+
+    /// Links the stored nodes of the given sorted keys into a tree of minimal height, in O(n)
+    ///
+    /// The middle key of each range becomes the root of the subtree of that range, except for the whole range
+    /// when the position of its root is given. Ranges are kept on a stack rather than linked recursively, which
+    /// only ever holds O(log n) ranges.
+    fn link_balanced(&mut self, keys: &[K], root: Option<usize>)
+    where
+        K: Clone
+    {
+        self.link_balanced_below(keys, root, None);
+    }
+
+    /// Links the stored nodes of the given sorted keys into a subtree of minimal height, as
+    /// [`link_balanced`](Self::link_balanced) does, under the given parent rather than as the whole tree
+    ///
+    /// The parent is given as its key and whether the subtree is its left subtree. The nodes may have been linked
+    /// before, their former links being replaced.
+    fn link_balanced_below(&mut self, keys: &[K], root: Option<usize>, above: Option<(&K, bool)>)
+    where
+        K: Clone
+    {
+        // Ranges of keys left to link, with the position of the key of their parent and whether they are its left
+        // subtree
+        let mut ranges: Vec<(usize, usize, Option<usize>, bool)> = vec![(0, keys.len(), None, false)];
+
+        while let Some((start, end, parent, is_left)) = ranges.pop() {
+            if start == end {
+                continue;
+            }
+
+            let middle = match (parent, root) {
+                (None, Some(root)) => root,
+                _ => start + (end - start) / 2,
+            };
+            let key = &keys[middle];
+
+            let (parent, is_left) = match parent {
+                Some(parent) => (Some(keys[parent].clone()), is_left),
+                None => above.map_or((None, false), |(parent, is_left)| (Some(parent.clone()), is_left)),
+            };
+
+            if let Some(node) = self.nodes.get_mut(key) {
+                let height = levels(middle - start).max(levels(end - middle - 1));
+                node.set_height(height);
+                node.set_rank(height);
+                node.set_size(end - start);
+                let _ = node.unlink_children();
+                let _ = match parent.clone() {
+                    None => node.unlink_parent(),
+                    Some(parent) => node.link_parent(parent),
+                };
+            }
+
+            match parent {
+                None => self.root_key = Some(key.clone()),
+                Some(parent) => {
+                    if let Some(parent_node) = self.nodes.get_mut(&parent) {
+                        let child = key.clone();
+                        let _ = if is_left { parent_node.link_left(child) } else { parent_node.link_right(child) };
+                    }
+                },
+            }
+
+            ranges.push((middle + 1, end, Some(middle), false));
+            ranges.push((start, middle, Some(middle), true));
+        }
+    }
+
+    /// Returns where a node holding the given value would be linked, as the key of its parent and whether it
+    /// would be its left child, or [`None`] if the tree is empty
+    ///
+    /// The node is placed among the nodes whose properties are equal to its own following the map's
+    /// [tie policy](TiePolicy). Only the property getter and ordering function are called, the tree isn't changed.
+    fn slot_of(&mut self, value: &V) -> Option<(K, bool)>
+    where
+        K: Clone
+    {
+        let Self { root_key, nodes, prop_fn, ordering_fn, config, .. } = self;
+        let mut slot = None;
+        let mut current = root_key.as_ref().and_then(|key| nodes.get(key));
+
+        while let Some(node) = current {
+            let is_left = match ordering_fn(prop_fn(value), prop_fn(node.value())) {
+                Ordering::Less => true,
+                Ordering::Equal => config.tie_policy() == TiePolicy::Before,
+                Ordering::Greater => false,
+            };
+            slot = Some((node.key(), is_left));
+            current = if is_left { nodes.left_of(node) } else { nodes.right_of(node) };
+        }
+
+        slot.map(|(key, is_left)| (key.clone(), is_left))
+    }
+
+    /// Inserts an entry right after or right before the node of the given key in the order of the map, see
+    /// [`CursorMut::insert_after`] and [`CursorMut::insert_before`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map doesn't contain the given key.
+    pub(crate) fn insert_next_to(
+        &mut self,
+        anchor: &K,
+        key: K,
+        value: V,
+        after: bool,
+    ) -> Result<(), RejectedEntry<K, V>>
+    where
+        K: Eq + Clone
+    {
+        if self.nodes.contains_key(&key) {
+            return Err(RejectedEntry { key, value, reason: RejectionReason::DuplicateKey });
+        }
+
+        let Self { nodes, prop_fn, ordering_fn, .. } = self;
+        let anchor_node = nodes.get(anchor).expect("the anchor should be a node of the map");
+        let neighbor = if after { successor(nodes, anchor_node) } else { predecessor(nodes, anchor_node) };
+        let (first, last) = if after { (Some(anchor_node), neighbor) } else { (neighbor, Some(anchor_node)) };
+
+        let in_order = first
+            .is_none_or(|first| ordering_fn(prop_fn(first.value()), prop_fn(&value)) != Ordering::Greater)
+            && last.is_none_or(|last| ordering_fn(prop_fn(&value), prop_fn(last.value())) != Ordering::Greater);
+        if !in_order {
+            return Err(RejectedEntry { key, value, reason: RejectionReason::OutOfOrder });
+        }
+
+        // The entry becomes the leftmost node of the right subtree of the anchor, or the rightmost node of its left
+        // subtree, which is the child slot right next to the anchor in the order of the map
+        let slot = match (after, nodes.right_of(anchor_node), nodes.left_of(anchor_node)) {
+            (true, None, _) => (anchor.clone(), false),
+            (true, Some(right), _) => (leftmost(nodes, right).key().clone(), true),
+            (false, _, None) => (anchor.clone(), true),
+            (false, _, Some(left)) => (rightmost(nodes, left).key().clone(), false),
+        };
+
+        self.attach(TravlNode::new(key, value), Some(slot));
+        Ok(())
+    }
+
+    /// Stores an unlinked node and links it at the given slot, see [`slot_of`](Self::slot_of), rebalancing the
+    /// tree along the path from its parent to the root
+    fn attach(&mut self, mut node: TravlNode<K, V>, slot: Option<(K, bool)>)
+    where
+        K: Eq + Clone
+    {
+        let key = node.key().clone();
+
+        match slot {
+            None => self.root_key = Some(key.clone()),
+            Some((parent, is_left)) => {
+                if let Some(parent_node) = self.nodes.get_mut(&parent) {
+                    let child = key.clone();
+                    let _ = if is_left { parent_node.link_left(child) } else { parent_node.link_right(child) };
+                }
+                let _ = node.link_parent(parent);
+            },
+        }
+
+        let parent = node.parent().cloned();
+        let _ = self.nodes.insert(key, node);
+        self.rebalance_from(parent);
+    }
+
+    /// Unlinks the node of the given key and takes it out of the storage, rebalancing the tree along the path
+    /// from the lowest node whose children changed to the root
+    ///
+    /// The returned node is unlinked, as if it was just created.
+    fn detach(&mut self, key: &K) -> Option<TravlNode<K, V>>
+    where
+        K: Eq + Clone
+    {
+        let mut node = self.nodes.remove(key)?;
+        let parent = node.unlink_parent();
+        let (replacement, lowest) = match node.unlink_children() {
+            (None, child) | (child, None) => (child, parent.clone()),
+            (Some(left), Some(right)) => {
+                // The in-order successor of the node, the leftmost node of its right subtree, takes its place
+                let mut successor = right.clone();
+                while let Some(left_key) = self.nodes.get(&successor).and_then(|node| node.left().cloned()) {
+                    successor = left_key;
+                }
+
+                let lowest = if successor == right {
+                    successor.clone()
+                } else {
+                    let successor_node = self.nodes.get_mut(&successor)?;
+                    let successor_parent = successor_node.unlink_parent()?;
+                    let successor_right = successor_node.link_right(right.clone());
+                    self.relink_parent(&right, successor.clone());
+                    if let Some(successor_right) = &successor_right {
+                        self.relink_parent(successor_right, successor_parent.clone());
+                    }
+                    self.replace_child(&successor_parent, &successor, successor_right);
+                    successor_parent
+                };
+
+                let successor_node = self.nodes.get_mut(&successor)?;
+                let _ = successor_node.link_left(left.clone());
+                // The successor takes the height of the node it replaces, so that rebalancing compares the new
+                // height of the subtree with its height before the removal
+                successor_node.set_height(node.height());
+                successor_node.set_size(node.size());
+                successor_node.set_rank(node.rank());
+                self.relink_parent(&left, successor.clone());
+
+                (Some(successor), Some(lowest))
+            },
+        };
+
+        match &parent {
+            None => self.root_key.clone_from(&replacement),
+            Some(parent) => self.replace_child(parent, key, replacement.clone()),
+        }
+        if let Some(replacement) = &replacement
+            && let Some(replacement_node) = self.nodes.get_mut(replacement)
+        {
+            let _ = match parent {
+                None => replacement_node.unlink_parent(),
+                Some(parent) => replacement_node.link_parent(parent),
+            };
+        }
+
+        node.set_height(0);
+        node.set_size(1);
+        node.set_rank(0);
+        self.rebalance_from(lowest);
+        Some(node)
+    }
+
+    /// Links the node of the given key to a new parent
+    fn relink_parent(&mut self, key: &K, parent: K) {
+        if let Some(node) = self.nodes.get_mut(key) {
+            let _ = node.link_parent(parent);
+        }
+    }
+
+    /// Replaces the given child of a node by another child, or by no child
+    fn replace_child(&mut self, parent: &K, child: &K, replacement: Option<K>)
+    where
+        K: Eq
+    {
+        let Some(parent_node) = self.nodes.get_mut(parent) else {
+            return;
+        };
+
+        let _ = match (parent_node.left() == Some(child), replacement) {
+            (true, Some(replacement)) => parent_node.link_left(replacement),
+            (true, None) => parent_node.unlink_left(),
+            (false, Some(replacement)) => parent_node.link_right(replacement),
+            (false, None) => parent_node.unlink_right(),
+        };
+    }
+
+    /// Returns the number of levels of the left and right subtrees of the node of the given key
+    fn children_levels(&self, key: &K) -> (u64, u64) {
+        let levels = |node: Option<&TravlNode<K, V>>| node.map_or(0, |node| node.height() + 1);
+
+        self.nodes
+            .get(key)
+            .map_or((0, 0), |node| (levels(self.nodes.left_of(node)), levels(self.nodes.right_of(node))))
+    }
+
+    /// Returns the number of nodes of the left and right subtrees of the node of the given key
+    fn children_sizes(&self, key: &K) -> (usize, usize) {
+        let sizes = |node: Option<&TravlNode<K, V>>| node.map_or(0, TravlNode::size);
+
+        self.nodes
+            .get(key)
+            .map_or((0, 0), |node| (sizes(self.nodes.left_of(node)), sizes(self.nodes.right_of(node))))
+    }
+
+    /// Recomputes the height and size of the node of the given key out of the ones of its children
+    fn update_node(&mut self, key: &K) {
+        let (left_levels, right_levels) = self.children_levels(key);
+        let (left_size, right_size) = self.children_sizes(key);
+
+        if let Some(node) = self.nodes.get_mut(key) {
+            node.set_height(left_levels.max(right_levels));
+            node.set_size(left_size.saturating_add(right_size).saturating_add(1));
+            self.metrics.record_height_recomputation();
+        }
+    }
+
+    /// Recomputes the size of the node of the given key out of the ones of its children
+    fn update_size(&mut self, key: &K) {
+        let (left_size, right_size) = self.children_sizes(key);
+
+        if let Some(node) = self.nodes.get_mut(key) {
+            node.set_size(left_size.saturating_add(right_size).saturating_add(1));
+        }
+    }
+
+    /// Fixes the heights and balances the imbalanced nodes on the path from the given node to the root, following
+    /// the map's [balancing strategy](Balancing)
+    ///
+    /// Heights are fixed in a single pass over the path, which stops balancing as soon as a subtree keeps the
+    /// height it had before the change, since neither the heights nor the balance of the nodes above it changed.
+    /// Only the sizes of the nodes above it are fixed, up to the root, while weight-balanced maps keep balancing
+    /// up to the root.
+    fn rebalance_from(&mut self, mut current: Option<K>)
+    where
+        K: Eq + Clone
+    {
+        let balancing = self.config.balancing();
+        if balancing == Balancing::Rank {
+            self.rebalance_ranks_from(current);
+            return;
+        }
+
+        while let Some(key) = current.take() {
+            let Some(node) = self.nodes.get(&key) else {
+                break;
+            };
+            let height = node.height();
+            current = node.parent().cloned();
+
+            let subtree = match balancing {
+                Balancing::Strict | Balancing::Relaxed(_) => self.balance(key),
+                Balancing::Scapegoat => self.rebuild_if_imbalanced(key),
+                Balancing::Weight => self.balance_weights(key),
+                Balancing::Rank => key,
+            };
+            if balancing != Balancing::Weight && self.nodes.get(&subtree).map(TravlNode::height) == Some(height) {
+                break;
+            }
+        }
+
+        self.update_sizes(current);
+    }
+
+    /// Recomputes the sizes of the nodes on the path from the given node to the root
+    fn update_sizes(&mut self, mut current: Option<K>)
+    where
+        K: Clone
+    {
+        while let Some(key) = current {
+            self.update_size(&key);
+            current = self.nodes.get(&key).and_then(|node| node.parent().cloned());
+        }
+    }
+
+    /// Recomputes the heights and sizes of the nodes on the path from the given node to the root
+    fn update_path(&mut self, mut current: Option<K>)
+    where
+        K: Clone
+    {
+        while let Some(key) = current {
+            self.update_node(&key);
+            current = self.nodes.get(&key).and_then(|node| node.parent().cloned());
+        }
+    }
+
+    /// Fixes the ranks of the nodes on the path from the given node to the root of a
+    /// [rank-balanced](Balancing::Rank) tree, and then their heights and sizes
+    ///
+    /// Going up the path, a child an insertion raised to the rank of its parent promotes the parent, and a child a
+    /// removal lowered three ranks below its parent demotes the parent, until a single or double rotation fixes
+    /// the ranks for good or no rank needs fixing anymore.
+    fn rebalance_ranks_from(&mut self, mut current: Option<K>)
+    where
+        K: Eq + Clone
+    {
+        // Rotations recompute the heights and sizes of the nodes they move out of the ones of their children
+        self.update_path(current.clone());
+
+        while let Some(key) = current.take() {
+            let Some(node) = self.nodes.get(&key) else {
+                break;
+            };
+            let rank_levels = node.rank() + 1;
+            let parent = node.parent().cloned();
+            let children = (node.left().cloned(), node.right().cloned());
+            let (left_levels, right_levels) = self.children_rank_levels(&key);
+            let differences = (rank_levels.saturating_sub(left_levels), rank_levels.saturating_sub(right_levels));
+
+            if children == (None, None) && rank_levels > 1 {
+                // A removal left a leaf of rank 1
+                self.change_rank(&key, false, 1);
+                current = parent;
+            } else if differences.0 == 0 || differences.1 == 0 {
+                // An insertion raised a child to the rank of its parent
+                let is_left = differences.0 == 0;
+                if (if is_left { differences.1 } else { differences.0 }) == 1 {
+                    self.change_rank(&key, true, 1);
+                    current = parent;
+                    continue;
+                }
+
+                let child = if is_left { children.0 } else { children.1 };
+                let child = child.expect("a child of the rank of its parent should exist");
+                let (child_left, child_right) = self.children_rank_levels(&child);
+                let inner_difference = rank_levels.saturating_sub(if is_left { child_right } else { child_left });
+
+                if inner_difference == 2 {
+                    let _ = self.rotate(&key, is_left);
+                    self.change_rank(&key, false, 1);
+                } else {
+                    let inner =
+                        self.nodes.get(&child).and_then(|node| if is_left { node.right() } else { node.left() });
+                    let inner = inner.cloned().expect("a child one rank below its parent should exist");
+                    let _ = self.rotate(&child, !is_left);
+                    let _ = self.rotate(&key, is_left);
+                    self.change_rank(&inner, true, 1);
+                    self.change_rank(&child, false, 1);
+                    self.change_rank(&key, false, 1);
+                }
+
+                self.update_path(parent);
+                break;
+            } else if differences.0 == 3 || differences.1 == 3 {
+                // A removal lowered a child three ranks below its parent
+                let is_left = differences.0 == 3;
+                let sibling = if is_left { children.1 } else { children.0 };
+                let sibling = sibling.expect("the sibling of a child three ranks below its parent should exist");
+                if (if is_left { differences.1 } else { differences.0 }) == 2 {
+                    self.change_rank(&key, false, 1);
+                    current = parent;
+                    continue;
+                }
+
+                let sibling_levels = rank_levels - 1;
+                let (sibling_left, sibling_right) = self.children_rank_levels(&sibling);
+                let (outer, inner) =
+                    if is_left { (sibling_right, sibling_left) } else { (sibling_left, sibling_right) };
+                let (outer_difference, inner_difference) = (sibling_levels - outer, sibling_levels - inner);
+
+                if outer_difference == 2 && inner_difference == 2 {
+                    self.change_rank(&key, false, 1);
+                    self.change_rank(&sibling, false, 1);
+                    current = parent;
+                    continue;
+                }
+
+                if outer_difference == 1 {
+                    let _ = self.rotate(&key, !is_left);
+                    self.change_rank(&sibling, true, 1);
+                    let is_leaf =
+                        self.nodes.get(&key).is_some_and(|node| node.left().is_none() && node.right().is_none());
+                    self.change_rank(&key, false, if is_leaf { 2 } else { 1 });
+                } else {
+                    let inner =
+                        self.nodes.get(&sibling).and_then(|node| if is_left { node.left() } else { node.right() });
+                    let inner = inner.cloned().expect("a child one rank below its parent should exist");
+                    let _ = self.rotate(&sibling, is_left);
+                    let _ = self.rotate(&key, !is_left);
+                    self.change_rank(&inner, true, 2);
+                    self.change_rank(&sibling, false, 1);
+                    self.change_rank(&key, false, 2);
+                }
+
+                self.update_path(parent);
+                break;
+            }
+        }
+    }
+
+    /// Returns the ranks plus one of the left and right children of the node of the given key, missing children
+    /// having rank -1
+    fn children_rank_levels(&self, key: &K) -> (u64, u64) {
+        let levels = |node: Option<&TravlNode<K, V>>| node.map_or(0, |node| node.rank() + 1);
+
+        self.nodes
+            .get(key)
+            .map_or((0, 0), |node| (levels(self.nodes.left_of(node)), levels(self.nodes.right_of(node))))
+    }
+
+    /// Promotes or demotes the node of the given key by the given number of ranks
+    fn change_rank(&mut self, key: &K, promote: bool, ranks: u64) {
+        if let Some(node) = self.nodes.get_mut(key) {
+            let rank = if promote { node.rank() + ranks } else { node.rank().saturating_sub(ranks) };
+            node.set_rank(rank);
+        }
+    }
+
+    /// Rotates the subtree of the node of the given key if the heights of its children differ too much,
+    /// returning the key of the root of the subtree, and fixes the heights of the nodes it moved
+    ///
+    /// The children must be balanced, and their heights must differ by at most one more than allowed, which is
+    /// the case after a single insertion or removal. Nodes moved down by a rotation are balanced again, which
+    /// larger imbalance factors can require.
+    fn balance(&mut self, key: K) -> K
+    where
+        K: Eq + Clone
+    {
+        let max_difference = self.config.effective_imbalance_factor().saturating_add(1);
+        let (left_levels, right_levels) = self.children_levels(&key);
+        let exceeds = |higher: u64, lower: u64| higher.checked_sub(lower).is_some_and(|diff| diff > max_difference);
+
+        let child = if exceeds(left_levels, right_levels) {
+            self.nodes.get(&key).and_then(|node| node.left().cloned()).map(|left| (left, true))
+        } else if exceeds(right_levels, left_levels) {
+            self.nodes.get(&key).and_then(|node| node.right().cloned()).map(|right| (right, false))
+        } else {
+            None
+        };
+
+        let Some((child, is_left)) = child else {
+            self.update_node(&key);
+            return key;
+        };
+
+        let (child_left, child_right) = self.children_levels(&child);
+        let (outer, inner) = if is_left { (child_left, child_right) } else { (child_right, child_left) };
+
+        if outer >= inner {
+            let root = self.rotate(&key, is_left);
+            let _ = self.balance(key);
+            self.update_node(&root);
+            return root;
+        }
+
+        let _ = self.rotate(&child, !is_left);
+        let root = self.rotate(&key, is_left);
+        let _ = self.balance(child);
+        let _ = self.balance(key);
+        self.update_node(&root);
+        root
+    }
+
+    /// Rotates the subtree of the node of the given key if one of its children weighs too much more than the other,
+    /// returning the key of the root of the subtree, and fixes the heights and sizes of the nodes it moved
+    ///
+    /// The children must be balanced, and the subtree at most one insertion or removal away from balanced, in
+    /// which case a single or double rotation always balances it, see [`WEIGHT_RATIO`].
+    fn balance_weights(&mut self, key: K) -> K
+    where
+        K: Eq + Clone
+    {
+        let (left_size, right_size) = self.children_sizes(&key);
+        let is_left = if weights_balanced(left_size, right_size) { None } else { Some(left_size > right_size) };
+
+        let Some((child, is_left)) = is_left.and_then(|is_left| {
+            let node = self.nodes.get(&key)?;
+            let child = if is_left { node.left() } else { node.right() };
+            child.cloned().map(|child| (child, is_left))
+        }) else {
+            self.update_node(&key);
+            return key;
+        };
+
+        let (child_left, child_right) = self.children_sizes(&child);
+        let (outer, inner) = if is_left { (child_left, child_right) } else { (child_right, child_left) };
+
+        if inner + 1 < WEIGHT_ROTATION_RATIO.saturating_mul(outer + 1) {
+            return self.rotate(&key, is_left);
+        }
+
+        let _ = self.rotate(&child, !is_left);
+        self.rotate(&key, is_left)
+    }
+
+    /// Rebuilds the subtree of the node of the given key at minimal height if the heights of its children differ
+    /// too much, returning the key of the root of the subtree, and fixes the height of the node otherwise
+    fn rebuild_if_imbalanced(&mut self, key: K) -> K
+    where
+        K: Eq + Clone
+    {
+        let (left_levels, right_levels) = self.children_levels(&key);
+        if left_levels.abs_diff(right_levels) <= self.config.imbalance_factor().saturating_add(1) {
+            self.update_node(&key);
+            return key;
+        }
+
+        let Some(node) = self.nodes.get(&key) else {
+            return key;
+        };
+        let above = node
+            .parent()
+            .map(|parent| (parent.clone(), self.nodes.get(parent).is_some_and(|parent| parent.left() == Some(&key))));
+        let keys: Vec<K> = InOrderIter::new(&self.nodes, Some(node)).map(|node| node.key().clone()).collect();
+
+        self.link_balanced_below(&keys, None, above.as_ref().map(|(parent, is_left)| (parent, *is_left)));
+        keys[keys.len() / 2].clone()
+    }
+
+    /// Rotates the subtree of the node of the given key, to the right if its left child is to take its place and
+    /// to the left otherwise, returning the key of that child
+    fn rotate(&mut self, key: &K, to_right: bool) -> K
+    where
+        K: Eq + Clone
+    {
+        let node = self.nodes.get_mut(key).expect("a rotated node should be stored");
+        let pivot = if to_right { node.unlink_left() } else { node.unlink_right() };
+        let pivot = pivot.expect("a rotated node should have a child taking its place");
+        let parent = node.link_parent(pivot.clone());
+
+        // The inner subtree of the pivot moves under the rotated node
+        let pivot_node = self.nodes.get_mut(&pivot).expect("a linked node should be stored");
+        let inner = if to_right { pivot_node.link_right(key.clone()) } else { pivot_node.link_left(key.clone()) };
+        let _ = match parent.clone() {
+            None => pivot_node.unlink_parent(),
+            Some(parent) => pivot_node.link_parent(parent),
+        };
+
+        if let Some(inner) = inner {
+            self.relink_parent(&inner, key.clone());
+            if let Some(node) = self.nodes.get_mut(key) {
+                let _ = if to_right { node.link_left(inner) } else { node.link_right(inner) };
+            }
+        }
+
+        match parent {
+            None => self.root_key = Some(pivot.clone()),
+            Some(parent) => self.replace_child(&parent, key, Some(pivot.clone())),
+        }
+
+        self.update_node(key);
+        self.update_node(&pivot);
+        pivot
+    }
+
+    /// Sets the configuration of a map whose tree was built without it
+    #[cfg(feature = "std")]
+    pub(crate) fn set_config(&mut self, config: TravlMapConfig) {
+        self.config = config;
+    }
+
+    /// Returns the imbalance factor
+    #[must_use]
+    pub fn imbalance_factor(&self) -> u64 {
+        self.config.imbalance_factor()
+    }
+
+    /// Returns the tie policy
+    #[must_use]
+    pub fn tie_policy(&self) -> TiePolicy {
+        self.config.tie_policy()
+    }
+
+    /// Returns the balancing strategy
+    #[must_use]
+    pub fn balancing(&self) -> Balancing {
+        self.config.balancing()
+    }
+
+    /// Returns the configuration of the map
+    #[must_use]
+    pub fn config(&self) -> TravlMapConfig {
+        self.config
+    }
+
+    /// Returns the key of the root node, if there is one
+    #[must_use]
+    pub fn root_key(&self) -> Option<&K> {
+        self.root_key.as_ref()
+    }
+
+    /// Returns the storage of the nodes, a [`NodeMap`] by default
+    #[must_use]
+    pub fn nodes(&self) -> &S {
+        &self.nodes
+    }
+
+    /// Returns the property getter function
+    #[must_use]
+    pub fn prop_fn(&self) -> &PropFn<'a, V, P> {
+        &self.prop_fn
+    }
+
+    /// Returns the ordering function
+    #[must_use]
+    pub fn ordering_fn(&self) -> &OrdFn<'a, P> {
+        &self.ordering_fn
+    }
+
+    /// Returns whether the map is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the number of nodes within the tree
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the number of nodes the map can hold without reallocating its storage
+    ///
+    /// With a [`NodeMap`] or a [`SlabStorage`](crate::storage::SlabStorage), the slots of removed nodes are
+    /// reused by later insertions and the storage doesn't grow under churn as long as the number of nodes stays
+    /// stable.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Reserves storage for at least `additional` more nodes
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows [`usize`].
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    /// Shrinks the storage as much as possible, releasing the slots left over by removed nodes
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+    }
+
+    /// Replaces the property getter function and reorders the tree accordingly
+    ///
+    /// The tree is rebuilt in O(n log n), entries ordered the same by the new function keeping their relative
+    /// order.
+    pub fn replace_prop_fn(&mut self, prop_fn: PropFn<'a, V, P>)
+    where
+        K: Clone
+    {
+        self.prop_fn = prop_fn;
+        let entries = self.take_entries();
+        self.rebuild_from(entries);
+    }
+
+    /// Replaces the ordering function and reorders the tree accordingly
+    ///
+    /// The tree is rebuilt in O(n log n), entries ordered the same by the new function keeping their relative
+    /// order.
+    pub fn replace_ordering_fn(&mut self, ordering_fn: OrdFn<'a, P>)
+    where
+        K: Clone
+    {
+        self.ordering_fn = ordering_fn;
+        let entries = self.take_entries();
+        self.rebuild_from(entries);
+    }
+}
+
+impl<'n, K, V, P, S> IntoIterator for &'n TravlMap<'_, K, V, P, S>
+where
+    S: NodeStorage<K, V>
+{
+    type Item = (&'n K, &'n V);
+    type IntoIter = Iter<'n, K, V, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'n, K, V, P, S> IntoParallelIterator for &'n TravlMap<'_, K, V, P, S>
+where
+    K: Sync,
+    V: Sync,
+    S: NodeStorage<K, V> + Sync
+{
+    type Item = (&'n K, &'n V);
+    type Iter = ParIter<'n, K, V, S>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIter {
+            inner: ParNodeIter::new(&self.nodes, self.root()),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<K, V, P, S> IntoParallelIterator for TravlMap<'_, K, V, P, S>
+where
+    K: Send,
+    V: Send,
+    S: NodeStorage<K, V>
+{
+    type Item = (K, V);
+    type Iter = rayon::vec::IntoIter<(K, V)>;
+
+    /// Consumes the map, yielding its entries in parallel and in no particular order
+    fn into_par_iter(self) -> Self::Iter {
+        let entries: Vec<(K, V)> = self.nodes.into_values().map(TravlNode::into_entry).collect();
+        entries.into_par_iter()
+    }
+}
+
+/// Returns whether subtrees of the given numbers of nodes are within the [weight ratio](WEIGHT_RATIO) of each other
+fn weights_balanced(left_size: usize, right_size: usize) -> bool {
+    let (left_weight, right_weight) = (left_size.saturating_add(1), right_size.saturating_add(1));
+
+    left_weight.saturating_mul(WEIGHT_RATIO) >= right_weight && right_weight.saturating_mul(WEIGHT_RATIO) >= left_weight
+}
+
+/// Returns the number of levels of a subtree of the given number of nodes linked by
+/// [`TravlMap::build_balanced`], which splits ranges at their middle
+fn levels(node_count: usize) -> u64 {
+    node_count.checked_ilog2().map_or(0, |height| u64::from(height) + 1)
+}
+
+/// Number of nodes under which [`par_link_balanced`] links a subtree on the current thread
+#[cfg(feature = "rayon")]
+const PAR_LINK_MIN_LEN: usize = 1 << 12;
+
+/// Links sorted nodes into a subtree of minimal height, whose root is linked to the given parent, linking the
+/// subtrees of large ranges on the [`rayon`] thread pool
+///
+/// The middle node of each range becomes the root of the subtree of that range, as in
+/// [`TravlMap::build_balanced`].
+#[cfg(feature = "rayon")]
+fn par_link_balanced<K, V>(nodes: &mut [TravlNode<K, V>], parent: Option<&K>)
+where
+    K: Clone + Send + Sync,
+    V: Send
+{
+    if nodes.is_empty() {
+        return;
+    }
+
+    let middle = nodes.len() / 2;
+    let left_key = (middle > 0).then(|| nodes[middle / 2].key().clone());
+    let right_middle = middle + 1 + (nodes.len() - middle - 1) / 2;
+    let right_key = (middle + 1 < nodes.len()).then(|| nodes[right_middle].key().clone());
+    let height = u64::from(nodes.len().ilog2());
+    let size = nodes.len();
+    let is_large = size >= PAR_LINK_MIN_LEN;
+
+    let (left, rest) = nodes.split_at_mut(middle);
+    let (node, right) = rest.split_first_mut().expect("the middle of a non-empty range should exist");
+    node.set_height(height);
+    node.set_rank(height);
+    node.set_size(size);
+    let _ = node.link_children((left_key, right_key));
+    if let Some(parent) = parent {
+        let _ = node.link_parent(parent.clone());
+    }
+
+    let key = Some(node.key());
+    if is_large {
+        rayon::join(|| par_link_balanced(left, key), || par_link_balanced(right, key));
+    } else {
+        par_link_balanced(left, key);
+        par_link_balanced(right, key);
+    }
+}
+
+/// Merges two sorted runs into a single one, entries of the first run coming first on ties
+#[cfg(feature = "std")]
+fn merge_runs<T, F>(first: Vec<T>, second: Vec<T>, compare: &mut F) -> Vec<T>
+where
+    F: FnMut(&T, &T) -> Ordering
+{
+    let mut merged = Vec::with_capacity(first.len() + second.len());
+    let mut first = first.into_iter().peekable();
+    let mut second = second.into_iter().peekable();
+
+    while let (Some(a), Some(b)) = (first.peek(), second.peek()) {
+        let next = if compare(a, b) == Ordering::Greater { second.next() } else { first.next() };
+        merged.extend(next);
+    }
+
+    merged.extend(first);
+    merged.extend(second);
+    merged
+}
+
+/// Batch of insertions into a [`TravlMap`], rebalancing the tree once when the batch ends
+///
+/// Created by [`TravlMap::batch`]. The batch ends when it is [finished](Self::finish) or dropped.
+pub struct Batch<'m, 'a, K, V, P, S = NodeMap<K, V>>
+where
+    K: Clone,
+    S: NodeStorage<K, V>
+{
+    map: &'m mut TravlMap<'a, K, V, P, S>,
+    pending: Vec<(K, V)>,
+}
+
+impl<K, V, P, S> Batch<'_, '_, K, V, P, S>
+where
+    K: Clone,
+    S: NodeStorage<K, V>
+{
+    /// Buffers an entry, replacing the value of its key when the batch ends if it already exists
+    pub fn insert(&mut self, key: K, value: V) {
+        self.pending.push((key, value));
+    }
+
+    /// Returns the number of buffered entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether no entry is buffered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Ends the batch, rebuilding the tree with the buffered entries
+    pub fn finish(self) {}
+
+    /// Rebuilds the tree out of its current entries and the buffered ones
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        // When a key is inserted multiple times, only its last value is kept. Buffered entries are looked up
+        // in a storage of the map's kind, so that keys don't need more than the map's storage requires
+        let mut pending_nodes = S::with_capacity(self.pending.len());
+        let mut pending_keys = Vec::with_capacity(self.pending.len());
+        for (key, value) in core::mem::take(&mut self.pending).into_iter().rev() {
+            if !pending_nodes.contains_key(&key) {
+                pending_keys.push(key.clone());
+                pending_nodes.insert(key.clone(), TravlNode::new(key, value));
+            }
+        }
+
+        // Current entries are taken in order so that entries with equal properties keep their relative order
+        let ordered_keys: Vec<K> = self.map.iter().map(|(key, _)| key.clone()).collect();
+        self.map.poisoned = true;
+        let nodes = &mut self.map.nodes;
+        let mut entries: Vec<(K, V)> = ordered_keys
+            .iter()
+            .filter(|key| !pending_nodes.contains_key(key))
+            .filter_map(|key| nodes.remove(key))
+            .map(TravlNode::into_entry)
+            .collect();
+        entries.extend(
+            pending_keys.iter().rev().filter_map(|key| pending_nodes.remove(key)).map(TravlNode::into_entry)
+        );
+
+        // The nodes of the keys which were inserted again are dropped, keeping the storage itself
+        self.map.nodes.clear();
+        self.map.root_key = None;
+        self.map.rebuild_from(entries);
+    }
+}
+
+impl<K, V, P, S> Extend<(K, V)> for Batch<'_, '_, K, V, P, S>
+where
+    K: Clone,
+    S: NodeStorage<K, V>
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, entries: I) {
+        self.pending.extend(entries);
+    }
+}
+
+impl<K, V, P, S> Drop for Batch<'_, '_, K, V, P, S>
+where
+    K: Clone,
+    S: NodeStorage<K, V>
+{
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Builder configuring a [`TravlMap`] step by step
+///
+/// Created by [`TravlMap::builder`]. Properties are the values themselves and are ordered by [`Ord`] unless
+/// a [property getter](Self::prop) or an [ordering function](TravlMapBuilder::ordering) is given: `O` is
+/// `()` until an ordering function is given, in which case properties don't need to implement [`Ord`].
+/// Nodes are stored in a [`NodeMap`] unless [another storage](Self::storage) is given.
+pub struct TravlMapBuilder<'a, K, V, P = V, O = (), S = NodeMap<K, V>> {
+    config: TravlMapConfig,
+    capacity: usize,
+    prop_fn: PropFn<'a, V, P>,
+    ordering_fn: O,
+    /// Storage given to the builder, a new one being created when building the map otherwise
+    storage: Option<S>,
+    key_marker: PhantomData<fn() -> K>,
+}
+
+impl<'a, K, V, P, O, S> TravlMapBuilder<'a, K, V, P, O, S> {
+    /// Sets the imbalance factor of the map, 0 by default
+    ///
+    /// # Panics
+    ///
+    /// Panics if the imbalance factor exceeds [`MAX_IMBALANCE_FACTOR`].
+    #[must_use]
+    pub fn imbalance_factor(mut self, imbalance_factor: u64) -> Self {
+        let imbalance_factor = TravlMapConfig::new(imbalance_factor).imbalance_factor();
+        self.config = TravlMapConfig { imbalance_factor, ..self.config };
+        self
+    }
+
+    /// Sets the tie policy of the map, placing inserted entries after the entries whose properties are equal to
+    /// theirs by default
+    #[must_use]
+    pub fn tie_policy(mut self, tie_policy: TiePolicy) -> Self {
+        self.config = self.config.with_tie_policy(tie_policy);
+        self
+    }
+
+    /// Sets the balancing strategy of the map, rotating nodes as soon as they are imbalanced by default
+    #[must_use]
+    pub fn balancing(mut self, balancing: Balancing) -> Self {
+        self.config = self.config.with_balancing(balancing);
+        self
+    }
+
+    /// Sets the configuration of the map, for example the one of another map
+    #[must_use]
+    pub fn config(mut self, config: TravlMapConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the number of entries the map can hold without reallocating its node storage
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the ordering function, comparing the properties of values
+    #[must_use]
+    pub fn ordering<F>(self, ordering_fn: F) -> TravlMapBuilder<'a, K, V, P, OrdFn<'a, P>, S>
+    where
+        F: Fn(&P, &P) -> Ordering + Send + Sync + 'a
+    {
+        TravlMapBuilder {
+            config: self.config,
+            capacity: self.capacity,
+            prop_fn: self.prop_fn,
+            ordering_fn: Box::new(ordering_fn),
+            storage: self.storage,
+            key_marker: PhantomData,
+        }
+    }
+
+    /// Sets the storage of the nodes, see the [`storage`](crate::storage) module
+    #[must_use]
+    pub fn storage<T>(self) -> TravlMapBuilder<'a, K, V, P, O, T>
+    where
+        K: 'a,
+        V: 'a,
+        T: NodeStorage<K, V>
+    {
+        self.with_storage(None)
+    }
+
+    /// Sets the storage of the nodes to the given one, for example a [`SlabStorage`](crate::storage::SlabStorage)
+    /// allocating its slots using a custom allocator, see the [`storage`](crate::storage) module
+    ///
+    /// Nodes already stored in it are removed when the map is built.
+    #[must_use]
+    pub fn storage_in<T>(self, storage: T) -> TravlMapBuilder<'a, K, V, P, O, T>
+    where
+        K: 'a,
+        V: 'a,
+        T: NodeStorage<K, V>
+    {
+        self.with_storage(Some(storage))
+    }
+
+    /// Sets the hasher of the keys, storing the nodes in a [`NodeMap`] hashing them with it
+    ///
+    /// Since storages are created with [`Default`], for example when buffering the entries of a
+    /// [batch](TravlMap::batch), the hasher needs to implement it as well.
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    #[must_use]
+    pub fn hasher<H>(self, hasher: H) -> TravlMapBuilder<'a, K, V, P, O, NodeMap<K, V, H>>
+    where
+        K: Hash + Eq + 'a,
+        V: 'a,
+        H: BuildHasher + Default
+    {
+        self.with_storage(Some(NodeMap::with_hasher(hasher)))
+    }
+
+    /// Replaces the storage of the nodes, which is created when building the map if none is given
+    fn with_storage<T>(self, storage: Option<T>) -> TravlMapBuilder<'a, K, V, P, O, T> {
+        TravlMapBuilder {
+            config: self.config,
+            capacity: self.capacity,
+            prop_fn: self.prop_fn,
+            ordering_fn: self.ordering_fn,
+            storage,
+            key_marker: PhantomData,
+        }
+    }
+
+    /// Creates the map, turning what was given as the ordering function into one
+    fn build_with<F>(self, into_ordering_fn: F) -> TravlMap<'a, K, V, P, S>
+    where
+        S: NodeStorage<K, V>,
+        F: FnOnce(O) -> OrdFn<'a, P>
+    {
+        let nodes = if let Some(mut nodes) = self.storage {
+            nodes.clear();
+            nodes.reserve(self.capacity);
+            nodes
+        } else {
+            S::with_capacity(self.capacity)
+        };
+
+        TravlMap {
+            config: self.config,
+            root_key: None,
+            nodes,
+            prop_fn: self.prop_fn,
+            ordering_fn: into_ordering_fn(self.ordering_fn),
+            poisoned: false,
+            metrics: Metrics::default(),
+        }
+    }
+}
+
+impl<'a, K, V, P, S> TravlMapBuilder<'a, K, V, P, (), S> {
+    /// Sets the property getter, returning the property of values the map is ordered by
+    ///
+    /// Since the ordering function compares properties, the property getter is set before it.
+    #[must_use]
+    pub fn prop<Q, F>(self, prop_fn: F) -> TravlMapBuilder<'a, K, V, Q, (), S>
+    where
+        F: Fn(&V) -> &Q + Send + Sync + 'a
+    {
+        TravlMapBuilder {
+            config: self.config,
+            capacity: self.capacity,
+            prop_fn: Box::new(prop_fn),
+            ordering_fn: (),
+            storage: self.storage,
+            key_marker: PhantomData,
+        }
+    }
+
+    /// Creates the map, ordering properties by [`Ord`]
+    #[must_use]
+    pub fn build(self) -> TravlMap<'a, K, V, P, S>
+    where
+        P: Ord + 'a,
+        S: NodeStorage<K, V>
+    {
+        self.build_with(|()| Box::new(Ord::cmp))
+    }
+}
+
+impl<'a, K, V, P, S> TravlMapBuilder<'a, K, V, P, OrdFn<'a, P>, S>
+where
+    K: 'a,
+    V: 'a,
+    S: NodeStorage<K, V>
+{
+    /// Creates the map, ordering properties with the given ordering function
+    #[must_use]
+    pub fn build(self) -> TravlMap<'a, K, V, P, S> {
+        self.build_with(|ordering_fn| ordering_fn)
+    }
+}
+
+impl<K, V, P, O, S> Debug for TravlMapBuilder<'_, K, V, P, O, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TravlMapBuilder")
+            .field("config", &self.config)
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder creating a [`TravlMap`] out of a stream of sorted entries
+///
+/// Entries are moved into the map as soon as they are pushed, so building a map from a stream
+/// (for example entries read from a file or deserialized one by one) doesn't require collecting
+/// all entries beforehand. Since entries are never sorted by the builder, they must be pushed
+/// in the order defined by the map's property getter and ordering function.
+pub struct SortedEntriesBuilder<'a, K, V, P = V> {
+    map: TravlMap<'a, K, V, P>,
+    /// Keys of the pushed entries, in order
+    keys: Vec<K>,
+}
+
+impl<'a, K, V, P> SortedEntriesBuilder<'a, K, V, P>
+where
+    K: Clone,
+    NodeMap<K, V>: NodeStorage<K, V>
+{
+    /// Creates a builder filling the given map, replacing its current entries
+    #[must_use]
+    pub fn new(mut map: TravlMap<'a, K, V, P>) -> Self {
+        map.root_key = None;
+        map.nodes.clear();
+
+        Self { map, keys: Vec::new() }
+    }
+
+    /// Returns the number of entries pushed so far
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether no entry has been pushed yet
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Pushes an entry, which must be ordered after all previously pushed entries
+    ///
+    /// # Errors
+    ///
+    /// Returns the entry back if it is ordered before the previous entry or if its key was already pushed,
+    /// the order being checked first. For duplicate keys, the returned key is the one that was already pushed.
+    pub fn push(&mut self, key: K, value: V) -> Result<(), RejectedEntry<K, V>> {
+        if let Some(last) = self.keys.last().and_then(|last_key| self.map.nodes.get(last_key)) {
+            let last_prop = (self.map.prop_fn)(last.value());
+            if (self.map.ordering_fn)(last_prop, (self.map.prop_fn)(&value)) == Ordering::Greater {
+                return Err(RejectedEntry { key, value, reason: RejectionReason::OutOfOrder });
+            }
+        }
+
+        if let Some(pushed) = self.map.nodes.get(&key) {
+            return Err(RejectedEntry {
+                key: pushed.key().clone(),
+                value,
+                reason: RejectionReason::DuplicateKey,
+            });
+        }
+
+        self.keys.push(key.clone());
+        self.map.nodes.insert(key.clone(), TravlNode::new(key, value));
+        Ok(())
+    }
+
+    /// Pushes all entries of a fallible stream, stopping at the first error
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the stream, or the first entry rejected by [`push`](Self::push).
+    pub fn try_extend<I, E>(&mut self, entries: I) -> Result<(), E>
+    where
+        I: IntoIterator<Item = Result<(K, V), E>>,
+        E: From<RejectedEntry<K, V>>
+    {
+        for entry in entries {
+            let (key, value) = entry?;
+            self.push(key, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Links the pushed entries into a balanced tree and returns the map
+    #[must_use]
+    pub fn finish(self) -> TravlMap<'a, K, V, P> {
+        let Self { mut map, keys } = self;
+        map.link_balanced(&keys, None);
+        map
+    }
+
+    /// Links the pushed entries into a balanced tree whose root is the node of the given key if possible, see
+    /// [`TravlMap::build_balanced_around`], and returns the map
+    #[cfg(feature = "serde")]
+    pub(crate) fn finish_around(self, root: Option<&K>) -> TravlMap<'a, K, V, P>
+    where
+        K: Eq
+    {
+        let Self { mut map, keys } = self;
+        let root = root.and_then(|root| map.root_position(&keys, root));
+        map.link_balanced(&keys, root);
+        map
+    }
+}
+
+/// Parallel iterator over the entries of a [`TravlMap`], in no particular order
+///
+/// Created by [`par_iter`](rayon::iter::IntoParallelRefIterator::par_iter). The tree is split at
+/// subtree boundaries and disjoint subtrees are iterated over on the [`rayon`] thread pool.
+#[cfg(feature = "rayon")]
+#[derive(Clone, Debug)]
+pub struct ParIter<'n, K, V, S = NodeMap<K, V>> {
+    inner: ParNodeIter<'n, K, V, S>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'n, K, V, S> ParallelIterator for ParIter<'n, K, V, S>
+where
+    K: Sync,
+    V: Sync,
+    S: NodeStorage<K, V> + Sync
+{
+    type Item = (&'n K, &'n V);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>
+    {
+        self.inner.map(|node| (node.key(), node.value())).drive_unindexed(consumer)
+    }
+}
+
+/// In-order iterator over the entries of a [`TravlMap`]
+pub struct Iter<'n, K, V, S = NodeMap<K, V>> {
+    inner: InOrderIter<'n, K, V, S>,
+    remaining: usize,
+}
+
+impl<K, V, S> Clone for Iter<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<K, V, S> Debug for Iter<'_, K, V, S>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Iter").field("inner", &self.inner).field("remaining", &self.remaining).finish()
+    }
+}
+
+impl<'n, K, V, S> Iterator for Iter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>
+{
+    type Item = (&'n K, &'n V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.inner.next()?;
+        self.remaining = self.remaining.saturating_sub(1);
+
+        Some((node.key(), node.value()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+
+    fn count(self) -> usize {
+        self.remaining
+    }
+
+    fn fold<B, F>(self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B
+    {
+        // The remaining length only matters to the iterator, so it isn't updated along the way
+        self.inner.fold(init, |accumulator, node| f(accumulator, (node.key(), node.value())))
+    }
+}
+
+impl<K, V, S> ExactSizeIterator for Iter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+impl<K, V, S> FusedIterator for Iter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// In-order iterator over the entries of a [`TravlMap`], grouped in batches
+pub struct Chunks<'n, K, V, S = NodeMap<K, V>> {
+    inner: Iter<'n, K, V, S>,
+    chunk_size: usize,
+}
+
+impl<K, V, S> Clone for Chunks<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            chunk_size: self.chunk_size,
+        }
+    }
+}
+
+impl<K, V, S> Debug for Chunks<'_, K, V, S>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Chunks").field("inner", &self.inner).field("chunk_size", &self.chunk_size).finish()
+    }
+}
+
+impl<'n, K, V, S> Iterator for Chunks<'n, K, V, S>
+where
+    S: NodeStorage<K, V>
+{
+    type Item = Vec<(&'n K, &'n V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<_> = self.inner.by_ref().take(self.chunk_size).collect();
+        (!chunk.is_empty()).then_some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.inner.len().div_ceil(self.chunk_size);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<K, V, S> ExactSizeIterator for Chunks<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+impl<K, V, S> FusedIterator for Chunks<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// In-order iterator over the keys of a [`TravlMap`] along with the property of their value
+pub struct Props<'m, 'a, K, V, P, S = NodeMap<K, V>> {
+    inner: InOrderIter<'m, K, V, S>,
+    prop_fn: &'m mut PropFn<'a, V, P>,
+}
+
+impl<'m, K, V, P, S> Iterator for Props<'m, '_, K, V, P, S>
+where
+    S: NodeStorage<K, V>
+{
+    type Item = (&'m K, &'m P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.inner.next()?;
+        Some((node.key(), (self.prop_fn)(node.value())))
+    }
+}
+
+impl<K, V, P, S> FusedIterator for Props<'_, '_, K, V, P, S> where S: NodeStorage<K, V> {}
+
+/// In-order iterator over the pairs of adjacent entries of a [`TravlMap`]
+pub struct Pairs<'n, K, V, S = NodeMap<K, V>> {
+    inner: Iter<'n, K, V, S>,
+    previous: Option<(&'n K, &'n V)>,
+}
+
+impl<K, V, S> Clone for Pairs<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            previous: self.previous,
+        }
+    }
+}
+
+impl<K, V, S> Debug for Pairs<'_, K, V, S>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Pairs").field("inner", &self.inner).field("previous", &self.previous).finish()
+    }
+}
+
+impl<'n, K, V, S> Iterator for Pairs<'n, K, V, S>
+where
+    S: NodeStorage<K, V>
+{
+    type Item = ((&'n K, &'n V), (&'n K, &'n V));
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.inner.next()?;
+        let previous = self.previous.replace(current)?;
+
+        Some((previous, current))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = if self.previous.is_some() { self.inner.len() } else { 0 };
+        (remaining, Some(remaining))
+    }
+}
+
+impl<K, V, S> ExactSizeIterator for Pairs<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+impl<K, V, S> FusedIterator for Pairs<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// In-order iterator over the windows of adjacent entries of a [`TravlMap`]
+pub struct Windows<'n, K, V, S = NodeMap<K, V>> {
+    inner: Iter<'n, K, V, S>,
+    window: VecDeque<(&'n K, &'n V)>,
+    window_size: usize,
+}
+
+impl<K, V, S> Clone for Windows<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            window: self.window.clone(),
+            window_size: self.window_size,
+        }
+    }
+}
+
+impl<K, V, S> Debug for Windows<'_, K, V, S>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Windows")
+            .field("inner", &self.inner)
+            .field("window", &self.window)
+            .field("window_size", &self.window_size)
+            .finish()
+    }
+}
+
+impl<'n, K, V, S> Iterator for Windows<'n, K, V, S>
+where
+    S: NodeStorage<K, V>
+{
+    type Item = Vec<(&'n K, &'n V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.window.len() == self.window_size {
+            self.window.pop_front();
+        }
+
+        while self.window.len() < self.window_size {
+            self.window.push_back(self.inner.next()?);
+        }
+
+        Some(self.window.iter().copied().collect())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Once the window is full, every remaining entry produces exactly one more window
+        let remaining = if self.window.len() == self.window_size {
+            self.inner.len()
+        } else {
+            (self.window.len() + self.inner.len() + 1).saturating_sub(self.window_size)
+        };
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<K, V, S> ExactSizeIterator for Windows<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+impl<K, V, S> FusedIterator for Windows<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Iterator merging the entries of multiple [`TravlMap`]s into a single sorted stream
+///
+/// See [`TravlMap::merge_iter`].
+pub struct MergeIter<'m, 'a, K, V, P, S = NodeMap<K, V>> {
+    prop_fn: &'m mut PropFn<'a, V, P>,
+    ordering_fn: &'m mut OrdFn<'a, P>,
+    sources: Vec<Iter<'m, K, V, S>>,
+    /// Next entry of each source
+    heads: Vec<Option<(&'m K, &'m V)>>,
+    policy: EqualPropPolicy,
+}
+
+impl<K, V, P, S> MergeIter<'_, '_, K, V, P, S> {
+    /// Compares the properties of the given values
+    fn compare(&mut self, a: &V, b: &V) -> Ordering {
+        (self.ordering_fn)((self.prop_fn)(a), (self.prop_fn)(b))
+    }
+}
+
+impl<'m, K, V, P, S> Iterator for MergeIter<'m, '_, K, V, P, S>
+where
+    S: NodeStorage<K, V>
+{
+    type Item = (usize, &'m K, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut selected: Option<(usize, &'m V)> = None;
+
+        // Ties are won by the earliest source, as only strictly smaller entries replace the selection
+        for source in 0..self.heads.len() {
+            let Some((_, value)) = self.heads[source] else {
+                continue;
+            };
+
+            if selected.is_none_or(|(_, selected_value)| self.compare(value, selected_value) == Ordering::Less) {
+                selected = Some((source, value));
+            }
+        }
+
+        let (source, _) = selected?;
+        let (key, value) = self.heads[source].expect("selected source should have an entry");
+        self.heads[source] = self.sources[source].next();
+
+        if self.policy == EqualPropPolicy::KeepAll {
+            return Some((source, key, value));
+        }
+
+        // Skip every other entry with an equal property, keeping the last one if requested.
+        // Sources before the selected one cannot have equal entries as ties are won by the earliest source.
+        let mut merged = (source, key, value);
+        for other in source..self.heads.len() {
+            while let Some((other_key, other_value)) = self.heads[other] {
+                if self.compare(other_value, value) != Ordering::Equal {
+                    break;
+                }
+
+                self.heads[other] = self.sources[other].next();
+                if self.policy == EqualPropPolicy::KeepLast {
+                    merged = (other, other_key, other_value);
+                }
+            }
+        }
+
+        Some(merged)
+    }
+}
+
+/// Iterator over the differences between two [`TravlMap`]s
+///
+/// See [`TravlMap::diff`].
+pub struct Diff<'m, 'a, K, V, P, S = NodeMap<K, V>> {
+    prop_fn: &'m mut PropFn<'a, V, P>,
+    ordering_fn: &'m mut OrdFn<'a, P>,
+    old_nodes: &'m S,
+    new_nodes: &'m S,
+    old_entries: Iter<'m, K, V, S>,
+    new_entries: Iter<'m, K, V, S>,
+    old_head: Option<(&'m K, &'m V)>,
+    new_head: Option<(&'m K, &'m V)>,
+}
+
+impl<'m, K, V, P, S> Iterator for Diff<'m, '_, K, V, P, S>
+where
+    V: PartialEq,
+    S: NodeStorage<K, V>
+{
+    type Item = DiffItem<'m, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let take_old = match (self.old_head, self.new_head) {
+                (None, None) => return None,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (Some((_, old_value)), Some((_, new_value))) => {
+                    (self.ordering_fn)((self.prop_fn)(old_value), (self.prop_fn)(new_value)) != Ordering::Greater
+                },
+            };
+
+            if take_old {
+                let (key, old_value) = core::mem::replace(&mut self.old_head, self.old_entries.next())?;
+
+                match self.new_nodes.get(key) {
+                    None => return Some(DiffItem::Removed(key, old_value)),
+                    Some(node) if node.value() != old_value => {
+                        return Some(DiffItem::Changed(key, old_value, node.value()));
+                    },
+                    Some(_) => {},
+                }
+            } else {
+                let (key, new_value) = core::mem::replace(&mut self.new_head, self.new_entries.next())?;
+
+                // Keys present in both maps are handled when encountered in the old map
+                if !self.old_nodes.contains_key(key) {
+                    return Some(DiffItem::Added(key, new_value));
+                }
+            }
+        }
+    }
+}
+
+impl<K, V, P, S> FusedIterator for Diff<'_, '_, K, V, P, S>
+where
+    V: PartialEq,
+    S: NodeStorage<K, V>
+{
+}
+
+/// Returns the first node of the subtree of the given root whose property isn't ordered before the given one
+fn lower_bound<'n, K, V, P, S>(
+    nodes: &'n S,
+    root: Option<&'n TravlNode<K, V>>,
+    prop: &P,
+    prop_fn: &PropFn<'_, V, P>,
+    ordering_fn: &OrdFn<'_, P>,
+) -> Option<&'n TravlNode<K, V>>
+where
+    S: NodeStorage<K, V>
+{
+    let mut first = None;
+    let mut current = root;
+
+    while let Some(node) = current {
+        if ordering_fn(prop_fn(node.value()), prop) == Ordering::Less {
+            current = nodes.right_of(node);
+        } else {
+            first = Some(node);
+            current = nodes.left_of(node);
+        }
+    }
+
+    first
+}
+
+/// Returns the last node of the subtree of the given root whose property isn't ordered after the given one
+fn floor<'n, K, V, P, S>(
+    nodes: &'n S,
+    root: Option<&'n TravlNode<K, V>>,
+    prop: &P,
+    prop_fn: &PropFn<'_, V, P>,
+    ordering_fn: &OrdFn<'_, P>,
+) -> Option<&'n TravlNode<K, V>>
+where
+    S: NodeStorage<K, V>
+{
+    let mut last = None;
+    let mut current = root;
+
+    while let Some(node) = current {
+        if ordering_fn(prop_fn(node.value()), prop) == Ordering::Greater {
+            current = nodes.left_of(node);
+        } else {
+            last = Some(node);
+            current = nodes.right_of(node);
+        }
+    }
+
+    last
 }
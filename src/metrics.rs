@@ -0,0 +1,78 @@
+//! Counters of the work performed by tree operations, enabled by the `metrics` feature
+//!
+//! Counters are meant for tuning imbalance factors with data: a higher imbalance factor usually
+//! means fewer rotations on writes, at the cost of deeper trees and more comparisons on lookups.
+
+use crate::core::AVLRotation;
+
+/// Counters of the work performed by the operations that led to a map
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TreeMetrics {
+    right_rotations: u64,
+    left_rotations: u64,
+    right_left_rotations: u64,
+    left_right_rotations: u64,
+    comparisons: u64,
+    height_recomputations: u64,
+    rebalances: u64,
+}
+
+impl TreeMetrics {
+    /// Returns the number of rotations of the given type
+    #[must_use]
+    pub fn rotations(&self, rotation: AVLRotation) -> u64 {
+        match rotation {
+            AVLRotation::Right => self.right_rotations,
+            AVLRotation::Left => self.left_rotations,
+            AVLRotation::RightLeft => self.right_left_rotations,
+            AVLRotation::LeftRight => self.left_right_rotations,
+        }
+    }
+
+    /// Returns the number of rotations of any type
+    #[must_use]
+    pub fn total_rotations(&self) -> u64 {
+        self.right_rotations + self.left_rotations + self.right_left_rotations + self.left_right_rotations
+    }
+
+    /// Returns the number of key comparisons
+    #[must_use]
+    pub fn comparisons(&self) -> u64 {
+        self.comparisons
+    }
+
+    /// Returns the number of times the height of a node was computed
+    #[must_use]
+    pub fn height_recomputations(&self) -> u64 {
+        self.height_recomputations
+    }
+
+    /// Returns the number of times a node was found too imbalanced and had to be rotated
+    #[must_use]
+    pub fn rebalances(&self) -> u64 {
+        self.rebalances
+    }
+
+    /// Counts a rotation of the given type, which rebalances a node
+    pub(crate) fn record_rotation(&mut self, rotation: AVLRotation) {
+        let counter = match rotation {
+            AVLRotation::Right => &mut self.right_rotations,
+            AVLRotation::Left => &mut self.left_rotations,
+            AVLRotation::RightLeft => &mut self.right_left_rotations,
+            AVLRotation::LeftRight => &mut self.left_right_rotations,
+        };
+
+        *counter += 1;
+        self.rebalances += 1;
+    }
+
+    /// Counts a key comparison
+    pub(crate) fn record_comparison(&mut self) {
+        self.comparisons += 1;
+    }
+
+    /// Counts the computation of a node's height
+    pub(crate) fn record_height_recomputation(&mut self) {
+        self.height_recomputations += 1;
+    }
+}
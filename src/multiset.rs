@@ -0,0 +1,260 @@
+//! Ordered multiset, counting how many times each member was inserted
+//!
+//! A [`TravlMultiSet`] stores each distinct member once along with its count in a [`TravlMapPersistent`],
+//! so repeated members don't take any more room and counting the occurrences of a member is O(log n).
+
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::iter::FusedIterator;
+
+use crate::persistent::{PersistentIter, TravlMapPersistent};
+
+/// Ordered multiset, see the [module-level documentation](self)
+pub struct TravlMultiSet<T> {
+    counts: TravlMapPersistent<T, usize>,
+    len: usize,
+}
+
+impl<T> TravlMultiSet<T> {
+    /// Creates an empty multiset
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            counts: TravlMapPersistent::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of members in the multiset, counting every occurrence
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of distinct members in the multiset
+    #[must_use]
+    pub fn distinct_len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if the multiset contains no member
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the members in order, repeating each member as many times as it occurs
+    #[must_use]
+    pub fn iter(&self) -> MultiSetIter<'_, T> {
+        MultiSetIter {
+            counts: self.counts.iter(),
+            current: None,
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the distinct members in order, along with their counts
+    #[must_use]
+    pub fn counts(&self) -> Counts<'_, T> {
+        Counts {
+            inner: self.counts.iter(),
+        }
+    }
+}
+
+impl<T> TravlMultiSet<T>
+where
+    T: Ord + Clone
+{
+    /// Returns the number of occurrences of the given member, 0 if it isn't in the multiset
+    #[must_use]
+    pub fn count<Q>(&self, member: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.counts.get(member).copied().unwrap_or(0)
+    }
+
+    /// Returns `true` if the given member occurs at least once
+    #[must_use]
+    pub fn contains<Q>(&self, member: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.counts.contains_key(member)
+    }
+
+    /// Adds one occurrence of a member, returning its new count
+    pub fn insert(&mut self, member: T) -> usize {
+        self.insert_many(member, 1)
+    }
+
+    /// Adds `occurrences` occurrences of a member, returning its new count
+    ///
+    /// Adding 0 occurrences leaves the multiset untouched.
+    pub fn insert_many(&mut self, member: T, occurrences: usize) -> usize {
+        let count = self.count(&member) + occurrences;
+
+        if occurrences != 0 {
+            self.counts.insert_in_place(member, count);
+            self.len += occurrences;
+        }
+        count
+    }
+
+    /// Removes one occurrence of a member, returning `true` if it occurred
+    pub fn remove<Q>(&mut self, member: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.remove_many(member, 1) == 1
+    }
+
+    /// Removes up to `occurrences` occurrences of a member, returning how many were removed
+    pub fn remove_many<Q>(&mut self, member: &Q, occurrences: usize) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        let Some((stored, &count)) = self.counts.get_key_value(member) else {
+            return 0;
+        };
+
+        let removed = count.min(occurrences);
+        if removed == count {
+            self.counts.remove_in_place(member);
+        } else {
+            self.counts.insert_in_place(stored.clone(), count - removed);
+        }
+
+        self.len -= removed;
+        removed
+    }
+
+    /// Removes every occurrence of a member, returning how many were removed
+    pub fn remove_all<Q>(&mut self, member: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.remove_many(member, usize::MAX)
+    }
+}
+
+impl<T> Clone for TravlMultiSet<T> {
+    fn clone(&self) -> Self {
+        Self {
+            counts: self.counts.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Default for TravlMultiSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for TravlMultiSet<T>
+where
+    T: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.counts()).finish()
+    }
+}
+
+impl<T> PartialEq for TravlMultiSet<T>
+where
+    T: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.counts().eq(other.counts())
+    }
+}
+
+impl<T> Eq for TravlMultiSet<T> where T: Eq {}
+
+impl<T> FromIterator<T> for TravlMultiSet<T>
+where
+    T: Ord + Clone
+{
+    fn from_iter<I: IntoIterator<Item = T>>(members: I) -> Self {
+        let mut multiset = Self::new();
+        for member in members {
+            multiset.insert(member);
+        }
+        multiset
+    }
+}
+
+impl<'s, T> IntoIterator for &'s TravlMultiSet<T> {
+    type IntoIter = MultiSetIter<'s, T>;
+    type Item = &'s T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the members of a [`TravlMultiSet`], repeating each member as many times as it occurs
+///
+/// Created by [`TravlMultiSet::iter`].
+pub struct MultiSetIter<'s, T> {
+    counts: PersistentIter<'s, T, usize>,
+    /// Member being repeated, along with the number of times it remains to be yielded
+    current: Option<(&'s T, usize)>,
+    remaining: usize,
+}
+
+impl<'s, T> Iterator for MultiSetIter<'s, T> {
+    type Item = &'s T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (member, left) = match self.current.take() {
+            Some(current) => current,
+            None => self.counts.next().map(|(member, count)| (member, *count))?,
+        };
+
+        if left > 1 {
+            self.current = Some((member, left - 1));
+        }
+        self.remaining -= 1;
+        Some(member)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for MultiSetIter<'_, T> {}
+
+impl<T> FusedIterator for MultiSetIter<'_, T> {}
+
+/// Iterator over the distinct members of a [`TravlMultiSet`] along with their counts
+///
+/// Created by [`TravlMultiSet::counts`].
+pub struct Counts<'s, T> {
+    inner: PersistentIter<'s, T, usize>,
+}
+
+impl<'s, T> Iterator for Counts<'s, T> {
+    type Item = (&'s T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(member, count)| (member, *count))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Counts<'_, T> {}
+
+impl<T> FusedIterator for Counts<'_, T> {}
@@ -0,0 +1,188 @@
+//! Observers notified of the structural changes of persistent maps, to maintain derived indexes or emit
+//! metrics without wrapping every call site
+//!
+//! Observers are registered on a [`TravlMapPersistent`](crate::persistent::TravlMapPersistent) with
+//! [`with_observer`](crate::persistent::TravlMapPersistent::with_observer), and are carried over to the
+//! maps returned by its insertions and removals. Notifications happen synchronously, while the new map is
+//! being built, so observers should be quick.
+//!
+//! With the `std` feature, `ChangeTracker` is an observer recording which keys changed, so that changes can
+//! be synchronized elsewhere without comparing whole maps. With the `tracing` feature, `TracingObserver`
+//! emits an event for each notification, within the spans the map opens for its insertions and removals.
+
+#[cfg(feature = "std")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use alloc::collections::btree_map::Entry;
+#[cfg(feature = "tracing")]
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "tracing")]
+use std::hash::DefaultHasher;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError};
+
+use crate::core::AVLRotation;
+
+/// Observer of the structural changes of a map, whose methods do nothing by default
+///
+/// Observers are shared between the versions of a map, which may be sent to other threads: they can only
+/// record what they observe through interior mutability, for example with atomics or a mutex.
+pub trait TreeObserver<K>: Send + Sync {
+    /// Called when a key is inserted, `replaced` being `true` if the key existed and only its value changed
+    fn on_insert(&self, key: &K, replaced: bool) {
+        let _ = (key, replaced);
+    }
+
+    /// Called when a key is removed
+    fn on_remove(&self, key: &K) {
+        let _ = key;
+    }
+
+    /// Called when a node is found too imbalanced and is about to be rotated
+    fn on_rebalance(&self, key: &K) {
+        let _ = key;
+    }
+
+    /// Called when a subtree is rotated, with the key of the node becoming the root of the subtree
+    fn on_rotation(&self, key: &K, rotation: AVLRotation) {
+        let _ = (key, rotation);
+    }
+}
+
+/// Net change of a key since changes were last taken from a [`ChangeTracker`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Change {
+    /// The key didn't exist and was inserted
+    Inserted,
+    /// The key existed and its value was replaced
+    Updated,
+    /// The key existed and was removed
+    Removed,
+}
+
+/// Observer recording the keys inserted, updated or removed since changes were last taken
+///
+/// Changes of a key are merged into its net change: a key inserted then removed didn't change, while a
+/// key removed then inserted again was updated. The tracker observes every map derived from the one it
+/// was registered on, so it should only be used with maps whose older versions aren't changed anymore.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ChangeTracker<K> {
+    changes: Mutex<BTreeMap<K, Change>>,
+}
+
+#[cfg(feature = "std")]
+impl<K> ChangeTracker<K> {
+    /// Creates a tracker without any change
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            changes: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the changes recorded since changes were last taken, ordered by key, and forgets them
+    pub fn take_changes(&self) -> BTreeMap<K, Change> {
+        // Changes are recorded in a single operation, so a panicking thread can't leave them inconsistent
+        let mut changes = self.changes.lock().unwrap_or_else(PoisonError::into_inner);
+        core::mem::take(&mut *changes)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> ChangeTracker<K>
+where
+    K: Ord + Clone
+{
+    /// Merges a change of a key into its net change
+    fn record(&self, key: &K, change: Change) {
+        let mut changes = self.changes.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match changes.entry(key.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(change);
+            },
+            Entry::Occupied(mut entry) => match (*entry.get(), change) {
+                (Change::Inserted, Change::Removed) => {
+                    entry.remove();
+                },
+                (Change::Inserted, _) => {},
+                (Change::Removed, Change::Inserted) => {
+                    entry.insert(Change::Updated);
+                },
+                (_, change) => {
+                    entry.insert(change);
+                },
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> Default for ChangeTracker<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> TreeObserver<K> for ChangeTracker<K>
+where
+    K: Ord + Clone + Send
+{
+    fn on_insert(&self, key: &K, replaced: bool) {
+        self.record(key, if replaced { Change::Updated } else { Change::Inserted });
+    }
+
+    fn on_remove(&self, key: &K) {
+        self.record(key, Change::Removed);
+    }
+}
+
+/// Observer emitting a `tracing` event at the trace level for each notification
+///
+/// Keys are identified by their hash, so that they don't need to implement [`Debug`](core::fmt::Debug) and
+/// don't leak into traces. Hashes are computed with [`DefaultHasher::new`], which is stable across runs of
+/// the same build.
+#[cfg(feature = "tracing")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TracingObserver;
+
+#[cfg(feature = "tracing")]
+impl TracingObserver {
+    /// Creates an observer emitting events
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Returns the hash identifying a key in traces
+#[cfg(feature = "tracing")]
+fn key_hash<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(feature = "tracing")]
+impl<K> TreeObserver<K> for TracingObserver
+where
+    K: Hash
+{
+    fn on_insert(&self, key: &K, replaced: bool) {
+        tracing::trace!(key_hash = key_hash(key), replaced, "inserted key");
+    }
+
+    fn on_remove(&self, key: &K) {
+        tracing::trace!(key_hash = key_hash(key), "removed key");
+    }
+
+    fn on_rebalance(&self, key: &K) {
+        tracing::trace!(key_hash = key_hash(key), "rebalancing node");
+    }
+
+    fn on_rotation(&self, key: &K, rotation: AVLRotation) {
+        tracing::trace!(key_hash = key_hash(key), ?rotation, "rotated subtree");
+    }
+}
@@ -0,0 +1,194 @@
+//! Maps whose keys are only partially ordered, such as floats, following a policy for incomparable keys
+//!
+//! Partially ordered keys can't be used as such in an ordered map, since a key incomparable with another
+//! one, such as NaN, has no position in the tree. A [`PartialOrdMap`] compares keys with
+//! [`PartialOrd`], and an [`IncomparablePolicy`] decides how to order the keys [`PartialOrd`] can't: rejecting
+//! them, ordering invalid keys such as NaN before or after all others, or falling back to a total order.
+
+use core::cmp::Ordering;
+use core::fmt::{self, Debug};
+
+use crate::error::TravlError;
+use crate::persistent::{PersistentIter, TravlMapPersistent};
+
+/// Policy ordering the values [`PartialOrd`] can't compare
+///
+/// Values incomparable with themselves, such as NaN, are called invalid below. Two valid values which are
+/// incomparable with each other can only be ordered by a [`TotalOrder`](Self::TotalOrder) adapter.
+pub enum IncomparablePolicy<P> {
+    /// Incomparable values are rejected with [`TravlError::Incomparable`]
+    Reject,
+    /// Invalid values are ordered before valid ones, and are all equal to each other
+    SortFirst,
+    /// Invalid values are ordered after valid ones, and are all equal to each other
+    SortLast,
+    /// Incomparable values are ordered by the given total order, such as [`f64::total_cmp`]
+    TotalOrder(fn(&P, &P) -> Ordering),
+}
+
+impl<P> IncomparablePolicy<P>
+where
+    P: PartialOrd
+{
+    /// Compares two values, following the policy if [`PartialOrd`] can't compare them
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::Incomparable`] if the policy doesn't order the values.
+    pub fn compare(&self, a: &P, b: &P) -> Result<Ordering, TravlError> {
+        if let Some(ordering) = a.partial_cmp(b) {
+            return Ok(ordering);
+        }
+
+        let invalid_ordering = match self {
+            Self::Reject => return Err(TravlError::Incomparable),
+            Self::TotalOrder(compare) => return Ok(compare(a, b)),
+            Self::SortFirst => Ordering::Less,
+            Self::SortLast => Ordering::Greater,
+        };
+
+        let is_valid = |value: &P| value.partial_cmp(value).is_some();
+        match (is_valid(a), is_valid(b)) {
+            (true, true) => Err(TravlError::Incomparable),
+            (false, false) => Ok(Ordering::Equal),
+            (false, true) => Ok(invalid_ordering),
+            (true, false) => Ok(invalid_ordering.reverse()),
+        }
+    }
+}
+
+impl<P> Clone for IncomparablePolicy<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for IncomparablePolicy<P> {}
+
+impl<P> Debug for IncomparablePolicy<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reject => f.write_str("Reject"),
+            Self::SortFirst => f.write_str("SortFirst"),
+            Self::SortLast => f.write_str("SortLast"),
+            Self::TotalOrder(_) => f.write_str("TotalOrder(..)"),
+        }
+    }
+}
+
+/// Map whose keys are only partially ordered, see the [module-level documentation](self)
+pub struct PartialOrdMap<K, V> {
+    map: TravlMapPersistent<K, V>,
+    policy: IncomparablePolicy<K>,
+}
+
+impl<K, V> PartialOrdMap<K, V> {
+    /// Creates an empty map ordering incomparable keys with the given policy
+    #[must_use]
+    pub fn new(policy: IncomparablePolicy<K>) -> Self {
+        Self {
+            map: TravlMapPersistent::new(),
+            policy,
+        }
+    }
+
+    /// Returns the policy ordering incomparable keys
+    #[must_use]
+    pub fn policy(&self) -> IncomparablePolicy<K> {
+        self.policy
+    }
+
+    /// Returns the number of entries in the map
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entry
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the entries, ordered by key
+    #[must_use]
+    pub fn iter(&self) -> PersistentIter<'_, K, V> {
+        self.map.iter()
+    }
+}
+
+impl<K, V> PartialOrdMap<K, V>
+where
+    K: PartialOrd + Clone,
+    V: Clone
+{
+    /// Returns the value associated to the given key, if it exists
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::Incomparable`] if the key can't be compared to a key of the map.
+    pub fn get(&self, key: &K) -> Result<Option<&V>, TravlError> {
+        self.map.try_get_by(key, |a, b| self.policy.compare(a, b))
+    }
+
+    /// Returns whether the map contains the given key
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::Incomparable`] if the key can't be compared to a key of the map.
+    pub fn contains_key(&self, key: &K) -> Result<bool, TravlError> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    /// Inserts an entry, returning the previous value of the key
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::Incomparable`] if the key can't be compared to a key of the map, leaving the
+    /// map untouched.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, TravlError> {
+        let previous = self.get(&key)?.cloned();
+        self.map = self.map.try_insert_by(key, value, |a, b| self.policy.compare(a, b))?;
+        Ok(previous)
+    }
+
+    /// Removes the entry of the given key, returning its value if it existed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::Incomparable`] if the key can't be compared to a key of the map, leaving the
+    /// map untouched.
+    pub fn remove(&mut self, key: &K) -> Result<Option<V>, TravlError> {
+        let (map, removed) = self.map.try_remove_by(key, |a, b| self.policy.compare(a, b))?;
+        self.map = map;
+        Ok(removed)
+    }
+}
+
+impl<K, V> Clone for PartialOrdMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+            policy: self.policy,
+        }
+    }
+}
+
+impl<K, V> Debug for PartialOrdMap<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'m, K, V> IntoIterator for &'m PartialOrdMap<K, V> {
+    type IntoIter = PersistentIter<'m, K, V>;
+    type Item = (&'m K, &'m V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
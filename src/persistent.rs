@@ -0,0 +1,1919 @@
+//! Persistent map, whose operations return new maps sharing their unchanged subtrees
+//!
+//! Unlike [`TravlMap`](crate::map::TravlMap), nodes of a [`TravlMapPersistent`] are reference-counted
+//! and never mutated: inserting or removing an entry only copies the nodes on the path from the root
+//! to that entry, and the previous map stays valid. Cloning a map is O(1), which makes keeping older
+//! versions around (for example to undo operations) cheap.
+//!
+//! Entries are ordered by key.
+//!
+//! Maps can also change in place, copy-on-write: [`snapshot`](TravlMapPersistent::snapshot) returns an O(1)
+//! copy for readers to hold, and [`insert_in_place`](TravlMapPersistent::insert_in_place) and
+//! [`remove_in_place`](TravlMapPersistent::remove_in_place) only copy the nodes a snapshot still shares,
+//! moving the entries of the others.
+//!
+//! Operations returning new maps are unwind-safe: since changes are built as new nodes next to the existing
+//! ones, a panic of [`Ord`], of a comparison function or of an [`Augmentation`] leaves the original map
+//! untouched, only dropping the nodes copied so far. In-place operations move the nodes out of the map
+//! instead, so such a panic leaves it empty, though still valid.
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Write};
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+
+use crate::checksum;
+use crate::core::{AVLRotation, BalanceFactor, MAX_HEIGHT, Metrics, check_imbalance_factor};
+use crate::error::TravlError;
+#[cfg(feature = "async")]
+use crate::map::IterStream;
+#[cfg(feature = "metrics")]
+use crate::metrics::TreeMetrics;
+use crate::observer::TreeObserver;
+use crate::stats::{NodeShape, StructuralDiff, TreeStats};
+use crate::validation::Violation;
+
+type Link<K, V, A> = Option<Arc<PersistentNode<K, V, A>>>;
+
+/// Entry and children of a node taken apart, see [`into_parts`]
+type Parts<K, V, A> = (K, V, Link<K, V, A>, Link<K, V, A>);
+
+/// Observers of a map, shared by all of its versions
+type Observers<K> = Arc<[Arc<dyn TreeObserver<K>>]>;
+
+/// Function checking the invariants of the maps derived from a map, see
+/// [`TravlMapPersistent::with_validation`]
+type Validator<K, V, A> = fn(&TravlMapPersistent<K, V, A>);
+
+/// Value maintained for every subtree of a [`TravlMapPersistent`], summarizing the entries of the subtree
+///
+/// Augmentations are computed out of the augmentations of a node's children whenever the node is created,
+/// including by rotations, so they are always up to date. Maps that don't need any use `()`.
+pub trait Augmentation<K, V>: Clone {
+    /// Returns the augmentation of a single entry
+    fn from_entry(key: &K, value: &V) -> Self;
+
+    /// Combines the augmentations of two consecutive groups of entries, `self` being the one ordered first
+    #[must_use]
+    fn combine(&self, next: &Self) -> Self;
+}
+
+impl<K, V> Augmentation<K, V> for () {
+    fn from_entry(_key: &K, _value: &V) -> Self {}
+
+    fn combine(&self, _next: &Self) -> Self {}
+}
+
+/// Immutable node of a [`TravlMapPersistent`]
+pub(crate) struct PersistentNode<K, V, A> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+    /// Number of levels of the subtree of the node, 1 for leaves
+    height: u64,
+    /// Number of entries of the subtree of the node
+    size: usize,
+    /// Augmentation of the subtree of the node
+    pub(crate) augmentation: A,
+    pub(crate) left: Link<K, V, A>,
+    pub(crate) right: Link<K, V, A>,
+}
+
+impl<K, V, A> PersistentNode<K, V, A>
+where
+    A: Augmentation<K, V>
+{
+    /// Creates a node out of its entry and children, computing its height and augmentation
+    fn new(key: K, value: V, left: Link<K, V, A>, right: Link<K, V, A>) -> Arc<Self> {
+        // Heights never exceed MAX_HEIGHT, which leaves room for one more level
+        let height = height(left.as_ref()).max(height(right.as_ref())) + 1;
+        assert!(height <= MAX_HEIGHT, "{}", TravlError::HeightLimitExceeded);
+        Self::with_height(key, value, height, left, right)
+    }
+
+    /// Creates a node out of its entry, children and known height, computing its size and augmentation
+    fn with_height(key: K, value: V, height: u64, left: Link<K, V, A>, right: Link<K, V, A>) -> Arc<Self> {
+        let size = size(left.as_ref()) + size(right.as_ref()) + 1;
+        let mut augmentation = A::from_entry(&key, &value);
+
+        if let Some(left) = &left {
+            augmentation = left.augmentation.combine(&augmentation);
+        }
+        if let Some(right) = &right {
+            augmentation = augmentation.combine(&right.augmentation);
+        }
+
+        Arc::new(Self {
+            key,
+            value,
+            height,
+            size,
+            augmentation,
+            left,
+            right,
+        })
+    }
+}
+
+/// Returns whether the key is ordered before the given start bound
+fn is_before_start<Q>(key: &Q, start: Bound<&Q>) -> bool
+where
+    Q: Ord + ?Sized
+{
+    match start {
+        Bound::Included(start) => key < start,
+        Bound::Excluded(start) => key <= start,
+        Bound::Unbounded => false,
+    }
+}
+
+/// Returns whether the key is ordered after the given end bound
+fn is_after_end<Q>(key: &Q, end: Bound<&Q>) -> bool
+where
+    Q: Ord + ?Sized
+{
+    match end {
+        Bound::Included(end) => key > end,
+        Bound::Excluded(end) => key >= end,
+        Bound::Unbounded => false,
+    }
+}
+
+/// Returns the augmentation of the entries of the given subtree whose keys are within the given bounds
+///
+/// Once a node within the bounds is found, each of its subtrees is only bounded on one side, and the
+/// recursion follows a single path within each of them.
+fn aggregate<K, V, A, Q>(node: Option<&PersistentNode<K, V, A>>, start: Bound<&Q>, end: Bound<&Q>) -> Option<A>
+where
+    K: Borrow<Q>,
+    A: Augmentation<K, V>,
+    Q: Ord + ?Sized
+{
+    let node = node?;
+
+    if let (Bound::Unbounded, Bound::Unbounded) = (start, end) {
+        return Some(node.augmentation.clone());
+    }
+
+    let key = node.key.borrow();
+    if is_before_start(key, start) {
+        return aggregate(node.right.as_deref(), start, end);
+    }
+    if is_after_end(key, end) {
+        return aggregate(node.left.as_deref(), start, end);
+    }
+
+    let own = A::from_entry(&node.key, &node.value);
+    let left = aggregate(node.left.as_deref(), start, Bound::Unbounded);
+    let right = aggregate(node.right.as_deref(), Bound::Unbounded, end);
+
+    let with_left = match left {
+        Some(left) => left.combine(&own),
+        None => own,
+    };
+
+    match right {
+        Some(right) => Some(with_left.combine(&right)),
+        None => Some(with_left),
+    }
+}
+
+/// Panics with the violations of the invariants of the map and a dump of its tree, if there are any
+fn assert_valid<K, V, A>(map: &TravlMapPersistent<K, V, A>)
+where
+    K: Ord + Clone + Debug,
+    V: Debug
+{
+    let violations = map.validate();
+    if violations.is_empty() {
+        return;
+    }
+
+    let mut report = String::from("invalid tree:\n");
+    for violation in &violations {
+        let _ = writeln!(report, "- {violation}");
+    }
+    report.push('\n');
+    dump(map.root.as_deref(), 0, &mut report);
+
+    panic!("{report}");
+}
+
+/// Writes every node of the given subtree on its own line, indented by depth, in key order
+fn dump<K, V, A>(node: Option<&PersistentNode<K, V, A>>, depth: usize, out: &mut String)
+where
+    K: Debug,
+    V: Debug
+{
+    let Some(node) = node else {
+        return;
+    };
+
+    dump(node.left.as_deref(), depth + 1, out);
+    let _ = writeln!(
+        out,
+        "{:indent$}{:?}: {:?} (height {}, size {})",
+        "",
+        node.key,
+        node.value,
+        node.height,
+        node.size,
+        indent = depth * 2
+    );
+    dump(node.right.as_deref(), depth + 1, out);
+}
+
+/// Returns the end bound of the keys ordered before the given start bound, [`None`] if there are none
+fn end_before<Q>(start: Bound<&Q>) -> Option<Bound<&Q>>
+where
+    Q: ?Sized
+{
+    match start {
+        Bound::Included(start) => Some(Bound::Excluded(start)),
+        Bound::Excluded(start) => Some(Bound::Included(start)),
+        Bound::Unbounded => None,
+    }
+}
+
+/// Returns the start bound of the keys ordered after the given end bound, [`None`] if there are none
+fn start_after<Q>(end: Bound<&Q>) -> Option<Bound<&Q>>
+where
+    Q: ?Sized
+{
+    end_before(end)
+}
+
+/// Takes a node apart into its entry and children, moving them out of the node if no other map shares it and
+/// cloning them otherwise
+fn into_parts<K, V, A>(node: Arc<PersistentNode<K, V, A>>) -> Parts<K, V, A>
+where
+    K: Clone,
+    V: Clone
+{
+    match Arc::try_unwrap(node) {
+        Ok(node) => (node.key, node.value, node.left, node.right),
+        Err(node) => (node.key.clone(), node.value.clone(), node.left.clone(), node.right.clone()),
+    }
+}
+
+/// Returns whether the first height exceeds the second by more than the given difference
+///
+/// Heights are subtracted rather than added to the difference, which can't overflow.
+fn exceeds(higher: u64, lower: u64, max_difference: u64) -> bool {
+    higher.checked_sub(lower).is_some_and(|difference| difference > max_difference)
+}
+
+/// Returns the height of the subtree of the given node, 0 if there is none
+fn height<K, V, A>(node: Option<&Arc<PersistentNode<K, V, A>>>) -> u64 {
+    node.map_or(0, |node| node.height)
+}
+
+/// Returns the number of entries of the subtree of the given node, 0 if there is none
+fn size<K, V, A>(node: Option<&Arc<PersistentNode<K, V, A>>>) -> usize {
+    node.map_or(0, |node| node.size)
+}
+
+/// Appends the shapes of the nodes of a subtree in key order, returning the span of the subtree
+fn push_shapes<'m, K, V, A>(
+    node: &'m PersistentNode<K, V, A>,
+    depth: usize,
+    shapes: &mut Vec<NodeShape<'m, K>>,
+) -> (&'m K, &'m K) {
+    let first = match node.left.as_deref() {
+        Some(left) => push_shapes(left, depth + 1, shapes).0,
+        None => &node.key,
+    };
+
+    let index = shapes.len();
+    shapes.push(NodeShape {
+        key: &node.key,
+        depth,
+        height: node.height,
+        span: (first, &node.key),
+    });
+
+    let last = match node.right.as_deref() {
+        Some(right) => push_shapes(right, depth + 1, shapes).1,
+        None => &node.key,
+    };
+
+    shapes[index].span.1 = last;
+    (first, last)
+}
+
+/// Value kept for a key present in both maps given to [`TravlMapPersistent::merge_with`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resolution<V> {
+    /// Keep the value of the map being merged into
+    KeepMine,
+    /// Keep the value of the other map
+    KeepTheirs,
+    /// Keep the given value, usually combining both values
+    Combined(V),
+}
+
+/// Returns the keys of both maps in order, each key once
+fn union_keys<'m, K, V, A>(
+    first: &'m TravlMapPersistent<K, V, A>,
+    second: &'m TravlMapPersistent<K, V, A>,
+) -> impl Iterator<Item = &'m K>
+where
+    K: Ord
+{
+    let mut first = first.iter().map(|(key, _)| key).peekable();
+    let mut second = second.iter().map(|(key, _)| key).peekable();
+
+    core::iter::from_fn(move || match (first.peek(), second.peek()) {
+        (Some(first_key), Some(second_key)) => match first_key.cmp(second_key) {
+            Ordering::Less => first.next(),
+            Ordering::Greater => second.next(),
+            Ordering::Equal => {
+                second.next();
+                first.next()
+            },
+        },
+        (Some(_), None) => first.next(),
+        (None, _) => second.next(),
+    })
+}
+
+/// Key changed differently on both sides of a [three-way merge](TravlMapPersistent::merge3)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeConflict<K, V> {
+    key: K,
+    base: Option<V>,
+    left: Option<V>,
+    right: Option<V>,
+}
+
+impl<K, V> MergeConflict<K, V> {
+    /// Returns the conflicting key
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the value of the key in the common ancestor, [`None`] if it didn't exist
+    #[must_use]
+    pub fn base(&self) -> Option<&V> {
+        self.base.as_ref()
+    }
+
+    /// Returns the value of the key on the left side, [`None`] if it was removed or never existed
+    #[must_use]
+    pub fn left(&self) -> Option<&V> {
+        self.left.as_ref()
+    }
+
+    /// Returns the value of the key on the right side, [`None`] if it was removed or never existed
+    #[must_use]
+    pub fn right(&self) -> Option<&V> {
+        self.right.as_ref()
+    }
+}
+
+/// Persistent map ordered by key, see the [module-level documentation](self)
+///
+/// Every subtree of the map maintains an [`Augmentation`] of type `A`, none by default.
+pub struct TravlMapPersistent<K, V, A = ()> {
+    imbalance_factor: u64,
+    root: Link<K, V, A>,
+    len: usize,
+    metrics: Metrics,
+    observers: Observers<K>,
+    validator: Option<Validator<K, V, A>>,
+}
+
+impl<K, V> TravlMapPersistent<K, V> {
+    /// Creates an empty map
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_imbalance_factor(0)
+    }
+
+    /// Creates an empty map using the given imbalance factor
+    ///
+    /// The heights of the subtrees of any node differ by at most `imbalance_factor + 1`, so an imbalance
+    /// factor of 0 gives a classic AVL tree while larger ones trade lookup speed for fewer rotations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the imbalance factor exceeds [`MAX_IMBALANCE_FACTOR`](crate::core::MAX_IMBALANCE_FACTOR), see
+    /// [`try_with_imbalance_factor`](Self::try_with_imbalance_factor) for a fallible version.
+    #[must_use]
+    pub fn with_imbalance_factor(imbalance_factor: u64) -> Self {
+        Self::new_augmented_with_imbalance_factor(imbalance_factor)
+    }
+
+    /// Creates an empty map using the given imbalance factor, if it doesn't exceed
+    /// [`MAX_IMBALANCE_FACTOR`](crate::core::MAX_IMBALANCE_FACTOR)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::ImbalanceFactorTooLarge`] if the imbalance factor exceeds the maximum.
+    pub fn try_with_imbalance_factor(imbalance_factor: u64) -> Result<Self, TravlError> {
+        Self::try_new_augmented_with_imbalance_factor(imbalance_factor)
+    }
+}
+
+impl<K, V, A> TravlMapPersistent<K, V, A> {
+    /// Creates an empty map maintaining an augmentation of type `A`
+    #[must_use]
+    pub fn new_augmented() -> Self {
+        Self::new_augmented_with_imbalance_factor(0)
+    }
+
+    /// Creates an empty map maintaining an augmentation of type `A`, using the given imbalance factor
+    ///
+    /// See [`with_imbalance_factor`](TravlMapPersistent::with_imbalance_factor) about the imbalance factor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the imbalance factor exceeds [`MAX_IMBALANCE_FACTOR`](crate::core::MAX_IMBALANCE_FACTOR).
+    #[must_use]
+    pub fn new_augmented_with_imbalance_factor(imbalance_factor: u64) -> Self {
+        Self::try_new_augmented_with_imbalance_factor(imbalance_factor).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates an empty map maintaining an augmentation of type `A`, using the given imbalance factor if it
+    /// doesn't exceed [`MAX_IMBALANCE_FACTOR`](crate::core::MAX_IMBALANCE_FACTOR)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::ImbalanceFactorTooLarge`] if the imbalance factor exceeds the maximum.
+    pub fn try_new_augmented_with_imbalance_factor(imbalance_factor: u64) -> Result<Self, TravlError> {
+        Ok(Self {
+            imbalance_factor: check_imbalance_factor(imbalance_factor)?,
+            root: None,
+            len: 0,
+            metrics: Metrics::default(),
+            observers: Arc::new([]),
+            validator: None,
+        })
+    }
+
+    /// Returns the imbalance factor
+    #[must_use]
+    pub fn imbalance_factor(&self) -> u64 {
+        self.imbalance_factor
+    }
+
+    /// Returns the number of entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the map is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the counters of the work performed by the insertions and removals that led to this map
+    ///
+    /// Counters are carried over from a map to the maps created out of it, lookups aren't counted.
+    #[cfg(feature = "metrics")]
+    #[must_use]
+    pub fn metrics(&self) -> &TreeMetrics {
+        &self.metrics
+    }
+
+    /// Returns the height of the tree, 0 for an empty map
+    #[must_use]
+    pub fn height(&self) -> u64 {
+        height(self.root.as_ref())
+    }
+
+    /// Gathers the structural statistics of the tree, see [`TreeStats`]
+    #[must_use]
+    pub fn stats(&self) -> TreeStats {
+        // Subtrees may differ by `imbalance_factor + 1` levels here, which is still within the allowed imbalance
+        let allowed_imbalance = self.max_difference();
+        let mut stats = TreeStats::default();
+        let mut stack: Vec<_> = self.root.as_deref().map(|root| (root, 0)).into_iter().collect();
+
+        while let Some((node, depth)) = stack.pop() {
+            let (left, right) = (node.left.as_ref(), node.right.as_ref());
+            let balance_factor = BalanceFactor::from_heights(height(left), height(right), allowed_imbalance);
+            stats.record(depth, left.is_none() && right.is_none(), balance_factor);
+
+            stack.extend(right.into_iter().chain(left).map(|child| (&**child, depth + 1)));
+        }
+
+        stats
+    }
+
+    /// Returns a stable digest of the entries and shape of the tree, see the [`checksum`] module
+    ///
+    /// Maps holding the same entries in trees of the same shape have the same digest, including in other
+    /// processes.
+    #[must_use]
+    pub fn structural_hash(&self) -> u64
+    where
+        K: Hash,
+        V: Hash
+    {
+        let mut stack = vec![self.root.as_deref()];
+
+        checksum::structural_hash(core::iter::from_fn(|| {
+            let node = stack.pop()?;
+            if let Some(node) = node {
+                stack.extend([node.right.as_deref(), node.left.as_deref()]);
+            }
+
+            Some(node.map(|node| (&node.key, &node.value)))
+        }))
+    }
+
+    /// Checks the invariants of the tree, returning every violation found, see [`Violation`]
+    ///
+    /// Checks that the heights and sizes recorded in the nodes and map are correct, that heights are within the
+    /// imbalance factor, and that keys are strictly increasing in order.
+    #[must_use]
+    pub fn validate(&self) -> Vec<Violation<K>>
+    where
+        K: Ord + Clone
+    {
+        let mut violations = Vec::new();
+        let mut previous = None;
+        let (_, size) = self.validate_node(self.root.as_deref(), &mut previous, &mut violations);
+
+        if size != self.len {
+            violations.push(Violation::WrongSize { key: None, size: self.len, expected: size });
+        }
+        violations
+    }
+
+    /// Checks the invariants of the given subtree, returning its actual height and size
+    fn validate_node<'m>(
+        &self,
+        node: Option<&'m PersistentNode<K, V, A>>,
+        previous: &mut Option<&'m K>,
+        violations: &mut Vec<Violation<K>>,
+    ) -> (u64, usize)
+    where
+        K: Ord + Clone
+    {
+        let Some(node) = node else {
+            return (0, 0);
+        };
+
+        let (left_height, left_size) = self.validate_node(node.left.as_deref(), previous, violations);
+
+        if let Some(previous) = previous.replace(&node.key)
+            && previous >= &node.key
+        {
+            violations.push(Violation::OutOfOrder { previous: previous.clone(), key: node.key.clone() });
+        }
+
+        let (right_height, right_size) = self.validate_node(node.right.as_deref(), previous, violations);
+        let (expected_height, expected_size) = (left_height.max(right_height) + 1, left_size + right_size + 1);
+
+        if node.height != expected_height {
+            let key = node.key.clone();
+            violations.push(Violation::WrongHeight { key, height: node.height, expected: expected_height });
+        }
+        if node.size != expected_size {
+            let key = Some(node.key.clone());
+            violations.push(Violation::WrongSize { key, size: node.size, expected: expected_size });
+        }
+
+        let difference = left_height.abs_diff(right_height);
+        if difference > self.max_difference() {
+            violations.push(Violation::Imbalanced { key: node.key.clone(), difference });
+        }
+
+        (expected_height, expected_size)
+    }
+
+    /// Compares the shape of the tree with the one of another map, see [`StructuralDiff`]
+    ///
+    /// Maps holding the same entries can be shaped differently depending on the order of the insertions and
+    /// removals that built them, which changes the cost of their lookups.
+    #[must_use]
+    pub fn structural_diff<'m>(&'m self, other: &'m Self) -> StructuralDiff<'m, K>
+    where
+        K: Ord
+    {
+        let shapes = |map: &'m Self| {
+            let mut shapes = Vec::with_capacity(map.len);
+            if let Some(root) = map.root.as_deref() {
+                push_shapes(root, 0, &mut shapes);
+            }
+            shapes
+        };
+
+        StructuralDiff::compare(shapes(self), shapes(other))
+    }
+
+    /// Registers an observer notified of the insertions, removals and rotations of the maps derived from this
+    /// one, see [`TreeObserver`]
+    ///
+    /// Observers are carried over to every map returned by [`insert`](Self::insert) and
+    /// [`remove`](Self::remove), but not to maps collected or created otherwise.
+    #[must_use]
+    pub fn with_observer<O>(mut self, observer: Arc<O>) -> Self
+    where
+        O: TreeObserver<K> + 'static
+    {
+        let observer: Arc<dyn TreeObserver<K>> = observer;
+        self.observers = self.observers.iter().cloned().chain([observer]).collect();
+        self
+    }
+
+    /// Makes the maps derived from this one check their invariants, panicking as soon as one is broken
+    ///
+    /// Every map returned by an insertion, removal or range operation is [validated](Self::validate), and any
+    /// violation panics with the violations and a dump of the tree. Validation walks the whole tree, so it is
+    /// meant for debug builds, for example enabled when `cfg!(debug_assertions)` holds.
+    #[must_use]
+    pub fn with_validation(mut self) -> Self
+    where
+        K: Ord + Clone + Debug,
+        V: Debug
+    {
+        self.validator = Some(assert_valid);
+        self
+    }
+
+    /// Returns the augmentation of the whole map, [`None`] for an empty map
+    #[must_use]
+    pub fn augmentation(&self) -> Option<&A> {
+        self.root.as_ref().map(|root| &root.augmentation)
+    }
+
+    /// Returns the root node
+    pub(crate) fn root(&self) -> Option<&PersistentNode<K, V, A>> {
+        self.root.as_deref()
+    }
+
+    /// Returns the largest allowed difference between the heights of the subtrees of a node
+    fn max_difference(&self) -> u64 {
+        // Can't overflow, constructors don't accept imbalance factors beyond MAX_IMBALANCE_FACTOR
+        self.imbalance_factor + 1
+    }
+
+    /// Validates the map if validation was enabled, see [`with_validation`](Self::with_validation)
+    fn checked(self) -> Self {
+        if let Some(validate) = self.validator {
+            validate(&self);
+        }
+        self
+    }
+
+    /// Validates the map after it was changed in place if validation was enabled
+    fn check_in_place(&self) {
+        if let Some(validate) = self.validator {
+            validate(self);
+        }
+    }
+
+    /// Starts an insertion or removal on this map
+    fn update(&self) -> Update<'_, K> {
+        Update {
+            max_difference: self.max_difference(),
+            metrics: self.metrics,
+            observers: &self.observers,
+        }
+    }
+
+    /// Returns an iterator over the entries, ordered by key
+    #[must_use]
+    pub fn iter(&self) -> PersistentIter<'_, K, V, A> {
+        let mut iter = PersistentIter {
+            stack: Vec::new(),
+            remaining: self.len,
+        };
+        iter.push_left_branch(self.root.as_deref());
+        iter
+    }
+
+    /// Returns an iterator over the entries whose keys are within the given range, ordered by key
+    ///
+    /// Subtrees before the start of the range are skipped, so the iterator starts in O(log n).
+    #[must_use]
+    pub fn range<Q, R>(&self, range: R) -> PersistentRange<'_, K, V, Q, R, A>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>
+    {
+        let mut stack = Vec::new();
+        let mut current = self.root.as_deref();
+
+        // Nodes before the start are skipped along with their left subtree
+        while let Some(node) = current {
+            if is_before_start(node.key.borrow(), range.start_bound()) {
+                current = node.right.as_deref();
+            } else {
+                stack.push(node);
+                current = node.left.as_deref();
+            }
+        }
+
+        PersistentRange {
+            stack,
+            range,
+            key: PhantomData,
+        }
+    }
+
+    /// Returns a stream over the entries, ordered by key
+    ///
+    /// See [`IterStream`] for how the stream cooperates with the executor.
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub fn stream(&self) -> IterStream<PersistentIter<'_, K, V, A>> {
+        IterStream::new(self.iter())
+    }
+
+    /// Returns a stream over the entries whose keys are within the given range, ordered by key
+    ///
+    /// See [`range`](Self::range) and [`IterStream`].
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub fn range_stream<Q, R>(&self, range: R) -> IterStream<PersistentRange<'_, K, V, Q, R, A>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>
+    {
+        IterStream::new(self.range(range))
+    }
+
+    /// Returns the entry with the lowest key, in O(log n)
+    #[must_use]
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns the entry with the greatest key, in O(log n)
+    #[must_use]
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns the entry at the given position in key order, the first entry being at position 0
+    ///
+    /// Every node knows the number of entries of its subtree, so this runs in O(log n).
+    #[must_use]
+    pub fn select(&self, mut rank: usize) -> Option<(&K, &V)> {
+        let mut current = self.root.as_deref();
+
+        while let Some(node) = current {
+            let left_size = size(node.left.as_ref());
+            current = match rank.cmp(&left_size) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Greater => {
+                    rank -= left_size + 1;
+                    node.right.as_deref()
+                },
+            };
+        }
+
+        None
+    }
+
+    /// Returns the number of entries whose keys are ordered before the given key, in O(log n)
+    #[must_use]
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        let mut rank = 0;
+        let mut current = self.root.as_deref();
+
+        while let Some(node) = current {
+            if key > node.key.borrow() {
+                rank += size(node.left.as_ref()) + 1;
+                current = node.right.as_deref();
+            } else {
+                current = node.left.as_deref();
+            }
+        }
+
+        rank
+    }
+
+    /// Returns the entry at the given fraction of the key order, `0.0` giving the first entry and `1.0`
+    /// the last one
+    ///
+    /// Fractions falling between two entries give the lower one. Runs in O(log n), see
+    /// [`select`](Self::select).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` isn't between 0 and 1.
+    #[must_use]
+    pub fn quantile(&self, q: f64) -> Option<(&K, &V)> {
+        assert!((0.0..=1.0).contains(&q), "quantile must be between 0 and 1");
+
+        #[allow(
+            clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "the rank is between 0 and the length of the map, positions beyond 2^53 don't need to be exact"
+        )]
+        // Truncating the rank rounds it down, since it isn't negative
+        let rank = (self.len.saturating_sub(1) as f64 * q) as usize;
+        self.select(rank)
+    }
+
+    /// Returns the entry at the given percentage of the key order, see [`quantile`](Self::quantile)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` isn't between 0 and 100.
+    #[must_use]
+    pub fn percentile(&self, p: f64) -> Option<(&K, &V)> {
+        assert!((0.0..=100.0).contains(&p), "percentile must be between 0 and 100");
+
+        self.quantile(p / 100.0)
+    }
+
+    /// Returns the entry in the middle of the key order, the lower one for maps of even length
+    #[must_use]
+    pub fn median(&self) -> Option<(&K, &V)> {
+        self.quantile(0.5)
+    }
+
+    /// Returns the augmentation of the entries whose keys are within the given range, [`None`] if there are
+    /// none
+    ///
+    /// Subtrees fully within the range contribute their maintained augmentation, so this runs in O(log n)
+    /// whatever the number of entries in the range. Augmentations are combined in key order, which allows
+    /// non-commutative ones.
+    #[must_use]
+    pub fn range_aggregate<Q, R>(&self, range: R) -> Option<A>
+    where
+        K: Borrow<Q>,
+        A: Augmentation<K, V>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>
+    {
+        aggregate(self.root.as_deref(), range.start_bound(), range.end_bound())
+    }
+
+    /// Returns the value associated to the given key, if it exists
+    #[must_use]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    /// Returns the stored key equal to the given key along with its value, if it exists
+    #[must_use]
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        let mut current = self.root.as_deref();
+
+        while let Some(node) = current {
+            current = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some((&node.key, &node.value)),
+            };
+        }
+
+        None
+    }
+
+    /// Returns whether the map contains the given key
+    #[must_use]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<K, V, A> TravlMapPersistent<K, V, A>
+where
+    K: Ord + Clone,
+    V: Clone,
+    A: Augmentation<K, V>
+{
+    /// Returns a new map with the given entry, replacing the value of the key if it already exists
+    ///
+    /// Only the nodes on the path to the entry are copied, the rest of the tree is shared with this map.
+    #[must_use]
+    pub fn insert(&self, key: K, value: V) -> Self {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("insert", len = self.len, height = self.height()).entered();
+
+        let mut update = self.update();
+        let (root, replaced) = update.insert_into(self.root.as_ref(), key, value, &mut K::cmp);
+
+        let map = Self {
+            imbalance_factor: self.imbalance_factor,
+            root: Some(root),
+            len: if replaced { self.len } else { self.len + 1 },
+            metrics: update.metrics,
+            observers: self.observers.clone(),
+            validator: self.validator,
+        };
+
+        map.checked()
+    }
+
+    /// Returns a new map with the given entry, which must not replace an existing entry
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TravlError::DuplicateKey`] if the key already exists, leaving this map untouched.
+    pub fn try_insert(&self, key: K, value: V) -> Result<Self, TravlError> {
+        if self.contains_key(&key) {
+            return Err(TravlError::DuplicateKey);
+        }
+
+        Ok(self.insert(key, value))
+    }
+
+    /// Returns a new map without the entry of the given key, along with the removed value
+    ///
+    /// Returns a clone of this map if the key doesn't exist.
+    #[must_use]
+    pub fn remove<Q>(&self, key: &Q) -> (Self, Option<V>)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("remove", len = self.len, height = self.height()).entered();
+
+        let mut update = self.update();
+
+        let mut compare = |node_key: &K| key.cmp(node_key.borrow());
+
+        let Some((root, value)) = update.remove_from(self.root.as_ref(), &mut compare) else {
+            let map = Self {
+                metrics: update.metrics,
+                ..self.clone()
+            };
+
+            return (map, None);
+        };
+
+        let map = Self {
+            imbalance_factor: self.imbalance_factor,
+            root,
+            len: self.len - 1,
+            metrics: update.metrics,
+            observers: self.observers.clone(),
+            validator: self.validator,
+        };
+
+        (map.checked(), Some(value))
+    }
+
+    /// Returns a snapshot of the map in O(1), sharing all of its nodes
+    ///
+    /// Snapshots are logically immutable copies: readers can hold them, on other threads too, while this map
+    /// keeps changing through [`insert_in_place`](Self::insert_in_place) and
+    /// [`remove_in_place`](Self::remove_in_place), which copy the nodes a snapshot still shares before
+    /// changing them.
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// Inserts the given entry into this map, replacing the value of the key if it already exists and
+    /// returning the replaced value
+    ///
+    /// Nodes on the path to the entry are copied if another map, such as a [snapshot](Self::snapshot), shares
+    /// them, and their entries are moved otherwise, so a map holding its nodes alone doesn't clone any key or
+    /// value. A panic of [`Ord`] or of an [`Augmentation`] leaves the map empty, since its nodes were taken out
+    /// of it.
+    pub fn insert_in_place(&mut self, key: K, value: V) -> Option<V> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("insert_in_place", len = self.len, height = self.height()).entered();
+
+        let mut update = Update {
+            max_difference: self.max_difference(),
+            metrics: self.metrics,
+            observers: &self.observers,
+        };
+        // The map is left empty rather than holding a stale length if the update panics
+        let len = mem::take(&mut self.len);
+        let (root, replaced) = update.insert_owned(self.root.take(), key, value);
+
+        self.metrics = update.metrics;
+        self.root = Some(root);
+        self.len = if replaced.is_none() { len + 1 } else { len };
+        self.check_in_place();
+
+        replaced
+    }
+
+    /// Removes the entry of the given key from this map, returning the removed value
+    ///
+    /// Nodes are copied or moved as [`insert_in_place`](Self::insert_in_place) does, panics leaving the map
+    /// empty likewise, and the map is left untouched if the key doesn't exist.
+    pub fn remove_in_place<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("remove_in_place", len = self.len, height = self.height()).entered();
+
+        if !self.contains_key(key) {
+            return None;
+        }
+
+        let mut update = Update {
+            max_difference: self.max_difference(),
+            metrics: self.metrics,
+            observers: &self.observers,
+        };
+        // The map is left empty rather than holding a stale length if the update panics
+        let len = mem::take(&mut self.len);
+        let (root, removed) = update.remove_owned(self.root.take(), &mut |node_key: &K| key.cmp(node_key.borrow()));
+
+        self.metrics = update.metrics;
+        self.root = root;
+        self.len = len - 1;
+        self.check_in_place();
+
+        removed
+    }
+
+    /// Returns a new map holding the entries of both maps, resolving the keys present in both with the
+    /// given closure
+    ///
+    /// The closure is given each key present in both maps, in key order, along with the value of this map
+    /// and the value of `other`. Runs in O(m log(n + m)) for m entries in `other`.
+    #[must_use]
+    pub fn merge_with<F>(&self, other: &Self, mut resolve: F) -> Self
+    where
+        F: FnMut(&K, &V, &V) -> Resolution<V>
+    {
+        let mut merged = self.clone();
+
+        for (key, theirs) in other {
+            let value = match self.get(key) {
+                None => theirs.clone(),
+                Some(mine) => match resolve(key, mine, theirs) {
+                    Resolution::KeepMine => continue,
+                    Resolution::KeepTheirs => theirs.clone(),
+                    Resolution::Combined(value) => value,
+                },
+            };
+
+            merged = merged.insert(key.clone(), value);
+        }
+
+        merged
+    }
+
+    /// Merges the changes made to `left` and `right` since their common ancestor `base`, returning the merged
+    /// map along with the conflicting keys
+    ///
+    /// A key changed on one side only takes the value of that side, or is removed if that side removed it.
+    /// A key changed the same way on both sides takes that change. A key changed differently on both sides
+    /// is a conflict: it keeps its state in `base` and is reported, ordered by key, for the caller to
+    /// resolve.
+    ///
+    /// The merged map is built out of `left`, and runs in O(m log n) for m keys in `base` and `right`.
+    #[must_use]
+    pub fn merge3(base: &Self, left: &Self, right: &Self) -> (Self, Vec<MergeConflict<K, V>>)
+    where
+        V: PartialEq
+    {
+        let mut merged = left.clone();
+        let mut conflicts = Vec::new();
+
+        for key in union_keys(base, right) {
+            let (base_value, left_value, right_value) = (base.get(key), left.get(key), right.get(key));
+
+            // Keys unchanged on the right, or changed the same way on both sides, stay as they are on the left
+            if right_value == base_value || right_value == left_value {
+                continue;
+            }
+
+            let target = if left_value == base_value {
+                right_value
+            } else {
+                conflicts.push(MergeConflict {
+                    key: key.clone(),
+                    base: base_value.cloned(),
+                    left: left_value.cloned(),
+                    right: right_value.cloned(),
+                });
+                base_value
+            };
+
+            merged = match target {
+                Some(value) => merged.insert(key.clone(), value.clone()),
+                None => merged.remove(key).0,
+            };
+        }
+
+        (merged, conflicts)
+    }
+
+    /// Returns a new map whose entries within the given range are copied from `other`, replacing those of
+    /// this map
+    ///
+    /// Subtrees of `other` fully within the range are shared rather than copied, and the map is split and
+    /// joined around the range in O(log n + log m). Observers aren't notified of the copied entries, unless
+    /// the maps have different imbalance factors: entries are then inserted one by one, since the subtrees
+    /// of `other` may be too imbalanced for this map.
+    #[must_use]
+    pub fn copy_range_from<Q, R>(&self, other: &Self, range: R) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>
+    {
+        let (start, end) = (range.start_bound(), range.end_bound());
+        let mut update = self.update();
+
+        let copied = if other.imbalance_factor == self.imbalance_factor {
+            update.extract(other.root.as_ref(), start, end)
+        } else {
+            other.range((start, end)).fold(None, |tree, (key, value)| {
+                Some(update.insert_into(tree.as_ref(), key.clone(), value.clone(), &mut K::cmp).0)
+            })
+        };
+
+        let before = end_before(start).and_then(|before| update.extract(self.root.as_ref(), Bound::Unbounded, before));
+        let after = start_after(end).and_then(|after| update.extract(self.root.as_ref(), after, Bound::Unbounded));
+        let root = update.concat(before, copied);
+        let root = update.concat(root, after);
+
+        let map = Self {
+            imbalance_factor: self.imbalance_factor,
+            len: size(root.as_ref()),
+            root,
+            metrics: update.metrics,
+            observers: self.observers.clone(),
+            validator: self.validator,
+        };
+
+        map.checked()
+    }
+
+    /// Returns a new map without the entries within the given range, along with a map of these entries
+    ///
+    /// Both maps share the subtrees of this map fully within or outside of the range, and are built in
+    /// O(log n). Observers aren't notified of the removed entries, and aren't carried over to the map of
+    /// the removed entries, unlike [validation](Self::with_validation).
+    #[must_use]
+    pub fn take_range<Q, R>(&self, range: R) -> (Self, Self)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>
+    {
+        let (start, end) = (range.start_bound(), range.end_bound());
+        let mut update = self.update();
+
+        let taken = update.extract(self.root.as_ref(), start, end);
+        let before = end_before(start).and_then(|before| update.extract(self.root.as_ref(), Bound::Unbounded, before));
+        let after = start_after(end).and_then(|after| update.extract(self.root.as_ref(), after, Bound::Unbounded));
+        let rest = update.concat(before, after);
+
+        let taken = Self {
+            imbalance_factor: self.imbalance_factor,
+            len: size(taken.as_ref()),
+            root: taken,
+            metrics: Metrics::default(),
+            observers: Arc::new([]),
+            validator: self.validator,
+        };
+        let rest = Self {
+            imbalance_factor: self.imbalance_factor,
+            len: size(rest.as_ref()),
+            root: rest,
+            metrics: update.metrics,
+            observers: self.observers.clone(),
+            validator: self.validator,
+        };
+
+        (rest.checked(), taken.checked())
+    }
+}
+
+impl<K, V, A> TravlMapPersistent<K, V, A>
+where
+    K: Clone,
+    V: Clone,
+    A: Augmentation<K, V>
+{
+    /// Returns the value associated to the given key, comparing keys with a fallible function
+    ///
+    /// The function compares the given key to the keys of the map, and must order keys the same way the map
+    /// does: it's meant for comparisons which can fail, for example because they need I/O or can detect
+    /// invalid data.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the comparison function.
+    pub fn try_get_by<F, E>(&self, key: &K, mut compare: F) -> Result<Option<&V>, E>
+    where
+        F: FnMut(&K, &K) -> Result<Ordering, E>
+    {
+        let mut current = self.root.as_deref();
+
+        while let Some(node) = current {
+            current = match compare(key, &node.key)? {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Ok(Some(&node.value)),
+            };
+        }
+
+        Ok(None)
+    }
+
+    /// Returns a new map with the given entry, comparing keys with a fallible function
+    ///
+    /// See [`try_get_by`](Self::try_get_by) about the comparison function. Keys are all compared before the
+    /// new map is built, so a failing comparison doesn't copy any node.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the comparison function.
+    #[allow(clippy::missing_panics_doc, reason = "the tree is walked along the path recorded on it")]
+    pub fn try_insert_by<F, E>(&self, key: K, value: V, compare: F) -> Result<Self, E>
+    where
+        F: FnMut(&K, &K) -> Result<Ordering, E>
+    {
+        let mut path = self.path_to(&key, compare)?.into_iter();
+        let mut update = self.update();
+        let (root, replaced) = update.insert_into(self.root.as_ref(), key, value, &mut |_, _| {
+            path.next().expect("the path should lead to the position of the key")
+        });
+
+        let map = Self {
+            imbalance_factor: self.imbalance_factor,
+            root: Some(root),
+            len: if replaced { self.len } else { self.len + 1 },
+            metrics: update.metrics,
+            observers: self.observers.clone(),
+            validator: self.validator,
+        };
+
+        Ok(map.checked())
+    }
+
+    /// Returns a new map without the entry of the given key, along with the removed value, comparing keys
+    /// with a fallible function
+    ///
+    /// See [`try_insert_by`](Self::try_insert_by) about the comparison function.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the comparison function.
+    #[allow(clippy::missing_panics_doc, reason = "the tree is walked along the path recorded on it")]
+    pub fn try_remove_by<F, E>(&self, key: &K, compare: F) -> Result<(Self, Option<V>), E>
+    where
+        F: FnMut(&K, &K) -> Result<Ordering, E>
+    {
+        let mut path = self.path_to(key, compare)?.into_iter();
+        let mut update = self.update();
+        let mut follow_path = |_: &K| path.next().expect("the path should lead to the position of the key");
+
+        let Some((root, value)) = update.remove_from(self.root.as_ref(), &mut follow_path) else {
+            let map = Self {
+                metrics: update.metrics,
+                ..self.clone()
+            };
+
+            return Ok((map, None));
+        };
+
+        let map = Self {
+            imbalance_factor: self.imbalance_factor,
+            root,
+            len: self.len - 1,
+            metrics: update.metrics,
+            observers: self.observers.clone(),
+            validator: self.validator,
+        };
+
+        Ok((map.checked(), Some(value)))
+    }
+
+    /// Compares the given key to the keys on the path leading to its position, returning the orderings
+    fn path_to<F, E>(&self, key: &K, mut compare: F) -> Result<Vec<Ordering>, E>
+    where
+        F: FnMut(&K, &K) -> Result<Ordering, E>
+    {
+        let mut path = Vec::with_capacity(usize::try_from(self.height()).unwrap_or_default());
+        let mut current = self.root.as_deref();
+
+        while let Some(node) = current {
+            let ordering = compare(key, &node.key)?;
+            path.push(ordering);
+
+            current = match ordering {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => None,
+            };
+        }
+
+        Ok(path)
+    }
+}
+
+/// State of a single insertion or removal
+struct Update<'m, K> {
+    /// Largest allowed difference between the heights of the subtrees of a node
+    max_difference: u64,
+    metrics: Metrics,
+    observers: &'m [Arc<dyn TreeObserver<K>>],
+}
+
+impl<K> Update<'_, K> {
+    /// Notifies every observer of an event
+    fn notify(&self, event: impl Fn(&dyn TreeObserver<K>)) {
+        for observer in self.observers {
+            event(observer.as_ref());
+        }
+    }
+
+    /// Creates a node out of its entry and children
+    fn node<V, A>(
+        &mut self,
+        key: K,
+        value: V,
+        left: Link<K, V, A>,
+        right: Link<K, V, A>,
+    ) -> Arc<PersistentNode<K, V, A>>
+    where
+        A: Augmentation<K, V>
+    {
+        self.metrics.record_height_recomputation();
+        PersistentNode::new(key, value, left, right)
+    }
+
+    /// Inserts the entry into the given subtree, returning the new subtree and whether a value was replaced
+    ///
+    /// Keys are compared with the given function, the inserted key first.
+    fn insert_into<V, A, F>(
+        &mut self,
+        node: Option<&Arc<PersistentNode<K, V, A>>>,
+        key: K,
+        value: V,
+        compare: &mut F,
+    ) -> (Arc<PersistentNode<K, V, A>>, bool)
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>,
+        F: FnMut(&K, &K) -> Ordering
+    {
+        let Some(node) = node else {
+            self.notify(|observer| observer.on_insert(&key, false));
+            return (self.node(key, value, None, None), false);
+        };
+
+        self.metrics.record_comparison();
+        match compare(&key, &node.key) {
+            Ordering::Less => {
+                let (left, replaced) = self.insert_into(node.left.as_ref(), key, value, compare);
+                let node = self.relink(node, Some(left), node.right.clone());
+                (node, replaced)
+            },
+            Ordering::Greater => {
+                let (right, replaced) = self.insert_into(node.right.as_ref(), key, value, compare);
+                let node = self.relink(node, node.left.clone(), Some(right));
+                (node, replaced)
+            },
+            Ordering::Equal => {
+                self.notify(|observer| observer.on_insert(&key, true));
+                (self.node(key, value, node.left.clone(), node.right.clone()), true)
+            },
+        }
+    }
+
+    /// Removes the entry of a key from the given subtree, returning the new subtree and the removed value
+    ///
+    /// The removed key is located with the given function, comparing it to the key of each node. Returns
+    /// [`None`] if the key isn't in the subtree.
+    fn remove_from<V, A, F>(
+        &mut self,
+        node: Option<&Arc<PersistentNode<K, V, A>>>,
+        compare: &mut F,
+    ) -> Option<(Link<K, V, A>, V)>
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>,
+        F: FnMut(&K) -> Ordering
+    {
+        let node = node?;
+
+        self.metrics.record_comparison();
+        match compare(&node.key) {
+            Ordering::Less => {
+                let (left, value) = self.remove_from(node.left.as_ref(), compare)?;
+                let node = self.relink(node, left, node.right.clone());
+                Some((Some(node), value))
+            },
+            Ordering::Greater => {
+                let (right, value) = self.remove_from(node.right.as_ref(), compare)?;
+                let node = self.relink(node, node.left.clone(), right);
+                Some((Some(node), value))
+            },
+            Ordering::Equal => {
+                self.notify(|observer| observer.on_remove(&node.key));
+
+                let replacement = match (&node.left, &node.right) {
+                    (None, right) => right.clone(),
+                    (left, None) => left.clone(),
+                    (left, Some(right)) => {
+                        // The node is replaced by its in-order successor
+                        let (right, (key, value)) = self.remove_min(right);
+                        Some(self.balance(key, value, left.clone(), right))
+                    },
+                };
+
+                Some((replacement, node.value.clone()))
+            },
+        }
+    }
+
+    /// Inserts the entry into the given subtree, taken out of its map, returning the new subtree and the
+    /// replaced value
+    ///
+    /// Keys are compared using [`Ord`]. Nodes on the path are taken apart, see [`into_parts`].
+    fn insert_owned<V, A>(
+        &mut self,
+        node: Link<K, V, A>,
+        key: K,
+        value: V,
+    ) -> (Arc<PersistentNode<K, V, A>>, Option<V>)
+    where
+        K: Ord + Clone,
+        V: Clone,
+        A: Augmentation<K, V>
+    {
+        let Some(node) = node else {
+            self.notify(|observer| observer.on_insert(&key, false));
+            return (self.node(key, value, None, None), None);
+        };
+
+        self.metrics.record_comparison();
+        let ordering = key.cmp(&node.key);
+        let (node_key, node_value, left, right) = into_parts(node);
+
+        match ordering {
+            Ordering::Less => {
+                let (left, replaced) = self.insert_owned(left, key, value);
+                (self.balance(node_key, node_value, Some(left), right), replaced)
+            },
+            Ordering::Greater => {
+                let (right, replaced) = self.insert_owned(right, key, value);
+                (self.balance(node_key, node_value, left, Some(right)), replaced)
+            },
+            Ordering::Equal => {
+                self.notify(|observer| observer.on_insert(&key, true));
+                (self.node(key, value, left, right), Some(node_value))
+            },
+        }
+    }
+
+    /// Removes the entry of a key from the given subtree, taken out of its map, returning the new subtree and
+    /// the removed value
+    ///
+    /// The removed key is located as in [`remove_from`](Self::remove_from), and must be in the subtree. Nodes
+    /// on the path are taken apart, see [`into_parts`].
+    fn remove_owned<V, A, F>(&mut self, node: Link<K, V, A>, compare: &mut F) -> (Link<K, V, A>, Option<V>)
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>,
+        F: FnMut(&K) -> Ordering
+    {
+        let Some(node) = node else {
+            return (None, None);
+        };
+
+        self.metrics.record_comparison();
+        let ordering = compare(&node.key);
+        let (key, value, left, right) = into_parts(node);
+
+        match ordering {
+            Ordering::Less => {
+                let (left, removed) = self.remove_owned(left, compare);
+                (Some(self.balance(key, value, left, right)), removed)
+            },
+            Ordering::Greater => {
+                let (right, removed) = self.remove_owned(right, compare);
+                (Some(self.balance(key, value, left, right)), removed)
+            },
+            Ordering::Equal => {
+                self.notify(|observer| observer.on_remove(&key));
+
+                let replacement = match (left, right) {
+                    (None, right) => right,
+                    (left, None) => left,
+                    (left, Some(right)) => {
+                        // The node is replaced by its in-order successor
+                        let (right, (key, value)) = self.remove_min_owned(right);
+                        Some(self.balance(key, value, left, right))
+                    },
+                };
+
+                (replacement, Some(value))
+            },
+        }
+    }
+
+    /// Removes the smallest entry of the given subtree, taken out of its map, returning the new subtree and the
+    /// removed entry
+    fn remove_min_owned<V, A>(&mut self, node: Arc<PersistentNode<K, V, A>>) -> (Link<K, V, A>, (K, V))
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>
+    {
+        let (key, value, left, right) = into_parts(node);
+
+        match left {
+            None => (right, (key, value)),
+            Some(left) => {
+                let (left, min) = self.remove_min_owned(left);
+                (Some(self.balance(key, value, left, right)), min)
+            },
+        }
+    }
+
+    /// Removes the smallest entry of the given subtree, returning the new subtree and the removed entry
+    fn remove_min<V, A>(&mut self, node: &Arc<PersistentNode<K, V, A>>) -> (Link<K, V, A>, (K, V))
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>
+    {
+        match &node.left {
+            None => (node.right.clone(), (node.key.clone(), node.value.clone())),
+            Some(left) => {
+                let (left, min) = self.remove_min(left);
+                let node = self.relink(node, left, node.right.clone());
+                (Some(node), min)
+            },
+        }
+    }
+
+    /// Returns the subtree of the entries of the given subtree whose keys are within the given bounds
+    ///
+    /// Subtrees fully within the bounds are shared, and the others are joined back together around the
+    /// entries within the bounds, see [`aggregate`] for the shape of the recursion.
+    fn extract<V, A, Q>(
+        &mut self,
+        node: Option<&Arc<PersistentNode<K, V, A>>>,
+        start: Bound<&Q>,
+        end: Bound<&Q>,
+    ) -> Link<K, V, A>
+    where
+        K: Borrow<Q> + Clone,
+        V: Clone,
+        A: Augmentation<K, V>,
+        Q: Ord + ?Sized
+    {
+        let node = node?;
+
+        if let (Bound::Unbounded, Bound::Unbounded) = (start, end) {
+            return Some(Arc::clone(node));
+        }
+
+        let key = node.key.borrow();
+        if is_before_start(key, start) {
+            return self.extract(node.right.as_ref(), start, end);
+        }
+        if is_after_end(key, end) {
+            return self.extract(node.left.as_ref(), start, end);
+        }
+
+        let left = self.extract(node.left.as_ref(), start, Bound::Unbounded);
+        let right = self.extract(node.right.as_ref(), Bound::Unbounded, end);
+        Some(self.join(left, node.key.clone(), node.value.clone(), right))
+    }
+
+    /// Joins two balanced subtrees of any heights with an entry ordered between them
+    ///
+    /// The entry is placed along the side of the higher subtree, at the first node whose height is close
+    /// enough to the lower subtree, which runs in O(difference of heights).
+    fn join<V, A>(
+        &mut self,
+        left: Link<K, V, A>,
+        key: K,
+        value: V,
+        right: Link<K, V, A>,
+    ) -> Arc<PersistentNode<K, V, A>>
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>
+    {
+        let left_height = height(left.as_ref());
+        let right_height = height(right.as_ref());
+
+        match (left, right) {
+            (Some(left), right) if exceeds(left_height, right_height, self.max_difference) => {
+                let joined = self.join(left.right.clone(), key, value, right);
+                self.balance(left.key.clone(), left.value.clone(), left.left.clone(), Some(joined))
+            },
+            (left, Some(right)) if exceeds(right_height, left_height, self.max_difference) => {
+                let joined = self.join(left, key, value, right.left.clone());
+                self.balance(right.key.clone(), right.value.clone(), Some(joined), right.right.clone())
+            },
+            (left, right) => self.node(key, value, left, right),
+        }
+    }
+
+    /// Concatenates two balanced subtrees, the keys of the first being ordered before those of the second
+    fn concat<V, A>(&mut self, first: Link<K, V, A>, second: Link<K, V, A>) -> Link<K, V, A>
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>
+    {
+        match (first, second) {
+            (first, None) => first,
+            (first, Some(second)) => {
+                let (second, (key, value)) = self.remove_min(&second);
+                Some(self.join(first, key, value, second))
+            },
+        }
+    }
+
+    /// Copies the node with new children, rebalancing it only if the height of one of its children changed
+    ///
+    /// When the heights of the children are unchanged, so are the balance and the height of the node: its
+    /// height is copied over instead of being recomputed, and the rebalancing stops propagating upwards.
+    /// The augmentation of the node is always recomputed, since the entries of its subtree changed.
+    fn relink<V, A>(
+        &mut self,
+        node: &PersistentNode<K, V, A>,
+        left: Link<K, V, A>,
+        right: Link<K, V, A>,
+    ) -> Arc<PersistentNode<K, V, A>>
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>
+    {
+        let heights_changed = height(left.as_ref()) != height(node.left.as_ref())
+            || height(right.as_ref()) != height(node.right.as_ref());
+
+        if heights_changed {
+            return self.balance(node.key.clone(), node.value.clone(), left, right);
+        }
+
+        PersistentNode::with_height(node.key.clone(), node.value.clone(), node.height, left, right)
+    }
+
+    /// Creates a node out of its entry and children, rotating it if its children's heights differ too much
+    ///
+    /// The children must be balanced, and their heights must differ by at most one more than allowed,
+    /// which is the case after a single insertion or removal.
+    fn balance<V, A>(
+        &mut self,
+        key: K,
+        value: V,
+        left: Link<K, V, A>,
+        right: Link<K, V, A>,
+    ) -> Arc<PersistentNode<K, V, A>>
+    where
+        K: Clone,
+        V: Clone,
+        A: Augmentation<K, V>
+    {
+        let max_difference = self.max_difference;
+        let left_height = height(left.as_ref());
+        let right_height = height(right.as_ref());
+
+        if exceeds(left_height, right_height, max_difference) {
+            let left = left.expect("a left subtree higher than the right one should exist");
+
+            self.notify(|observer| observer.on_rebalance(&key));
+
+            if height(left.left.as_ref()) >= height(left.right.as_ref()) {
+                self.metrics.record_rotation(AVLRotation::Right);
+                self.notify(|observer| observer.on_rotation(&left.key, AVLRotation::Right));
+                let (left_key, left_value, left_left, left_right) = into_parts(left);
+                let right = self.balance(key, value, left_right, right);
+                return self.node(left_key, left_value, left_left, Some(right));
+            }
+
+            self.metrics.record_rotation(AVLRotation::LeftRight);
+            let (left_key, left_value, left_left, pivot) = into_parts(left);
+            let pivot = pivot.expect("the higher inner grandchild should exist");
+            self.notify(|observer| observer.on_rotation(&pivot.key, AVLRotation::LeftRight));
+            let (pivot_key, pivot_value, pivot_left, pivot_right) = into_parts(pivot);
+            let new_left = self.balance(left_key, left_value, left_left, pivot_left);
+            let new_right = self.balance(key, value, pivot_right, right);
+            return self.node(pivot_key, pivot_value, Some(new_left), Some(new_right));
+        }
+
+        if exceeds(right_height, left_height, max_difference) {
+            let right = right.expect("a right subtree higher than the left one should exist");
+
+            self.notify(|observer| observer.on_rebalance(&key));
+
+            if height(right.right.as_ref()) >= height(right.left.as_ref()) {
+                self.metrics.record_rotation(AVLRotation::Left);
+                self.notify(|observer| observer.on_rotation(&right.key, AVLRotation::Left));
+                let (right_key, right_value, right_left, right_right) = into_parts(right);
+                let left = self.balance(key, value, left, right_left);
+                return self.node(right_key, right_value, Some(left), right_right);
+            }
+
+            self.metrics.record_rotation(AVLRotation::RightLeft);
+            let (right_key, right_value, pivot, right_right) = into_parts(right);
+            let pivot = pivot.expect("the higher inner grandchild should exist");
+            self.notify(|observer| observer.on_rotation(&pivot.key, AVLRotation::RightLeft));
+            let (pivot_key, pivot_value, pivot_left, pivot_right) = into_parts(pivot);
+            let new_left = self.balance(key, value, left, pivot_left);
+            let new_right = self.balance(right_key, right_value, pivot_right, right_right);
+            return self.node(pivot_key, pivot_value, Some(new_left), Some(new_right));
+        }
+
+        self.node(key, value, left, right)
+    }
+}
+
+impl<K, V, A> Clone for TravlMapPersistent<K, V, A> {
+    /// Clones the map in O(1), sharing all of its nodes and observers, and keeping its validation
+    fn clone(&self) -> Self {
+        Self {
+            imbalance_factor: self.imbalance_factor,
+            root: self.root.clone(),
+            len: self.len,
+            metrics: self.metrics,
+            observers: self.observers.clone(),
+            validator: self.validator,
+        }
+    }
+}
+
+impl<K, V, A> Default for TravlMapPersistent<K, V, A> {
+    fn default() -> Self {
+        Self::new_augmented()
+    }
+}
+
+impl<K, V, A> Debug for TravlMapPersistent<K, V, A>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, A> PartialEq for TravlMapPersistent<K, V, A>
+where
+    K: PartialEq,
+    V: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<K, V, A> Eq for TravlMapPersistent<K, V, A>
+where
+    K: Eq,
+    V: Eq
+{
+}
+
+impl<K, V, A> FromIterator<(K, V)> for TravlMapPersistent<K, V, A>
+where
+    K: Ord + Clone,
+    V: Clone,
+    A: Augmentation<K, V>
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        entries.into_iter().fold(Self::new_augmented(), |map, (key, value)| map.insert(key, value))
+    }
+}
+
+impl<'m, K, V, A> IntoIterator for &'m TravlMapPersistent<K, V, A> {
+    type IntoIter = PersistentIter<'m, K, V, A>;
+    type Item = (&'m K, &'m V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a [`TravlMapPersistent`], ordered by key
+///
+/// Created by [`TravlMapPersistent::iter`].
+pub struct PersistentIter<'m, K, V, A = ()> {
+    stack: Vec<&'m PersistentNode<K, V, A>>,
+    remaining: usize,
+}
+
+impl<'m, K, V, A> PersistentIter<'m, K, V, A> {
+    /// Pushes the given node and its chain of left children onto the stack
+    fn push_left_branch(&mut self, mut node: Option<&'m PersistentNode<K, V, A>>) {
+        while let Some(current) = node {
+            self.stack.push(current);
+            node = current.left.as_deref();
+        }
+    }
+}
+
+impl<'m, K, V, A> Iterator for PersistentIter<'m, K, V, A> {
+    type Item = (&'m K, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_branch(node.right.as_deref());
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, A> ExactSizeIterator for PersistentIter<'_, K, V, A> {}
+
+impl<K, V, A> FusedIterator for PersistentIter<'_, K, V, A> {}
+
+/// Iterator over the entries of a [`TravlMapPersistent`] whose keys are within a range, ordered by key
+///
+/// Created by [`TravlMapPersistent::range`].
+pub struct PersistentRange<'m, K, V, Q, R, A = ()>
+where
+    Q: ?Sized
+{
+    stack: Vec<&'m PersistentNode<K, V, A>>,
+    range: R,
+    key: PhantomData<fn(&Q)>,
+}
+
+impl<'m, K, V, Q, R, A> Iterator for PersistentRange<'m, K, V, Q, R, A>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>
+{
+    type Item = (&'m K, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+
+        if is_after_end(node.key.borrow(), self.range.end_bound()) {
+            self.stack.clear();
+            return None;
+        }
+
+        // The right subtree only holds keys after this one, which are all after the start
+        let mut current = node.right.as_deref();
+        while let Some(right) = current {
+            self.stack.push(right);
+            current = right.left.as_deref();
+        }
+
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K, V, Q, R, A> FusedIterator for PersistentRange<'_, K, V, Q, R, A>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>
+{
+}
@@ -0,0 +1,186 @@
+//! Support for [`proptest`] strategies generating maps
+//!
+//! Generated maps are always built from their entries, so every map produced while shrinking
+//! is a balanced tree as well: shrinking removes entries rather than altering the tree directly.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use core::hash::Hash;
+use core::ops::RangeInclusive;
+use std::collections::HashSet;
+
+use proptest::collection::SizeRange;
+use proptest::prelude::RngExt;
+use proptest::strategy::{NewTree, Strategy, ValueTree};
+use proptest::test_runner::TestRunner;
+
+use crate::core::MAX_IMBALANCE_FACTOR;
+use crate::error::TravlError;
+use crate::map::{TravlMap, TravlMapConfig};
+
+/// Number of attempts at generating a unique key for each entry
+const KEY_ATTEMPTS: usize = 4;
+
+/// Strategy generating [`TravlMap`]s using the default property getter and ordering function
+///
+/// Keys and values are generated by the given strategies. The number of entries, the imbalance factor
+/// and the density of entries sharing their property with another entry can be configured.
+#[derive(Clone, Debug)]
+pub struct TravlMapStrategy<KS, VS> {
+    keys: KS,
+    values: VS,
+    size: SizeRange,
+    imbalance_factor: RangeInclusive<u64>,
+    duplicate_prop_density: f64,
+}
+
+impl<KS, VS> TravlMapStrategy<KS, VS> {
+    /// Creates a strategy generating up to 100 entries, with imbalance factors up to 3 and no forced duplicates
+    #[must_use]
+    pub fn new(keys: KS, values: VS) -> Self {
+        Self {
+            keys,
+            values,
+            size: (0..100).into(),
+            imbalance_factor: 0..=3,
+            duplicate_prop_density: 0.0,
+        }
+    }
+
+    /// Sets the range of the number of entries of the generated maps
+    ///
+    /// Fewer entries may be generated if the key strategy doesn't produce enough distinct keys.
+    #[must_use]
+    pub fn with_size(mut self, size: impl Into<SizeRange>) -> Self {
+        self.size = size.into();
+        self
+    }
+
+    /// Sets the range of the imbalance factor of the generated maps
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range goes beyond [`MAX_IMBALANCE_FACTOR`].
+    #[must_use]
+    pub fn with_imbalance_factor(mut self, imbalance_factor: RangeInclusive<u64>) -> Self {
+        assert!(
+            *imbalance_factor.end() <= MAX_IMBALANCE_FACTOR,
+            "{}",
+            TravlError::ImbalanceFactorTooLarge
+        );
+        self.imbalance_factor = imbalance_factor;
+        self
+    }
+
+    /// Sets the probability, between 0 and 1, that an entry reuses the value of a previous entry
+    ///
+    /// Entries reusing a value share the same property, exercising how equal properties are handled.
+    #[must_use]
+    pub fn with_duplicate_prop_density(mut self, duplicate_prop_density: f64) -> Self {
+        self.duplicate_prop_density = duplicate_prop_density.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl<KS, VS> Strategy for TravlMapStrategy<KS, VS>
+where
+    KS: Strategy,
+    KS::Value: Hash + Eq + Clone + 'static,
+    VS: Strategy,
+    VS::Value: Ord + Clone + 'static
+{
+    type Tree = TravlMapValueTree<KS::Value, VS::Value>;
+    type Value = TravlMap<'static, KS::Value, VS::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let size = runner.rng().random_range(self.size.start()..=self.size.end_incl());
+        let imbalance_factor = runner.rng().random_range(self.imbalance_factor.clone());
+
+        let mut keys = HashSet::with_capacity(size);
+        let mut entries: Vec<(KS::Value, VS::Value)> = Vec::with_capacity(size);
+
+        for _ in 0..size.saturating_mul(KEY_ATTEMPTS) {
+            if entries.len() == size {
+                break;
+            }
+
+            let key = self.keys.new_tree(runner)?.current();
+            if !keys.insert(key.clone()) {
+                continue;
+            }
+
+            let value = if !entries.is_empty() && runner.rng().random_bool(self.duplicate_prop_density) {
+                let index = runner.rng().random_range(0..entries.len());
+                entries[index].1.clone()
+            } else {
+                self.values.new_tree(runner)?.current()
+            };
+
+            entries.push((key, value));
+        }
+
+        Ok(TravlMapValueTree {
+            entries,
+            imbalance_factor,
+            next: 0,
+            removed: None,
+            min_size: self.size.start(),
+        })
+    }
+}
+
+/// Value tree of [`TravlMapStrategy`], shrinking maps by removing their entries one at a time
+pub struct TravlMapValueTree<K, V> {
+    entries: Vec<(K, V)>,
+    imbalance_factor: u64,
+    next: usize,
+    removed: Option<(usize, (K, V))>,
+    min_size: usize,
+}
+
+impl<K, V> Debug for TravlMapValueTree<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TravlMapValueTree")
+            .field("entries", &self.entries)
+            .field("imbalance_factor", &self.imbalance_factor)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K, V> ValueTree for TravlMapValueTree<K, V>
+where
+    K: Hash + Eq + Clone + Debug + 'static,
+    V: Ord + Clone + Debug + 'static
+{
+    type Value = TravlMap<'static, K, V>;
+
+    fn current(&self) -> Self::Value {
+        let config = TravlMapConfig::new(self.imbalance_factor);
+        TravlMap::from_entries(config, self.entries.clone(), Box::new(|x| x), Box::new(Ord::cmp))
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.entries.len() <= self.min_size || self.next >= self.entries.len() {
+            return false;
+        }
+
+        self.removed = Some((self.next, self.entries.remove(self.next)));
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some((index, entry)) = self.removed.take() else {
+            return false;
+        };
+
+        // The entry is needed to reproduce the failure, so shrinking moves on to the next one
+        self.entries.insert(index, entry);
+        self.next = index + 1;
+        true
+    }
+}
@@ -0,0 +1,177 @@
+//! Text renderings of trees, meant for quick debugging in a terminal or in visualization tools
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display, Write};
+
+use crate::core::TravlNode;
+use crate::storage::NodeStorage;
+
+/// Renders the subtree of the given root using box-drawing characters
+///
+/// Each node is written on its own line as `key: value (balance factor)`, indented under its parent,
+/// with its left child listed before its right child. The imbalance factor is used to compute the
+/// [`BalanceFactor`](crate::core::BalanceFactor) of each node. An empty tree renders as an empty string.
+#[must_use]
+pub fn render_ascii<K, V, S>(nodes: &S, root: Option<&TravlNode<K, V>>, imbalance_factor: u64) -> String
+where
+    K: Debug,
+    V: Debug,
+    S: NodeStorage<K, V>
+{
+    let mut output = String::new();
+    // Writing into a `String` never fails
+    let _ = write_ascii(&mut output, nodes, root, imbalance_factor);
+    output
+}
+
+/// Writes the rendering of [`render_ascii`] into the given writer
+///
+/// # Errors
+///
+/// Returns an error if writing to the writer fails.
+pub fn write_ascii<W, K, V, S>(
+    writer: &mut W,
+    nodes: &S,
+    root: Option<&TravlNode<K, V>>,
+    imbalance_factor: u64,
+) -> fmt::Result
+where
+    W: Write,
+    K: Debug,
+    V: Debug,
+    S: NodeStorage<K, V>
+{
+    // Nodes left to write, with the prefix of their line, their side relative to their parent and whether
+    // they are the last child of their parent
+    let mut stack = Vec::from_iter(root.map(|root| (root, String::new(), None, true)));
+
+    while let Some((node, prefix, side, is_last)) = stack.pop() {
+        let child_prefix = match side {
+            None => {
+                writer.write_str(&prefix)?;
+                prefix
+            },
+            Some(side) => {
+                let connector = if is_last { "└── " } else { "├── " };
+                write!(writer, "{prefix}{connector}{side}: ")?;
+
+                let continuation = if is_last { "    " } else { "│   " };
+                prefix + continuation
+            },
+        };
+
+        writeln!(
+            writer,
+            "{:?}: {:?} ({:?})",
+            node.key(),
+            node.value(),
+            node.balance_factor(nodes, imbalance_factor)
+        )?;
+
+        // Pushed in reverse so the left child is written first
+        if let Some(right) = nodes.right_of(node) {
+            stack.push((right, child_prefix.clone(), Some('R'), true));
+        }
+
+        if let Some(left) = nodes.left_of(node) {
+            stack.push((left, child_prefix, Some('L'), node.right().is_none()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders the subtree of the given root as a nested JSON object
+///
+/// Each node is written as `{"key": ..., "value": ..., "height": ..., "left": ..., "right": ...}`, where the
+/// key and value are strings holding their [`Display`] representations and missing children are `null`.
+/// This doesn't depend on serde, and is intended to be fed to tree visualization tools. An empty tree
+/// renders as `null`.
+#[must_use]
+pub fn render_json<K, V, S>(nodes: &S, root: Option<&TravlNode<K, V>>) -> String
+where
+    K: Display,
+    V: Display,
+    S: NodeStorage<K, V>
+{
+    let mut output = String::new();
+    // Writing into a `String` never fails
+    let _ = write_json(&mut output, nodes, root);
+    output
+}
+
+/// Part of the JSON output left to write
+enum JsonPart<'n, K, V> {
+    Node(Option<&'n TravlNode<K, V>>),
+    Raw(&'static str),
+}
+
+/// Writes the rendering of [`render_json`] into the given writer
+///
+/// # Errors
+///
+/// Returns an error if writing to the writer fails.
+pub fn write_json<W, K, V, S>(writer: &mut W, nodes: &S, root: Option<&TravlNode<K, V>>) -> fmt::Result
+where
+    W: Write,
+    K: Display,
+    V: Display,
+    S: NodeStorage<K, V>
+{
+    // Written iteratively so that degenerate trees don't overflow the stack
+    let mut stack = vec![JsonPart::Node(root)];
+
+    while let Some(part) = stack.pop() {
+        let node = match part {
+            JsonPart::Raw(raw) => {
+                writer.write_str(raw)?;
+                continue;
+            },
+            JsonPart::Node(None) => {
+                writer.write_str("null")?;
+                continue;
+            },
+            JsonPart::Node(Some(node)) => node,
+        };
+
+        writer.write_str("{\"key\":")?;
+        write_json_string(writer, node.key())?;
+        writer.write_str(",\"value\":")?;
+        write_json_string(writer, node.value())?;
+        write!(writer, ",\"height\":{},\"left\":", node.height())?;
+
+        stack.extend([
+            JsonPart::Raw("}"),
+            JsonPart::Node(nodes.right_of(node)),
+            JsonPart::Raw(",\"right\":"),
+            JsonPart::Node(nodes.left_of(node)),
+        ]);
+    }
+
+    Ok(())
+}
+
+/// Writes the [`Display`] representation of the given value as an escaped JSON string
+fn write_json_string<W, T>(writer: &mut W, value: &T) -> fmt::Result
+where
+    W: Write,
+    T: Display + ?Sized
+{
+    writer.write_char('"')?;
+
+    for c in value.to_string().chars() {
+        match c {
+            '"' => writer.write_str("\\\"")?,
+            '\\' => writer.write_str("\\\\")?,
+            '\n' => writer.write_str("\\n")?,
+            '\r' => writer.write_str("\\r")?,
+            '\t' => writer.write_str("\\t")?,
+            c if c.is_control() => write!(writer, "\\u{:04x}", u32::from(c))?,
+            c => writer.write_char(c)?,
+        }
+    }
+
+    writer.write_char('"')
+}
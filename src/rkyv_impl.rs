@@ -0,0 +1,172 @@
+//! Support for [`rkyv`] zero-copy archives
+//!
+//! Maps are archived as their configuration along with their entries, in order.
+//! Since the entries are sorted, read-only queries can be performed directly on the archived
+//! representation using binary searches, without deserializing the map first.
+//! As the property getter and ordering function of a map cannot be archived, queries take
+//! a comparison function working on archived values instead.
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::Hash;
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::map::TravlMap;
+
+/// Archivable representation of a [`TravlMap`]
+///
+/// Created by [`TravlMap::to_archive`], the archived counterpart of this type is [`ArchivedTravlMapArchive`].
+#[derive(Archive, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TravlMapArchive<K, V> {
+    imbalance_factor: u64,
+    entries: Vec<ArchiveEntry<K, V>>,
+}
+
+impl<K, V> TravlMapArchive<K, V> {
+    /// Returns the imbalance factor of the archived map
+    #[must_use]
+    pub fn imbalance_factor(&self) -> u64 {
+        self.imbalance_factor
+    }
+
+    /// Returns the entries of the archived map, in order
+    #[must_use]
+    pub fn entries(&self) -> &[ArchiveEntry<K, V>] {
+        &self.entries
+    }
+}
+
+/// Entry of a [`TravlMapArchive`]
+#[derive(Archive, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveEntry<K, V> {
+    key: K,
+    value: V,
+}
+
+impl<K, V> ArchiveEntry<K, V> {
+    /// Returns the entry's key
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the entry's value
+    #[must_use]
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+}
+
+impl<K, V> ArchivedArchiveEntry<K, V>
+where
+    K: Archive,
+    V: Archive
+{
+    /// Returns the entry's archived key
+    #[must_use]
+    pub fn key(&self) -> &K::Archived {
+        &self.key
+    }
+
+    /// Returns the entry's archived value
+    #[must_use]
+    pub fn value(&self) -> &V::Archived {
+        &self.value
+    }
+}
+
+impl<K, V> ArchivedTravlMapArchive<K, V>
+where
+    K: Archive,
+    V: Archive
+{
+    /// Returns the imbalance factor of the archived map
+    #[must_use]
+    pub fn imbalance_factor(&self) -> u64 {
+        self.imbalance_factor.to_native()
+    }
+
+    /// Returns the archived entries, in order
+    #[must_use]
+    pub fn entries(&self) -> &[ArchivedArchiveEntry<K, V>] {
+        self.entries.as_slice()
+    }
+
+    /// Returns whether the archived map is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of archived entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Finds an entry using the given comparison function
+    ///
+    /// The comparison function is given the archived values of the entries and must return how
+    /// the property of these values is ordered compared to the searched one.
+    /// If multiple entries match, any one of them may be returned.
+    pub fn find<F>(&self, mut compare: F) -> Option<&ArchivedArchiveEntry<K, V>>
+    where
+        F: FnMut(&V::Archived) -> Ordering
+    {
+        let entries = self.entries();
+
+        entries
+            .binary_search_by(|entry| compare(entry.value()))
+            .ok()
+            .map(|index| &entries[index])
+    }
+
+    /// Returns the number of entries ordered before the searched property, using the given comparison function
+    ///
+    /// See [`find`](Self::find) for the expectations on the comparison function.
+    pub fn rank<F>(&self, mut compare: F) -> usize
+    where
+        F: FnMut(&V::Archived) -> Ordering
+    {
+        self.entries()
+            .partition_point(|entry| compare(entry.value()) == Ordering::Less)
+    }
+
+    /// Returns the entries whose property lies between two bounds, both included
+    ///
+    /// The comparison functions are given the archived values of the entries and must return how
+    /// the property of these values is ordered compared to the lower and upper bounds respectively.
+    pub fn range<L, U>(&self, mut compare_lower: L, mut compare_upper: U) -> &[ArchivedArchiveEntry<K, V>]
+    where
+        L: FnMut(&V::Archived) -> Ordering,
+        U: FnMut(&V::Archived) -> Ordering
+    {
+        let entries = self.entries();
+        let start = entries.partition_point(|entry| compare_lower(entry.value()) == Ordering::Less);
+        let end = entries.partition_point(|entry| compare_upper(entry.value()) != Ordering::Greater);
+
+        entries.get(start..end).unwrap_or_default()
+    }
+}
+
+impl<K, V, P> TravlMap<'_, K, V, P>
+where
+    K: Hash + Eq + Clone,
+    V: Clone
+{
+    /// Returns an archivable representation of the map
+    #[must_use]
+    pub fn to_archive(&self) -> TravlMapArchive<K, V> {
+        TravlMapArchive {
+            imbalance_factor: self.imbalance_factor(),
+            entries: self
+                .iter()
+                .map(|(key, value)| ArchiveEntry {
+                    key: key.clone(),
+                    value: value.clone(),
+                })
+                .collect(),
+        }
+    }
+}
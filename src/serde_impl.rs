@@ -0,0 +1,1415 @@
+//! Implementations of the [`serde`] traits
+//!
+//! Maps are serialized as their configuration and the key of their root along with their entries, in order.
+//! Since the property getter and ordering function of a map cannot be serialized, they must be
+//! supplied again when deserializing, using a [`TravlMapSeed`].
+//!
+//! Alternatively, maps can be deserialized as a [`DeserializedMap`], to which the functions
+//! can be attached afterwards, for example through a [`TravlMapBuilder`](crate::map::TravlMapBuilder) using
+//! [`DeserializedMap::build`].
+//!
+//! Other formats can be chosen using [`SerdeFormat`], both when serializing (see
+//! [`TravlMap::with_serde_format`]) and when deserializing (see [`TravlMapSeed::with_format`]).
+//!
+//! The representation depends on whether the serializer [is human-readable](Serializer::is_human_readable):
+//! human-readable formats such as JSON get maps as structs with named fields and entries as
+//! `{"key": ..., "value": ...}` objects, while compact formats such as bincode get maps as tuples
+//! and entries as key-value pairs. Both representations are accepted when deserializing.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use std::collections::HashMap;
+
+use serde::de::{self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::ser::{SerializeStruct, SerializeTuple};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::core::TravlNode;
+use crate::map::{
+    Balancing, OrdFn, PropFn, SortedEntriesBuilder, TiePolicy, TravlMap, TravlMapBuilder, TravlMapConfig,
+    TravlMapSnapshot,
+};
+use crate::storage::NodeStorage;
+use crate::traversal::PreOrderIter;
+use crate::validation::Violation;
+
+/// Version of the serialization format of maps
+///
+/// The version is serialized first along with maps and is to be incremented whenever their serialized
+/// representation changes, so that maps serialized using older versions can still be told apart and deserialized.
+/// Maps serialized using an unknown version are rejected.
+///
+/// Since the [flat format](SerdeFormat::Flat) only contains entries, it isn't versioned.
+pub const SERDE_FORMAT_VERSION: u32 = 1;
+
+/// Name of the serialized map struct
+const MAP_NAME: &str = "TravlMap";
+
+/// Names of the serialized map fields
+const MAP_FIELDS: &[&str] = &["version", "imbalance_factor", "tie_policy", "balancing", "root", "entries"];
+
+/// Name of the serialized entry struct, in human-readable formats
+const ENTRY_NAME: &str = "Entry";
+
+/// Names of the serialized entry fields, in human-readable formats
+const ENTRY_FIELDS: &[&str] = &["key", "value"];
+
+/// Names of the serialized configuration fields
+const CONFIG_FIELDS: &[&str] = &["imbalance_factor", "tie_policy", "balancing"];
+
+/// Names of the serialized tie policies
+const TIE_POLICY_VARIANTS: &[&str] = &["After", "Before"];
+
+/// Names of the serialized balancing strategies
+const BALANCING_VARIANTS: &[&str] = &["Strict", "Relaxed", "Scapegoat", "Weight", "Rank"];
+
+/// Names of the serialized map fields, using the [structural format](SerdeFormat::Structural), whose root is
+/// the first serialized node
+const STRUCTURAL_MAP_FIELDS: &[&str] = &["version", "imbalance_factor", "tie_policy", "balancing", "nodes"];
+
+impl<K, V, P> Serialize for TravlMap<'_, K, V, P>
+where
+    K: Serialize + Hash + Eq,
+    V: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        serialize_map_parts(serializer, MAP_FIELDS, self.config(), Some(&self.root_key()), &Entries(self))
+    }
+}
+
+/// Serializes the parts of a map, as a struct with the given field names in human-readable formats
+/// and as a tuple in compact formats
+///
+/// The root is only serialized when given, the field names then including its own.
+fn serialize_map_parts<S, R, C>(
+    serializer: S,
+    fields: &'static [&'static str],
+    config: TravlMapConfig,
+    root: Option<&R>,
+    content: &C,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    R: Serialize,
+    C: Serialize
+{
+    let content_field = fields[fields.len() - 1];
+
+    if serializer.is_human_readable() {
+        let mut state = serializer.serialize_struct(MAP_NAME, fields.len())?;
+        state.serialize_field("version", &SERDE_FORMAT_VERSION)?;
+        state.serialize_field("imbalance_factor", &config.imbalance_factor())?;
+        state.serialize_field("tie_policy", &config.tie_policy())?;
+        state.serialize_field("balancing", &config.balancing())?;
+        if let Some(root) = root {
+            state.serialize_field("root", root)?;
+        }
+        state.serialize_field(content_field, content)?;
+        state.end()
+    } else {
+        let mut state = serializer.serialize_tuple(fields.len())?;
+        state.serialize_element(&SERDE_FORMAT_VERSION)?;
+        state.serialize_element(&config.imbalance_factor())?;
+        state.serialize_element(&config.tie_policy())?;
+        state.serialize_element(&config.balancing())?;
+        if let Some(root) = root {
+            state.serialize_element(root)?;
+        }
+        state.serialize_element(content)?;
+        state.end()
+    }
+}
+
+/// Format used to (de)serialize a [`TravlMap`]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SerdeFormat {
+    /// Configuration of the map along with its entries, in order
+    #[default]
+    Full,
+    /// Only the entries of the map, in order, as a sequence of key-value pairs
+    ///
+    /// The configuration of the map isn't serialized, maps deserialized from this format
+    /// use the default configuration.
+    Flat,
+    /// Configuration of the map along with its nodes, including their heights and links
+    ///
+    /// Nodes are serialized in pre-order and links are serialized as the index of the linked node,
+    /// allowing deserialized maps to have the exact same structure as the serialized ones.
+    /// Deserialized nodes are checked to form a valid tree, as [`TravlMap::validate`] would.
+    Structural,
+}
+
+/// Map serialized using a specific [`SerdeFormat`]
+///
+/// Created by [`TravlMap::with_serde_format`].
+pub struct FormattedMap<'m, 'a, K, V, P> {
+    map: &'m TravlMap<'a, K, V, P>,
+    format: SerdeFormat,
+}
+
+impl<'m, 'a, K, V, P> FormattedMap<'m, 'a, K, V, P> {
+    /// Wraps the given map so that it is serialized using the given format
+    pub(crate) fn new(map: &'m TravlMap<'a, K, V, P>, format: SerdeFormat) -> Self {
+        Self { map, format }
+    }
+}
+
+impl<K, V, P> Serialize for FormattedMap<'_, '_, K, V, P>
+where
+    K: Serialize + Hash + Eq,
+    V: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        match self.format {
+            SerdeFormat::Full => self.map.serialize(serializer),
+            SerdeFormat::Flat => Entries(self.map).serialize(serializer),
+            SerdeFormat::Structural => serialize_map_parts(
+                serializer,
+                STRUCTURAL_MAP_FIELDS,
+                self.map.config(),
+                None::<&()>,
+                &Structure(self.map),
+            ),
+        }
+    }
+}
+
+/// Serializes the entries of a map as a sequence of entries, in order
+struct Entries<'m, 'a, K, V, P>(&'m TravlMap<'a, K, V, P>);
+
+impl<K, V, P> Serialize for Entries<'_, '_, K, V, P>
+where
+    K: Serialize + Hash + Eq,
+    V: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        serializer.collect_seq(self.0.iter().map(|(key, value)| EntryRef(key, value)))
+    }
+}
+
+/// Serializes an entry, as a struct in human-readable formats and as a key-value pair in compact formats
+struct EntryRef<'e, K, V>(&'e K, &'e V);
+
+impl<K, V> Serialize for EntryRef<'_, K, V>
+where
+    K: Serialize,
+    V: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        if serializer.is_human_readable() {
+            let mut state = serializer.serialize_struct(ENTRY_NAME, ENTRY_FIELDS.len())?;
+            state.serialize_field("key", self.0)?;
+            state.serialize_field("value", self.1)?;
+            state.end()
+        } else {
+            (self.0, self.1).serialize(serializer)
+        }
+    }
+}
+
+/// Deserialized entry, either from a struct or from a key-value pair
+struct Entry<K, V>(K, V);
+
+impl<K, V> Entry<K, V> {
+    /// Converts deserialized entries into key-value pairs
+    fn into_pairs(entries: Vec<Self>) -> Vec<(K, V)> {
+        entries.into_iter().map(|Entry(key, value)| (key, value)).collect()
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for Entry<K, V>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_struct(ENTRY_NAME, ENTRY_FIELDS, EntryVisitor(PhantomData))
+        } else {
+            deserializer.deserialize_tuple(ENTRY_FIELDS.len(), EntryVisitor(PhantomData))
+        }
+    }
+}
+
+/// Serialized entry field, in human-readable formats
+enum EntryField {
+    Key,
+    Value,
+}
+
+impl<'de> Deserialize<'de> for EntryField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        struct EntryFieldVisitor;
+
+        impl Visitor<'_> for EntryFieldVisitor {
+            type Value = EntryField;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("`key` or `value`")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<EntryField, E>
+            where
+                E: de::Error
+            {
+                match value {
+                    "key" => Ok(EntryField::Key),
+                    "value" => Ok(EntryField::Value),
+                    _ => Err(de::Error::unknown_field(value, ENTRY_FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(EntryFieldVisitor)
+    }
+}
+
+/// Visitor deserializing an entry, either from a struct or from a key-value pair
+struct EntryVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K, V> Visitor<'de> for EntryVisitor<K, V>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>
+{
+    type Value = Entry<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an entry")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>
+    {
+        let key = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let value = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        Ok(Entry(key, value))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>
+    {
+        let mut key = None;
+        let mut value = None;
+
+        while let Some(field) = map.next_key()? {
+            match field {
+                EntryField::Key => set_once(&mut key, "key", || map.next_value())?,
+                EntryField::Value => set_once(&mut value, "value", || map.next_value())?,
+            }
+        }
+
+        Ok(Entry(
+            key.ok_or_else(|| de::Error::missing_field("key"))?,
+            value.ok_or_else(|| de::Error::missing_field("value"))?,
+        ))
+    }
+}
+
+/// Serializes the nodes of a map in pre-order, along with their heights and the indices of the nodes they link to
+struct Structure<'m, 'a, K, V, P>(&'m TravlMap<'a, K, V, P>);
+
+impl<K, V, P> Serialize for Structure<'_, '_, K, V, P>
+where
+    K: Serialize + Hash + Eq,
+    V: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let nodes: Vec<_> = PreOrderIter::new(self.0.nodes(), self.0.root()).collect();
+        let indices: HashMap<&K, usize> = nodes.iter().enumerate().map(|(index, node)| (node.key(), index)).collect();
+
+        serializer.collect_seq(nodes.iter().map(|node| IndexedNode::new(node, &indices)))
+    }
+}
+
+impl<'de, 'a, K, V> Deserialize<'de> for TravlMap<'a, K, V>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone + 'a,
+    V: Deserialize<'de> + Ord + 'a
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        TravlMapSeed::new(Box::new(|x| x), Box::new(Ord::cmp)).deserialize(deserializer)
+    }
+}
+
+/// Map deserialized without its property getter and ordering function
+///
+/// Deserializes maps serialized using the [full format](SerdeFormat::Full), keeping their configuration,
+/// the key of their root and their entries until the functions are attached, which rebuilds the tree. The
+/// rebuilt tree keeps the serialized root when balanced subtrees on either side of it respect the imbalance
+/// factor, which is the case of most trees.
+#[derive(Clone, Debug)]
+pub struct DeserializedMap<K, V> {
+    config: TravlMapConfig,
+    root: Option<K>,
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> DeserializedMap<K, V> {
+    /// Returns the configuration of the map
+    #[must_use]
+    pub fn config(&self) -> TravlMapConfig {
+        self.config
+    }
+
+    /// Returns the key of the root of the serialized map, if it had one
+    #[must_use]
+    pub fn root_key(&self) -> Option<&K> {
+        self.root.as_ref()
+    }
+
+    /// Returns the entries of the map, in their serialized order
+    #[must_use]
+    pub fn entries(&self) -> &[(K, V)] {
+        &self.entries
+    }
+
+    /// Builds the map out of the one returned by the given hook, which is given a builder using the
+    /// deserialized configuration
+    ///
+    /// The hook attaches the property getter and ordering function, and possibly changes the configuration or
+    /// the storage, before building the map. The deserialized entries then replace the entries of the built map.
+    #[must_use]
+    pub fn build<'a, P, S, F>(self, hook: F) -> TravlMap<'a, K, V, P, S>
+    where
+        K: Eq + Clone + 'a,
+        V: 'a,
+        S: NodeStorage<K, V>,
+        F: FnOnce(TravlMapBuilder<'a, K, V>) -> TravlMap<'a, K, V, P, S>
+    {
+        let mut map = hook(TravlMap::builder().config(self.config).capacity(self.entries.len()));
+        map.rebuild_around(self.entries, self.root.as_ref());
+        map
+    }
+
+    /// Attaches the given property getter and ordering function, rebuilding the map
+    #[must_use]
+    pub fn attach<'a, P>(self, prop_fn: PropFn<'a, V, P>, ordering_fn: OrdFn<'a, P>) -> TravlMap<'a, K, V, P>
+    where
+        K: Hash + Eq + Clone
+    {
+        let mut map = TravlMap::new_with_prop_getter_and_ordering(prop_fn, ordering_fn);
+        map.set_config(self.config);
+        map.rebuild_around(self.entries, self.root.as_ref());
+        map
+    }
+
+    /// Attaches the default property getter and ordering function, rebuilding the map
+    #[must_use]
+    pub fn attach_default<'a>(self) -> TravlMap<'a, K, V>
+    where
+        K: Hash + Eq + Clone,
+        V: Ord + 'a
+    {
+        self.attach(Box::new(|x| x), Box::new(Ord::cmp))
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for DeserializedMap<K, V>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let visitor = MapPartsVisitor::new(PhantomData, true);
+        let parts: MapParts<K, Vec<Entry<K, V>>> = deserializer.deserialize_struct(MAP_NAME, MAP_FIELDS, visitor)?;
+
+        Ok(Self {
+            config: parts.config,
+            root: parts.root,
+            entries: Entry::into_pairs(parts.content),
+        })
+    }
+}
+
+impl<K, V> Serialize for TravlMapSnapshot<K, V>
+where
+    K: Serialize,
+    V: Serialize
+{
+    /// Serializes the snapshot using the [full format](SerdeFormat::Full) of maps
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let root: Option<&K> = None;
+        serialize_map_parts(serializer, MAP_FIELDS, self.config, Some(&root), &SnapshotEntries(&self.entries))
+    }
+}
+
+/// Serializes the entries of a snapshot as a sequence of entries, in order
+struct SnapshotEntries<'s, K, V>(&'s [(K, V)]);
+
+impl<K, V> Serialize for SnapshotEntries<'_, K, V>
+where
+    K: Serialize,
+    V: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        serializer.collect_seq(self.0.iter().map(|(key, value)| EntryRef(key, value)))
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for TravlMapSnapshot<K, V>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        DeserializedMap::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl<K, V> From<DeserializedMap<K, V>> for TravlMapSnapshot<K, V> {
+    fn from(map: DeserializedMap<K, V>) -> Self {
+        Self {
+            config: map.config,
+            entries: map.entries,
+        }
+    }
+}
+
+impl Serialize for TravlMapConfig {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let mut state = serializer.serialize_struct("TravlMapConfig", CONFIG_FIELDS.len())?;
+        state.serialize_field("imbalance_factor", &self.imbalance_factor())?;
+        state.serialize_field("tie_policy", &self.tie_policy())?;
+        state.serialize_field("balancing", &self.balancing())?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TravlMapConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        struct ConfigVisitor;
+
+        impl<'de> Visitor<'de> for ConfigVisitor {
+            type Value = TravlMapConfig;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct TravlMapConfig")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>
+            {
+                let imbalance_factor = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let tie_policy = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let balancing = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+
+                Ok(TravlMapConfig::new(check_imbalance_factor(imbalance_factor)?)
+                    .with_tie_policy(tie_policy)
+                    .with_balancing(balancing))
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>
+            {
+                let mut imbalance_factor = None;
+                let mut tie_policy = None;
+                let mut balancing = None;
+
+                while let Some(field) = map.next_key::<MapField>()? {
+                    match field {
+                        MapField::ImbalanceFactor => {
+                            set_once(&mut imbalance_factor, "imbalance_factor", || map.next_value())?;
+                        },
+                        MapField::TiePolicy => set_once(&mut tie_policy, "tie_policy", || map.next_value())?,
+                        MapField::Balancing => set_once(&mut balancing, "balancing", || map.next_value())?,
+                        MapField::Version => return Err(de::Error::unknown_field("version", CONFIG_FIELDS)),
+                        MapField::Root => return Err(de::Error::unknown_field("root", CONFIG_FIELDS)),
+                        MapField::Content => return Err(de::Error::unknown_field("entries", CONFIG_FIELDS)),
+                    }
+                }
+
+                let imbalance_factor = imbalance_factor.ok_or_else(|| de::Error::missing_field("imbalance_factor"))?;
+                Ok(TravlMapConfig::new(check_imbalance_factor(imbalance_factor)?)
+                    .with_tie_policy(tie_policy.ok_or_else(|| de::Error::missing_field("tie_policy"))?)
+                    .with_balancing(balancing.ok_or_else(|| de::Error::missing_field("balancing"))?))
+            }
+        }
+
+        deserializer.deserialize_struct("TravlMapConfig", CONFIG_FIELDS, ConfigVisitor)
+    }
+}
+
+impl Serialize for TiePolicy {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        match self {
+            TiePolicy::After => serializer.serialize_unit_variant("TiePolicy", 0, TIE_POLICY_VARIANTS[0]),
+            TiePolicy::Before => serializer.serialize_unit_variant("TiePolicy", 1, TIE_POLICY_VARIANTS[1]),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TiePolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        struct TiePolicyVisitor;
+
+        impl<'de> Visitor<'de> for TiePolicyVisitor {
+            type Value = TiePolicy;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("enum TiePolicy")
+            }
+
+            fn visit_enum<A>(self, data: A) -> Result<TiePolicy, A::Error>
+            where
+                A: EnumAccess<'de>
+            {
+                let (tie_policy, variant) = data.variant_seed(TiePolicyVariant)?;
+                variant.unit_variant()?;
+                Ok(tie_policy)
+            }
+        }
+
+        deserializer.deserialize_enum("TiePolicy", TIE_POLICY_VARIANTS, TiePolicyVisitor)
+    }
+}
+
+/// Seed deserializing the variant of a [`TiePolicy`], by name or by index
+struct TiePolicyVariant;
+
+impl<'de> DeserializeSeed<'de> for TiePolicyVariant {
+    type Value = TiePolicy;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl Visitor<'_> for TiePolicyVariant {
+    type Value = TiePolicy;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("`After` or `Before`")
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<TiePolicy, E>
+    where
+        E: de::Error
+    {
+        match value {
+            0 => Ok(TiePolicy::After),
+            1 => Ok(TiePolicy::Before),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(value), &self)),
+        }
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<TiePolicy, E>
+    where
+        E: de::Error
+    {
+        match value {
+            "After" => Ok(TiePolicy::After),
+            "Before" => Ok(TiePolicy::Before),
+            _ => Err(de::Error::unknown_variant(value, TIE_POLICY_VARIANTS)),
+        }
+    }
+}
+
+impl Serialize for Balancing {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        match self {
+            Balancing::Strict => serializer.serialize_unit_variant("Balancing", 0, BALANCING_VARIANTS[0]),
+            Balancing::Relaxed(slack) => {
+                serializer.serialize_newtype_variant("Balancing", 1, BALANCING_VARIANTS[1], slack)
+            },
+            Balancing::Scapegoat => serializer.serialize_unit_variant("Balancing", 2, BALANCING_VARIANTS[2]),
+            Balancing::Weight => serializer.serialize_unit_variant("Balancing", 3, BALANCING_VARIANTS[3]),
+            Balancing::Rank => serializer.serialize_unit_variant("Balancing", 4, BALANCING_VARIANTS[4]),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Balancing {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        struct BalancingVisitor;
+
+        impl<'de> Visitor<'de> for BalancingVisitor {
+            type Value = Balancing;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("enum Balancing")
+            }
+
+            fn visit_enum<A>(self, data: A) -> Result<Balancing, A::Error>
+            where
+                A: EnumAccess<'de>
+            {
+                let (balancing, variant) = data.variant_seed(BalancingVariant)?;
+                match balancing {
+                    Balancing::Relaxed(_) => variant.newtype_variant().map(Balancing::Relaxed),
+                    Balancing::Strict | Balancing::Scapegoat | Balancing::Weight | Balancing::Rank => {
+                        variant.unit_variant().map(|()| balancing)
+                    },
+                }
+            }
+        }
+
+        deserializer.deserialize_enum("Balancing", BALANCING_VARIANTS, BalancingVisitor)
+    }
+}
+
+/// Seed deserializing the variant of a [`Balancing`] strategy, by name or by index
+///
+/// The data of the variant is deserialized afterwards, the returned strategy only telling the variants apart.
+struct BalancingVariant;
+
+impl<'de> DeserializeSeed<'de> for BalancingVariant {
+    type Value = Balancing;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl Visitor<'_> for BalancingVariant {
+    type Value = Balancing;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("`Strict`, `Relaxed`, `Scapegoat`, `Weight` or `Rank`")
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Balancing, E>
+    where
+        E: de::Error
+    {
+        match value {
+            0 => Ok(Balancing::Strict),
+            1 => Ok(Balancing::Relaxed(0)),
+            2 => Ok(Balancing::Scapegoat),
+            3 => Ok(Balancing::Weight),
+            4 => Ok(Balancing::Rank),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(value), &self)),
+        }
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Balancing, E>
+    where
+        E: de::Error
+    {
+        match value {
+            "Strict" => Ok(Balancing::Strict),
+            "Relaxed" => Ok(Balancing::Relaxed(0)),
+            "Scapegoat" => Ok(Balancing::Scapegoat),
+            "Weight" => Ok(Balancing::Weight),
+            "Rank" => Ok(Balancing::Rank),
+            _ => Err(de::Error::unknown_variant(value, BALANCING_VARIANTS)),
+        }
+    }
+}
+
+/// Seed deserializing a [`TravlMap`] using the given property getter and ordering function
+///
+/// The tree is rebuilt from the deserialized entries, sorting them with the given functions.
+pub struct TravlMapSeed<'a, K, V, P> {
+    prop_fn: PropFn<'a, V, P>,
+    ordering_fn: OrdFn<'a, P>,
+    format: SerdeFormat,
+    key_marker: PhantomData<K>,
+}
+
+impl<'a, K, V, P> TravlMapSeed<'a, K, V, P> {
+    /// Creates a seed using the given property getter and ordering function
+    #[must_use]
+    pub fn new(prop_fn: PropFn<'a, V, P>, ordering_fn: OrdFn<'a, P>) -> Self {
+        Self {
+            prop_fn,
+            ordering_fn,
+            format: SerdeFormat::default(),
+            key_marker: PhantomData,
+        }
+    }
+
+    /// Sets the format the map is expected to be serialized in
+    #[must_use]
+    pub fn with_format(mut self, format: SerdeFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Turns the seed into one streaming the deserialized entries directly into the map
+    ///
+    /// Entries are not collected and sorted before building the tree, which avoids holding all of them
+    /// in memory twice, but they must have been serialized in the order defined by the seed's functions.
+    /// This has no effect when deserializing the [structural format](SerdeFormat::Structural).
+    #[must_use]
+    pub fn streaming(self) -> StreamingTravlMapSeed<'a, K, V, P> {
+        StreamingTravlMapSeed { seed: self }
+    }
+}
+
+impl<'de, 'a, K, V, P> DeserializeSeed<'de> for TravlMapSeed<'a, K, V, P>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone + 'a,
+    V: Deserialize<'de> + 'a
+{
+    type Value = TravlMap<'a, K, V, P>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let parts: MapParts<K, Vec<Entry<K, V>>> = match self.format {
+            SerdeFormat::Full => {
+                let visitor = MapPartsVisitor::new(PhantomData, true);
+                deserializer.deserialize_struct(MAP_NAME, MAP_FIELDS, visitor)?
+            },
+            SerdeFormat::Flat => MapParts {
+                config: TravlMapConfig::default(),
+                root: None,
+                content: Vec::deserialize(deserializer)?,
+            },
+            SerdeFormat::Structural => {
+                let parts: MapParts<K, Vec<NodeRecord<K, V>>> = deserializer.deserialize_struct(
+                    MAP_NAME,
+                    STRUCTURAL_MAP_FIELDS,
+                    MapPartsVisitor::new(PhantomData, false),
+                )?;
+
+                check_links(&parts.content)?;
+
+                return TravlMap::from_structure(parts.config, parts.content, self.prop_fn, self.ordering_fn)
+                    .map_err(|violation| invalid_structure(&violation));
+            },
+        };
+
+        let mut map = TravlMap::new_with_prop_getter_and_ordering(self.prop_fn, self.ordering_fn);
+        map.set_config(parts.config);
+        map.rebuild_around(Entry::into_pairs(parts.content), parts.root.as_ref());
+        Ok(map)
+    }
+}
+
+/// Seed deserializing a [`TravlMap`] by streaming its entries into a [`SortedEntriesBuilder`]
+///
+/// Created by [`TravlMapSeed::streaming`].
+pub struct StreamingTravlMapSeed<'a, K, V, P> {
+    seed: TravlMapSeed<'a, K, V, P>,
+}
+
+impl<'de, 'a, K, V, P> DeserializeSeed<'de> for StreamingTravlMapSeed<'a, K, V, P>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone + 'a,
+    V: Deserialize<'de> + 'a
+{
+    type Value = TravlMap<'a, K, V, P>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let format = self.seed.format;
+        if format == SerdeFormat::Structural {
+            return self.seed.deserialize(deserializer);
+        }
+
+        let TravlMapSeed { prop_fn, ordering_fn, .. } = self.seed;
+        let builder = SortedEntriesBuilder::new(TravlMap::new_with_prop_getter_and_ordering(prop_fn, ordering_fn));
+
+        let parts = if format == SerdeFormat::Flat {
+            MapParts {
+                config: TravlMapConfig::default(),
+                root: None,
+                content: EntriesStream(builder).deserialize(deserializer)?,
+            }
+        } else {
+            let visitor = MapPartsVisitor::new(EntriesStream(builder), true);
+            deserializer.deserialize_struct(MAP_NAME, MAP_FIELDS, visitor)?
+        };
+
+        let mut map = parts.content.finish_around(parts.root.as_ref());
+        map.set_config(parts.config);
+        Ok(map)
+    }
+}
+
+/// Seed deserializing a sequence of entries by pushing them one by one into a [`SortedEntriesBuilder`]
+struct EntriesStream<'a, K, V, P>(SortedEntriesBuilder<'a, K, V, P>);
+
+impl<'de, 'a, K, V, P> DeserializeSeed<'de> for EntriesStream<'a, K, V, P>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone,
+    V: Deserialize<'de>
+{
+    type Value = SortedEntriesBuilder<'a, K, V, P>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a, K, V, P> Visitor<'de> for EntriesStream<'a, K, V, P>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone,
+    V: Deserialize<'de>
+{
+    type Value = SortedEntriesBuilder<'a, K, V, P>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of sorted entries")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>
+    {
+        while let Some(Entry(key, value)) = seq.next_element()? {
+            self.0.push(key, value).map_err(de::Error::custom)?;
+        }
+
+        Ok(self.0)
+    }
+}
+
+/// Deserialized content of a map, before the tree is rebuilt
+///
+/// The content is either the entries or the nodes of the map, depending on the format. The root is only
+/// serialized along with entries, the root of serialized nodes being the first one.
+struct MapParts<K, C> {
+    config: TravlMapConfig,
+    root: Option<K>,
+    content: C,
+}
+
+/// Serialized map field
+enum MapField {
+    Version,
+    ImbalanceFactor,
+    TiePolicy,
+    Balancing,
+    Root,
+    /// Entries or nodes of the map, depending on the format
+    Content,
+}
+
+impl<'de> Deserialize<'de> for MapField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        struct MapFieldVisitor;
+
+        impl Visitor<'_> for MapFieldVisitor {
+            type Value = MapField;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str(
+                    "`version`, `imbalance_factor`, `tie_policy`, `balancing`, `root`, `entries` or `nodes`",
+                )
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<MapField, E>
+            where
+                E: de::Error
+            {
+                match value {
+                    "version" => Ok(MapField::Version),
+                    "imbalance_factor" => Ok(MapField::ImbalanceFactor),
+                    "tie_policy" => Ok(MapField::TiePolicy),
+                    "balancing" => Ok(MapField::Balancing),
+                    "root" => Ok(MapField::Root),
+                    "entries" | "nodes" => Ok(MapField::Content),
+                    _ => Err(de::Error::unknown_field(value, MAP_FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(MapFieldVisitor)
+    }
+}
+
+/// Visitor deserializing the content of a map, deserializing the entries or nodes using the given seed
+struct MapPartsVisitor<K, S> {
+    content_seed: S,
+    /// Whether the root is serialized, which is the case along with entries
+    has_root: bool,
+    key_marker: PhantomData<K>,
+}
+
+impl<K, S> MapPartsVisitor<K, S> {
+    /// Creates a visitor deserializing the content using the given seed
+    fn new(content_seed: S, has_root: bool) -> Self {
+        Self { content_seed, has_root, key_marker: PhantomData }
+    }
+}
+
+impl<'de, K, S> Visitor<'de> for MapPartsVisitor<K, S>
+where
+    K: Deserialize<'de>,
+    S: DeserializeSeed<'de>
+{
+    type Value = MapParts<K, S::Value>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct TravlMap")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>
+    {
+        let version = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        check_version(version)?;
+
+        let imbalance_factor = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let tie_policy = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let balancing = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        let mut length = 4;
+
+        let mut root = None;
+        if self.has_root {
+            root = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(length, &self))?;
+            length += 1;
+        }
+
+        let content = seq
+            .next_element_seed(self.content_seed)?
+            .ok_or_else(|| de::Error::invalid_length(length, &"struct TravlMap"))?;
+
+        Ok(MapParts {
+            config: TravlMapConfig::new(check_imbalance_factor(imbalance_factor)?)
+                .with_tie_policy(tie_policy)
+                .with_balancing(balancing),
+            root,
+            content,
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>
+    {
+        let mut version = None;
+        let mut imbalance_factor = None;
+        let mut tie_policy = None;
+        let mut balancing = None;
+        let mut root = None;
+        let mut content = None;
+        let mut content_seed = Some(self.content_seed);
+
+        while let Some(field) = map.next_key()? {
+            match field {
+                MapField::Version => set_once(&mut version, "version", || map.next_value())?,
+                MapField::ImbalanceFactor => set_once(&mut imbalance_factor, "imbalance_factor", || map.next_value())?,
+                MapField::TiePolicy => set_once(&mut tie_policy, "tie_policy", || map.next_value())?,
+                MapField::Balancing => set_once(&mut balancing, "balancing", || map.next_value())?,
+                MapField::Root if self.has_root => set_once(&mut root, "root", || map.next_value())?,
+                MapField::Root => return Err(de::Error::unknown_field("root", STRUCTURAL_MAP_FIELDS)),
+                MapField::Content => {
+                    let seed = content_seed.take().ok_or_else(|| de::Error::duplicate_field("entries"))?;
+                    content = Some(map.next_value_seed(seed)?);
+                },
+            }
+        }
+
+        check_version(version.ok_or_else(|| de::Error::missing_field("version"))?)?;
+        let imbalance_factor = imbalance_factor.ok_or_else(|| de::Error::missing_field("imbalance_factor"))?;
+
+        Ok(MapParts {
+            config: TravlMapConfig::new(check_imbalance_factor(imbalance_factor)?)
+                .with_tie_policy(tie_policy.ok_or_else(|| de::Error::missing_field("tie_policy"))?)
+                .with_balancing(balancing.ok_or_else(|| de::Error::missing_field("balancing"))?),
+            root: root.flatten(),
+            content: content.ok_or_else(|| de::Error::missing_field("entries"))?,
+        })
+    }
+}
+
+/// Checks that a map was serialized using the current format version, the only one there is so far
+fn check_version<E>(version: u32) -> Result<(), E>
+where
+    E: de::Error
+{
+    if version == SERDE_FORMAT_VERSION {
+        Ok(())
+    } else {
+        Err(E::invalid_value(de::Unexpected::Unsigned(u64::from(version)), &"a supported format version"))
+    }
+}
+
+/// Checks that a deserialized imbalance factor doesn't exceed
+/// [`MAX_IMBALANCE_FACTOR`](crate::core::MAX_IMBALANCE_FACTOR)
+fn check_imbalance_factor<E>(imbalance_factor: u64) -> Result<u64, E>
+where
+    E: de::Error
+{
+    crate::core::check_imbalance_factor(imbalance_factor).map_err(|_| {
+        E::invalid_value(
+            de::Unexpected::Unsigned(imbalance_factor),
+            &"an imbalance factor of at most MAX_IMBALANCE_FACTOR",
+        )
+    })
+}
+
+/// Checks that all the links of the given node records are valid indices
+fn check_links<K, V, E>(records: &[NodeRecord<K, V>]) -> Result<(), E>
+where
+    E: de::Error
+{
+    let links = records
+        .iter()
+        .flat_map(|record| [record.parent(), record.left(), record.right()])
+        .flatten();
+
+    for index in links {
+        if index >= records.len() {
+            return Err(E::invalid_value(
+                de::Unexpected::Unsigned(index as u64),
+                &"the index of a serialized node",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the error of node records which don't form a valid tree
+///
+/// Keys aren't required to be printable, so the error only describes the violation.
+fn invalid_structure<K, E>(violation: &Violation<K>) -> E
+where
+    E: de::Error
+{
+    let reason = match violation {
+        Violation::MissingRoot => "the nodes have no root",
+        Violation::RootHasParent(_) => "the root has a parent",
+        Violation::Unreachable(_) => "a node can't be reached from the root",
+        Violation::ForeignNode(_) => "a node links to a node which isn't serialized",
+        Violation::ParentMismatch { .. } => "a child doesn't link back to its parent",
+        Violation::KeyMismatch(_) => "a node is stored under another key",
+        Violation::WrongHeight { .. } => "a node has the wrong height",
+        Violation::WrongSize { key: None, .. } => "several nodes share a key",
+        Violation::WrongSize { key: Some(_), .. } => "the nodes aren't serialized in pre-order",
+        Violation::Imbalanced { .. } | Violation::WeightImbalanced { .. } | Violation::WrongRank { .. } => {
+            "a node is imbalanced"
+        },
+        Violation::OutOfOrder { .. } => "the nodes are out of order",
+    };
+
+    E::custom(format_args!("invalid tree structure: {reason}"))
+}
+
+/// Names of the serialized node fields
+const NODE_FIELDS: &[&str] = &["key", "value", "height", "parent", "left", "right"];
+
+/// Node serialized along with the indices of the nodes it links to
+///
+/// Rather than repeating the keys of the linked nodes, links are serialized as indices
+/// within a list of nodes, for example the position of the nodes within a traversal.
+/// Links to nodes that don't have an index are serialized as missing links.
+pub struct IndexedNode<'n, K, V> {
+    node: &'n TravlNode<K, V>,
+    parent: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+impl<'n, K, V> IndexedNode<'n, K, V>
+where
+    K: Hash + Eq
+{
+    /// Wraps the given node, finding the indices of the nodes it links to using their keys
+    #[must_use]
+    pub fn new<S>(node: &'n TravlNode<K, V>, indices: &HashMap<&K, usize, S>) -> Self
+    where
+        S: core::hash::BuildHasher
+    {
+        let index_of = |linked: Option<&K>| linked.and_then(|linked| indices.get(linked).copied());
+
+        Self {
+            node,
+            parent: index_of(node.parent()),
+            left: index_of(node.left()),
+            right: index_of(node.right()),
+        }
+    }
+}
+
+impl<K, V> Serialize for IndexedNode<'_, K, V>
+where
+    K: Serialize,
+    V: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let mut state = serializer.serialize_struct("TravlNode", NODE_FIELDS.len())?;
+        state.serialize_field("key", self.node.key())?;
+        state.serialize_field("value", self.node.value())?;
+        state.serialize_field("height", &self.node.height())?;
+        state.serialize_field("parent", &self.parent)?;
+        state.serialize_field("left", &self.left)?;
+        state.serialize_field("right", &self.right)?;
+        state.end()
+    }
+}
+
+/// Deserialized node, linking to other nodes by their indices
+///
+/// Counterpart of [`IndexedNode`] used for deserialization.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeRecord<K, V> {
+    key: K,
+    value: V,
+    height: u64,
+    parent: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+impl<K, V> NodeRecord<K, V> {
+    /// Returns the node's key
+    #[must_use]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the node's value
+    #[must_use]
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// Returns the node's height
+    #[must_use]
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Returns the index of the node's parent
+    #[must_use]
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// Returns the index of the node's left child
+    #[must_use]
+    pub fn left(&self) -> Option<usize> {
+        self.left
+    }
+
+    /// Returns the index of the node's right child
+    #[must_use]
+    pub fn right(&self) -> Option<usize> {
+        self.right
+    }
+
+    /// Creates an unlinked node out of the record's key and value
+    #[must_use]
+    pub fn into_node(self) -> TravlNode<K, V> {
+        TravlNode::new(self.key, self.value)
+    }
+}
+
+/// Serialized node field
+enum NodeField {
+    Key,
+    Value,
+    Height,
+    Parent,
+    Left,
+    Right,
+}
+
+impl<'de> Deserialize<'de> for NodeField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        struct NodeFieldVisitor;
+
+        impl Visitor<'_> for NodeFieldVisitor {
+            type Value = NodeField;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("`key`, `value`, `height`, `parent`, `left` or `right`")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<NodeField, E>
+            where
+                E: de::Error
+            {
+                match value {
+                    "key" => Ok(NodeField::Key),
+                    "value" => Ok(NodeField::Value),
+                    "height" => Ok(NodeField::Height),
+                    "parent" => Ok(NodeField::Parent),
+                    "left" => Ok(NodeField::Left),
+                    "right" => Ok(NodeField::Right),
+                    _ => Err(de::Error::unknown_field(value, NODE_FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(NodeFieldVisitor)
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for NodeRecord<K, V>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        deserializer.deserialize_struct("TravlNode", NODE_FIELDS, NodeRecordVisitor(PhantomData))
+    }
+}
+
+/// Visitor deserializing a [`NodeRecord`]
+struct NodeRecordVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K, V> Visitor<'de> for NodeRecordVisitor<K, V>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>
+{
+    type Value = NodeRecord<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct TravlNode")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>
+    {
+        Ok(NodeRecord {
+            key: seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?,
+            value: seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?,
+            height: seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?,
+            parent: seq.next_element()?.ok_or_else(|| de::Error::invalid_length(3, &self))?,
+            left: seq.next_element()?.ok_or_else(|| de::Error::invalid_length(4, &self))?,
+            right: seq.next_element()?.ok_or_else(|| de::Error::invalid_length(5, &self))?,
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>
+    {
+        let mut key = None;
+        let mut value = None;
+        let mut height = None;
+        let mut parent = None;
+        let mut left = None;
+        let mut right = None;
+
+        while let Some(field) = map.next_key()? {
+            match field {
+                NodeField::Key => set_once(&mut key, "key", || map.next_value())?,
+                NodeField::Value => set_once(&mut value, "value", || map.next_value())?,
+                NodeField::Height => set_once(&mut height, "height", || map.next_value())?,
+                NodeField::Parent => set_once(&mut parent, "parent", || map.next_value())?,
+                NodeField::Left => set_once(&mut left, "left", || map.next_value())?,
+                NodeField::Right => set_once(&mut right, "right", || map.next_value())?,
+            }
+        }
+
+        Ok(NodeRecord {
+            key: key.ok_or_else(|| de::Error::missing_field("key"))?,
+            value: value.ok_or_else(|| de::Error::missing_field("value"))?,
+            height: height.ok_or_else(|| de::Error::missing_field("height"))?,
+            parent: parent.ok_or_else(|| de::Error::missing_field("parent"))?,
+            left: left.ok_or_else(|| de::Error::missing_field("left"))?,
+            right: right.ok_or_else(|| de::Error::missing_field("right"))?,
+        })
+    }
+}
+
+/// Sets a deserialized field, failing if it was already set
+fn set_once<T, E, F>(field: &mut Option<T>, name: &'static str, value: F) -> Result<(), E>
+where
+    E: de::Error,
+    F: FnOnce() -> Result<T, E>
+{
+    if field.is_some() {
+        return Err(E::duplicate_field(name));
+    }
+
+    *field = Some(value()?);
+    Ok(())
+}
@@ -1,4 +1,4 @@
-//! Map similar to [`BTreeSet`](std::collections::BTreeSet) and its operations
+//! Map similar to [`BTreeSet`](alloc::collections::BTreeSet) and its operations
 //! 
 //! Refer to the [`core`](crate::core) module for information about the inner workings
 //! of the AVL tree, its nodes and related operations.
@@ -0,0 +1,413 @@
+//! Map keeping its entries in a sorted inline array while there are few of them
+//!
+//! A [`SmallTravlMap`] orders its entries like a [`TravlMap`], following a property getter, an ordering function
+//! and a [tie policy](crate::map::TiePolicy), but holds up to `N` of them in an array stored within the map,
+//! sorted in the order of the map. There are no nodes, heights or links to maintain then: lookups are linear,
+//! searches by property are binary, and entries are shifted into place, which beats walking a tree for tiny
+//! maps and doesn't allocate.
+//!
+//! Inserting more than `N` entries promotes the map to tree form: the entries are moved, already sorted, into a
+//! balanced [`TravlMap`] handling every later operation, until removals empty it and the array takes over
+//! again. Maps converted from a [`TravlMap`], such as one configured by [`TravlMap::builder`], keep its
+//! configuration once promoted.
+
+use core::cmp::Ordering;
+use core::fmt::{self, Debug};
+use core::iter::FusedIterator;
+use core::slice;
+
+use alloc::vec::Vec;
+
+use crate::core::TravlNode;
+use crate::map::{Iter, NodeMap, SearchType, TiePolicy, TravlMap};
+use crate::storage::NodeStorage;
+
+/// Number of entries a [`SmallTravlMap`] holds inline by default
+pub const DEFAULT_INLINE_CAPACITY: usize = 16;
+
+/// Map holding up to `N` entries inline, see the [module-level documentation](self)
+pub struct SmallTravlMap<'a, K, V, P = V, const N: usize = DEFAULT_INLINE_CAPACITY>
+where
+    V: 'a
+{
+    /// Entries in the order of the map while the tree is empty, the first `len` slots being filled
+    inline: [Option<(K, V)>; N],
+    len: usize,
+    /// Tree holding the entries once they outnumber `N`, whose property getter, ordering function and
+    /// configuration order the inline entries otherwise
+    tree: TravlMap<'a, K, V, P>,
+}
+
+impl<K, V, P, const N: usize> Debug for SmallTravlMap<'_, K, V, P, N>
+where
+    K: Debug,
+    V: Debug,
+    NodeMap<K, V>: NodeStorage<K, V>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V> Default for SmallTravlMap<'a, K, V>
+where
+    V: Ord + 'a
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, K, T, const N: usize> SmallTravlMap<'a, K, T, T, N>
+where
+    T: Ord + 'a
+{
+    /// Creates an empty map ordering its entries by value
+    #[must_use]
+    pub fn new() -> Self {
+        Self::around(TravlMap::new())
+    }
+}
+
+impl<'a, K, V, P, const N: usize> SmallTravlMap<'a, K, V, P, N> {
+    /// Creates an empty map using a custom property getter and ordering function, given as closures or function
+    /// pointers
+    ///
+    /// See [`TravlMap::with_ordering`] about boxing.
+    #[must_use]
+    pub fn with_prop_getter_and_ordering<G, F>(prop_fn: G, ordering_fn: F) -> Self
+    where
+        G: Fn(&V) -> &P + Send + Sync + 'a,
+        F: Fn(&P, &P) -> Ordering + Send + Sync + 'a
+    {
+        Self::around(TravlMap::with_prop_getter_and_ordering(prop_fn, ordering_fn))
+    }
+
+    /// Creates a map holding no entry inline around the given tree
+    fn around(tree: TravlMap<'a, K, V, P>) -> Self {
+        Self {
+            inline: [const { None }; N],
+            len: 0,
+            tree,
+        }
+    }
+}
+
+impl<'a, K, V, P, const N: usize> From<TravlMap<'a, K, V, P>> for SmallTravlMap<'a, K, V, P, N>
+where
+    K: Clone,
+    NodeMap<K, V>: NodeStorage<K, V>
+{
+    /// Wraps a map, keeping its property getter, ordering function and configuration
+    ///
+    /// The entries of the map are moved inline if there are at most `N` of them.
+    fn from(mut tree: TravlMap<'a, K, V, P>) -> Self {
+        if tree.len() > N {
+            return Self::around(tree);
+        }
+
+        let entries = tree.take_ordered_entries();
+        let mut map = Self::around(tree);
+        for entry in entries {
+            map.inline[map.len] = Some(entry);
+            map.len += 1;
+        }
+
+        map
+    }
+}
+
+impl<'a, K, V, P, const N: usize> SmallTravlMap<'a, K, V, P, N>
+where
+    NodeMap<K, V>: NodeStorage<K, V>
+{
+    /// Returns the number of entries in the map
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len + self.tree.len()
+    }
+
+    /// Returns `true` if the map contains no entry
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the entries are held inline rather than in a tree
+    #[must_use]
+    pub fn is_inline(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns the tree holding the entries once the map was promoted, or [`None`] while they're held inline
+    #[must_use]
+    pub fn as_map(&self) -> Option<&TravlMap<'a, K, V, P>> {
+        (!self.tree.is_empty()).then_some(&self.tree)
+    }
+
+    /// Returns a mutable reference to the tree holding the entries once the map was promoted, or [`None`] while
+    /// they're held inline
+    ///
+    /// Entries removed from the tree until it is empty are held inline again.
+    #[must_use]
+    pub fn as_map_mut(&mut self) -> Option<&mut TravlMap<'a, K, V, P>> {
+        (!self.tree.is_empty()).then_some(&mut self.tree)
+    }
+
+    /// Returns the entries held inline, in the order of the map
+    fn inline_entries(&self) -> impl Iterator<Item = &(K, V)> {
+        self.inline[..self.len].iter().flatten()
+    }
+
+    /// Returns the index of the inline entry of the given key, if there is one
+    fn inline_position(&self, key: &K) -> Option<usize>
+    where
+        K: Eq
+    {
+        self.inline_entries().position(|(entry_key, _)| entry_key == key)
+    }
+
+    /// Returns whether the map contains a given key
+    #[must_use]
+    pub fn contains_key(&self, key: &K) -> bool
+    where
+        K: Eq
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns the value associated to the given key, if it exists
+    ///
+    /// Inline entries are searched linearly, which stays cheaper than hashing the key for the few entries held
+    /// inline.
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&V>
+    where
+        K: Eq
+    {
+        if !self.tree.is_empty() {
+            return self.tree.get(key).map(TravlNode::value);
+        }
+
+        self.inline_entries().find(|(entry_key, _)| entry_key == key).map(|(_, value)| value)
+    }
+
+    /// Finds the entry whose property matches the given one according to the search type, see
+    /// [`TravlMap::find`]
+    ///
+    /// Inline entries are searched by binary search, in O(log N).
+    #[must_use]
+    pub fn find(&self, prop: &P, search_type: SearchType) -> Option<(&K, &V)> {
+        if !self.tree.is_empty() {
+            return self.tree.find(prop, search_type).map(|node| (node.key(), node.value()));
+        }
+
+        let Self { inline, len, tree } = self;
+        let entries = &inline[..*len];
+        let ordering_of = |entry: &Option<(K, V)>| {
+            entry.as_ref().map_or(Ordering::Greater, |(_, value)| tree.compare_to_prop(value, prop))
+        };
+
+        let entry = match search_type {
+            SearchType::Equality => {
+                let index = entries.partition_point(|entry| ordering_of(entry) == Ordering::Less);
+                entries.get(index).filter(|entry| ordering_of(entry) == Ordering::Equal)
+            },
+            #[allow(deprecated)]
+            SearchType::Nearest => {
+                let index = entries.partition_point(|entry| ordering_of(entry) == Ordering::Less);
+                entries.get(index).or_else(|| entries.last())
+            },
+            SearchType::NearestToBottom => {
+                let index = entries.partition_point(|entry| ordering_of(entry) != Ordering::Greater);
+                index.checked_sub(1).and_then(|index| entries.get(index))
+            },
+            SearchType::NearestToTop => {
+                let index = entries.partition_point(|entry| ordering_of(entry) == Ordering::Less);
+                entries.get(index)
+            },
+        };
+
+        entry.as_ref().and_then(|entry| entry.as_ref()).map(|(key, value)| (key, value))
+    }
+
+    /// Inserts an entry, returning the value previously associated to its key
+    ///
+    /// Entries are placed as [`TravlMap::insert`] places them, following the map's [tie policy](TiePolicy). The
+    /// map is promoted to tree form when it already holds `N` entries inline and the key is new, in O(N).
+    pub fn insert(&mut self, key: K, value: V) -> Option<V>
+    where
+        K: Eq + Clone
+    {
+        if !self.tree.is_empty() {
+            return self.tree.insert(key, value);
+        }
+
+        if let Some(index) = self.inline_position(&key) {
+            let (_, previous) = self.take_inline(index);
+
+            // As with `TravlMap::apply`, an entry still ordered between its neighbors stays in place
+            let stays = index.checked_sub(1).is_none_or(|before| self.compare_inline(before, &value).is_le())
+                && (index == self.len || self.compare_inline(index, &value).is_ge());
+            let index = if stays { index } else { self.inline_slot(&value) };
+            self.put_inline(index, (key, value));
+
+            return Some(previous);
+        }
+
+        if self.len == N {
+            self.promote();
+            return self.tree.insert(key, value);
+        }
+
+        let index = self.inline_slot(&value);
+        self.put_inline(index, (key, value));
+        None
+    }
+
+    /// Removes the entry of the given key, returning its value
+    ///
+    /// A tree emptied by removals hands the following entries back to the inline array.
+    pub fn remove(&mut self, key: &K) -> Option<V>
+    where
+        K: Eq + Clone
+    {
+        if !self.tree.is_empty() {
+            return self.tree.remove(key);
+        }
+
+        let index = self.inline_position(key)?;
+        Some(self.take_inline(index).1)
+    }
+
+    /// Returns an iterator over the entries, in the order of the map
+    #[must_use]
+    pub fn iter(&self) -> SmallIter<'_, K, V> {
+        let inner = if self.tree.is_empty() {
+            SmallIterInner::Inline(self.inline[..self.len].iter())
+        } else {
+            SmallIterInner::Tree(self.tree.iter())
+        };
+
+        SmallIter { inner }
+    }
+
+    /// Converts the map into a [`TravlMap`], promoting it to tree form if needed
+    #[must_use]
+    pub fn into_map(mut self) -> TravlMap<'a, K, V, P>
+    where
+        K: Clone
+    {
+        self.promote();
+        self.tree
+    }
+
+    /// Moves the inline entries into the tree, which is built balanced out of them in O(N) as they're sorted
+    /// already
+    fn promote(&mut self)
+    where
+        K: Clone
+    {
+        let entries: Vec<(K, V)> = self.inline[..self.len].iter_mut().filter_map(Option::take).collect();
+        self.len = 0;
+
+        if !entries.is_empty() {
+            self.tree.rebuild_from(entries);
+        }
+    }
+
+    /// Compares the value of the inline entry at the given index to the given value
+    fn compare_inline(&mut self, index: usize, value: &V) -> Ordering {
+        let Self { inline, tree, .. } = self;
+        inline[index].as_ref().map_or(Ordering::Greater, |(_, entry_value)| tree.compare_values(entry_value, value))
+    }
+
+    /// Returns the index where an entry of the given value belongs among the inline entries, following the map's
+    /// tie policy
+    fn inline_slot(&mut self, value: &V) -> usize {
+        let Self { inline, len, tree } = self;
+        let tie_policy = tree.tie_policy();
+
+        inline[..*len].partition_point(|entry| {
+            let ordering =
+                entry.as_ref().map_or(Ordering::Greater, |(_, entry_value)| tree.compare_values(entry_value, value));
+            ordering == Ordering::Less || ordering == Ordering::Equal && tie_policy == TiePolicy::After
+        })
+    }
+
+    /// Shifts the inline entries from the given index onwards by one slot and puts the entry in the freed slot
+    fn put_inline(&mut self, index: usize, entry: (K, V)) {
+        self.inline[self.len] = Some(entry);
+        self.inline[index..=self.len].rotate_right(1);
+        self.len += 1;
+    }
+
+    /// Takes the inline entry at the given index out, shifting the following entries back by one slot
+    fn take_inline(&mut self, index: usize) -> (K, V) {
+        self.inline[index..self.len].rotate_left(1);
+        self.len -= 1;
+        self.inline[self.len].take().expect("the inline entries should fill the first slots")
+    }
+}
+
+impl<'m, K, V, P, const N: usize> IntoIterator for &'m SmallTravlMap<'_, K, V, P, N>
+where
+    NodeMap<K, V>: NodeStorage<K, V>
+{
+    type Item = (&'m K, &'m V);
+    type IntoIter = SmallIter<'m, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a [`SmallTravlMap`], in the order of the map
+pub struct SmallIter<'m, K, V> {
+    inner: SmallIterInner<'m, K, V>,
+}
+
+/// Source of a [`SmallIter`], depending on the form of the map
+#[allow(clippy::large_enum_variant, reason = "iterators are short-lived and boxing would allocate for tiny maps")]
+enum SmallIterInner<'m, K, V> {
+    Inline(slice::Iter<'m, Option<(K, V)>>),
+    Tree(Iter<'m, K, V>),
+}
+
+impl<K, V> Debug for SmallIter<'_, K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            SmallIterInner::Inline(entries) => f.debug_tuple("SmallIter").field(entries).finish(),
+            SmallIterInner::Tree(entries) => f.debug_tuple("SmallIter").field(entries).finish(),
+        }
+    }
+}
+
+impl<'m, K, V> Iterator for SmallIter<'m, K, V>
+where
+    NodeMap<K, V>: NodeStorage<K, V>
+{
+    type Item = (&'m K, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            SmallIterInner::Inline(entries) => entries.next()?.as_ref().map(|(key, value)| (key, value)),
+            SmallIterInner::Tree(entries) => entries.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            SmallIterInner::Inline(entries) => entries.size_hint(),
+            SmallIterInner::Tree(entries) => entries.size_hint(),
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for SmallIter<'_, K, V> where NodeMap<K, V>: NodeStorage<K, V> {}
+
+impl<K, V> FusedIterator for SmallIter<'_, K, V> where NodeMap<K, V>: NodeStorage<K, V> {}
@@ -0,0 +1,207 @@
+//! Compact binary snapshots of maps
+//!
+//! Snapshots are written by [`TravlMap::write_snapshot`] and restored by [`TravlMap::read_snapshot`],
+//! without going through serde. A snapshot is laid out as:
+//!
+//! - the magic bytes [`SNAPSHOT_MAGIC`],
+//! - the format version [`SNAPSHOT_VERSION`], as a little-endian `u32`,
+//! - the number of entries, as a little-endian `u64`,
+//! - the entries in order, each key followed by its value, encoded using [`SnapshotCodec`].
+//!
+//! Since entries are stored in order, restoring a snapshot doesn't need to sort them again.
+//!
+//! [`TravlMap::write_snapshot`]: crate::map::TravlMap::write_snapshot
+//! [`TravlMap::read_snapshot`]: crate::map::TravlMap::read_snapshot
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::io::{self, Read, Write};
+
+/// Magic bytes starting every snapshot
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"TRVL";
+
+/// Version of the snapshot layout, written after the magic bytes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Binary encoding of keys and values within snapshots
+///
+/// Numbers are encoded in little-endian, and variable-length values are prefixed by their length
+/// as a little-endian `u64`.
+pub trait SnapshotCodec: Sized {
+    /// Writes the value into the writer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the writer fails.
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    /// Reads a value from the reader
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the reader fails or if the data read is invalid.
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_snapshot_codec_for_numbers {
+    ($($number:ty),* $(,)?) => {
+        $(
+            impl SnapshotCodec for $number {
+                fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+
+                fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+                    let mut bytes = [0; size_of::<$number>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(Self::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_snapshot_codec_for_numbers!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl SnapshotCodec for usize {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        u64::try_from(*self).map_err(invalid_data)?.encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Self::try_from(u64::decode(reader)?).map_err(invalid_data)
+    }
+}
+
+impl SnapshotCodec for isize {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        i64::try_from(*self).map_err(invalid_data)?.encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Self::try_from(i64::decode(reader)?).map_err(invalid_data)
+    }
+}
+
+impl SnapshotCodec for bool {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        u8::from(*self).encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            byte => Err(invalid_data(format!("invalid boolean byte {byte}"))),
+        }
+    }
+}
+
+impl SnapshotCodec for char {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        u32::from(*self).encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let code = u32::decode(reader)?;
+        Self::from_u32(code).ok_or_else(|| invalid_data(format!("invalid character code {code:#x}")))
+    }
+}
+
+impl SnapshotCodec for String {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.len().encode(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = usize::decode(reader)?;
+        let mut bytes = Vec::new();
+        reader.take(len as u64).read_to_end(&mut bytes)?;
+
+        if bytes.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Self::from_utf8(bytes).map_err(invalid_data)
+    }
+}
+
+impl<T> SnapshotCodec for Vec<T>
+where
+    T: SnapshotCodec
+{
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.len().encode(writer)?;
+        self.iter().try_for_each(|item| item.encode(writer))
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = usize::decode(reader)?;
+        // The length isn't trusted to preallocate, as a corrupted snapshot could request any amount of memory
+        (0..len).map(|_| T::decode(reader)).collect()
+    }
+}
+
+impl<T> SnapshotCodec for Option<T>
+where
+    T: SnapshotCodec
+{
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.is_some().encode(writer)?;
+        self.as_ref().map_or(Ok(()), |value| value.encode(writer))
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        bool::decode(reader)?.then(|| T::decode(reader)).transpose()
+    }
+}
+
+impl<A, B> SnapshotCodec for (A, B)
+where
+    A: SnapshotCodec,
+    B: SnapshotCodec
+{
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.encode(writer)?;
+        self.1.encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Ok((A::decode(reader)?, B::decode(reader)?))
+    }
+}
+
+/// Writes the header of a snapshot holding the given number of entries
+pub(crate) fn write_header<W: Write>(writer: &mut W, len: usize) -> io::Result<()> {
+    writer.write_all(&SNAPSHOT_MAGIC)?;
+    SNAPSHOT_VERSION.encode(writer)?;
+    len.encode(writer)
+}
+
+/// Reads the header of a snapshot, returning its number of entries
+pub(crate) fn read_header<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let mut magic = [0; SNAPSHOT_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+
+    if magic != SNAPSHOT_MAGIC {
+        return Err(invalid_data("not a travl snapshot"));
+    }
+
+    let version = u32::decode(reader)?;
+    if version != SNAPSHOT_VERSION {
+        return Err(invalid_data(format!("unsupported snapshot version {version}")));
+    }
+
+    usize::decode(reader)
+}
+
+/// Creates an error for invalid data found in a snapshot
+pub(crate) fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn core::error::Error + Send + Sync>>
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
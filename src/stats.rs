@@ -0,0 +1,295 @@
+//! Structural statistics about trees
+//!
+//! Statistics are gathered in a single traversal by a [`StatsVisitor`], making them cheap enough
+//! to be collected periodically, for example to monitor how a tree evolves over time. Maps return the
+//! statistics of their tree with [`TravlMap::stats`](crate::map::TravlMap::stats) and
+//! [`TravlMapPersistent::stats`](crate::persistent::TravlMapPersistent::stats).
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::ControlFlow;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(all(feature = "hashbrown", not(feature = "std")))]
+use hashbrown::HashMap;
+
+use crate::core::{BalanceFactor, TravlNode};
+use crate::storage::NodeStorage;
+use crate::traversal::{Visitor, visit};
+
+/// Structural statistics about a tree
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreeStats {
+    node_count: usize,
+    leaf_count: usize,
+    /// Depth of the shallowest leaf
+    min_leaf_depth: Option<usize>,
+    nodes_per_level: Vec<usize>,
+    depth_sum: usize,
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    balance_factors: HashMap<BalanceFactor, usize>,
+}
+
+impl TreeStats {
+    /// Gathers the statistics of the subtree of the given root, whose nodes are stored in the given storage
+    ///
+    /// The imbalance factor is used to compute the [`BalanceFactor`] of each node.
+    #[must_use]
+    pub fn collect<K, V, S>(nodes: &S, root: Option<&TravlNode<K, V>>, imbalance_factor: u64) -> Self
+    where
+        S: NodeStorage<K, V>
+    {
+        let mut visitor = StatsVisitor::new(imbalance_factor);
+        let _ = visit(nodes, root, &mut visitor);
+        visitor.finish()
+    }
+
+    /// Returns the height of the tree, its number of levels
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.nodes_per_level.len()
+    }
+
+    /// Returns the number of nodes of the tree
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Returns the number of leaves of the tree, nodes without any children
+    #[must_use]
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Returns the theoretical minimum height of a tree holding as many nodes, that of a complete tree
+    ///
+    /// Comparing it to [`height`](Self::height) tells how much longer than necessary the longest lookups are,
+    /// for example when the imbalance factor is relaxed.
+    #[must_use]
+    pub fn minimum_height(&self) -> usize {
+        (usize::BITS - self.node_count.leading_zeros()) as usize
+    }
+
+    /// Returns the depth of the shallowest leaf, the root being at depth 0
+    ///
+    /// Returns [`None`] if the tree is empty.
+    #[must_use]
+    pub fn min_depth(&self) -> Option<usize> {
+        self.min_leaf_depth
+    }
+
+    /// Returns the depth of the deepest node, the root being at depth 0
+    ///
+    /// Returns [`None`] if the tree is empty.
+    #[must_use]
+    pub fn max_depth(&self) -> Option<usize> {
+        self.height().checked_sub(1)
+    }
+
+    /// Returns the number of nodes on each level of the tree, starting from the root's level
+    #[must_use]
+    pub fn nodes_per_level(&self) -> &[usize] {
+        &self.nodes_per_level
+    }
+
+    /// Returns the average depth of the nodes, the root being at depth 0
+    ///
+    /// Returns [`None`] if the tree is empty.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, reason = "averages don't need to be exact for large trees")]
+    pub fn average_depth(&self) -> Option<f64> {
+        (self.node_count != 0).then(|| self.depth_sum as f64 / self.node_count as f64)
+    }
+
+    /// Returns the number of nodes having each [`BalanceFactor`]
+    ///
+    /// Only available with the `std` or `hashbrown` feature.
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    #[must_use]
+    pub fn balance_factors(&self) -> &HashMap<BalanceFactor, usize> {
+        &self.balance_factors
+    }
+
+    /// Records a node found at the given depth
+    #[cfg_attr(
+        not(any(feature = "std", feature = "hashbrown")),
+        allow(unused_variables, reason = "balance factors are only counted in a hash map")
+    )]
+    pub(crate) fn record(&mut self, depth: usize, is_leaf: bool, balance_factor: BalanceFactor) {
+        self.node_count += 1;
+        self.depth_sum += depth;
+
+        if is_leaf {
+            self.leaf_count += 1;
+            self.min_leaf_depth = Some(self.min_leaf_depth.map_or(depth, |min| min.min(depth)));
+        }
+
+        if self.nodes_per_level.len() <= depth {
+            self.nodes_per_level.resize(depth + 1, 0);
+        }
+        self.nodes_per_level[depth] += 1;
+
+        #[cfg(any(feature = "std", feature = "hashbrown"))]
+        {
+            *self.balance_factors.entry(balance_factor).or_default() += 1;
+        }
+    }
+}
+
+/// Visitor gathering [`TreeStats`] about the nodes it visits
+#[derive(Clone, Debug, Default)]
+pub struct StatsVisitor {
+    imbalance_factor: u64,
+    stats: TreeStats,
+}
+
+impl StatsVisitor {
+    /// Creates a visitor using the given imbalance factor to compute the [`BalanceFactor`] of each node
+    #[must_use]
+    pub fn new(imbalance_factor: u64) -> Self {
+        Self {
+            imbalance_factor,
+            stats: TreeStats::default(),
+        }
+    }
+
+    /// Returns the statistics gathered so far
+    #[must_use]
+    pub fn stats(&self) -> &TreeStats {
+        &self.stats
+    }
+
+    /// Consumes the visitor and returns the statistics it gathered
+    #[must_use]
+    pub fn finish(self) -> TreeStats {
+        self.stats
+    }
+}
+
+impl<K, V, S> Visitor<K, V, S> for StatsVisitor
+where
+    S: NodeStorage<K, V>
+{
+    fn visit(&mut self, nodes: &S, node: &TravlNode<K, V>, depth: usize) -> ControlFlow<()> {
+        self.stats.record(depth, !node.is_internal(), node.balance_factor(nodes, self.imbalance_factor));
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// Shape of a node within its tree, as compared by [`StructuralDiff`]
+pub(crate) struct NodeShape<'m, K> {
+    pub(crate) key: &'m K,
+    pub(crate) depth: usize,
+    pub(crate) height: u64,
+    /// Smallest and greatest keys of the subtree of the node
+    pub(crate) span: (&'m K, &'m K),
+}
+
+/// Differences between the shapes of two trees, regardless of their values
+///
+/// Only the keys present in both trees are compared, the others are counted as unmatched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructuralDiff<'m, K> {
+    depth_changes: Vec<(&'m K, usize, usize)>,
+    height_changes: Vec<(&'m K, u64, u64)>,
+    span_changes: usize,
+    unmatched_keys: usize,
+}
+
+impl<'m, K> StructuralDiff<'m, K>
+where
+    K: Ord
+{
+    /// Compares the shapes of the nodes of two trees, both given in key order
+    pub(crate) fn compare<I, J>(shapes: I, other_shapes: J) -> Self
+    where
+        I: IntoIterator<Item = NodeShape<'m, K>>,
+        J: IntoIterator<Item = NodeShape<'m, K>>
+    {
+        let mut diff = Self {
+            depth_changes: Vec::new(),
+            height_changes: Vec::new(),
+            span_changes: 0,
+            unmatched_keys: 0,
+        };
+        let mut shapes = shapes.into_iter().peekable();
+        let mut other_shapes = other_shapes.into_iter().peekable();
+
+        loop {
+            let ordering = match (shapes.peek(), other_shapes.peek()) {
+                (Some(shape), Some(other_shape)) => shape.key.cmp(other_shape.key),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+
+            match ordering {
+                Ordering::Less => {
+                    shapes.next();
+                    diff.unmatched_keys += 1;
+                },
+                Ordering::Greater => {
+                    other_shapes.next();
+                    diff.unmatched_keys += 1;
+                },
+                Ordering::Equal => {
+                    let (Some(shape), Some(other_shape)) = (shapes.next(), other_shapes.next()) else {
+                        unreachable!("both shapes were peeked");
+                    };
+
+                    if shape.depth != other_shape.depth {
+                        diff.depth_changes.push((shape.key, shape.depth, other_shape.depth));
+                    }
+                    if shape.height != other_shape.height {
+                        diff.height_changes.push((shape.key, shape.height, other_shape.height));
+                    }
+                    if shape.span != other_shape.span {
+                        diff.span_changes += 1;
+                    }
+                },
+            }
+        }
+
+        diff
+    }
+}
+
+impl<'m, K> StructuralDiff<'m, K> {
+    /// Returns the keys sitting at different depths, with their depth in the first and in the second tree
+    #[must_use]
+    pub fn depth_changes(&self) -> &[(&'m K, usize, usize)] {
+        &self.depth_changes
+    }
+
+    /// Returns the keys whose subtrees have different heights, with their height in the first and in the second
+    /// tree
+    #[must_use]
+    pub fn height_changes(&self) -> &[(&'m K, u64, u64)] {
+        &self.height_changes
+    }
+
+    /// Returns the number of keys present in only one of the trees
+    #[must_use]
+    pub fn unmatched_keys(&self) -> usize {
+        self.unmatched_keys
+    }
+
+    /// Estimates the number of rotations turning the first tree into the second one
+    ///
+    /// A rotation changes the keys below exactly two nodes, so at least half of the nodes whose subtree holds
+    /// different keys need to be rotated. The estimate is this lower bound, which is only meaningful for trees
+    /// holding the same keys.
+    #[must_use]
+    pub fn rotation_distance_estimate(&self) -> usize {
+        self.span_changes.div_ceil(2)
+    }
+
+    /// Returns `true` if both trees hold the same keys in the same shape
+    #[must_use]
+    pub fn is_identical(&self) -> bool {
+        self.depth_changes.is_empty() && self.span_changes == 0 && self.unmatched_keys == 0
+    }
+}
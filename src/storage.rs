@@ -0,0 +1,471 @@
+//! Storage of the nodes of a [`TravlMap`](crate::map::TravlMap), by key
+//!
+//! Maps store their nodes in a [`NodeStorage`], chosen when building them with
+//! [`TravlMapBuilder::storage`](crate::map::TravlMapBuilder::storage):
+//!
+//! - [`HashMap`]s, the default through [`NodeMap`](crate::map::NodeMap), look keys up in O(1), with any
+//!   [`BuildHasher`]
+//! - [`BTreeMap`]s, named [`OrderedNodeMap`], look keys up in O(log n) by descending a tree of keys sorted by
+//!   [`Ord`], so that keys which can't or shouldn't implement [`Hash`] can be used
+//! - [`SlabStorage`] keeps nodes next to each other in a [`Vec`], reusing the slots of removed nodes, and
+//!   finds the slots of keys sorted by [`Ord`] in O(log n), which keeps nodes dense without `std` or `hashbrown`
+//!   at the cost of O(n) insertions and removals, allocating them with any [`Allocator`]
+//!
+//! A storage can also be given to the builder directly with
+//! [`TravlMapBuilder::storage_in`](crate::map::TravlMapBuilder::storage_in), for example a slab created with
+//! [`SlabStorage::new_in`] to control where nodes live, such as in an arena dedicated to a request.
+
+use alloc::collections::BTreeMap;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+pub use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::vec::Vec;
+
+use crate::core::TravlNode;
+
+/// Storage addressing nodes by descending a [`BTreeMap`] of their keys, which only need to implement [`Ord`]
+pub type OrderedNodeMap<K, V> = BTreeMap<K, TravlNode<K, V>>;
+
+/// Storage of the nodes of a map by key
+///
+/// The key a node is stored under is the key of the node, and nodes link to each other by key, so following
+/// a link is a lookup in the storage.
+pub trait NodeStorage<K, V>: Default {
+    /// Creates an empty storage able to hold at least `capacity` nodes without reallocating
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// Returns the number of stored nodes
+    fn len(&self) -> usize;
+
+    /// Returns whether no node is stored
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of nodes the storage can hold without reallocating
+    fn capacity(&self) -> usize;
+
+    /// Returns the node stored under the given key, if there is one
+    fn get(&self, key: &K) -> Option<&TravlNode<K, V>>;
+
+    /// Returns a mutable reference to the node stored under the given key, if there is one
+    fn get_mut(&mut self, key: &K) -> Option<&mut TravlNode<K, V>>;
+
+    /// Returns whether a node is stored under the given key
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the parent of the given node, if it has one stored here
+    fn parent_of(&self, node: &TravlNode<K, V>) -> Option<&TravlNode<K, V>> {
+        node.parent().and_then(|key| self.get(key))
+    }
+
+    /// Returns the left child of the given node, if it has one stored here
+    fn left_of(&self, node: &TravlNode<K, V>) -> Option<&TravlNode<K, V>> {
+        node.left().and_then(|key| self.get(key))
+    }
+
+    /// Returns the right child of the given node, if it has one stored here
+    fn right_of(&self, node: &TravlNode<K, V>) -> Option<&TravlNode<K, V>> {
+        node.right().and_then(|key| self.get(key))
+    }
+
+    /// Stores a node under the given key, returning the node previously stored under it
+    fn insert(&mut self, key: K, node: TravlNode<K, V>) -> Option<TravlNode<K, V>>;
+
+    /// Removes the node stored under the given key and returns it, if there is one
+    fn remove(&mut self, key: &K) -> Option<TravlNode<K, V>>;
+
+    /// Removes every node, keeping the allocated memory
+    fn clear(&mut self);
+
+    /// Reserves room for at least `additional` more nodes
+    fn reserve(&mut self, additional: usize);
+
+    /// Releases as much of the unused memory as possible
+    fn shrink_to_fit(&mut self);
+
+    /// Returns an iterator over the keys and nodes, in an order specific to the storage
+    fn iter<'s>(&'s self) -> impl Iterator<Item = (&'s K, &'s TravlNode<K, V>)>
+    where
+        K: 's,
+        V: 's;
+
+    /// Removes every node, returning an iterator over the nodes removed
+    fn drain(&mut self) -> impl Iterator<Item = TravlNode<K, V>>;
+
+    /// Returns an iterator over the nodes, consuming the storage
+    fn into_values(self) -> impl Iterator<Item = TravlNode<K, V>>;
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S> NodeStorage<K, V> for HashMap<K, TravlNode<K, V>, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default
+{
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::default())
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    fn get(&self, key: &K) -> Option<&TravlNode<K, V>> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut TravlNode<K, V>> {
+        self.get_mut(key)
+    }
+
+    fn insert(&mut self, key: K, node: TravlNode<K, V>) -> Option<TravlNode<K, V>> {
+        self.insert(key, node)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<TravlNode<K, V>> {
+        self.remove(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.reserve(additional);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.shrink_to_fit();
+    }
+
+    fn iter<'s>(&'s self) -> impl Iterator<Item = (&'s K, &'s TravlNode<K, V>)>
+    where
+        K: 's,
+        V: 's
+    {
+        self.iter()
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = TravlNode<K, V>> {
+        self.drain().map(|(_, node)| node)
+    }
+
+    fn into_values(self) -> impl Iterator<Item = TravlNode<K, V>> {
+        self.into_values()
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<K, V, S> NodeStorage<K, V> for hashbrown::HashMap<K, TravlNode<K, V>, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default
+{
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::default())
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    fn get(&self, key: &K) -> Option<&TravlNode<K, V>> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut TravlNode<K, V>> {
+        self.get_mut(key)
+    }
+
+    fn insert(&mut self, key: K, node: TravlNode<K, V>) -> Option<TravlNode<K, V>> {
+        self.insert(key, node)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<TravlNode<K, V>> {
+        self.remove(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.reserve(additional);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.shrink_to_fit();
+    }
+
+    fn iter<'s>(&'s self) -> impl Iterator<Item = (&'s K, &'s TravlNode<K, V>)>
+    where
+        K: 's,
+        V: 's
+    {
+        self.iter()
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = TravlNode<K, V>> {
+        self.drain().map(|(_, node)| node)
+    }
+
+    fn into_values(self) -> impl Iterator<Item = TravlNode<K, V>> {
+        self.into_values()
+    }
+}
+
+/// Keys are kept sorted, and nodes don't need any memory beyond the tree of the map
+impl<K, V> NodeStorage<K, V> for BTreeMap<K, TravlNode<K, V>>
+where
+    K: Ord
+{
+    fn with_capacity(_capacity: usize) -> Self {
+        Self::new()
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, key: &K) -> Option<&TravlNode<K, V>> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut TravlNode<K, V>> {
+        self.get_mut(key)
+    }
+
+    fn insert(&mut self, key: K, node: TravlNode<K, V>) -> Option<TravlNode<K, V>> {
+        self.insert(key, node)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<TravlNode<K, V>> {
+        self.remove(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear();
+    }
+
+    fn reserve(&mut self, _additional: usize) {}
+
+    fn shrink_to_fit(&mut self) {}
+
+    fn iter<'s>(&'s self) -> impl Iterator<Item = (&'s K, &'s TravlNode<K, V>)>
+    where
+        K: 's,
+        V: 's
+    {
+        self.iter()
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = TravlNode<K, V>> {
+        core::mem::take(self).into_values()
+    }
+
+    fn into_values(self) -> impl Iterator<Item = TravlNode<K, V>> {
+        self.into_values()
+    }
+}
+
+/// Storage keeping nodes next to each other in a [`Vec`], see the [module-level documentation](self)
+///
+/// The slots of removed nodes are reused by later insertions. Keys are only stored within their nodes: the
+/// slots holding nodes are indexed by a [`Vec`] of their positions sorted by the keys of their nodes, so keys
+/// need to implement [`Ord`]. Looking a key up is a binary search, while inserting or removing one shifts the
+/// positions after it within the index.
+///
+/// Slots and positions are allocated using the allocator `A`, the global allocator by default. Since storages
+/// are created with [`Default`] when maps are built, `A` also needs to implement it to store the nodes of a
+/// map, see [`new_in`](Self::new_in).
+#[derive(Clone, Debug)]
+pub struct SlabStorage<K, V, A = Global>
+where
+    A: Allocator
+{
+    slots: Vec<Option<TravlNode<K, V>>, A>,
+    /// Positions of the slots holding nodes, sorted by the keys of their nodes
+    index: Vec<usize, A>,
+    /// Positions of the empty slots
+    free: Vec<usize, A>,
+}
+
+impl<K, V> SlabStorage<K, V> {
+    /// Creates an empty storage
+    #[must_use]
+    pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<K, V, A> SlabStorage<K, V, A>
+where
+    A: Allocator + Clone
+{
+    /// Creates an empty storage allocating its slots using the given allocator
+    ///
+    /// A map can store its nodes in it through [`storage_in`](crate::map::TravlMapBuilder::storage_in).
+    #[must_use]
+    pub fn new_in(alloc: A) -> Self {
+        Self::with_capacity_in(0, alloc)
+    }
+
+    /// Creates an empty storage able to hold at least `capacity` nodes without reallocating, allocating its slots
+    /// using the given allocator
+    #[must_use]
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self {
+            slots: Vec::with_capacity_in(capacity, alloc.clone()),
+            index: Vec::with_capacity_in(capacity, alloc.clone()),
+            free: Vec::new_in(alloc),
+        }
+    }
+
+    /// Returns the allocator the slots are allocated with
+    #[must_use]
+    pub fn allocator(&self) -> &A {
+        self.slots.allocator()
+    }
+}
+
+impl<K, V, A> SlabStorage<K, V, A>
+where
+    A: Allocator
+{
+    /// Returns the node held by the slot at the given position of the index
+    fn indexed(&self, position: usize) -> &TravlNode<K, V> {
+        self.slots[position].as_ref().expect("indexed slots should hold a node")
+    }
+
+    /// Searches the index for the given key, returning where its position is or would be within the index
+    fn search(&self, key: &K) -> Result<usize, usize>
+    where
+        K: Ord
+    {
+        self.index.binary_search_by(|position| self.indexed(*position).key().cmp(key))
+    }
+}
+
+impl<K, V, A> Default for SlabStorage<K, V, A>
+where
+    A: Allocator + Clone + Default
+{
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<K, V, A> NodeStorage<K, V> for SlabStorage<K, V, A>
+where
+    K: Ord,
+    A: Allocator + Clone + Default
+{
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, A::default())
+    }
+    fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.capacity().min(self.index.capacity())
+    }
+
+    fn get(&self, key: &K) -> Option<&TravlNode<K, V>> {
+        let found = self.search(key).ok()?;
+        self.slots[self.index[found]].as_ref()
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut TravlNode<K, V>> {
+        let found = self.search(key).ok()?;
+        self.slots[self.index[found]].as_mut()
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.search(key).is_ok()
+    }
+
+    fn insert(&mut self, key: K, node: TravlNode<K, V>) -> Option<TravlNode<K, V>> {
+        let at = match self.search(&key) {
+            Ok(found) => return self.slots[self.index[found]].replace(node),
+            Err(at) => at,
+        };
+
+        let position = if let Some(position) = self.free.pop() {
+            self.slots[position] = Some(node);
+            position
+        } else {
+            self.slots.push(Some(node));
+            self.slots.len() - 1
+        };
+        self.index.insert(at, position);
+        None
+    }
+
+    fn remove(&mut self, key: &K) -> Option<TravlNode<K, V>> {
+        let found = self.search(key).ok()?;
+        let position = self.index.remove(found);
+        self.free.push(position);
+        self.slots[position].take()
+    }
+
+    fn clear(&mut self) {
+        self.slots.clear();
+        self.index.clear();
+        self.free.clear();
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.slots.reserve(additional.saturating_sub(self.free.len()));
+        self.index.reserve(additional);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        // Empty slots at the end are dropped, the others keep the positions of the nodes after them
+        while self.slots.last().is_some_and(Option::is_none) {
+            self.slots.pop();
+        }
+        self.free.retain(|position| *position < self.slots.len());
+
+        self.slots.shrink_to_fit();
+        self.index.shrink_to_fit();
+        self.free.shrink_to_fit();
+    }
+
+    fn iter<'s>(&'s self) -> impl Iterator<Item = (&'s K, &'s TravlNode<K, V>)>
+    where
+        K: 's,
+        V: 's
+    {
+        self.slots.iter().flatten().map(|node| (node.key(), node))
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = TravlNode<K, V>> {
+        self.index.clear();
+        self.free.clear();
+
+        self.slots.drain(..).flatten()
+    }
+
+    fn into_values(self) -> impl Iterator<Item = TravlNode<K, V>> {
+        self.slots.into_iter().flatten()
+    }
+}
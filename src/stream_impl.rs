@@ -0,0 +1,85 @@
+//! Support for async [`Stream`]s, to stream large result sets from async services
+//!
+//! Iterating over a map never blocks, but iterating over millions of entries without ever
+//! returning to the executor starves the other tasks of its thread. [`IterStream`] yields
+//! back to the executor every few items so that long traversals cooperate with other tasks.
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+
+/// Number of items an [`IterStream`] produces before yielding back to the executor, by default
+pub const DEFAULT_YIELD_INTERVAL: usize = 128;
+
+/// Stream producing the items of an iterator, yielding back to the executor at regular intervals
+///
+/// Created by [`TravlMap::stream`](crate::map::TravlMap::stream) and
+/// [`TravlMapPersistent::stream`](crate::persistent::TravlMapPersistent::stream) among others,
+/// or out of any iterator with [`IterStream::new`].
+#[derive(Clone, Debug)]
+pub struct IterStream<I> {
+    iter: I,
+    yield_interval: usize,
+    since_yield: usize,
+}
+
+impl<I> IterStream<I>
+where
+    I: Iterator
+{
+    /// Creates a stream producing the items of the given iterator, yielding every
+    /// [`DEFAULT_YIELD_INTERVAL`] items
+    #[must_use]
+    pub fn new(iter: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            iter: iter.into_iter(),
+            yield_interval: DEFAULT_YIELD_INTERVAL,
+            since_yield: 0,
+        }
+    }
+
+    /// Sets the number of items produced before yielding back to the executor
+    ///
+    /// # Panics
+    ///
+    /// Panics if `yield_interval` is 0.
+    #[must_use]
+    pub fn with_yield_interval(mut self, yield_interval: usize) -> Self {
+        assert!(yield_interval != 0, "yield interval must be non-zero");
+
+        self.yield_interval = yield_interval;
+        self
+    }
+
+    /// Returns the underlying iterator
+    #[must_use]
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+impl<I> Stream for IterStream<I>
+where
+    I: Iterator + Unpin
+{
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.since_yield == this.yield_interval {
+            // The task is woken right away, it is only moved to the back of the executor's queue
+            this.since_yield = 0;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        this.since_yield += 1;
+        Poll::Ready(this.iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
@@ -0,0 +1,219 @@
+//! Map deleting its entries lazily, by marking them with tombstones until it is compacted
+//!
+//! Removing an entry from a [`TravlMapPersistent`] copies the path leading to its node and rebalances the
+//! tree. A [`TombstoneMap`] only looks the key up and records it in a set of tombstones, which lookups and
+//! iterations skip. Tombstoned entries are purged from the tree all at once by
+//! [`compact`](TombstoneMap::compact), which suits removals coming in bursts whose individual latency
+//! matters more than their total cost.
+
+use core::fmt::{self, Debug};
+use core::hash::Hash;
+use core::iter::FusedIterator;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+#[cfg(not(feature = "std"))]
+use hashbrown::HashSet;
+
+use crate::persistent::{PersistentIter, TravlMapPersistent};
+
+/// Map deleting its entries lazily, see the [module-level documentation](self)
+pub struct TombstoneMap<K, V> {
+    map: TravlMapPersistent<K, V>,
+    /// Keys of the entries of the map which were removed, but not purged from its tree yet
+    tombstones: HashSet<K>,
+}
+
+impl<K, V> TombstoneMap<K, V> {
+    /// Creates an empty map
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_persistent(TravlMapPersistent::new())
+    }
+
+    /// Creates a map holding the entries of the given map, without any tombstone
+    #[must_use]
+    pub fn from_persistent(map: TravlMapPersistent<K, V>) -> Self {
+        Self {
+            map,
+            tombstones: HashSet::new(),
+        }
+    }
+
+    /// Returns the number of entries in the map, excluding the tombstoned ones
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len() - self.tombstones.len()
+    }
+
+    /// Returns `true` if the map contains no entry, excluding the tombstoned ones
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of tombstoned entries, which remain in the tree until the map is compacted
+    #[must_use]
+    pub fn tombstone_len(&self) -> usize {
+        self.tombstones.len()
+    }
+}
+
+impl<K, V> TombstoneMap<K, V>
+where
+    K: Hash + Eq
+{
+    /// Returns an iterator over the entries, ordered by key and skipping the tombstoned ones
+    #[must_use]
+    pub fn iter(&self) -> TombstoneIter<'_, K, V> {
+        TombstoneIter {
+            entries: self.map.iter(),
+            tombstones: &self.tombstones,
+            remaining: self.len(),
+        }
+    }
+}
+
+impl<K, V> TombstoneMap<K, V>
+where
+    K: Ord + Clone + Hash,
+    V: Clone
+{
+    /// Returns the value associated to the given key, if it exists and isn't tombstoned
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        if self.tombstones.contains(key) {
+            return None;
+        }
+
+        self.map.get(key)
+    }
+
+    /// Returns whether the map contains the given key, and it isn't tombstoned
+    #[must_use]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts an entry, returning the previous value of the key if it wasn't tombstoned
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = if self.tombstones.remove(&key) {
+            None
+        } else {
+            self.map.get(&key).cloned()
+        };
+
+        self.map = self.map.insert(key, value);
+        previous
+    }
+
+    /// Tombstones the entry of the given key, returning its value if it existed
+    ///
+    /// The key is only looked up: its node stays in the tree, without copying any node nor rebalancing the tree,
+    /// until the map is [compacted](Self::compact).
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = self.get(key).cloned()?;
+        self.tombstones.insert(key.clone());
+        Some(removed)
+    }
+
+    /// Purges the tombstoned entries from the tree, rebalancing it, and returns how many were purged
+    pub fn compact(&mut self) -> usize {
+        let purged = self.tombstones.len();
+
+        for key in self.tombstones.drain() {
+            self.map = self.map.remove(&key).0;
+        }
+
+        purged
+    }
+
+    /// Compacts the map and returns its entries as a persistent map
+    #[must_use]
+    pub fn into_persistent(mut self) -> TravlMapPersistent<K, V> {
+        self.compact();
+        self.map
+    }
+}
+
+impl<K, V> Clone for TombstoneMap<K, V>
+where
+    K: Clone
+{
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+            tombstones: self.tombstones.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for TombstoneMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Debug for TombstoneMap<K, V>
+where
+    K: Hash + Eq + Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for TombstoneMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        Self::from_persistent(entries.into_iter().collect())
+    }
+}
+
+impl<'m, K, V> IntoIterator for &'m TombstoneMap<K, V>
+where
+    K: Hash + Eq
+{
+    type IntoIter = TombstoneIter<'m, K, V>;
+    type Item = (&'m K, &'m V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a [`TombstoneMap`], ordered by key and skipping the tombstoned ones
+///
+/// Created by [`TombstoneMap::iter`].
+pub struct TombstoneIter<'m, K, V> {
+    entries: PersistentIter<'m, K, V>,
+    tombstones: &'m HashSet<K>,
+    remaining: usize,
+}
+
+impl<'m, K, V> Iterator for TombstoneIter<'m, K, V>
+where
+    K: Hash + Eq
+{
+    type Item = (&'m K, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tombstones = self.tombstones;
+        let entry = self.entries.find(|(key, _)| !tombstones.contains(*key))?;
+        self.remaining -= 1;
+
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for TombstoneIter<'_, K, V> where K: Hash + Eq {}
+
+impl<K, V> FusedIterator for TombstoneIter<'_, K, V> where K: Hash + Eq {}
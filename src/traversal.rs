@@ -1,3 +1,1096 @@
 //! Traversal utils
-//! 
+//!
 //! Contains tools for traversing the AVL trees using custom [visitors](https://rust-unofficial.github.io/patterns/patterns/behavioural/visitor.html)
+//!
+//! All iterators borrow the storage of a tree along with the node they start from, and follow the nodes' links
+//! by looking the linked keys up in the storage. They can therefore be started from any node of a tree and will
+//! only visit the subtree that descends from that node.
+//!
+//! None of the traversals are recursive: they either keep an explicit stack/queue
+//! or step through the nodes' parent links, so trees that are very deep (for example because of
+//! a large imbalance factor) cannot overflow the call stack.
+//!
+//! Depth-first traversals keep their stack inline for the first [`PATH_CAPACITY`] nodes and only
+//! allocate for deeper trees, so iterating over balanced trees doesn't allocate at all.
+//! Breadth-first traversals keep a queue as wide as the tree, which is always allocated.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use core::iter::FusedIterator;
+use core::ops::ControlFlow;
+use core::ptr;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::core::TravlNode;
+use crate::map::NodeMap;
+use crate::storage::NodeStorage;
+
+/// Number of nodes the stacks of depth-first traversals hold before allocating
+///
+/// An AVL tree of this height holds many more nodes than can fit in memory, so only trees built
+/// with a large imbalance factor or linked by hand can be deep enough to allocate.
+pub const PATH_CAPACITY: usize = 64;
+
+/// Function returning one of the children of a node, looking it up in the given storage
+type ChildFn<'n, K, V, S> = fn(&'n S, &'n TravlNode<K, V>) -> Option<&'n TravlNode<K, V>>;
+
+/// Order in which [`walk_with`] visits the nodes of a tree
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TraversalOrder {
+    /// Left, node, right
+    #[default]
+    InOrder,
+    /// Node, left, right
+    PreOrder,
+    /// Left, right, node
+    PostOrder,
+    /// Breadth-first, left to right
+    LevelOrder,
+    /// Right, node, left
+    ReverseInOrder,
+}
+
+/// Calls the given function on the nodes of the subtree of the given root, following the given order
+///
+/// The traversal stops as soon as the function returns [`ControlFlow::Break`], and the break value is returned.
+pub fn walk_with<'n, K, V, S, B, F>(
+    nodes: &'n S,
+    root: Option<&'n TravlNode<K, V>>,
+    order: TraversalOrder,
+    f: F,
+) -> ControlFlow<B>
+where
+    S: NodeStorage<K, V>,
+    F: FnMut(&'n TravlNode<K, V>) -> ControlFlow<B>,
+{
+    match order {
+        TraversalOrder::InOrder => InOrderIter::new(nodes, root).try_for_each(f),
+        TraversalOrder::PreOrder => PreOrderIter::new(nodes, root).try_for_each(f),
+        TraversalOrder::PostOrder => PostOrderIter::new(nodes, root).try_for_each(f),
+        TraversalOrder::LevelOrder => LevelOrderIter::new(nodes, root).try_for_each(f),
+        TraversalOrder::ReverseInOrder => ReverseInOrderIter::new(nodes, root).try_for_each(f),
+    }
+}
+
+/// Visitor called on the nodes of a tree
+///
+/// See [`visit`] for driving a visitor through a tree.
+pub trait Visitor<K, V, S = NodeMap<K, V>> {
+    /// Visits a node located at the given depth, the root being at depth 0, the nodes it links to being stored
+    /// in the given storage
+    ///
+    /// Returning [`ControlFlow::Break`] stops the traversal.
+    fn visit(&mut self, nodes: &S, node: &TravlNode<K, V>, depth: usize) -> ControlFlow<()>;
+}
+
+/// Drives the given visitor through the subtree of the given root, in pre-order
+///
+/// Returns [`ControlFlow::Break`] if the visitor stopped the traversal early.
+pub fn visit<K, V, S, T>(nodes: &S, root: Option<&TravlNode<K, V>>, visitor: &mut T) -> ControlFlow<()>
+where
+    S: NodeStorage<K, V>,
+    T: Visitor<K, V, S> + ?Sized,
+{
+    for entry in PreOrderDepthIter::new(nodes, root) {
+        visitor.visit(nodes, entry.node(), entry.depth())?;
+    }
+
+    ControlFlow::Continue(())
+}
+
+/// Traversal that can be rooted at any node of a tree
+///
+/// Implemented by all the traversal iterators of this module, allowing to start them
+/// generically, for example from [`TravlMap::traverse_subtree`](crate::map::TravlMap::traverse_subtree).
+pub trait Traversal<'n, K, V, S>: Iterator + Sized {
+    /// Creates a traversal visiting the subtree of the given root, whose nodes are stored in the given storage
+    fn from_root(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self;
+}
+
+/// Implements [`Traversal`] for iterators exposing a `new(nodes, root)` constructor
+macro_rules! impl_traversal {
+    ($($iter:ident),* $(,)?) => {
+        $(
+            impl<'n, K, V, S> Traversal<'n, K, V, S> for $iter<'n, K, V, S>
+            where
+                S: NodeStorage<K, V>,
+            {
+                fn from_root(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+                    Self::new(nodes, root)
+                }
+            }
+        )*
+    };
+}
+
+/// Implements [`Clone`] and [`Debug`] for iterators borrowing a storage, without requiring the storage to
+/// implement them
+macro_rules! impl_clone_debug {
+    ($($iter:ident { $($field:ident),* $(,)? }),* $(,)?) => {
+        $(
+            impl<K, V, S> Clone for $iter<'_, K, V, S> {
+                fn clone(&self) -> Self {
+                    Self {
+                        nodes: self.nodes,
+                        $($field: self.$field.clone(),)*
+                    }
+                }
+            }
+
+            impl<K, V, S> Debug for $iter<'_, K, V, S>
+            where
+                K: Debug,
+                V: Debug,
+            {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.debug_struct(stringify!($iter))
+                        $(.field(stringify!($field), &self.$field))*
+                        .finish_non_exhaustive()
+                }
+            }
+        )*
+    };
+}
+
+impl_traversal!(
+    InOrderIter,
+    ReverseInOrderIter,
+    ParentLinkedInOrderIter,
+    PreOrderIter,
+    PostOrderIter,
+    LevelOrderIter,
+    ZigZagIter,
+    LeafIter,
+    InternalIter,
+    BoundaryIter,
+    PreOrderDepthIter,
+    LevelOrderDepthIter,
+);
+
+/// Stack holding its first [`PATH_CAPACITY`] items inline, only spilling to the heap beyond that
+#[derive(Clone)]
+struct PathStack<T> {
+    inline: [Option<T>; PATH_CAPACITY],
+    len: usize,
+    spilled: Vec<T>,
+}
+
+impl<T> PathStack<T>
+where
+    T: Copy
+{
+    /// Creates an empty stack, without allocating
+    fn new() -> Self {
+        Self {
+            inline: [None; PATH_CAPACITY],
+            len: 0,
+            spilled: Vec::new(),
+        }
+    }
+
+    /// Pushes an item on top of the stack
+    fn push(&mut self, item: T) {
+        if self.len < PATH_CAPACITY {
+            self.inline[self.len] = Some(item);
+            self.len += 1;
+        } else {
+            self.spilled.push(item);
+        }
+    }
+
+    /// Removes the item on top of the stack and returns it
+    fn pop(&mut self) -> Option<T> {
+        if let Some(item) = self.spilled.pop() {
+            return Some(item);
+        }
+
+        self.len = self.len.checked_sub(1)?;
+        self.inline[self.len].take()
+    }
+}
+
+impl<T> Debug for PathStack<T>
+where
+    T: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.inline[..self.len].iter().flatten()).entries(&self.spilled).finish()
+    }
+}
+
+impl<T> Extend<T> for PathStack<T>
+where
+    T: Copy
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        for item in items {
+            self.push(item);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for PathStack<T>
+where
+    T: Copy
+{
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Self {
+        let mut stack = Self::new();
+        stack.extend(items);
+        stack
+    }
+}
+
+impl_clone_debug!(
+    InOrderIter { stack },
+    ReverseInOrderIter { stack },
+    ParentLinkedInOrderIter { root, next },
+    PreOrderIter { stack },
+    PostOrderIter { stack },
+    LevelOrderIter { queue },
+    ZigZagIter { current_level, next_level, left_to_right },
+    LeafIter { inner },
+    InternalIter { inner },
+    BoundaryIter { left_spine, leaves, right_spine },
+    PreOrderDepthIter { stack },
+    LevelOrderDepthIter { queue },
+);
+
+/// In-order (left, node, right) iterator over the nodes of a tree
+pub struct InOrderIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    stack: PathStack<&'n TravlNode<K, V>>,
+}
+
+impl<'n, K, V, S> InOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        let mut iter = Self { nodes, stack: PathStack::new() };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    /// Pushes the given node and all of its left descendants onto the stack
+    fn push_left_spine(&mut self, mut node: Option<&'n TravlNode<K, V>>) {
+        while let Some(current) = node {
+            self.stack.push(current);
+            node = self.nodes.left_of(current);
+        }
+    }
+}
+
+impl<'n, K, V, S> Iterator for InOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(self.nodes.right_of(node));
+        Some(node)
+    }
+
+    fn fold<B, F>(mut self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B
+    {
+        // The nodes left on the stack are visited along with their right subtrees, whose left spines are pushed
+        // without going through next
+        let mut accumulator = init;
+        while let Some(node) = self.stack.pop() {
+            accumulator = f(accumulator, node);
+            self.push_left_spine(self.nodes.right_of(node));
+        }
+
+        accumulator
+    }
+}
+
+impl<K, V, S> FusedIterator for InOrderIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Reverse in-order (right, node, left) iterator over the nodes of a tree
+pub struct ReverseInOrderIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    stack: PathStack<&'n TravlNode<K, V>>,
+}
+
+impl<'n, K, V, S> ReverseInOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        let mut iter = Self { nodes, stack: PathStack::new() };
+        iter.push_right_spine(root);
+        iter
+    }
+
+    /// Pushes the given node and all of its right descendants onto the stack
+    fn push_right_spine(&mut self, mut node: Option<&'n TravlNode<K, V>>) {
+        while let Some(current) = node {
+            self.stack.push(current);
+            node = self.nodes.right_of(current);
+        }
+    }
+}
+
+impl<'n, K, V, S> Iterator for ReverseInOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_right_spine(self.nodes.left_of(node));
+        Some(node)
+    }
+
+    fn fold<B, F>(mut self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B
+    {
+        let mut accumulator = init;
+        while let Some(node) = self.stack.pop() {
+            accumulator = f(accumulator, node);
+            self.push_right_spine(self.nodes.left_of(node));
+        }
+
+        accumulator
+    }
+}
+
+impl<K, V, S> FusedIterator for ReverseInOrderIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Constant-space in-order (left, node, right) iterator over the nodes of a tree
+///
+/// Instead of keeping the path to the current node on a stack, this iterator steps from a node
+/// to its in-order successor using the nodes' parent links, which makes it suitable
+/// for very large trees. It therefore requires parent links to be set on every node of the subtree.
+pub struct ParentLinkedInOrderIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    root: Option<&'n TravlNode<K, V>>,
+    next: Option<&'n TravlNode<K, V>>,
+}
+
+impl<'n, K, V, S> ParentLinkedInOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self {
+            nodes,
+            root,
+            next: root.map(|root| leftmost(nodes, root)),
+        }
+    }
+
+    /// Creates an iterator starting at the given node and visiting all of its in-order successors
+    ///
+    /// Unlike [`ParentLinkedInOrderIter::new`], the iteration isn't restricted to a subtree
+    /// and continues until the last node of the whole tree.
+    #[must_use]
+    pub fn starting_at(nodes: &'n S, node: Option<&'n TravlNode<K, V>>) -> Self {
+        Self { nodes, root: None, next: node }
+    }
+
+    /// Creates an iterator starting at the given node and visiting its in-order successors
+    /// that belong to the subtree of the given root
+    ///
+    /// The starting node must belong to the subtree of the given root.
+    #[must_use]
+    pub fn starting_at_within(
+        nodes: &'n S,
+        root: Option<&'n TravlNode<K, V>>,
+        node: Option<&'n TravlNode<K, V>>,
+    ) -> Self {
+        Self { nodes, root, next: node }
+    }
+
+    /// Returns the in-order successor of the given node within the iterated subtree
+    fn successor(&self, node: &'n TravlNode<K, V>) -> Option<&'n TravlNode<K, V>> {
+        if let Some(right) = self.nodes.right_of(node) {
+            return Some(leftmost(self.nodes, right));
+        }
+
+        // Climb until we come up from a left child, without leaving the iterated subtree
+        let mut current = node;
+        loop {
+            if self.root.is_some_and(|root| ptr::eq(root, current)) {
+                return None;
+            }
+
+            let parent = self.nodes.parent_of(current)?;
+            if self.nodes.left_of(parent).is_some_and(|left| ptr::eq(left, current)) {
+                return Some(parent);
+            }
+
+            current = parent;
+        }
+    }
+}
+
+impl<'n, K, V, S> Iterator for ParentLinkedInOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next?;
+        self.next = self.successor(node);
+        Some(node)
+    }
+}
+
+impl<K, V, S> FusedIterator for ParentLinkedInOrderIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Returns the leftmost descendant of the given node, or the node itself if it has no left child
+pub(crate) fn leftmost<'n, K, V, S>(nodes: &'n S, mut node: &'n TravlNode<K, V>) -> &'n TravlNode<K, V>
+where
+    S: NodeStorage<K, V>,
+{
+    while let Some(left) = nodes.left_of(node) {
+        node = left;
+    }
+
+    node
+}
+
+/// Returns the rightmost descendant of the given node, or the node itself if it has no right child
+pub(crate) fn rightmost<'n, K, V, S>(nodes: &'n S, mut node: &'n TravlNode<K, V>) -> &'n TravlNode<K, V>
+where
+    S: NodeStorage<K, V>,
+{
+    while let Some(right) = nodes.right_of(node) {
+        node = right;
+    }
+
+    node
+}
+
+/// Returns the in-order successor of the given node, using parent links
+pub(crate) fn successor<'n, K, V, S>(nodes: &'n S, node: &'n TravlNode<K, V>) -> Option<&'n TravlNode<K, V>>
+where
+    S: NodeStorage<K, V>,
+{
+    if let Some(right) = nodes.right_of(node) {
+        return Some(leftmost(nodes, right));
+    }
+
+    let mut current = node;
+    loop {
+        let parent = nodes.parent_of(current)?;
+        if nodes.left_of(parent).is_some_and(|left| ptr::eq(left, current)) {
+            return Some(parent);
+        }
+
+        current = parent;
+    }
+}
+
+/// Returns the in-order predecessor of the given node, using parent links
+pub(crate) fn predecessor<'n, K, V, S>(nodes: &'n S, node: &'n TravlNode<K, V>) -> Option<&'n TravlNode<K, V>>
+where
+    S: NodeStorage<K, V>,
+{
+    if let Some(left) = nodes.left_of(node) {
+        return Some(rightmost(nodes, left));
+    }
+
+    let mut current = node;
+    loop {
+        let parent = nodes.parent_of(current)?;
+        if nodes.right_of(parent).is_some_and(|right| ptr::eq(right, current)) {
+            return Some(parent);
+        }
+
+        current = parent;
+    }
+}
+
+/// Pre-order (node, left, right) iterator over the nodes of a tree
+pub struct PreOrderIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    stack: PathStack<&'n TravlNode<K, V>>,
+}
+
+impl<'n, K, V, S> PreOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self { nodes, stack: root.into_iter().collect() }
+    }
+}
+
+impl<'n, K, V, S> Iterator for PreOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+
+        // Right is pushed first so that left is popped first
+        self.stack.extend(self.nodes.right_of(node));
+        self.stack.extend(self.nodes.left_of(node));
+
+        Some(node)
+    }
+}
+
+impl<K, V, S> FusedIterator for PreOrderIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Post-order (left, right, node) iterator over the nodes of a tree
+pub struct PostOrderIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    /// Nodes along with whether their children have already been pushed onto the stack
+    stack: PathStack<(&'n TravlNode<K, V>, bool)>,
+}
+
+impl<'n, K, V, S> PostOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self { nodes, stack: root.map(|node| (node, false)).into_iter().collect() }
+    }
+}
+
+impl<'n, K, V, S> Iterator for PostOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, children_pushed) = self.stack.pop()?;
+
+            if children_pushed {
+                return Some(node);
+            }
+
+            self.stack.push((node, true));
+            self.stack.extend(self.nodes.right_of(node).map(|right| (right, false)));
+            self.stack.extend(self.nodes.left_of(node).map(|left| (left, false)));
+        }
+    }
+}
+
+impl<K, V, S> FusedIterator for PostOrderIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Level-order (breadth-first, left to right) iterator over the nodes of a tree
+pub struct LevelOrderIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    queue: VecDeque<&'n TravlNode<K, V>>,
+}
+
+impl<'n, K, V, S> LevelOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self { nodes, queue: root.into_iter().collect() }
+    }
+}
+
+impl<'n, K, V, S> Iterator for LevelOrderIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(self.nodes.left_of(node));
+        self.queue.extend(self.nodes.right_of(node));
+        Some(node)
+    }
+}
+
+impl<K, V, S> FusedIterator for LevelOrderIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Zig-zag (spiral) level-order iterator over the nodes of a tree
+///
+/// The root level is visited from left to right, then each following level is visited
+/// in the opposite direction of the previous one.
+pub struct ZigZagIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    current_level: Vec<&'n TravlNode<K, V>>,
+    next_level: Vec<&'n TravlNode<K, V>>,
+    left_to_right: bool,
+}
+
+impl<'n, K, V, S> ZigZagIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self {
+            nodes,
+            current_level: root.into_iter().collect(),
+            next_level: Vec::new(),
+            left_to_right: true,
+        }
+    }
+}
+
+impl<'n, K, V, S> Iterator for ZigZagIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_level.is_empty() {
+            core::mem::swap(&mut self.current_level, &mut self.next_level);
+            self.left_to_right = !self.left_to_right;
+        }
+
+        let node = self.current_level.pop()?;
+
+        // The next level is a stack, so children are pushed in the opposite order
+        // of the one they will be visited in
+        if self.left_to_right {
+            self.next_level.extend(self.nodes.left_of(node));
+            self.next_level.extend(self.nodes.right_of(node));
+        } else {
+            self.next_level.extend(self.nodes.right_of(node));
+            self.next_level.extend(self.nodes.left_of(node));
+        }
+
+        Some(node)
+    }
+}
+
+impl<K, V, S> FusedIterator for ZigZagIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// In-order iterator only yielding the leaves of a tree, nodes without any children
+pub struct LeafIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    inner: InOrderIter<'n, K, V, S>,
+}
+
+impl<'n, K, V, S> LeafIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the leaves of the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self { nodes, inner: InOrderIter::new(nodes, root) }
+    }
+}
+
+impl<'n, K, V, S> Iterator for LeafIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.find(|node| !node.is_internal())
+    }
+}
+
+impl<K, V, S> FusedIterator for LeafIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// In-order iterator only yielding the internal nodes of a tree, nodes with at least one child
+pub struct InternalIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    inner: InOrderIter<'n, K, V, S>,
+}
+
+impl<'n, K, V, S> InternalIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the internal nodes of the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self { nodes, inner: InOrderIter::new(nodes, root) }
+    }
+}
+
+impl<'n, K, V, S> Iterator for InternalIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.find(|node| node.is_internal())
+    }
+}
+
+impl<K, V, S> FusedIterator for InternalIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Iterator over the boundary of a tree: its left spine, its leaves and its right spine
+///
+/// The left spine is visited from the root downwards, then the leaves from left to right,
+/// and finally the right spine from the bottom up to the root, going around the tree counterclockwise.
+/// Each node of the boundary is visited once, even if it belongs to multiple parts.
+pub struct BoundaryIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    left_spine: alloc::vec::IntoIter<&'n TravlNode<K, V>>,
+    leaves: LeafIter<'n, K, V, S>,
+    right_spine: core::iter::Rev<alloc::vec::IntoIter<&'n TravlNode<K, V>>>,
+}
+
+impl<'n, K, V, S> BoundaryIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the boundary of the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        let mut left_spine = Vec::new();
+        let mut right_spine = Vec::new();
+
+        if let Some(root) = root.filter(|root| root.is_internal()) {
+            left_spine.push(root);
+            left_spine.extend(Self::spine(nodes, nodes.left_of(root), S::left_of, S::right_of));
+            right_spine.extend(Self::spine(nodes, nodes.right_of(root), S::right_of, S::left_of));
+        }
+
+        Self {
+            nodes,
+            left_spine: left_spine.into_iter(),
+            leaves: LeafIter::new(nodes, root),
+            right_spine: right_spine.into_iter().rev(),
+        }
+    }
+
+    /// Returns the internal nodes on the path going from the given node towards the outside of the tree
+    ///
+    /// The `outer` child is followed whenever possible, otherwise the `inner` child is followed.
+    fn spine(
+        nodes: &'n S,
+        mut node: Option<&'n TravlNode<K, V>>,
+        outer: ChildFn<'n, K, V, S>,
+        inner: ChildFn<'n, K, V, S>,
+    ) -> Vec<&'n TravlNode<K, V>> {
+        let mut spine = Vec::new();
+
+        while let Some(current) = node.filter(|current| current.is_internal()) {
+            spine.push(current);
+            node = outer(nodes, current).or_else(|| inner(nodes, current));
+        }
+
+        spine
+    }
+}
+
+impl<'n, K, V, S> Iterator for BoundaryIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.left_spine
+            .next()
+            .or_else(|| self.leaves.next())
+            .or_else(|| self.right_spine.next())
+    }
+}
+
+impl<K, V, S> FusedIterator for BoundaryIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Node visited by a depth-annotating traversal, along with its position within the tree
+#[derive(Debug)]
+pub struct DepthEntry<'n, K, V> {
+    node: &'n TravlNode<K, V>,
+    depth: usize,
+    parent_key: Option<&'n K>,
+}
+
+impl<K, V> Clone for DepthEntry<'_, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for DepthEntry<'_, K, V> {}
+
+impl<'n, K, V> DepthEntry<'n, K, V> {
+    /// Returns the visited node
+    #[must_use]
+    pub fn node(&self) -> &'n TravlNode<K, V> {
+        self.node
+    }
+
+    /// Returns the depth of the node, the root being at depth 0
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the key of the node's parent within the traversal, if the node isn't the traversal's root
+    #[must_use]
+    pub fn parent_key(&self) -> Option<&'n K> {
+        self.parent_key
+    }
+
+    /// Creates the entries of the children of the node, looking them up in the given storage
+    fn children<S>(&self, nodes: &'n S) -> (Option<Self>, Option<Self>)
+    where
+        S: NodeStorage<K, V>,
+    {
+        let child = |node| Self {
+            node,
+            depth: self.depth + 1,
+            parent_key: Some(self.node.key()),
+        };
+
+        (nodes.left_of(self.node).map(child), nodes.right_of(self.node).map(child))
+    }
+}
+
+/// Pre-order iterator annotating each node with its depth and parent key
+pub struct PreOrderDepthIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    stack: PathStack<DepthEntry<'n, K, V>>,
+}
+
+impl<'n, K, V, S> PreOrderDepthIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self {
+            nodes,
+            stack: root.map(|node| DepthEntry { node, depth: 0, parent_key: None }).into_iter().collect(),
+        }
+    }
+}
+
+impl<'n, K, V, S> Iterator for PreOrderDepthIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = DepthEntry<'n, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.stack.pop()?;
+        let (left, right) = entry.children(self.nodes);
+
+        // Right is pushed first so that left is popped first
+        self.stack.extend(right);
+        self.stack.extend(left);
+
+        Some(entry)
+    }
+}
+
+impl<K, V, S> FusedIterator for PreOrderDepthIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Level-order iterator annotating each node with its depth and parent key
+pub struct LevelOrderDepthIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    queue: VecDeque<DepthEntry<'n, K, V>>,
+}
+
+impl<'n, K, V, S> LevelOrderDepthIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self {
+            nodes,
+            queue: root.map(|node| DepthEntry { node, depth: 0, parent_key: None }).into_iter().collect(),
+        }
+    }
+}
+
+impl<'n, K, V, S> Iterator for LevelOrderDepthIter<'n, K, V, S>
+where
+    S: NodeStorage<K, V>,
+{
+    type Item = DepthEntry<'n, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.queue.pop_front()?;
+        let (left, right) = entry.children(self.nodes);
+
+        self.queue.extend(left);
+        self.queue.extend(right);
+
+        Some(entry)
+    }
+}
+
+impl<K, V, S> FusedIterator for LevelOrderDepthIter<'_, K, V, S> where S: NodeStorage<K, V> {}
+
+/// Number of subtrees to split a traversal into per thread of the pool when traversing in parallel
+#[cfg(feature = "rayon")]
+const PAR_SUBTREES_PER_THREAD: usize = 4;
+
+/// List of borrowed nodes
+#[cfg(feature = "rayon")]
+type NodeRefs<'n, K, V> = Vec<&'n TravlNode<K, V>>;
+
+/// Splits the subtree of the given root into the nodes close to the root and disjoint subtrees,
+/// so that the subtrees can be processed in parallel
+///
+/// Returns the nodes that sit above the subtrees and the roots of the subtrees.
+#[cfg(feature = "rayon")]
+fn split_subtrees<'n, K, V, S>(
+    nodes: &'n S,
+    root: Option<&'n TravlNode<K, V>>,
+) -> (NodeRefs<'n, K, V>, NodeRefs<'n, K, V>)
+where
+    S: NodeStorage<K, V>,
+{
+    let target = rayon::current_num_threads().saturating_mul(PAR_SUBTREES_PER_THREAD);
+    let mut upper_nodes = Vec::new();
+    let mut subtree_roots: VecDeque<_> = root.into_iter().collect();
+
+    while subtree_roots.len() < target {
+        let Some(node) = subtree_roots.pop_front() else {
+            break;
+        };
+
+        upper_nodes.push(node);
+        subtree_roots.extend(nodes.left_of(node));
+        subtree_roots.extend(nodes.right_of(node));
+    }
+
+    (upper_nodes, subtree_roots.into())
+}
+
+/// Calls the given function on every node of the subtree of the given root, in parallel
+///
+/// The tree is split at subtree boundaries and disjoint subtrees are walked on the
+/// [`rayon`] thread pool, no particular visiting order is guaranteed.
+#[cfg(feature = "rayon")]
+pub fn par_walk<K, V, S, F>(nodes: &S, root: Option<&TravlNode<K, V>>, f: F)
+where
+    K: Sync,
+    V: Sync,
+    S: NodeStorage<K, V> + Sync,
+    F: Fn(&TravlNode<K, V>) + Send + Sync,
+{
+    let (upper_nodes, subtree_roots) = split_subtrees(nodes, root);
+
+    rayon::join(
+        || upper_nodes.into_par_iter().for_each(&f),
+        || subtree_roots.into_par_iter().for_each(|subtree| PreOrderIter::new(nodes, Some(subtree)).for_each(&f)),
+    );
+}
+
+/// Visits every node of the subtree of the given root in parallel and combines the results
+///
+/// The tree is split at subtree boundaries and disjoint subtrees are visited on the
+/// [`rayon`] thread pool, then the results are combined using `reduce`.
+/// Since no particular visiting or combining order is guaranteed, `reduce` should be
+/// both associative and commutative.
+///
+/// Returns [`None`] if the tree is empty.
+#[cfg(feature = "rayon")]
+pub fn par_visit<K, V, S, R, F, G>(nodes: &S, root: Option<&TravlNode<K, V>>, visit: F, reduce: G) -> Option<R>
+where
+    K: Sync,
+    V: Sync,
+    S: NodeStorage<K, V> + Sync,
+    R: Send,
+    F: Fn(&TravlNode<K, V>) -> R + Send + Sync,
+    G: Fn(R, R) -> R + Send + Sync,
+{
+    let (upper_nodes, subtree_roots) = split_subtrees(nodes, root);
+
+    upper_nodes
+        .into_par_iter()
+        .map(&visit)
+        .chain(
+            subtree_roots
+                .into_par_iter()
+                .filter_map(|subtree| PreOrderIter::new(nodes, Some(subtree)).map(&visit).reduce(&reduce)),
+        )
+        .reduce_with(&reduce)
+}
+
+/// Parallel iterator over the nodes of a tree, in no particular order
+///
+/// The tree is split at subtree boundaries and disjoint subtrees are iterated over on the
+/// [`rayon`] thread pool.
+#[cfg(feature = "rayon")]
+pub struct ParNodeIter<'n, K, V, S = NodeMap<K, V>> {
+    nodes: &'n S,
+    root: Option<&'n TravlNode<K, V>>,
+}
+
+#[cfg(feature = "rayon")]
+impl<K, V, S> Clone for ParNodeIter<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        Self { nodes: self.nodes, root: self.root }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<K, V, S> Debug for ParNodeIter<'_, K, V, S>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParNodeIter").field("root", &self.root).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'n, K, V, S> ParNodeIter<'n, K, V, S> {
+    /// Creates an iterator visiting the subtree of the given root
+    #[must_use]
+    pub fn new(nodes: &'n S, root: Option<&'n TravlNode<K, V>>) -> Self {
+        Self { nodes, root }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'n, K, V, S> ParallelIterator for ParNodeIter<'n, K, V, S>
+where
+    K: Sync,
+    V: Sync,
+    S: NodeStorage<K, V> + Sync,
+{
+    type Item = &'n TravlNode<K, V>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        let nodes = self.nodes;
+        let (upper_nodes, subtree_roots) = split_subtrees(nodes, self.root);
+
+        upper_nodes
+            .into_par_iter()
+            .chain(subtree_roots.into_par_iter().flat_map_iter(|subtree| PreOrderIter::new(nodes, Some(subtree))))
+            .drive_unindexed(consumer)
+    }
+}
@@ -0,0 +1,115 @@
+//! Invariants of trees, and the violations found when checking them
+//!
+//! [`TravlMap::validate`](crate::map::TravlMap::validate) and
+//! [`TravlMapPersistent::validate`](crate::persistent::TravlMapPersistent::validate) walk the whole tree and
+//! return every [`Violation`] they find, rather than stopping at the first one, which helps finding out
+//! which operation broke the tree, for example because of an inconsistent custom ordering function.
+
+use core::fmt::{self, Debug, Display};
+
+/// Broken invariant of a tree, naming the key of the node breaking it
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Violation<K> {
+    /// The map holds nodes, but none of them is its root
+    MissingRoot,
+    /// The root has a parent
+    RootHasParent(K),
+    /// The node is stored in the map, but can't be reached from the root
+    Unreachable(K),
+    /// A link leads to this key, under which the map stores no node
+    ForeignNode(K),
+    /// The child of a node doesn't link back to it as its parent
+    ParentMismatch {
+        /// Key of the parent
+        parent: K,
+        /// Key of the child
+        child: K,
+    },
+    /// The node is stored under another key than its own
+    KeyMismatch(K),
+    /// The height recorded in the node doesn't match the height of its subtree
+    WrongHeight {
+        /// Key of the node
+        key: K,
+        /// Height recorded in the node
+        height: u64,
+        /// Height of the subtree of the node
+        expected: u64,
+    },
+    /// The number of entries recorded in the node or map doesn't match the number of nodes
+    WrongSize {
+        /// Key of the node, [`None`] for the size recorded in the map
+        key: Option<K>,
+        /// Size recorded in the node or map
+        size: usize,
+        /// Number of nodes of the subtree or tree
+        expected: usize,
+    },
+    /// The heights of the subtrees of the node differ by more than the imbalance factor allows
+    Imbalanced {
+        /// Key of the node
+        key: K,
+        /// Difference between the heights of the subtrees of the node
+        difference: u64,
+    },
+    /// The numbers of nodes of the subtrees of the node are too far apart for a
+    /// [weight-balanced](crate::map::Balancing::Weight) tree, see [`WEIGHT_RATIO`](crate::map::WEIGHT_RATIO)
+    WeightImbalanced {
+        /// Key of the node
+        key: K,
+        /// Number of nodes of the left subtree
+        left_size: usize,
+        /// Number of nodes of the right subtree
+        right_size: usize,
+    },
+    /// The rank of the node doesn't exceed the ranks of its children by one or two, or isn't 0 while the node is a
+    /// leaf, in a [rank-balanced](crate::map::Balancing::Rank) tree
+    WrongRank {
+        /// Key of the node
+        key: K,
+        /// Rank of the node
+        rank: u64,
+    },
+    /// The node is ordered before the previous node of the in-order traversal
+    OutOfOrder {
+        /// Key of the previous node
+        previous: K,
+        /// Key of the node
+        key: K,
+    },
+}
+
+impl<K> Display for Violation<K>
+where
+    K: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRoot => f.write_str("the map holds nodes but has no root"),
+            Self::RootHasParent(key) => write!(f, "root {key:?} has a parent"),
+            Self::Unreachable(key) => write!(f, "node {key:?} can't be reached from the root"),
+            Self::ForeignNode(key) => write!(f, "a link leads to a node {key:?} which isn't stored in the map"),
+            Self::ParentMismatch { parent, child } => {
+                write!(f, "child {child:?} of node {parent:?} doesn't link back to it")
+            },
+            Self::KeyMismatch(key) => write!(f, "node {key:?} is stored under another key"),
+            Self::WrongHeight { key, height, expected } => {
+                write!(f, "node {key:?} has height {height} instead of {expected}")
+            },
+            Self::WrongSize { key: Some(key), size, expected } => {
+                write!(f, "node {key:?} has size {size} instead of {expected}")
+            },
+            Self::WrongSize { key: None, size, expected } => {
+                write!(f, "the map has length {size} instead of {expected}")
+            },
+            Self::Imbalanced { key, difference } => {
+                write!(f, "the subtrees of node {key:?} differ by {difference} levels")
+            },
+            Self::WeightImbalanced { key, left_size, right_size } => {
+                write!(f, "the subtrees of node {key:?} hold {left_size} and {right_size} nodes")
+            },
+            Self::WrongRank { key, rank } => write!(f, "node {key:?} can't have rank {rank}"),
+            Self::OutOfOrder { previous, key } => write!(f, "node {key:?} is ordered before node {previous:?}"),
+        }
+    }
+}
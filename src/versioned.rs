@@ -0,0 +1,174 @@
+//! Map numbering its versions, whose recent versions can still be read after later changes
+//!
+//! Every insertion and removal of a [`VersionedMap`] creates a new version of the map, numbered one after
+//! the previous one. Since versions are [`TravlMapPersistent`]s sharing their unchanged nodes, retaining
+//! earlier versions only costs the nodes their changes copied, and reading one doesn't block later writes:
+//! a long-running scan can iterate over [`snapshot_at`](VersionedMap::snapshot_at) while the map keeps
+//! changing, and always sees the entries as of the version it started from.
+
+use alloc::collections::VecDeque;
+use core::fmt::{self, Debug};
+
+use crate::persistent::{PersistentIter, TravlMapPersistent};
+
+/// Map retaining its recent versions, see the [module-level documentation](self)
+pub struct VersionedMap<K, V> {
+    map: TravlMapPersistent<K, V>,
+    version: u64,
+    /// Earlier versions still retained, the oldest first and the one just before the current version last
+    history: VecDeque<TravlMapPersistent<K, V>>,
+    retention: usize,
+}
+
+impl<K, V> VersionedMap<K, V> {
+    /// Creates an empty map at version 0, retaining at most `retention` versions before the current one
+    #[must_use]
+    pub fn new(retention: usize) -> Self {
+        Self {
+            map: TravlMapPersistent::new(),
+            version: 0,
+            history: VecDeque::new(),
+            retention,
+        }
+    }
+
+    /// Returns the maximum number of versions retained before the current one
+    #[must_use]
+    pub fn retention(&self) -> usize {
+        self.retention
+    }
+
+    /// Changes the maximum number of versions retained before the current one, forgetting the oldest versions
+    /// beyond it
+    pub fn set_retention(&mut self, retention: usize) {
+        self.retention = retention;
+        self.forget_beyond_retention();
+    }
+
+    /// Returns the current version
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the oldest version that can still be read
+    #[must_use]
+    pub fn oldest_version(&self) -> u64 {
+        self.version - self.history.len() as u64
+    }
+
+    /// Returns the number of entries in the current version
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the current version contains no entry
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the entries of the current version, ordered by key
+    #[must_use]
+    pub fn iter(&self) -> PersistentIter<'_, K, V> {
+        self.map.iter()
+    }
+
+    /// Returns the current version of the map
+    #[must_use]
+    pub fn snapshot(&self) -> TravlMapPersistent<K, V> {
+        self.map.clone()
+    }
+
+    /// Returns the map as of the given version, [`None`] if it is no longer retained or doesn't exist yet
+    #[must_use]
+    pub fn snapshot_at(&self, version: u64) -> Option<TravlMapPersistent<K, V>> {
+        if version == self.version {
+            return Some(self.snapshot());
+        }
+
+        let index = usize::try_from(version.checked_sub(self.oldest_version())?).ok()?;
+        self.history.get(index).cloned()
+    }
+
+    /// Makes the given map the next version
+    fn commit(&mut self, map: TravlMapPersistent<K, V>) {
+        self.history.push_back(core::mem::replace(&mut self.map, map));
+        self.version += 1;
+        self.forget_beyond_retention();
+    }
+
+    /// Forgets the oldest versions beyond the retention
+    fn forget_beyond_retention(&mut self) {
+        while self.history.len() > self.retention {
+            self.history.pop_front();
+        }
+    }
+}
+
+impl<K, V> VersionedMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone
+{
+    /// Returns the value associated to the given key in the current version, if it exists
+    #[must_use]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Inserts an entry in a new version, returning the previous value of the key
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = self.map.get(&key).cloned();
+        self.commit(self.map.insert(key, value));
+        previous
+    }
+
+    /// Removes the entry of the given key in a new version, returning its value if it existed
+    ///
+    /// Removing a missing key doesn't change the map and doesn't create a version.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (map, removed) = self.map.remove(key);
+
+        if removed.is_some() {
+            self.commit(map);
+        }
+        removed
+    }
+}
+
+impl<K, V> Clone for VersionedMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+            version: self.version,
+            history: self.history.clone(),
+            retention: self.retention,
+        }
+    }
+}
+
+impl<'m, K, V> IntoIterator for &'m VersionedMap<K, V> {
+    type IntoIter = PersistentIter<'m, K, V>;
+    type Item = (&'m K, &'m V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> Debug for VersionedMap<K, V>
+where
+    K: Debug,
+    V: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedMap")
+            .field("map", &self.map)
+            .field("version", &self.version)
+            .field("oldest_version", &self.oldest_version())
+            .field("retention", &self.retention)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,75 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use travl::core::TravlNode;
+use travl::storage::OrderedNodeMap;
+use travl::traversal::{
+    InOrderIter, InternalIter, LeafIter, PostOrderIter, PreOrderDepthIter, PreOrderIter, ReverseInOrderIter,
+};
+
+/// Height of the complete tree iterated over
+const TREE_HEIGHT: u32 = 16;
+
+/// Allocator counting the allocations made by the test binary
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Builds a complete tree whose keys are the integers of the given range into the given nodes,
+/// returning the key of its root
+fn complete_tree(nodes: &mut OrderedNodeMap<u32, ()>, start: u32, end: u32) -> Option<u32> {
+    if start >= end {
+        return None;
+    }
+
+    let middle = start + (end - start) / 2;
+    let mut node = TravlNode::new(middle, ());
+    let children = (complete_tree(nodes, start, middle), complete_tree(nodes, middle + 1, end));
+    for child in [children.0, children.1].into_iter().flatten() {
+        let _ = nodes.get_mut(&child).expect("child should be stored").link_parent(middle);
+    }
+    let _ = node.link_children(children);
+    nodes.insert(middle, node);
+
+    Some(middle)
+}
+
+/// Returns the number of allocations made while running the given function
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn depth_first_traversals_of_balanced_trees_do_not_allocate() {
+    let node_count = 2_u32.pow(TREE_HEIGHT) - 1;
+    let mut nodes = OrderedNodeMap::new();
+    let root_key = complete_tree(&mut nodes, 0, node_count);
+    let root = root_key.and_then(|key| nodes.get(&key));
+
+    let allocations = count_allocations(|| {
+        assert!(InOrderIter::new(&nodes, root).map(TravlNode::key).copied().eq(0..node_count));
+        assert!(ReverseInOrderIter::new(&nodes, root).map(TravlNode::key).copied().eq((0..node_count).rev()));
+        assert_eq!(PreOrderIter::new(&nodes, root).count(), node_count as usize);
+        assert_eq!(PostOrderIter::new(&nodes, root).count(), node_count as usize);
+        assert_eq!(PreOrderDepthIter::new(&nodes, root).count(), node_count as usize);
+        assert_eq!(LeafIter::new(&nodes, root).count() + InternalIter::new(&nodes, root).count(), node_count as usize);
+    });
+
+    assert_eq!(allocations, 0);
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use travl::map::{MapOperation, TravlMap};
+
+/// Returns bytes to generate values from, filled by a linear congruential generator
+fn bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            state.to_be_bytes()[0]
+        })
+        .collect()
+}
+
+#[test]
+fn generated_maps_are_valid_trees() {
+    let mut largest = 0;
+
+    for seed in 0..2_000 {
+        let bytes = bytes(seed, 1_024);
+        let mut map = TravlMap::<u8, u16>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+
+        assert_eq!(map.validate(), [], "seed {seed}");
+        assert!(map.imbalance_factor() <= 3);
+        largest = largest.max(map.len());
+    }
+
+    // Operation sequences stop or clear the map often, so only some of the generated maps hold several entries
+    assert!(largest >= 4);
+}
+
+#[test]
+fn generated_operations_match_the_reference_model() {
+    let bytes = bytes(7, 8_192);
+    let mut unstructured = Unstructured::new(&bytes);
+
+    let (mut map, mut entries) = (TravlMap::<u8, u16>::new(), Vec::new());
+    let mut largest = 0;
+    for _ in 0..500 {
+        let operation = MapOperation::arbitrary(&mut unstructured).unwrap();
+        match operation.clone() {
+            MapOperation::Insert(key, value) => {
+                map.insert(key, value);
+            },
+            MapOperation::Remove(key) => {
+                map.remove(&key);
+            },
+            MapOperation::Clear => {
+                let keys: Vec<u8> = map.iter().map(|(key, _)| *key).collect();
+                for key in keys {
+                    map.remove(&key);
+                }
+            },
+        }
+        operation.apply_to(&mut entries);
+
+        let mut expected = entries.clone();
+        expected.sort_by_key(|&(_, value)| value);
+        assert_eq!(map.len(), entries.len());
+        assert!(map.iter().map(|(_, value)| *value).eq(expected.into_iter().map(|(_, value)| value)));
+        largest = largest.max(map.len());
+    }
+    assert_eq!(map.validate(), []);
+    assert!(largest >= 5);
+}
@@ -0,0 +1,36 @@
+use travl::core::TravlNode;
+use travl::layout::BfsLayout;
+use travl::map::{Iter, TravlMap, TravlMapSnapshot};
+use travl::persistent::{PersistentIter, TravlMapPersistent};
+use travl::traversal::{InOrderIter, LevelOrderIter};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn maps_can_cross_thread_boundaries() {
+    assert_send_sync::<TravlNode<String, u32>>();
+    assert_send_sync::<TravlMap<'static, String, u32>>();
+    assert_send_sync::<TravlMap<'static, String, (u32, String), String>>();
+    assert_send_sync::<TravlMapSnapshot<String, u32>>();
+    assert_send_sync::<BfsLayout<String, u32>>();
+    assert_send_sync::<TravlMapPersistent<String, u32>>();
+}
+
+#[test]
+fn iterators_can_cross_thread_boundaries() {
+    assert_send_sync::<Iter<'static, String, u32>>();
+    assert_send_sync::<InOrderIter<'static, String, u32>>();
+    assert_send_sync::<LevelOrderIter<'static, String, u32>>();
+    assert_send_sync::<PersistentIter<'static, String, u32>>();
+}
+
+#[test]
+fn maps_can_be_moved_to_and_shared_between_threads() {
+    let map: TravlMap<'static, String, u32> = TravlMap::new_with_ordering(Box::new(|a, b| b.cmp(a)));
+    let map = std::thread::spawn(move || map).join().unwrap();
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| assert!(map.is_empty()));
+        scope.spawn(|| assert_eq!(map.len(), 0));
+    });
+}
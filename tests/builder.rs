@@ -0,0 +1,161 @@
+use travl::error::TravlError;
+use travl::map::{RejectionReason, SortedEntriesBuilder, TravlMap};
+
+#[derive(Debug)]
+struct Event {
+    timestamp: f64,
+}
+
+#[test]
+fn builders_configure_the_map() {
+    let map = TravlMap::<u32, u32>::builder().imbalance_factor(4).capacity(16).build();
+
+    assert_eq!(map.imbalance_factor(), 4);
+    assert!(map.capacity() >= 16);
+    assert!(map.is_empty());
+}
+
+#[test]
+fn builders_order_by_the_given_property_and_ordering() {
+    let map = TravlMap::<u32, Event>::builder()
+        .prop(|event| &event.timestamp)
+        .ordering(|a, b| b.total_cmp(a))
+        .build();
+
+    let mut builder = SortedEntriesBuilder::new(map);
+    builder.push(1, Event { timestamp: 2.5 }).unwrap();
+    builder.push(2, Event { timestamp: 1.0 }).unwrap();
+
+    let rejected = builder.push(3, Event { timestamp: 4.0 }).unwrap_err();
+    assert_eq!(TravlError::from(rejected), TravlError::OutOfOrder);
+}
+
+#[test]
+fn builders_order_properties_by_ord_by_default() {
+    let mut builder = SortedEntriesBuilder::new(TravlMap::<u32, (u32, u32)>::builder().prop(|value| &value.1).build());
+    builder.push(1, (9, 1)).unwrap();
+    builder.push(2, (0, 2)).unwrap();
+    assert!(builder.push(3, (5, 0)).is_err());
+}
+
+fn timestamp(event: &Event) -> &f64 {
+    &event.timestamp
+}
+
+#[test]
+fn constructors_accept_function_pointers_and_closures() {
+    let map = TravlMap::<u32, Event, _>::with_prop_getter_and_ordering(timestamp, f64::total_cmp);
+    let mut builder = SortedEntriesBuilder::new(map);
+    builder.push(1, Event { timestamp: 1.0 }).unwrap();
+    assert!(builder.push(2, Event { timestamp: 0.5 }).is_err());
+
+    let mut builder = SortedEntriesBuilder::new(TravlMap::<u32, u32>::with_ordering(|a, b| b.cmp(a)));
+    builder.push(1, 9).unwrap();
+    assert!(builder.push(2, 10).is_err());
+
+    let mut builder = SortedEntriesBuilder::new(TravlMap::<u32, (u32, u32), _>::with_prop_getter(|value| &value.1));
+    builder.push(1, (9, 1)).unwrap();
+    assert!(builder.push(2, (5, 0)).is_err());
+}
+
+#[test]
+fn finished_builders_link_their_entries_into_balanced_trees() {
+    for len in [0_u32, 1, 2, 7, 8] {
+        let mut builder = SortedEntriesBuilder::new(TravlMap::<u32, u32>::new());
+        for key in 0..len {
+            builder.push(key, key * 10).unwrap();
+        }
+
+        let mut map = builder.finish();
+        assert_eq!(map.len(), len as usize);
+        assert_eq!(map.validate(), []);
+        assert!(map.iter().map(|(key, _)| *key).eq(0..len));
+        assert_eq!(map.stats().height(), map.stats().minimum_height());
+    }
+}
+
+/// Key compared, ordered and hashed by its identifier only, remembering where it was created
+#[derive(Clone, Debug)]
+struct Tagged {
+    id: u32,
+    origin: &'static str,
+}
+
+impl PartialEq for Tagged {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Tagged {}
+
+impl std::hash::Hash for Tagged {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl PartialOrd for Tagged {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Tagged {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+#[test]
+fn rejected_entries_are_given_back_with_the_reason_of_their_rejection() {
+    let tagged = |id, origin| Tagged { id, origin };
+    let mut builder = SortedEntriesBuilder::new(TravlMap::<Tagged, u32>::new());
+    for id in 0..5 {
+        builder.push(tagged(id, "pushed"), id * 10).unwrap();
+    }
+
+    // Duplicate keys are rejected with the key that was already pushed
+    let rejected = builder.push(tagged(2, "duplicate"), 50).unwrap_err();
+    assert_eq!(rejected.reason(), RejectionReason::DuplicateKey);
+    assert_eq!((rejected.key().origin, *rejected.value()), ("pushed", 50));
+
+    // The order is checked before the key
+    let rejected = builder.push(tagged(3, "duplicate"), 0).unwrap_err();
+    assert_eq!(rejected.reason(), RejectionReason::OutOfOrder);
+    let (key, value) = rejected.into_entry();
+    assert_eq!((key.origin, value), ("duplicate", 0));
+
+    let mut map = builder.finish();
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().all(|(key, value)| key.origin == "pushed" && *value == key.id * 10));
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+#[derive(Clone, Default)]
+struct Seeded(u64);
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl std::hash::BuildHasher for Seeded {
+    type Hasher = std::hash::DefaultHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        let mut hasher = std::hash::DefaultHasher::new();
+        std::hash::Hasher::write_u64(&mut hasher, self.0);
+        hasher
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+#[test]
+fn builders_hash_the_keys_with_the_given_hasher() {
+    let mut map = TravlMap::<u32, u32>::builder().hasher(Seeded(42)).capacity(8).build();
+    for key in 0..32 {
+        map.insert(key, key * 10);
+    }
+
+    assert_eq!(map.nodes().hasher().0, 42);
+    assert!(map.validate().is_empty());
+    assert_eq!(map.len(), 32);
+    assert_eq!(map.get(&7).map(|node| *node.value()), Some(70));
+}
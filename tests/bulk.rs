@@ -0,0 +1,57 @@
+#![cfg(feature = "std")]
+
+use std::thread;
+
+use travl::bulk::BulkLoader;
+use travl::map::TravlMap;
+
+const FEEDER_COUNT: u32 = 4;
+const KEYS_PER_FEEDER: u32 = 250;
+
+#[test]
+fn runs_fed_by_several_threads_are_merged() {
+    let loader = BulkLoader::new();
+
+    let feeders: Vec<_> = (0..FEEDER_COUNT)
+        .map(|feeder| {
+            let mut runs = loader.feeder().with_run_len(16);
+            thread::spawn(move || {
+                for key in (feeder..FEEDER_COUNT * KEYS_PER_FEEDER).step_by(FEEDER_COUNT as usize) {
+                    runs.push(key, key * 10);
+                }
+            })
+        })
+        .collect();
+    for feeder in feeders {
+        feeder.join().unwrap();
+    }
+
+    let mut map = loader.finish(TravlMap::new());
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.len(), (FEEDER_COUNT * KEYS_PER_FEEDER) as usize);
+    assert!(map.iter().map(|(key, _)| *key).eq(0..FEEDER_COUNT * KEYS_PER_FEEDER));
+    assert!(map.iter().all(|(key, value)| *value == key * 10));
+    assert_eq!(map.stats().height(), map.stats().minimum_height());
+}
+
+#[test]
+fn finished_maps_keep_the_last_value_of_each_key() {
+    let mut existing = TravlMap::new();
+    existing.insert(100, 100);
+
+    let loader = BulkLoader::new();
+    let mut feeder = loader.feeder();
+    feeder.push(1, 10);
+    feeder.push(2, 20);
+    // Unsorted runs are sorted by the loader
+    feeder.send_run(vec![(3, 30), (1, 11)]);
+    feeder.push(2, 21);
+    feeder.flush();
+    drop(feeder);
+
+    let mut map = loader.finish(existing);
+
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().eq([(&1, &11), (&2, &21), (&3, &30)]));
+}
@@ -0,0 +1,41 @@
+use std::hash::Hasher;
+
+use travl::checksum::StableHasher;
+use travl::map::TravlMap;
+use travl::persistent::TravlMapPersistent;
+
+#[test]
+fn stable_hashers_compute_fnv_1a() {
+    assert_eq!(StableHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+
+    let mut hasher = StableHasher::new();
+    hasher.write(b"a");
+    assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+
+    let mut hasher = StableHasher::new();
+    hasher.write_usize(1);
+    let mut other = StableHasher::new();
+    other.write(&[1, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(hasher.finish(), other.finish());
+}
+
+#[test]
+fn structural_hashes_depend_on_entries_and_shape() {
+    let ascending: TravlMapPersistent<_, _> = (0..7_u32).map(|key| (key, key * 2)).collect();
+    let same = (0..7_u32).fold(TravlMapPersistent::new(), |map, key| map.insert(key, key * 2));
+    assert_eq!(ascending.structural_hash(), same.structural_hash());
+
+    let other_value = ascending.insert(3, 0);
+    assert_ne!(ascending.structural_hash(), other_value.structural_hash());
+
+    let relaxed = (0..7_u32).fold(TravlMapPersistent::with_imbalance_factor(2), |map, key| map.insert(key, key * 2));
+    assert!(!ascending.structural_diff(&relaxed).is_identical());
+    assert_ne!(ascending.structural_hash(), relaxed.structural_hash());
+}
+
+#[test]
+fn structural_hashes_are_stable() {
+    let map: TravlMapPersistent<_, _> = [(1_u32, "one"), (2, "two"), (3, "three")].into_iter().collect();
+    assert_eq!(map.structural_hash(), 0xc2e4_870c_e0a9_72f9);
+    assert_eq!(TravlMapPersistent::<u32, u32>::new().structural_hash(), TravlMap::<u32, u32>::new().structural_hash());
+}
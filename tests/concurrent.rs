@@ -0,0 +1,125 @@
+#![cfg(feature = "std")]
+
+use std::sync::Arc;
+use std::thread;
+
+use travl::concurrent::{ConcurrentTravlMap, Publisher, ShardedTravlMap};
+
+const WRITER_COUNT: u32 = 4;
+const KEYS_PER_WRITER: u32 = 1_000;
+
+#[test]
+fn concurrent_writes_are_all_applied() {
+    let map = Arc::new(ConcurrentTravlMap::new());
+
+    let writers: Vec<_> = (0..WRITER_COUNT)
+        .map(|writer| {
+            let map = Arc::clone(&map);
+            thread::spawn(move || {
+                for key in (writer..WRITER_COUNT * KEYS_PER_WRITER).step_by(WRITER_COUNT as usize) {
+                    assert_eq!(map.insert(key, writer), None);
+                }
+            })
+        })
+        .collect();
+
+    // Readers observe consistent versions while the writers are running
+    let reader = {
+        let map = Arc::clone(&map);
+        thread::spawn(move || {
+            for _ in 0..100 {
+                let entries = map.range(..);
+                assert!(entries.is_sorted_by_key(|(key, _)| *key));
+                assert!(entries.iter().all(|(key, writer)| key % WRITER_COUNT == *writer));
+            }
+        })
+    };
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    reader.join().unwrap();
+
+    assert_eq!(map.len(), (WRITER_COUNT * KEYS_PER_WRITER) as usize);
+    assert_eq!(map.get(&7), Some(3));
+    assert_eq!(map.remove(&7), Some(3));
+    assert!(!map.contains_key(&7));
+}
+
+#[test]
+fn range_returns_the_entries_within_bounds() {
+    let map = ConcurrentTravlMap::new();
+    for key in 0..100 {
+        map.insert(key, key * 2);
+    }
+
+    let keys = |entries: Vec<(u32, u32)>| entries.into_iter().map(|(key, _)| key).collect::<Vec<_>>();
+    assert_eq!(keys(map.range(10..15)), [10, 11, 12, 13, 14]);
+    assert_eq!(keys(map.range(95..)), [95, 96, 97, 98, 99]);
+    assert_eq!(keys(map.range(..=2)), [0, 1, 2]);
+    assert!(map.range(200..).is_empty());
+
+    let snapshot = map.snapshot();
+    map.remove(&12);
+    assert!(snapshot.contains_key(&12));
+    assert_eq!(keys(map.range(10..15)), [10, 11, 13, 14]);
+}
+
+#[test]
+fn readers_only_see_published_versions() {
+    let mut publisher = Publisher::new();
+    let reader = publisher.reader();
+
+    publisher.insert(1, "one");
+    publisher.insert(2, "two");
+    assert!(reader.load().is_empty());
+
+    publisher.publish();
+    let first_version = reader.load();
+
+    assert_eq!(publisher.remove(&1), Some("one"));
+    assert!(first_version.iter().eq([(&1, &"one"), (&2, &"two")]));
+
+    let other_reader = reader.clone();
+    thread::spawn(move || assert_eq!(other_reader.load().len(), 2)).join().unwrap();
+
+    publisher.publish();
+    assert!(reader.load().iter().eq([(&2, &"two")]));
+}
+
+#[test]
+fn sharded_maps_merge_queries_across_shards() {
+    let map = Arc::new(ShardedTravlMap::with_boundaries([750, 250, 500]));
+    assert_eq!(map.shard_count(), 4);
+
+    let writers: Vec<_> = (0..WRITER_COUNT)
+        .map(|writer| {
+            let map = Arc::clone(&map);
+            thread::spawn(move || {
+                for key in (writer..KEYS_PER_WRITER).step_by(WRITER_COUNT as usize) {
+                    assert_eq!(map.insert(key, writer), None);
+                }
+            })
+        })
+        .collect();
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    assert_eq!(map.len(), KEYS_PER_WRITER as usize);
+    assert!(map.shards().iter().all(|shard| shard.len() == 250));
+
+    let keys: Vec<u32> = map.range(240..260).into_iter().map(|(key, _)| key).collect();
+    assert!(keys.into_iter().eq(240..260));
+    assert_eq!(map.range(..).len(), KEYS_PER_WRITER as usize);
+    assert_eq!(map.range(600..=600), [(600, 0)]);
+
+    assert_eq!(map.rank(&0), 0);
+    assert_eq!(map.rank(&600), 600);
+    assert_eq!(map.rank(&5_000), KEYS_PER_WRITER as usize);
+
+    assert_eq!(map.remove(&600), Some(0));
+    assert_eq!(map.rank(&700), 699);
+    assert_eq!(map.get(&601), Some(1));
+}
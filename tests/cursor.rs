@@ -0,0 +1,127 @@
+use travl::map::{RejectionReason, TravlMap};
+
+/// Returns a map of the given keys, each key being its own value
+fn map_of(keys: impl IntoIterator<Item = u32>) -> TravlMap<'static, u32, u32> {
+    let mut map = TravlMap::new();
+    for key in keys {
+        map.insert(key, key);
+    }
+    map
+}
+
+#[test]
+fn cursors_move_along_the_structure_and_order_of_the_tree() {
+    let map = map_of(0..7);
+    let root = *map.root_key().unwrap();
+    let mut cursor = map.cursor(&root).unwrap();
+
+    assert_eq!(cursor.key(), &3);
+    assert_eq!(cursor.move_to_left().map(|node| *node.key()), Some(1));
+    assert_eq!(cursor.move_to_right().map(|node| *node.key()), Some(2));
+    assert!(cursor.move_to_right().is_none());
+    assert_eq!(cursor.key(), &2);
+    assert_eq!(cursor.peek_next().map(|node| *node.key()), Some(3));
+    assert_eq!(cursor.move_to_parent().map(|node| *node.key()), Some(1));
+
+    let mut cursor = map.cursor(&0).unwrap();
+    assert!(cursor.move_prev().is_none());
+    let keys: Vec<u32> = std::iter::from_fn(|| cursor.move_next().map(|node| *node.key())).collect();
+    assert_eq!(keys, [1, 2, 3, 4, 5, 6]);
+    assert!(map.cursor(&7).is_none());
+}
+
+#[test]
+fn mutable_cursors_move_like_read_only_cursors() {
+    let mut map = map_of(0..7);
+    let mut cursor = map.cursor_mut(&3).unwrap();
+
+    assert_eq!(cursor.move_prev().map(|node| *node.key()), Some(2));
+    assert_eq!(cursor.key(), &2);
+    assert_eq!(cursor.move_to_parent().map(|node| *node.key()), Some(1));
+    assert_eq!(cursor.move_to_left().map(|node| *node.key()), Some(0));
+    assert_eq!(cursor.key(), &0);
+    assert!(cursor.move_to_right().is_none());
+    assert!(cursor.move_to_parent().is_some() && cursor.move_to_parent().is_some());
+    assert!(cursor.move_to_parent().is_none());
+    *cursor.value_mut() += 100;
+    assert_eq!(cursor.as_cursor().value(), &103);
+}
+
+#[test]
+fn mutable_cursors_insert_next_to_their_node() {
+    let mut map = map_of((0..20).map(|key| key * 10));
+    let mut cursor = map.cursor_mut(&50).unwrap();
+
+    for key in [59, 58, 55, 51] {
+        cursor.insert_after(key, key).unwrap();
+    }
+    for key in [41, 45, 49] {
+        cursor.insert_before(key, key).unwrap();
+    }
+    assert_eq!(cursor.key(), &50);
+    assert_eq!(cursor.move_next().map(|node| *node.value()), Some(51));
+    assert_eq!(cursor.key(), &51);
+
+    assert_eq!(map.validate(), []);
+    let keys: Vec<u32> = map.iter().map(|(key, _)| *key).collect();
+    assert_eq!(&keys[4..14], [40, 41, 45, 49, 50, 51, 55, 58, 59, 60]);
+}
+
+#[test]
+fn mutable_cursors_reject_entries_out_of_place() {
+    let mut map = map_of([10, 20, 30]);
+    let mut cursor = map.cursor_mut(&20).unwrap();
+
+    let rejected = cursor.insert_after(35, 35).unwrap_err();
+    assert_eq!(rejected.reason(), RejectionReason::OutOfOrder);
+    assert_eq!(rejected.into_entry(), (35, 35));
+    assert_eq!(cursor.insert_before(5, 5).unwrap_err().reason(), RejectionReason::OutOfOrder);
+    assert_eq!(cursor.insert_after(10, 25).unwrap_err().reason(), RejectionReason::DuplicateKey);
+
+    // Entries whose properties are equal to their neighbors' fit on either side
+    cursor.insert_after(21, 20).unwrap();
+    cursor.insert_before(19, 20).unwrap();
+
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq([10, 19, 20, 21, 30]));
+}
+
+#[test]
+fn removing_through_mutable_cursors_moves_them_to_the_next_node() {
+    let mut map = map_of(0..10);
+    let cursor = map.cursor_mut(&4).unwrap();
+
+    let (entry, cursor) = cursor.remove_current();
+    assert_eq!(entry, (4, 4));
+    let mut cursor = cursor.unwrap();
+    assert_eq!(cursor.key(), &5);
+
+    while cursor.move_next().is_some() {}
+    let (entry, cursor) = cursor.remove_current();
+    assert_eq!(entry, (9, 9));
+    assert_eq!(cursor.unwrap().key(), &8);
+
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq([0, 1, 2, 3, 5, 6, 7, 8]));
+
+    let mut map = map_of([1]);
+    let (entry, cursor) = map.cursor_mut(&1).unwrap().remove_current();
+    assert_eq!(entry, (1, 1));
+    assert!(cursor.is_none());
+    assert!(map.is_empty());
+}
+
+#[test]
+fn removing_every_node_through_a_cursor_keeps_the_tree_valid() {
+    let mut map = map_of((0..200).map(|key| key * 7 % 200));
+    let mut cursor = map.cursor_mut(&100);
+
+    while let Some(current) = cursor {
+        let ((key, _), next) = current.remove_current();
+        assert!(key < 200);
+        cursor = next;
+    }
+
+    assert!(map.is_empty());
+    assert_eq!(map.validate(), []);
+}
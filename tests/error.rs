@@ -0,0 +1,63 @@
+use travl::core::MAX_IMBALANCE_FACTOR;
+use travl::error::TravlError;
+use travl::map::{SortedEntriesBuilder, TravlMap, TravlMapConfig};
+use travl::persistent::TravlMapPersistent;
+
+#[test]
+fn inserting_an_existing_key_fails_without_changing_the_map() {
+    let map: TravlMapPersistent<_, _> = [(1, "one"), (2, "two")].into_iter().collect();
+
+    let inserted = map.try_insert(3, "three").unwrap();
+    assert_eq!(inserted.len(), 3);
+
+    assert_eq!(map.try_insert(1, "uno").unwrap_err(), TravlError::DuplicateKey);
+    assert_eq!(map.get(&1), Some(&"one"));
+}
+
+#[test]
+fn rejected_entries_convert_into_errors() {
+    let mut builder = SortedEntriesBuilder::new(TravlMap::new());
+    builder.push(1, 10).unwrap();
+
+    let duplicate = builder.try_extend::<_, TravlError>([Ok((1, 20))]);
+    assert_eq!(duplicate, Err(TravlError::DuplicateKey));
+
+    let out_of_order = builder.try_extend([Ok((2, 20)), Ok((3, 5)), Err(TravlError::BrokenLink)]);
+    assert_eq!(out_of_order, Err(TravlError::OutOfOrder));
+    assert_eq!(builder.len(), 2);
+}
+
+#[test]
+fn empty_maps_have_no_root() {
+    let map: TravlMap<'_, u32, u32> = TravlMap::new();
+    assert_eq!(map.try_root().map(|root| root.is_none()), Ok(true));
+}
+
+#[test]
+fn imbalance_factors_beyond_the_maximum_are_rejected() {
+    let error = TravlMapPersistent::<u32, u32>::try_with_imbalance_factor(MAX_IMBALANCE_FACTOR + 1).unwrap_err();
+    assert_eq!(error, TravlError::ImbalanceFactorTooLarge);
+    assert_eq!(TravlMapConfig::try_new(u64::MAX), Err(TravlError::ImbalanceFactorTooLarge));
+
+    let config = TravlMapConfig::try_new(MAX_IMBALANCE_FACTOR).unwrap();
+    assert_eq!(config.imbalance_factor(), MAX_IMBALANCE_FACTOR);
+}
+
+#[test]
+fn the_maximum_imbalance_factor_never_rebalances() {
+    let mut map = TravlMapPersistent::try_with_imbalance_factor(MAX_IMBALANCE_FACTOR).unwrap();
+    for key in 0..100 {
+        map = map.insert(key, ());
+    }
+
+    assert_eq!(map.height(), 100);
+    assert_eq!(map.validate(), []);
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    assert!(!map.stats().balance_factors().contains_key(&travl::core::BalanceFactor::TooRightHeavy));
+}
+
+#[test]
+#[should_panic(expected = "the imbalance factor exceeds the maximum imbalance factor")]
+fn imbalance_factors_beyond_the_maximum_panic_in_infallible_constructors() {
+    let _ = TravlMapPersistent::<u32, u32>::with_imbalance_factor(u64::MAX);
+}
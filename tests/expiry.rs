@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+use travl::expiry::TravlExpiringMap;
+
+#[test]
+fn due_entries_are_expired_in_deadline_order() {
+    let mut map = TravlExpiringMap::new();
+    map.insert("session-a", 1, 30);
+    map.insert("session-b", 2, 10);
+    map.insert("session-c", 3, 20);
+    map.insert("session-d", 4, 40);
+
+    assert_eq!(map.next_deadline(), Some((&"session-b", &10)));
+    assert_eq!(map.expire_until(&5), []);
+    assert_eq!(map.expire_until(&20), [("session-b", 2), ("session-c", 3)]);
+    assert_eq!(map.len(), 2);
+    assert!(!map.contains_key(&"session-b"));
+    assert!(map.iter().eq([(&"session-a", &1), (&"session-d", &4)]));
+}
+
+#[test]
+fn deadlines_follow_updates_and_removals() {
+    let mut map = TravlExpiringMap::new();
+    map.insert(1, "one", 10);
+    map.insert(2, "two", 20);
+
+    assert_eq!(map.insert(1, "uno", 50), Some("one"));
+    assert_eq!(map.deadline(&1), Some(&50));
+    assert_eq!(map.set_deadline(&2, 60), Some(20));
+    assert_eq!(map.set_deadline(&3, 60), None);
+    assert_eq!(map.expire_until(&40), []);
+
+    assert_eq!(map.remove(&1), Some("uno"));
+    assert_eq!(map.expire_until(&100), [(2, "two")]);
+    assert!(map.is_empty());
+    assert_eq!(map.next_deadline(), None);
+}
+
+#[test]
+fn instants_can_be_used_as_deadlines() {
+    let now = Instant::now();
+    let mut cache = TravlExpiringMap::new();
+    cache.insert("short", (), now + Duration::from_secs(1));
+    cache.insert("long", (), now + Duration::from_secs(30));
+
+    assert_eq!(cache.expire_until(&(now + Duration::from_secs(2))), [("short", ())]);
+    assert_eq!(cache.get(&"long"), Some(&()));
+}
@@ -0,0 +1,19 @@
+#![cfg(feature = "hashbrown")]
+
+use travl::core::TravlNode;
+use travl::map::TravlMap;
+
+#[test]
+fn maps_store_their_nodes_in_hashbrown_maps() {
+    let entries: hashbrown::HashMap<u32, u32> = (0..100).map(|key| (key, 99 - key)).collect();
+
+    let mut map = TravlMap::from(entries);
+    map.insert(100, 50);
+
+    let nodes: &hashbrown::HashMap<u32, TravlNode<u32, u32>> = map.nodes();
+    assert_eq!(nodes.len(), 101);
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).take(3).eq([99, 98, 97]));
+    assert_eq!(map.iter().nth(50), Some((&49, &50)));
+    assert_eq!(map.iter().nth(51), Some((&100, &50)));
+}
@@ -0,0 +1,78 @@
+use std::cmp::Reverse;
+
+use travl::heap::TravlHeap;
+
+#[test]
+fn keys_are_popped_by_priority_then_by_key() {
+    let mut heap: TravlHeap<&str, u32> = [("c", 3), ("a", 1), ("d", 1), ("b", 2)].into_iter().collect();
+
+    assert_eq!(heap.len(), 4);
+    assert_eq!(heap.peek(), Some((&"a", &1)));
+    assert_eq!(heap.pop(), Some(("a", 1)));
+    assert_eq!(heap.pop(), Some(("d", 1)));
+    assert_eq!(heap.pop(), Some(("b", 2)));
+    assert_eq!(heap.pop(), Some(("c", 3)));
+    assert_eq!(heap.pop(), None);
+    assert!(heap.is_empty());
+}
+
+#[test]
+fn keys_are_addressable() {
+    let mut heap = TravlHeap::new();
+    for key in 0..10_u32 {
+        heap.push(key, Reverse(key * 10));
+    }
+
+    assert_eq!(heap.push(3, Reverse(5)), Some(Reverse(30)));
+    assert_eq!(heap.change_priority(&0, Reverse(1_000)), Some(Reverse(0)));
+    assert_eq!(heap.change_priority(&42, Reverse(1)), None);
+    assert_eq!(heap.remove_by_key(&9), Some(Reverse(90)));
+    assert_eq!(heap.remove_by_key(&9), None);
+
+    assert_eq!(heap.priority(&3), Some(&Reverse(5)));
+    assert!(!heap.contains_key(&42));
+    assert_eq!(heap.peek(), Some((&0, &Reverse(1_000))));
+
+    let order: Vec<u32> = std::iter::from_fn(|| heap.pop().map(|(key, _)| key)).collect();
+    assert_eq!(order, [0, 8, 7, 6, 5, 4, 2, 1, 3]);
+}
+
+#[test]
+fn appended_priorities_replace_existing_ones() {
+    let mut first: TravlHeap<u32, u32> = [(1, 10), (2, 20)].into_iter().collect();
+    let second: TravlHeap<u32, u32> = [(2, 5), (3, 30)].into_iter().collect();
+
+    first.append(&second);
+
+    assert!(first.iter().eq([(&2, &5), (&1, &10), (&3, &30)]));
+}
+
+#[test]
+fn peeked_keys_follow_every_change_of_the_queue() {
+    let mut heap = TravlHeap::new();
+    let mut snapshots = Vec::new();
+
+    for step in 0..200_u32 {
+        let key = step.wrapping_mul(7) % 23;
+        match step % 4 {
+            0 | 1 => {
+                heap.push(key, step.wrapping_mul(31) % 17);
+            },
+            2 => {
+                heap.change_priority(&key, step % 5);
+            },
+            _ => {
+                heap.remove_by_key(&heap.peek().map_or(key, |(first, _)| *first));
+            },
+        }
+
+        assert_eq!(heap.peek(), heap.iter().next());
+        snapshots.push((heap.clone(), heap.iter().next().map(|(key, priority)| (*key, *priority))));
+    }
+
+    // Changing a queue in place leaves its clones untouched
+    for (snapshot, first) in snapshots {
+        assert_eq!(snapshot.peek().map(|(key, priority)| (*key, *priority)), first);
+        assert_eq!(snapshot.peek(), snapshot.iter().next());
+    }
+}
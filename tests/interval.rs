@@ -0,0 +1,110 @@
+use std::ops::{Bound, Range};
+
+use travl::interval::{SweepEvent, TravlIntervalMap};
+
+/// Deterministic pseudo-random sequence, so that failures can be reproduced
+fn pseudo_random(seed: u64) -> impl Iterator<Item = u64> {
+    std::iter::successors(Some(seed), |state| {
+        Some(state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407))
+    })
+    .map(|state| state >> 33)
+}
+
+#[test]
+fn queries_match_a_linear_scan_through_insertions_and_removals() {
+    let mut map = TravlIntervalMap::new();
+    let mut model: Vec<(Range<u64>, u64)> = Vec::new();
+    let mut random = pseudo_random(7);
+
+    for step in 0..2_000 {
+        let start = random.next().unwrap() % 1_000;
+        let interval = start..start + 1 + random.next().unwrap() % 50;
+
+        if random.next().unwrap().is_multiple_of(4) && !model.is_empty() {
+            let index = usize::try_from(random.next().unwrap()).unwrap() % model.len();
+            let (interval, value) = model.remove(index);
+            assert_eq!(map.remove(&interval), Some(value));
+        } else {
+            let previous = model.iter().position(|(existing, _)| *existing == interval);
+            let previous = previous.map(|index| model.remove(index).1);
+            assert_eq!(map.insert(interval.clone(), step), previous);
+            model.push((interval, step));
+        }
+    }
+
+    model.sort_by_key(|(interval, _)| (interval.start, interval.end));
+    assert_eq!(map.len(), model.len());
+
+    for point in (0..1_100).step_by(7) {
+        let expected = model.iter().filter(|(interval, _)| interval.contains(&point));
+        assert!(map.query_point(&point).eq(expected.map(|(interval, value)| (interval, value))));
+    }
+
+    for start in (0..1_100).step_by(13) {
+        let range = start..start + 20;
+        let expected = model.iter().filter(|(interval, _)| interval.start < range.end && interval.end > range.start);
+        assert!(map.query_overlapping(range.clone()).eq(expected.map(|(interval, value)| (interval, value))));
+    }
+}
+
+#[test]
+fn range_bounds_are_honored() {
+    let map: TravlIntervalMap<u32, &str> = [(0..5, "a"), (5..10, "b"), (10..15, "c")].into_iter().collect();
+
+    let values = |range: (Bound<u32>, Bound<u32>)| {
+        map.query_overlapping(range).map(|(_, value)| *value).collect::<Vec<_>>()
+    };
+
+    assert_eq!(values((Bound::Included(5), Bound::Excluded(10))), ["b"]);
+    assert_eq!(values((Bound::Included(5), Bound::Included(10))), ["b", "c"]);
+    assert_eq!(values((Bound::Excluded(4), Bound::Unbounded)), ["a", "b", "c"]);
+    assert_eq!(map.query_overlapping(..).count(), 3);
+    assert_eq!(map.query_point(&10).map(|(_, value)| *value).collect::<Vec<_>>(), ["c"]);
+    assert_eq!(map.query_point(&15).count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "interval must not be empty")]
+fn empty_intervals_are_rejected() {
+    TravlIntervalMap::new().insert(3..3, ());
+}
+
+#[test]
+fn sweeping_orders_ends_before_starts_at_the_same_point() {
+    let map: TravlIntervalMap<u32, char> = [(0..5, 'a'), (5..8, 'b'), (2..5, 'c')].into_iter().collect();
+
+    let events: Vec<(bool, u32, char)> = map
+        .sweep()
+        .map(|event| (matches!(event, SweepEvent::Start(..)), *event.point(), *event.value()))
+        .collect();
+
+    assert_eq!(
+        events,
+        [(true, 0, 'a'), (true, 2, 'c'), (false, 5, 'a'), (false, 5, 'c'), (true, 5, 'b'), (false, 8, 'b')]
+    );
+}
+
+#[test]
+fn sweeping_detects_conflicts_and_allocates_rooms() {
+    let meetings: TravlIntervalMap<u32, &str> =
+        [(9..11, "standup"), (10..12, "review"), (11..13, "lunch"), (12..14, "retro"), (10..11, "sync")]
+            .into_iter()
+            .collect();
+
+    let mut sweep = meetings.sweep();
+    let mut conflicts = Vec::new();
+    let mut rooms_needed = 0;
+    while let Some(event) = sweep.next() {
+        if let SweepEvent::Start(_, meeting) = event {
+            let others = sweep.active().filter(|(_, other)| *other != meeting);
+            conflicts.extend(others.map(|(_, other)| (*other, *meeting)));
+            rooms_needed = rooms_needed.max(sweep.active_len());
+        }
+    }
+
+    assert_eq!(rooms_needed, 3);
+    assert_eq!(
+        conflicts,
+        [("standup", "sync"), ("standup", "review"), ("sync", "review"), ("review", "lunch"), ("lunch", "retro")]
+    );
+}
@@ -0,0 +1,56 @@
+use travl::journal::JournaledMap;
+use travl::persistent::TravlMapPersistent;
+
+/// Returns the entries of a map, to compare its logical state with earlier ones
+fn entries(map: &JournaledMap<u32, &'static str>) -> Vec<(u32, &'static str)> {
+    map.snapshot().iter().map(|(key, value)| (*key, *value)).collect()
+}
+
+#[test]
+fn undoing_and_redoing_restores_previous_states() {
+    let mut map = JournaledMap::new();
+    let mut states = vec![entries(&map)];
+
+    map.insert(1, "one");
+    states.push(entries(&map));
+    map.insert(2, "two");
+    states.push(entries(&map));
+    map.insert(1, "uno");
+    states.push(entries(&map));
+    map.remove(&2);
+    states.push(entries(&map));
+
+    assert_eq!(map.undo_len(), 4);
+    assert_eq!(map.remove(&42), None);
+    assert_eq!(map.undo_len(), 4);
+
+    for state in states.iter().rev().skip(1) {
+        assert_eq!(map.undo(1), 1);
+        assert_eq!(&entries(&map), state);
+    }
+    assert_eq!(map.undo(1), 0);
+
+    assert_eq!(map.redo(3), 3);
+    assert_eq!(entries(&map), states[3]);
+    assert_eq!(map.redo(5), 1);
+    assert_eq!(entries(&map), states[4]);
+}
+
+#[test]
+fn new_changes_discard_the_undone_ones() {
+    let mut map = JournaledMap::from_persistent([(1, "one")].into_iter().collect::<TravlMapPersistent<_, _>>());
+    map.insert(2, "two");
+    map.insert(3, "three");
+
+    assert_eq!(map.undo(2), 2);
+    assert_eq!(map.redo_len(), 2);
+
+    map.insert(4, "four");
+    assert_eq!(map.redo_len(), 0);
+    assert_eq!(map.redo(1), 0);
+    assert_eq!(entries(&map), [(1, "one"), (4, "four")]);
+
+    map.clear_history();
+    assert_eq!(map.undo(1), 0);
+    assert_eq!(map.get(&4), Some(&"four"));
+}
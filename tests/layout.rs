@@ -0,0 +1,44 @@
+use travl::map::TravlMap;
+
+/// Returns a map whose values are ten times their keys
+fn map(len: u32) -> TravlMap<'static, u32, u32> {
+    let mut map = TravlMap::new();
+    for key in (0..len).rev() {
+        map.insert(key, key * 10);
+    }
+    map
+}
+
+#[test]
+fn layouts_pack_entries_in_breadth_first_order() {
+    let layout = map(7).to_bfs_layout();
+
+    let keys: Vec<u32> = layout.entries().iter().map(|(key, _)| *key).collect();
+    assert_eq!(keys, [3, 1, 5, 0, 2, 4, 6]);
+    assert_eq!((layout.left_child(1), layout.right_child(1)), (Some(3), Some(4)));
+    assert_eq!((layout.left_child(3), layout.right_child(3)), (None, None));
+}
+
+#[test]
+fn layouts_are_searched_and_turned_back_into_maps() {
+    let map = map(100);
+    let layout = map.to_bfs_layout();
+    assert_eq!(layout.config(), map.config());
+
+    // Every entry sits between the entries of its left and right subtrees
+    for index in 0..layout.len() {
+        let value = layout.entries()[index].1;
+        assert!(layout.left_child(index).is_none_or(|child| layout.entries()[child].1 < value));
+        assert!(layout.right_child(index).is_none_or(|child| layout.entries()[child].1 > value));
+    }
+
+    for key in 0..100 {
+        assert_eq!(layout.binary_search_by(|_, value| value.cmp(&(key * 10))), Some(&(key, key * 10)));
+    }
+    assert_eq!(layout.binary_search_by(|_, value| value.cmp(&15)), None);
+
+    let mut rebuilt = layout.clone().rebuild_default();
+    assert_eq!(rebuilt.validate(), []);
+    assert_eq!(layout.into_sorted_vec(), map.to_sorted_vec());
+    assert_eq!(rebuilt, map);
+}
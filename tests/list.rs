@@ -0,0 +1,83 @@
+use travl::list::TravlList;
+
+/// Deterministic pseudo-random sequence, so that failures can be reproduced
+fn pseudo_random(seed: u64) -> impl Iterator<Item = usize> {
+    std::iter::successors(Some(seed), |state| {
+        Some(state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407))
+    })
+    .map(|state| usize::try_from(state >> 33).unwrap())
+}
+
+/// Returns the greatest height of an AVL tree of the given length
+fn avl_height_bound(len: usize) -> u64 {
+    u64::from((u32::try_from(len).unwrap() + 2).ilog2()) * 3 / 2 + 1
+}
+
+#[test]
+fn random_edits_match_a_vec_and_keep_the_tree_balanced() {
+    let mut list = TravlList::new();
+    let mut model = Vec::new();
+    let mut random = pseudo_random(3);
+
+    for step in 0..10_000 {
+        if random.next().unwrap().is_multiple_of(3) && !model.is_empty() {
+            let index = random.next().unwrap() % model.len();
+            assert_eq!(list.remove_at(index), model.remove(index));
+        } else {
+            let index = random.next().unwrap() % (model.len() + 1);
+            list.insert_at(index, step);
+            model.insert(index, step);
+        }
+    }
+
+    assert_eq!(list.len(), model.len());
+    assert!(list.iter().eq(model.iter()));
+    assert!(list.height() <= avl_height_bound(list.len()));
+
+    for (index, value) in model.iter().enumerate().step_by(17) {
+        assert_eq!(list.get(index), Some(value));
+    }
+    assert_eq!(list.get(model.len()), None);
+}
+
+#[test]
+fn splits_and_concatenations_preserve_the_order() {
+    let mut random = pseudo_random(9);
+
+    for _ in 0..200 {
+        let first_len = random.next().unwrap() % 300;
+        let second_len = random.next().unwrap() % 300;
+        let first: TravlList<usize> = (0..first_len).collect();
+        let second: TravlList<usize> = (first_len..first_len + second_len).collect();
+
+        let list = first.concat(second);
+        assert!(list.iter().copied().eq(0..first_len + second_len));
+        assert!(list.height() <= avl_height_bound(list.len()));
+
+        let index = random.next().unwrap() % (list.len() + 1);
+        let (before, after) = list.split_at(index);
+        assert!(before.iter().copied().eq(0..index));
+        assert!(after.iter().copied().eq(index..first_len + second_len));
+        assert!(before.height() <= avl_height_bound(before.len()));
+        assert!(after.height() <= avl_height_bound(after.len()));
+    }
+}
+
+#[test]
+fn elements_can_be_pushed_and_edited_in_place() {
+    let mut list = TravlList::new();
+    list.push_back('b');
+    list.push_front('a');
+    list.push_back('c');
+
+    *list.get_mut(1).unwrap() = 'B';
+
+    assert_eq!(format!("{list:?}"), "['a', 'B', 'c']");
+}
+
+#[test]
+#[should_panic(expected = "removal index (is 3) should be < len (is 3)")]
+fn removals_out_of_bounds_are_rejected() {
+    let mut list: TravlList<u8> = (0..3).collect();
+    list.remove_at(3);
+}
@@ -0,0 +1,70 @@
+use travl::lru::TravlLru;
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let mut cache = TravlLru::new(3);
+    assert_eq!(cache.push(1, "one"), None);
+    assert_eq!(cache.push(2, "two"), None);
+    assert_eq!(cache.push(3, "three"), None);
+
+    assert_eq!(cache.get(&1), Some(&"one"));
+    assert_eq!(cache.peek(&2), Some(&"two"));
+    assert_eq!(cache.push(4, "four"), Some((2, "two")));
+    assert_eq!(cache.insert(3, "tres"), Some("three"));
+    assert_eq!(cache.push(5, "five"), Some((1, "one")));
+
+    assert_eq!(cache.len(), 3);
+    assert!(cache.iter().eq([(&3, &"tres"), (&4, &"four"), (&5, &"five")]));
+}
+
+#[test]
+fn cached_keys_can_be_queried_by_range() {
+    let mut cache = TravlLru::new(100);
+    for key in 0..200_u32 {
+        cache.insert(key, key * 2);
+    }
+
+    assert!(cache.range(100..104).eq([(&100, &200), (&101, &202), (&102, &204), (&103, &206)]));
+    assert_eq!(cache.range(..100).count(), 0);
+    assert_eq!(cache.peek_lru(), Some((&100, &200)));
+
+    assert_eq!(cache.remove(&100), Some(200));
+    assert_eq!(cache.pop_lru(), Some((101, 202)));
+    assert_eq!(cache.len(), 98);
+}
+
+#[test]
+#[should_panic(expected = "capacity must be non-zero")]
+fn zero_capacity_is_rejected() {
+    let _ = TravlLru::<u32, u32>::new(0);
+}
+
+#[test]
+fn inserting_cached_keys_evicts_nothing() {
+    let mut cache = TravlLru::new(2);
+    cache.insert(1, 10);
+    cache.insert(2, 20);
+
+    assert_eq!(cache.push(1, 11), None);
+    assert_eq!(cache.insert(3, 30), None);
+    assert_eq!(cache.insert(1, 12), Some(11));
+
+    // Replacing the value of 1 marked it as used, so the insertion of 3 evicted 2
+    assert!(cache.iter().eq([(&1, &12), (&3, &30)]));
+    assert_eq!(cache.peek_lru(), Some((&3, &30)));
+}
+
+#[test]
+fn used_keys_are_marked_without_changing_clones() {
+    let mut cache = TravlLru::new(3);
+    for key in 0..3 {
+        cache.insert(key, key);
+    }
+    let snapshot = cache.clone();
+
+    assert_eq!(cache.get(&0), Some(&0));
+    assert_eq!(cache.push(3, 3), Some((1, 1)));
+    assert_eq!(cache.peek_lru(), Some((&2, &2)));
+    assert_eq!(snapshot.peek_lru(), Some((&0, &0)));
+    assert!(snapshot.iter().eq([(&0, &0), (&1, &1), (&2, &2)]));
+}
@@ -0,0 +1,583 @@
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use travl::map::{Balancing, DiffItem, EqualPropPolicy, SearchType, TiePolicy, TravlMap, WEIGHT_RATIO};
+
+/// Deterministic pseudo-random sequence, so that failures can be reproduced
+fn pseudo_random(seed: u64) -> impl Iterator<Item = u64> {
+    std::iter::successors(Some(seed), |state| {
+        Some(state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407))
+    })
+    .map(|state| state >> 33)
+}
+
+/// Balancing strategies every write test runs with
+const STRATEGIES: [Balancing; 5] =
+    [Balancing::Strict, Balancing::Relaxed(2), Balancing::Scapegoat, Balancing::Weight, Balancing::Rank];
+
+#[test]
+fn insertions_and_removals_keep_the_tree_valid() {
+    let configs = STRATEGIES.into_iter().flat_map(|balancing| [0, 1, 3].map(|factor| (balancing, factor)));
+    for (balancing, imbalance_factor) in configs {
+        let mut map = TravlMap::<u64, u64>::builder()
+            .imbalance_factor(imbalance_factor)
+            .balancing(balancing)
+            .build();
+        let mut expected = BTreeMap::new();
+
+        for (step, random) in pseudo_random(imbalance_factor).take(2_000).enumerate() {
+            let key = random % 300;
+            if step % 3 == 2 {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, random % 50), expected.insert(key, random % 50));
+            }
+
+            assert_eq!(map.validate(), [], "{balancing:?}, step {step}");
+        }
+
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().map(|(_, value)| *value).eq({
+            let mut values: Vec<u64> = expected.values().copied().collect();
+            values.sort_unstable();
+            values
+        }));
+    }
+}
+
+#[test]
+fn entries_with_equal_properties_keep_their_insertion_order() {
+    let mut map = TravlMap::<u32, (u32, u32), u32>::with_prop_getter(|value: &(u32, u32)| &value.0);
+
+    for key in 0..100 {
+        map.insert(key, (key % 3, key));
+    }
+
+    let values: Vec<(u32, u32)> = map.iter().map(|(_, value)| *value).collect();
+    let mut expected = values.clone();
+    expected.sort_by_key(|value| value.0);
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(values, expected);
+    assert!(values.windows(2).all(|pair| pair[0].0 < pair[1].0 || pair[0].1 < pair[1].1));
+}
+
+#[test]
+fn entries_with_equal_properties_follow_the_tie_policy() {
+    let mut map = TravlMap::<u32, (u32, u32)>::builder()
+        .tie_policy(TiePolicy::Before)
+        .prop(|value: &(u32, u32)| &value.0)
+        .build();
+
+    for key in 0..100 {
+        map.insert(key, (key % 3, key));
+    }
+
+    let values: Vec<(u32, u32)> = map.iter().map(|(_, value)| *value).collect();
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.tie_policy(), TiePolicy::Before);
+    assert!(values.windows(2).all(|pair| pair[0].0 < pair[1].0 || pair[0].1 > pair[1].1));
+}
+
+#[test]
+fn sequential_insertions_keep_the_tree_logarithmic() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in 0..1_023 {
+        map.insert(key, key);
+    }
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.stats().height(), map.stats().minimum_height());
+}
+
+#[test]
+fn relaxed_balancing_lets_trees_grow_higher() {
+    let mut strict = TravlMap::<u32, u32>::new();
+    let mut relaxed = TravlMap::<u32, u32>::builder().balancing(Balancing::Relaxed(2)).build();
+    for key in 0..1_023 {
+        strict.insert(key, key);
+        relaxed.insert(key, key);
+    }
+
+    assert_eq!(relaxed.validate(), []);
+    assert_eq!(relaxed.config().effective_imbalance_factor(), 2);
+    assert!(relaxed.stats().height() > strict.stats().height());
+
+    // Rebuilding the tree brings it back to minimal height
+    relaxed.compact();
+    assert_eq!(relaxed.stats().height(), relaxed.stats().minimum_height());
+    assert!(relaxed.iter().eq(strict.iter()));
+}
+
+/// Returns a map of records ordered by their score, the key being the initial score
+fn scores(len: u32) -> TravlMap<'static, u32, (u32, u32), u32> {
+    let mut map = TravlMap::with_prop_getter(|record: &(u32, u32)| &record.0);
+    for key in 0..len {
+        map.insert(key, (key * 10, key));
+    }
+    map
+}
+
+#[test]
+fn applied_closures_keeping_the_order_leave_nodes_in_place() {
+    let mut map = scores(50);
+    let root = *map.root_key().unwrap();
+
+    assert_eq!(map.apply(&root, |record| record.0 += 5), Some(false));
+    assert_eq!(map.apply(&20, |record| record.1 = 0), Some(false));
+
+    assert_eq!(map.root_key(), Some(&root));
+    assert_eq!(map.get(&root).unwrap().value().0, root * 10 + 5);
+    assert_eq!(map.validate(), []);
+    assert!(!map.is_poisoned());
+}
+
+#[test]
+fn applied_closures_changing_the_order_move_nodes() {
+    let mut map = scores(50);
+
+    assert_eq!(map.apply(&3, |record| record.0 = 1_000), Some(true));
+    assert_eq!(map.apply(&40, |record| record.0 = 0), Some(true));
+    // Moved nodes come after the nodes whose properties are equal to their own
+    assert_eq!(map.apply(&10, |record| record.0 = 200), Some(true));
+
+    assert_eq!(map.validate(), []);
+    assert!(!map.is_poisoned());
+    assert_eq!(map.len(), 50);
+
+    let keys: Vec<u32> = map.iter().map(|(key, _)| *key).collect();
+    assert_eq!(keys.first(), Some(&0));
+    assert_eq!(keys[1], 40);
+    assert_eq!(keys.last(), Some(&3));
+    let position = keys.iter().position(|key| *key == 10).unwrap();
+    assert_eq!(keys[position - 1], 20);
+}
+
+#[test]
+fn inserting_existing_keys_replaces_and_moves_their_values() {
+    let mut map = scores(20);
+
+    assert_eq!(map.insert(5, (500, 5)), Some((50, 5)));
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.iter().last().map(|(key, _)| *key), Some(5));
+}
+
+#[test]
+fn found_nodes_match_the_search_type() {
+    // Values 0, 10, 20, ... 190, with 50 twice
+    let mut map = scores(20);
+    map.insert(100, (50, 100));
+
+    let find = |prop: u32, search_type| map.find(&prop, search_type).map(|node| *node.key());
+
+    assert_eq!(find(50, SearchType::Equality), Some(5));
+    assert_eq!(find(55, SearchType::Equality), None);
+    assert_eq!(find(55, SearchType::NearestToBottom), Some(100));
+    assert_eq!(find(50, SearchType::NearestToBottom), Some(100));
+    assert_eq!(find(55, SearchType::NearestToTop), Some(6));
+    assert_eq!(find(50, SearchType::NearestToTop), Some(5));
+    assert_eq!(find(0, SearchType::NearestToBottom), Some(0));
+    assert_eq!(find(200, SearchType::NearestToTop), None);
+    assert_eq!(TravlMap::<u32, u32>::new().find(&0, SearchType::Equality).map(|node| *node.key()), None);
+}
+
+#[test]
+#[allow(deprecated)]
+fn nearest_nodes_round_up_unless_nothing_is_above() {
+    // Values 0, 10, 20, ... 190
+    let map = scores(20);
+    let find = |prop: u32| map.find(&prop, SearchType::Nearest).map(|node| *node.key());
+
+    assert_eq!(find(50), Some(5));
+    assert_eq!(find(51), Some(6));
+    assert_eq!(find(200), Some(19));
+    assert_eq!(TravlMap::<u32, u32>::new().find(&0, SearchType::Nearest).map(|node| *node.key()), None);
+}
+
+#[test]
+fn replaced_functions_reorder_the_tree() {
+    let mut map = scores(30);
+
+    map.replace_ordering_fn(Box::new(|a: &u32, b: &u32| b.cmp(a)));
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq((0..30).rev()));
+
+    map.replace_prop_fn(Box::new(|record: &(u32, u32)| &record.1));
+    map.replace_ordering_fn(Box::new(|a: &u32, b: &u32| (a % 3).cmp(&(b % 3))));
+    assert_eq!(map.validate(), []);
+    assert!(!map.is_poisoned());
+
+    // Entries ordered the same keep their relative order, reversed by the first replacement
+    let keys: Vec<u32> = map.iter().map(|(key, _)| *key).collect();
+    assert_eq!(&keys[..4], [27, 24, 21, 18]);
+}
+
+#[test]
+fn scapegoat_balancing_rebuilds_the_smallest_imbalanced_subtree() {
+    let mut map = TravlMap::<u32, u32>::builder().balancing(Balancing::Scapegoat).build();
+    for key in 1..=3 {
+        map.insert(key, key);
+    }
+
+    // Node 1 was imbalanced by inserting 3 as the right child of 2, and its subtree was rebuilt around 2
+    assert_eq!(map.root_key(), Some(&2));
+    assert_eq!(map.get(&2).unwrap().left(), Some(&1));
+    assert_eq!(map.get(&2).unwrap().right(), Some(&3));
+
+    map.insert(4, 4);
+    map.insert(5, 5);
+
+    // Only the subtree of 3 was imbalanced, the root keeps its place
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.root_key(), Some(&2));
+    assert_eq!(map.get(&2).unwrap().right(), Some(&4));
+    assert_eq!(map.get(&4).unwrap().left(), Some(&3));
+    assert_eq!(map.get(&4).unwrap().right(), Some(&5));
+    assert_eq!(map.get(&4).unwrap().parent(), Some(&2));
+
+    for key in 6..1_000 {
+        map.insert(key, key);
+    }
+    for key in (0..1_000).step_by(3) {
+        map.remove(&key);
+    }
+
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq((1..1_000).filter(|key| key % 3 != 0)));
+}
+
+#[test]
+fn weight_balanced_maps_keep_subtree_sizes_within_the_ratio() {
+    let mut map = TravlMap::<u32, u32>::builder().balancing(Balancing::Weight).build();
+    for key in 0..1_000 {
+        map.insert(key, key);
+    }
+    for key in (0..1_000).step_by(2) {
+        map.remove(&key);
+    }
+
+    assert_eq!(map.validate(), []);
+
+    let root = map.root().unwrap();
+    let [left, right] = [root.left(), root.right()].map(|child| map.get(child.unwrap()).unwrap().size());
+    assert_eq!(root.size(), 500);
+    assert_eq!(left + right + 1, 500);
+
+    let [left_weight, right_weight] = [left + 1, right + 1];
+    assert!(left_weight * WEIGHT_RATIO >= right_weight && right_weight * WEIGHT_RATIO >= left_weight);
+}
+
+#[test]
+fn rank_balanced_maps_stay_logarithmic_through_removals() {
+    let mut map = TravlMap::<u32, u32>::builder().balancing(Balancing::Rank).build();
+    for key in 0..1_024 {
+        map.insert(key, key);
+    }
+
+    // Insertions alone keep a rank-balanced tree as balanced as an AVL tree
+    assert_eq!(map.root().unwrap().height(), 10);
+
+    for key in (0..1_024).filter(|key| key % 8 != 0) {
+        map.remove(&key);
+        assert_eq!(map.validate(), [], "{key}");
+    }
+
+    // Removals never rebalance the tree further than two rotations, but heights stay below twice the AVL bound
+    assert_eq!(map.len(), 128);
+    assert!(map.root().unwrap().height() <= 14);
+    assert!(map.iter().map(|(key, _)| *key).eq((0..1_024).step_by(8)));
+}
+
+#[test]
+fn nodes_are_selected_and_ranked_by_their_position() {
+    for balancing in STRATEGIES {
+        let mut map = TravlMap::<u64, u64>::builder().balancing(balancing).build();
+        for (step, random) in pseudo_random(7).take(600).enumerate() {
+            if step % 4 == 3 {
+                map.remove(&(random % 400));
+            } else {
+                map.insert(random % 400, random % 1_000);
+            }
+        }
+
+        for (position, (key, _)) in map.iter().enumerate() {
+            assert_eq!(map.select(position).unwrap().key(), key, "{balancing:?}");
+            assert_eq!(map.rank(key), Some(position), "{balancing:?}");
+        }
+        assert!(map.select(map.len()).is_none());
+        assert_eq!(map.rank(&400), None);
+    }
+}
+
+#[test]
+fn entries_are_iterated_in_chunks() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in 0..10 {
+        map.insert(key, key * 10);
+    }
+
+    let chunks: Vec<Vec<u32>> =
+        map.iter_chunks(4).map(|chunk| chunk.into_iter().map(|(key, _)| *key).collect()).collect();
+    assert_eq!(chunks, [vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]]);
+    assert_eq!(map.iter_chunks(10).count(), 1);
+    assert!(map.iter_chunks(1).flatten().eq(map.iter()));
+    assert_eq!(TravlMap::<u32, u32>::new().iter_chunks(3).count(), 0);
+}
+
+#[test]
+fn adjacent_entries_are_iterated_in_pairs_and_windows() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in [3, 1, 4, 2, 5] {
+        map.insert(key, key * 10);
+    }
+
+    let pairs: Vec<(u32, u32)> = map.iter_pairs().map(|((previous, _), (next, _))| (*previous, *next)).collect();
+    assert_eq!(pairs, [(1, 2), (2, 3), (3, 4), (4, 5)]);
+
+    let windows: Vec<Vec<u32>> =
+        map.iter_windows(3).map(|window| window.iter().map(|(_, value)| **value).collect()).collect();
+    assert_eq!(windows, [vec![10, 20, 30], vec![20, 30, 40], vec![30, 40, 50]]);
+    assert_eq!(map.iter_windows(5).count(), 1);
+    assert_eq!(map.iter_windows(6).count(), 0);
+
+    for key in 2..=5 {
+        map.remove(&key);
+    }
+    assert_eq!(map.iter_pairs().count(), 0);
+}
+
+#[test]
+fn transformed_values_report_whether_the_tree_is_still_ordered() {
+    let mut map = scores(50);
+
+    let mut visited = Vec::new();
+    assert!(map.transform_values(|key, record| {
+        visited.push(*key);
+        record.0 *= 2;
+    }));
+    assert_eq!(visited.len(), 50);
+    assert_eq!(visited.last(), map.root_key());
+    assert!(map.iter().all(|(key, record)| record.0 == key * 20));
+
+    assert!(!map.transform_values(|key, record| record.0 = 1_000 - key));
+    assert!(map.iter().all(|(key, record)| record.0 == 1_000 - key));
+}
+
+/// Returns a map holding the given entries, ordered by their values
+fn map_of(entries: &[(u32, u32)]) -> TravlMap<'static, u32, u32> {
+    let mut map = TravlMap::new();
+    for &(key, value) in entries {
+        map.insert(key, value);
+    }
+    map
+}
+
+#[test]
+fn merged_maps_yield_their_entries_in_order() {
+    let mut first = map_of(&[(1, 10), (2, 30)]);
+    let (second, third) = (map_of(&[(10, 20), (11, 30)]), map_of(&[(20, 30), (21, 40)]));
+    let mut merge = |policy| -> Vec<(usize, u32, u32)> {
+        first.merge_iter(&[&second, &third], policy).map(|(source, key, value)| (source, *key, *value)).collect()
+    };
+
+    assert_eq!(merge(EqualPropPolicy::KeepAll), [
+        (0, 1, 10),
+        (1, 10, 20),
+        (0, 2, 30),
+        (1, 11, 30),
+        (2, 20, 30),
+        (2, 21, 40)
+    ]);
+    assert_eq!(merge(EqualPropPolicy::KeepFirst), [(0, 1, 10), (1, 10, 20), (0, 2, 30), (2, 21, 40)]);
+    assert_eq!(merge(EqualPropPolicy::KeepLast), [(0, 1, 10), (1, 10, 20), (2, 20, 30), (2, 21, 40)]);
+}
+
+#[test]
+fn diffs_list_added_removed_and_changed_entries_in_order() {
+    let entries = [(1, 10), (2, 20), (3, 30), (4, 40)];
+    let mut old = map_of(&entries);
+    let new = map_of(&[(1, 10), (3, 35), (4, 5), (5, 50)]);
+
+    // Differences are sorted by the properties of the old values, or of the new ones for added entries
+    assert_eq!(old.diff(&new).collect::<Vec<_>>(), [
+        DiffItem::Removed(&2, &20),
+        DiffItem::Changed(&3, &30, &35),
+        DiffItem::Changed(&4, &40, &5),
+        DiffItem::Added(&5, &50)
+    ]);
+    assert_eq!(old.diff(&map_of(&entries)).count(), 0);
+}
+
+#[test]
+fn keys_are_iterated_along_with_their_properties() {
+    let mut map = scores(5);
+    assert_eq!(map.apply(&1, |record| record.0 = 100), Some(true));
+
+    let props: Vec<(u32, u32)> = map.props().map(|(key, prop)| (*key, *prop)).collect();
+    assert_eq!(props, [(0, 0), (2, 20), (3, 30), (4, 40), (1, 100)]);
+}
+
+#[test]
+fn std_maps_are_converted_into_balanced_trees() {
+    let entries: BTreeMap<u32, u32> = (0..100).map(|key| (key, key * 7 % 100)).collect();
+    let check = |mut map: TravlMap<u32, u32>| {
+        assert_eq!(map.validate(), []);
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.stats().height(), map.stats().minimum_height());
+        assert!(map.iter().map(|(_, value)| *value).eq(0..100));
+        assert_eq!(map.into_btree_map(), entries);
+    };
+
+    check(TravlMap::from(entries.clone()));
+    #[cfg(feature = "std")]
+    check(TravlMap::from(entries.clone().into_iter().collect::<std::collections::HashMap<_, _>>()));
+}
+
+#[test]
+fn snapshots_rebuild_maps_with_new_functions() {
+    let mut map = TravlMap::<u32, u32>::builder().imbalance_factor(2).tie_policy(TiePolicy::Before).build();
+    for key in 0..50 {
+        map.insert(key, key % 10);
+    }
+
+    let snapshot = map.snapshot();
+    assert_eq!(snapshot.config(), map.config());
+    assert_eq!(snapshot.entries(), map.to_sorted_vec());
+
+    let mut rebuilt = snapshot.clone().rebuild_default();
+    assert_eq!(rebuilt.validate(), []);
+    assert_eq!(rebuilt.config(), map.config());
+    assert_eq!(rebuilt.to_sorted_vec(), map.to_sorted_vec());
+
+    // Entries are sorted again using the new functions, entries with equal properties keeping their order
+    let mut reversed = snapshot.rebuild(Box::new(|value: &u32| value), Box::new(|a: &u32, b: &u32| b.cmp(a)));
+    assert_eq!(reversed.validate(), []);
+    assert!(reversed.iter().map(|(key, _)| *key).take(6).eq([49, 39, 29, 19, 9, 48]));
+    assert_eq!(reversed.iter().last(), Some((&0, &0)));
+}
+
+#[test]
+fn maps_are_displayed_as_their_entries_in_order() {
+    let map = map_of(&[(1, 30), (2, 10), (3, 20)]);
+
+    assert_eq!(map.to_string(), "{2: 10, 3: 20, 1: 30}");
+    assert_eq!(TravlMap::<u32, u32>::new().to_string(), "{}");
+}
+
+/// Hashes the given value using the default hasher of the standard library
+fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn maps_with_equal_entries_are_equal_regardless_of_their_shape() {
+    let mut sequential = TravlMap::<u32, u32>::new();
+    for key in 0..100 {
+        sequential.insert(key, key);
+    }
+    let mut shuffled = TravlMap::<u32, u32>::builder().imbalance_factor(3).build();
+    for key in (0..100).map(|key| key * 37 % 100) {
+        shuffled.insert(key, key);
+    }
+    assert_ne!(sequential.root_key(), shuffled.root_key());
+
+    assert_eq!(sequential, shuffled);
+    assert_eq!(hash_of(&sequential), hash_of(&shuffled));
+
+    shuffled.insert(50, 500);
+    assert_ne!(sequential, shuffled);
+    assert_ne!(hash_of(&sequential), hash_of(&shuffled));
+    assert_eq!(HashSet::from([sequential, shuffled, TravlMap::new()]).len(), 3);
+}
+
+#[test]
+fn batched_insertions_are_applied_when_the_batch_ends() {
+    let mut map = scores(20);
+
+    let mut batch = map.batch();
+    batch.insert(100, (55, 100));
+    batch.extend((20..60).map(|key| (key, (key * 10, key))));
+    batch.insert(5, (1_000, 5));
+    batch.insert(100, (45, 100));
+    assert_eq!(batch.len(), 43);
+    batch.finish();
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.len(), 61);
+    assert_eq!(map.stats().height(), map.stats().minimum_height());
+    // Buffered entries replace the values of existing keys, only the last value of a key being kept
+    assert_eq!(map.iter().last(), Some((&5, &(1_000, 5))));
+    let keys: Vec<u32> = map.iter().map(|(key, _)| *key).skip(4).take(3).collect();
+    assert_eq!(keys, [4, 100, 6]);
+
+    // Dropped batches end as well, entries with equal properties coming after the existing ones
+    map.batch().insert(200, (10, 200));
+    assert_eq!(map.iter().nth(2), Some((&200, &(10, 200))));
+}
+
+#[test]
+fn storage_is_reused_under_churn_and_released_on_demand() {
+    let mut map = TravlMap::<u32, u32>::new();
+    map.reserve(1_000);
+    let capacity = map.capacity();
+    assert!(capacity >= 1_000);
+
+    for key in 0..1_000 {
+        map.insert(key, key);
+    }
+    for step in 0..10_000 {
+        map.remove(&(step % 1_000));
+        map.insert(step % 1_000, step);
+    }
+    assert_eq!(map.capacity(), capacity);
+
+    for key in 10..1_000 {
+        map.remove(&key);
+    }
+    map.shrink_to_fit();
+    assert!(map.capacity() < capacity);
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq(0..10));
+}
+
+#[test]
+fn compacted_maps_have_the_minimum_height() {
+    let mut map = TravlMap::<u32, u32>::builder().imbalance_factor(3).build();
+    for key in 0..1_000 {
+        map.insert(key, key);
+    }
+    for key in (0..900).filter(|key| key % 10 != 0) {
+        map.remove(&key);
+    }
+    let entries = map.to_sorted_vec();
+    assert!(map.stats().height() > map.stats().minimum_height());
+
+    map.compact();
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.stats().height(), map.stats().minimum_height());
+    assert_eq!(map.to_sorted_vec(), entries);
+    assert_eq!(map.config().imbalance_factor(), 3);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn rebalancing_stops_at_the_first_node_whose_height_is_unchanged() {
+    // 40 is the root, 20 its left child, 10 and 30 the children of 20, and 35 the right child of 30
+    let mut map = TravlMap::new();
+    for key in [40, 20, 60, 10, 30, 50, 70, 35] {
+        map.insert(key, key);
+    }
+    let before = map.metrics().height_recomputations();
+
+    // Inserting 5 below 10 grows the subtree of 10, but not the one of 20
+    map.insert(5, 5);
+
+    // Only 10 and 20 have their height computed, the root only has its size fixed
+    assert_eq!(map.metrics().height_recomputations() - before, 2);
+    assert_eq!(map.root().map(|root| (root.height(), root.size())), Some((3, 9)));
+    assert!(map.validate().is_empty());
+}
@@ -0,0 +1,57 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use travl::core::TravlNode;
+use travl::storage::{NodeStorage, OrderedNodeMap, SlabStorage};
+
+/// Number of nodes stored in each storage
+const NODE_COUNT: usize = 10_000;
+
+/// Allocator keeping track of the number of bytes allocated by the test binary
+struct MeasuringAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for MeasuringAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: MeasuringAllocator = MeasuringAllocator;
+
+/// Returns the storage filled with [`NODE_COUNT`] nodes, along with the number of bytes it allocated
+fn filled<S>() -> (S, usize)
+where
+    S: NodeStorage<usize, usize>
+{
+    let before = ALLOCATED.load(Ordering::Relaxed);
+
+    let mut storage = S::default();
+    for key in 0..NODE_COUNT {
+        storage.insert(key, TravlNode::new(key, key));
+    }
+    storage.shrink_to_fit();
+
+    (storage, ALLOCATED.load(Ordering::Relaxed) - before)
+}
+
+#[test]
+fn slab_storages_are_denser_than_ordered_node_maps() {
+    let (slab, slab_bytes) = filled::<SlabStorage<_, _>>();
+    let (ordered, ordered_bytes) = filled::<OrderedNodeMap<_, _>>();
+
+    assert_eq!((slab.len(), ordered.len()), (NODE_COUNT, NODE_COUNT));
+    assert!(slab_bytes < ordered_bytes, "slab: {slab_bytes} bytes, ordered: {ordered_bytes} bytes");
+
+    // Beyond its slots, the slab only needs the position of each node to find it
+    let slots = size_of::<Option<TravlNode<usize, usize>>>() * NODE_COUNT;
+    assert_eq!(slab_bytes, slots + size_of::<usize>() * NODE_COUNT);
+}
@@ -0,0 +1,38 @@
+use travl::multiset::TravlMultiSet;
+
+#[test]
+fn counts_follow_insertions_and_removals() {
+    let mut words: TravlMultiSet<String> = "the cat saw the dog and the bird".split(' ').map(String::from).collect();
+
+    assert_eq!(words.len(), 8);
+    assert_eq!(words.distinct_len(), 6);
+    assert_eq!(words.count("the"), 3);
+    assert_eq!(words.count("fish"), 0);
+
+    assert_eq!(words.insert_many("cat".to_owned(), 2), 3);
+    assert!(words.remove("the"));
+    assert!(!words.remove("fish"));
+    assert_eq!(words.remove_many("cat", 5), 3);
+    assert_eq!(words.remove_all("the"), 2);
+
+    assert!(!words.contains("cat"));
+    assert_eq!(words.len(), 4);
+    let remaining: Vec<(&str, usize)> = words.counts().map(|(word, count)| (word.as_str(), count)).collect();
+    assert_eq!(remaining, [("and", 1), ("bird", 1), ("dog", 1), ("saw", 1)]);
+}
+
+#[test]
+fn iterators_expand_or_count_duplicates() {
+    let numbers: TravlMultiSet<u32> = [3, 1, 3, 2, 3, 1].into_iter().collect();
+
+    let expanded: Vec<u32> = numbers.iter().copied().collect();
+    assert_eq!(expanded, [1, 1, 2, 3, 3, 3]);
+    assert_eq!(numbers.iter().len(), 6);
+
+    let counted: Vec<(u32, usize)> = numbers.counts().map(|(number, count)| (*number, count)).collect();
+    assert_eq!(counted, [(1, 2), (2, 1), (3, 3)]);
+
+    let mut by_frequency = counted;
+    by_frequency.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    assert_eq!(by_frequency.first(), Some(&(3, 3)));
+}
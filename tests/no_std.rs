@@ -0,0 +1,21 @@
+#![cfg(not(any(feature = "std", feature = "hashbrown")))]
+
+use travl::map::{NodeMap, TravlMap};
+use travl::storage::SlabStorage;
+
+#[test]
+fn maps_store_their_nodes_in_slabs_without_std() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in (0..500).map(|key| key * 7 % 500) {
+        map.insert(key, 500 - key);
+    }
+    for key in (0..500).step_by(2) {
+        map.remove(&key);
+    }
+
+    let nodes: &SlabStorage<u32, u32> = map.nodes();
+    let _: &NodeMap<u32, u32> = nodes;
+    assert_eq!(map.len(), 250);
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq((1..500).step_by(2).rev()));
+}
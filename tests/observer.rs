@@ -0,0 +1,115 @@
+use std::sync::{Arc, Mutex};
+
+use travl::core::AVLRotation;
+use travl::observer::TreeObserver;
+#[cfg(feature = "std")]
+use travl::observer::{Change, ChangeTracker};
+use travl::persistent::TravlMapPersistent;
+
+/// Observer recording every event it is notified of
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<String>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.events.lock().unwrap())
+    }
+
+    fn record(&self, event: String) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl TreeObserver<u32> for Recorder {
+    fn on_insert(&self, key: &u32, replaced: bool) {
+        self.record(format!("insert {key} {replaced}"));
+    }
+
+    fn on_remove(&self, key: &u32) {
+        self.record(format!("remove {key}"));
+    }
+
+    fn on_rebalance(&self, key: &u32) {
+        self.record(format!("rebalance {key}"));
+    }
+
+    fn on_rotation(&self, key: &u32, rotation: AVLRotation) {
+        self.record(format!("rotation {key} {rotation:?}"));
+    }
+}
+
+/// Observer only counting insertions, relying on the default methods for the other events
+#[derive(Default)]
+struct InsertionCounter {
+    count: Mutex<usize>,
+}
+
+impl TreeObserver<u32> for InsertionCounter {
+    fn on_insert(&self, _key: &u32, _replaced: bool) {
+        *self.count.lock().unwrap() += 1;
+    }
+}
+
+#[test]
+fn observers_are_notified_of_structural_changes() {
+    let recorder = Arc::new(Recorder::default());
+    let map = TravlMapPersistent::new().with_observer(Arc::clone(&recorder));
+
+    let map = map.insert(1, ()).insert(2, ());
+    assert_eq!(recorder.take(), ["insert 1 false", "insert 2 false"]);
+
+    let map = map.insert(3, ());
+    assert_eq!(recorder.take(), ["insert 3 false", "rebalance 1", "rotation 2 Left"]);
+
+    let map = map.insert(3, ()).insert(0, ()).insert(1, ());
+    assert_eq!(recorder.take(), ["insert 3 true", "insert 0 false", "insert 1 true"]);
+
+    let (map, _) = map.remove(&3);
+    assert_eq!(recorder.take(), ["remove 3", "rebalance 2", "rotation 1 Right"]);
+
+    let map = map.insert(4, ()).insert(5, ()).insert(3, ());
+    assert_eq!(recorder.take(), [
+        "insert 4 false",
+        "insert 5 false",
+        "rebalance 2",
+        "rotation 4 Left",
+        "insert 3 false",
+        "rebalance 1",
+        "rotation 2 RightLeft",
+    ]);
+    assert!(map.iter().map(|(key, ())| *key).eq(0..=5));
+}
+
+#[test]
+fn observers_are_shared_by_derived_maps() {
+    let counter = Arc::new(InsertionCounter::default());
+    let map = TravlMapPersistent::new().with_observer(Arc::clone(&counter));
+
+    let first = map.insert(1, "one");
+    let second = first.clone().insert(2, "two");
+    let _ = first.insert(3, "three");
+    let _ = second.remove(&1);
+
+    assert_eq!(*counter.count.lock().unwrap(), 3);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn change_trackers_record_net_changes_since_they_were_last_taken() {
+    let tracker = Arc::new(ChangeTracker::new());
+    let mut map = TravlMapPersistent::new().insert(1, "one").insert(2, "two").with_observer(Arc::clone(&tracker));
+
+    map = map.insert(3, "three").insert(1, "uno").remove(&2).0;
+    assert_eq!(tracker.take_changes().into_iter().collect::<Vec<_>>(), [
+        (1, Change::Updated),
+        (2, Change::Removed),
+        (3, Change::Inserted),
+    ]);
+    assert!(tracker.take_changes().is_empty());
+
+    map = map.insert(4, "four").remove(&4).0.remove(&3).0.insert(3, "tres").insert(5, "five").insert(5, "cinco");
+    assert_eq!(tracker.take_changes().into_iter().collect::<Vec<_>>(), [(3, Change::Updated), (5, Change::Inserted)]);
+    assert_eq!(map.len(), 3);
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "rayon")]
+
+use rayon::prelude::*;
+use travl::core::TravlNode;
+use travl::map::TravlMap;
+use travl::storage::OrderedNodeMap;
+use travl::traversal::{ParNodeIter, par_visit, par_walk};
+
+/// Builds a complete tree whose keys are the integers of the given range into the given nodes,
+/// returning the key of its root
+fn complete_tree(nodes: &mut OrderedNodeMap<u64, ()>, start: u64, end: u64) -> Option<u64> {
+    if start >= end {
+        return None;
+    }
+
+    let middle = start + (end - start) / 2;
+    let mut node = TravlNode::new(middle, ());
+    let children = (complete_tree(nodes, start, middle), complete_tree(nodes, middle + 1, end));
+    for child in [children.0, children.1].into_iter().flatten() {
+        let _ = nodes.get_mut(&child).expect("child should be stored").link_parent(middle);
+    }
+    let _ = node.link_children(children);
+    nodes.insert(middle, node);
+
+    Some(middle)
+}
+
+#[test]
+fn parallel_node_iteration_visits_every_node_once() {
+    let node_count = 100_000;
+    let mut nodes = OrderedNodeMap::new();
+    let root_key = complete_tree(&mut nodes, 0, node_count);
+    let root = root_key.and_then(|key| nodes.get(&key));
+
+    let mut keys: Vec<u64> = ParNodeIter::new(&nodes, root).map(|node| *node.key()).collect();
+    keys.par_sort_unstable();
+
+    assert!(keys.into_iter().eq(0..node_count));
+    assert_eq!(ParNodeIter::new(&nodes, root).map(|node| *node.key()).sum::<u64>(), (0..node_count).sum::<u64>());
+}
+
+#[test]
+fn parallel_walks_and_visits_reach_every_node_of_a_map() {
+    let mut map = TravlMap::<u64, u64>::new();
+    for key in 0..10_000 {
+        map.insert(key, key * 2);
+    }
+
+    let visited = std::sync::atomic::AtomicU64::new(0);
+    par_walk(map.nodes(), map.root(), |node| {
+        visited.fetch_add(*node.value(), std::sync::atomic::Ordering::Relaxed);
+    });
+    assert_eq!(visited.into_inner(), (0..10_000).map(|key| key * 2).sum::<u64>());
+
+    let last_rank = par_visit(map.nodes(), map.root(), |node| map.rank(node.key()).unwrap(), usize::max);
+    assert_eq!(last_rank, Some(9_999));
+    assert_eq!(par_visit(TravlMap::<u64, u64>::new().nodes(), None, |_| 1, |a, b| a + b), None);
+}
+
+#[test]
+fn empty_maps_iterate_in_parallel() {
+    let map: TravlMap<'static, u32, u32> = TravlMap::new();
+
+    assert_eq!(map.par_iter().count(), 0);
+    assert_eq!(map.into_par_iter().count(), 0);
+}
+
+/// Returns the key of the `index`-th entry of a shuffled sequence of distinct keys
+fn scattered_key(index: u64) -> u64 {
+    index.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 16
+}
+
+#[test]
+fn parallel_builds_match_sequential_builds() {
+    for len in [0, 1, 2, 7, 20_000] {
+        let entries: Vec<(u64, u64)> = (0..len).map(|index| (index, scattered_key(index))).collect();
+
+        let mut map: TravlMap<'static, u64, u64> = entries.clone().into_par_iter().collect();
+        let sequential = TravlMap::from(entries.iter().copied().collect::<std::collections::HashMap<_, _>>());
+
+        assert!(map.validate().is_empty());
+        assert_eq!(map.len(), sequential.len());
+        assert_eq!(map.stats().height(), sequential.stats().height());
+        assert_eq!(map.render_json(), sequential.render_json());
+        assert!(map.iter().map(|(_, value)| *value).is_sorted());
+    }
+}
+
+#[test]
+fn parallel_builds_keep_the_last_entry_of_each_key() {
+    let entries = (0..10_000_u64).map(|index| (index % 100, index));
+
+    let mut map: TravlMap<'static, u64, u64> = entries.collect::<Vec<_>>().into_par_iter().collect();
+
+    assert!(map.validate().is_empty());
+    assert_eq!(map.len(), 100);
+    assert!(map.iter().map(|(key, value)| (*key, *value)).eq((9_900..10_000).map(|value| (value % 100, value))));
+}
+
+#[test]
+fn parallel_builds_use_custom_orderings() {
+    let entries: Vec<(u64, (u64, String))> =
+        (0..10_000).map(|index| (index, (scattered_key(index), index.to_string()))).collect();
+
+    let mut map = TravlMap::from_par_iter_with_prop_getter_and_ordering(
+        entries,
+        |(_, name): &(u64, String)| name,
+        |a: &String, b: &String| b.cmp(a),
+    );
+
+    assert!(map.validate().is_empty());
+    assert_eq!(map.len(), 10_000);
+    assert!(map.iter().map(|(_, (_, name))| name).is_sorted_by(|a, b| a >= b));
+}
@@ -0,0 +1,51 @@
+use travl::error::TravlError;
+use travl::partial::{IncomparablePolicy, PartialOrdMap};
+
+/// Returns the keys of a map, in order
+fn keys(map: &PartialOrdMap<f64, ()>) -> Vec<f64> {
+    map.iter().map(|(key, ())| *key).collect()
+}
+
+#[test]
+fn invalid_keys_are_sorted_first_or_last() {
+    for (policy, nan_index) in [(IncomparablePolicy::SortFirst, 0), (IncomparablePolicy::SortLast, 3)] {
+        let mut map = PartialOrdMap::new(policy);
+        for key in [2.0, f64::NAN, -1.0, 0.5, f64::NAN] {
+            map.insert(key, ()).unwrap();
+        }
+
+        let keys = keys(&map);
+        assert_eq!(keys.len(), 4);
+        assert!(keys[nan_index].is_nan());
+        assert_eq!(keys.iter().filter(|key| !key.is_nan()).copied().collect::<Vec<_>>(), [-1.0, 0.5, 2.0]);
+
+        assert_eq!(map.get(&f64::NAN), Ok(Some(&())));
+        assert_eq!(map.remove(&f64::NAN), Ok(Some(())));
+        assert_eq!(map.contains_key(&f64::NAN), Ok(false));
+    }
+}
+
+#[test]
+fn incomparable_keys_can_be_rejected() {
+    let mut map = PartialOrdMap::new(IncomparablePolicy::Reject);
+    map.insert(1.0, ()).unwrap();
+
+    assert_eq!(map.insert(f64::NAN, ()), Err(TravlError::Incomparable));
+    assert_eq!(map.get(&f64::NAN), Err(TravlError::Incomparable));
+    assert_eq!(map.remove(&f64::NAN), Err(TravlError::Incomparable));
+    assert_eq!(keys(&map), [1.0]);
+}
+
+#[test]
+fn incomparable_keys_can_follow_a_total_order() {
+    let mut map = PartialOrdMap::new(IncomparablePolicy::TotalOrder(f64::total_cmp));
+    for key in [f64::NAN, 1.0, -f64::NAN, f64::INFINITY] {
+        map.insert(key, ()).unwrap();
+    }
+
+    let keys = keys(&map);
+    assert_eq!(keys.len(), 4);
+    assert!(keys[0].is_nan() && keys[0].is_sign_negative());
+    assert_eq!(keys[1..3], [1.0, f64::INFINITY]);
+    assert!(keys[3].is_nan() && keys[3].is_sign_positive());
+}
@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+use std::num::ParseIntError;
+use std::panic::{self, AssertUnwindSafe};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+use travl::core::BalanceFactor;
+use travl::persistent::{Augmentation, Resolution, TravlMapPersistent};
+
+const OPERATION_COUNT: u64 = 20_000;
+
+/// Deterministic pseudo-random sequence, so that failures can be reproduced
+fn pseudo_random(seed: u64) -> impl Iterator<Item = u64> {
+    std::iter::successors(Some(seed), |state| {
+        Some(state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407))
+    })
+    .map(|state| state >> 33)
+}
+
+#[test]
+fn random_operations_match_a_btree_map_and_keep_the_tree_balanced() {
+    for imbalance_factor in 0..3 {
+        let mut map = TravlMapPersistent::with_imbalance_factor(imbalance_factor);
+        let mut model = BTreeMap::new();
+        let mut random = pseudo_random(imbalance_factor);
+
+        for step in 0..OPERATION_COUNT {
+            let key = random.next().unwrap() % 1_000;
+
+            if random.next().unwrap().is_multiple_of(3) {
+                let (next, removed) = map.remove(&key);
+                assert_eq!(removed, model.remove(&key));
+                map = next;
+            } else {
+                map = map.insert(key, step);
+                model.insert(key, step);
+            }
+        }
+
+        assert_eq!(map.len(), model.len());
+        assert!(map.iter().eq(model.iter()));
+
+        // AVL trees are at most about 1.44 times higher than perfectly balanced trees
+        let len = u32::try_from(map.len()).unwrap();
+        let avl_height_bound = u64::from((len + 2).ilog2()) * 3 / 2 + 1;
+        assert!(map.height() <= avl_height_bound * (imbalance_factor + 1));
+    }
+}
+
+#[test]
+fn previous_versions_are_left_untouched() {
+    let empty = TravlMapPersistent::new();
+    let one = empty.insert(1, "one");
+    let two = one.insert(2, "two");
+    let (without_one, removed) = two.remove(&1);
+    let replaced = two.insert(2, "deux");
+
+    assert!(empty.is_empty());
+    assert!(one.iter().eq([(&1, &"one")]));
+    assert!(two.iter().eq([(&1, &"one"), (&2, &"two")]));
+    assert_eq!(removed, Some("one"));
+    assert!(without_one.iter().eq([(&2, &"two")]));
+    assert!(replaced.iter().eq([(&1, &"one"), (&2, &"deux")]));
+}
+
+/// Value counting how many times values were cloned
+#[derive(Debug, PartialEq)]
+struct Counted(u64);
+
+static CLONES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        CLONES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Self(self.0)
+    }
+}
+
+#[test]
+fn snapshots_keep_their_entries_while_the_map_changes_in_place() {
+    let mut map = TravlMapPersistent::new();
+    let mut model = BTreeMap::new();
+    let mut snapshots = Vec::new();
+    let mut random = pseudo_random(11);
+
+    for step in 0..2_000 {
+        let key = random.next().unwrap() % 300;
+
+        if step % 3 == 2 {
+            assert_eq!(map.remove_in_place(&key), model.remove(&key));
+        } else {
+            assert_eq!(map.insert_in_place(key, step), model.insert(key, step));
+        }
+        if step % 250 == 0 {
+            snapshots.push((map.snapshot(), model.clone()));
+        }
+    }
+
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().eq(model.iter()));
+    for (snapshot, model) in &snapshots {
+        assert_eq!(snapshot.validate(), []);
+        assert!(snapshot.iter().eq(model.iter()));
+    }
+}
+
+#[test]
+fn nodes_are_only_copied_while_a_snapshot_shares_them() {
+    // Only this test clones `Counted` values, so the counter isn't shared with other tests
+    let mut map = TravlMapPersistent::new();
+    for key in 0..1_023 {
+        map.insert_in_place(key, Counted(key));
+    }
+    map.remove_in_place(&0);
+    assert_eq!(CLONES.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+    // The nodes on the path to the entry are shared with the snapshot, and copied once
+    let snapshot = map.snapshot();
+    assert_eq!(map.insert_in_place(1_000, Counted(0)), Some(Counted(1_000)));
+    let copies = CLONES.load(std::sync::atomic::Ordering::Relaxed);
+    assert!((1..=10).contains(&copies), "{copies}");
+
+    map.insert_in_place(1_000, Counted(1));
+    assert_eq!(CLONES.load(std::sync::atomic::Ordering::Relaxed), copies);
+    assert_eq!(snapshot.get(&1_000), Some(&Counted(1_000)));
+    assert_eq!(map.get(&1_000), Some(&Counted(1)));
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn metrics_count_the_rotations_of_sorted_insertions() {
+    use travl::core::AVLRotation;
+
+    let map = (0..64).fold(TravlMapPersistent::new(), |map, key| map.insert(key, ()));
+    let metrics = map.metrics();
+
+    // Ascending keys always lean to the right, so every rebalance is a single left rotation
+    assert!(metrics.rotations(AVLRotation::Left) > 0);
+    assert_eq!(metrics.total_rotations(), metrics.rotations(AVLRotation::Left));
+    assert_eq!(metrics.rebalances(), metrics.total_rotations());
+    assert!(metrics.comparisons() > 0);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn rebalancing_stops_at_the_first_node_whose_height_is_unchanged() {
+    // 40 is the root, 20 its left child, 10 and 30 the children of 20, and 35 the right child of 30
+    let keys = [40, 20, 60, 10, 30, 50, 70, 35];
+    let map = keys.into_iter().fold(TravlMapPersistent::new(), |map, key| map.insert(key, ()));
+    let before = map.metrics().height_recomputations();
+
+    // Inserting 5 below 10 grows the subtree of 10, but not the one of 20
+    let map = map.insert(5, ());
+
+    // Only 5, 10 and 20 have their height computed, the root is copied without being rebalanced
+    assert_eq!(map.metrics().height_recomputations() - before, 3);
+    assert_eq!(map.height(), 4);
+}
+
+/// Sum, minimum, maximum and bitwise OR of the values of a subtree
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Summary {
+    sum: u64,
+    min: u64,
+    max: u64,
+    bits: u64,
+}
+
+impl Augmentation<u64, u64> for Summary {
+    fn from_entry(_key: &u64, value: &u64) -> Self {
+        Self {
+            sum: *value,
+            min: *value,
+            max: *value,
+            bits: *value,
+        }
+    }
+
+    fn combine(&self, next: &Self) -> Self {
+        Self {
+            sum: self.sum + next.sum,
+            min: self.min.min(next.min),
+            max: self.max.max(next.max),
+            bits: self.bits | next.bits,
+        }
+    }
+}
+
+#[test]
+fn range_aggregates_match_folding_the_range() {
+    let mut map = TravlMapPersistent::<u64, u64, Summary>::new_augmented();
+    let mut random = pseudo_random(11);
+
+    for _ in 0..OPERATION_COUNT {
+        let key = random.next().unwrap() % 500;
+        if random.next().unwrap().is_multiple_of(3) {
+            map = map.remove(&key).0;
+        } else {
+            map = map.insert(key, random.next().unwrap() % 10_000);
+        }
+    }
+
+    for start in (0..520).step_by(17) {
+        for len in [0, 1, 10, 200, 600] {
+            let expected = map
+                .range(start..start + len)
+                .map(|(key, value)| Summary::from_entry(key, value))
+                .reduce(|first, second| first.combine(&second));
+
+            assert_eq!(map.range_aggregate(start..start + len), expected);
+        }
+    }
+
+    assert_eq!(map.range_aggregate(..).as_ref(), map.augmentation());
+}
+
+/// Keys of a subtree in order, to check that augmentations are combined in key order
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Concatenation(Vec<u64>);
+
+impl Augmentation<u64, ()> for Concatenation {
+    fn from_entry(key: &u64, _value: &()) -> Self {
+        Self(vec![*key])
+    }
+
+    fn combine(&self, next: &Self) -> Self {
+        Self([self.0.as_slice(), next.0.as_slice()].concat())
+    }
+}
+
+#[test]
+fn range_aggregates_are_combined_in_key_order() {
+    let map: TravlMapPersistent<u64, (), Concatenation> = (0..100).rev().map(|key| (key, ())).collect();
+
+    assert_eq!(map.range_aggregate(20..=40), Some(Concatenation((20..=40).collect())));
+    assert_eq!(map.range_aggregate(100..), None);
+}
+
+#[test]
+fn order_statistics_match_the_sorted_entries() {
+    let mut map = TravlMapPersistent::new();
+    let mut random = pseudo_random(5);
+
+    for step in 0..OPERATION_COUNT {
+        let key = random.next().unwrap() % 2_000;
+        if random.next().unwrap().is_multiple_of(3) {
+            map = map.remove(&key).0;
+        } else {
+            map = map.insert(key, step);
+        }
+    }
+
+    let sorted: Vec<(&u64, &u64)> = map.iter().collect();
+    for (rank, entry) in sorted.iter().enumerate() {
+        assert_eq!(map.select(rank), Some(*entry));
+        assert_eq!(map.rank(entry.0), rank);
+    }
+    assert_eq!(map.select(sorted.len()), None);
+    assert_eq!(map.rank(&u64::MAX), sorted.len());
+}
+
+#[test]
+fn quantiles_pick_the_lower_entry_between_two() {
+    let map: TravlMapPersistent<u32, ()> = (1..=10).map(|key| (key, ())).collect();
+
+    assert_eq!(map.quantile(0.0), Some((&1, &())));
+    assert_eq!(map.quantile(1.0), Some((&10, &())));
+    assert_eq!(map.median(), Some((&5, &())));
+    assert_eq!(map.percentile(90.0), Some((&9, &())));
+    assert_eq!(TravlMapPersistent::<u32, ()>::new().median(), None);
+}
+
+#[test]
+#[should_panic(expected = "quantile must be between 0 and 1")]
+fn quantiles_outside_of_the_unit_interval_are_rejected() {
+    let _ = TravlMapPersistent::<u32, ()>::new().quantile(1.5);
+}
+
+#[test]
+fn merging_resolves_keys_present_in_both_maps() {
+    let mine: TravlMapPersistent<&str, u32> = [("apples", 3), ("pears", 1), ("plums", 4)].into_iter().collect();
+    let theirs: TravlMapPersistent<&str, u32> = [("apples", 5), ("kiwis", 2), ("pears", 7)].into_iter().collect();
+
+    let mut resolved = Vec::new();
+    let merged = mine.merge_with(&theirs, |fruit, mine, theirs| {
+        resolved.push(*fruit);
+        match *fruit {
+            "apples" => Resolution::Combined(mine + theirs),
+            _ => Resolution::KeepTheirs,
+        }
+    });
+
+    assert_eq!(resolved, ["apples", "pears"]);
+    assert!(merged.iter().eq([(&"apples", &8), (&"kiwis", &2), (&"pears", &7), (&"plums", &4)]));
+    assert_eq!(mine.merge_with(&theirs, |_, _, _| Resolution::KeepMine).get("pears"), Some(&1));
+}
+
+#[test]
+fn three_way_merges_apply_both_sides_and_report_conflicts() {
+    let base: TravlMapPersistent<u32, &str> = [(1, "a"), (2, "b"), (3, "c"), (4, "d"), (5, "e")].into_iter().collect();
+
+    let left = base.insert(1, "A").remove(&2).0.insert(4, "D").insert(6, "f").insert(7, "left");
+    let right = base.insert(3, "C").remove(&2).0.insert(4, "delta").remove(&5).0.insert(7, "right");
+
+    let (merged, conflicts) = TravlMapPersistent::merge3(&base, &left, &right);
+
+    assert!(merged.iter().eq([(&1, &"A"), (&3, &"C"), (&4, &"d"), (&6, &"f")]));
+
+    let conflicts: Vec<_> = conflicts
+        .iter()
+        .map(|conflict| (*conflict.key(), conflict.base(), conflict.left(), conflict.right()))
+        .collect();
+    assert_eq!(
+        conflicts,
+        [(4, Some(&"d"), Some(&"D"), Some(&"delta")), (7, None, Some(&"left"), Some(&"right"))]
+    );
+}
+
+/// Builds a map and its model out of pseudo-random keys
+fn random_map(seed: u64, len: u64, imbalance_factor: u64) -> (TravlMapPersistent<u64, u64>, BTreeMap<u64, u64>) {
+    let mut map = TravlMapPersistent::with_imbalance_factor(imbalance_factor);
+    let mut model = BTreeMap::new();
+
+    for (key, value) in pseudo_random(seed).take(usize::try_from(len).unwrap()).zip(0..) {
+        map = map.insert(key % 1_000, value);
+        model.insert(key % 1_000, value);
+    }
+
+    (map, model)
+}
+
+#[test]
+fn ranges_can_be_copied_and_taken_between_maps() {
+    for imbalance_factor in 0..3 {
+        for seed in 0..20 {
+            let (map, model) = random_map(seed, 300, imbalance_factor);
+            let (other, other_model) = random_map(seed + 100, 300, imbalance_factor + seed % 2);
+            let start = seed * 40;
+            let range = start..start + 300;
+
+            let copied = map.copy_range_from(&other, range.clone());
+            let mut expected = model.clone();
+            expected.retain(|key, _| !range.contains(key));
+            expected.extend(other_model.range(range.clone()).map(|(key, value)| (*key, *value)));
+
+            assert_eq!(copied.len(), expected.len());
+            assert!(copied.iter().eq(expected.iter()));
+
+            let (rest, taken) = map.take_range(range.clone());
+            assert!(taken.iter().eq(model.range(range.clone())));
+            assert!(rest.iter().eq(model.iter().filter(|(key, _)| !range.contains(key))));
+            assert_eq!(rest.len() + taken.len(), map.len());
+
+            if imbalance_factor == 0 {
+                for result in [&copied, &rest, &taken] {
+                    let len = u32::try_from(result.len()).unwrap();
+                    assert!(result.height() <= u64::from((len + 2).ilog2()) * 3 / 2 + 1);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn stats_of_sorted_insertions_describe_a_complete_tree() {
+    let map: TravlMapPersistent<_, _> = (0..127).map(|key| (key, ())).collect();
+    let stats = map.stats();
+
+    assert_eq!(stats.node_count(), 127);
+    assert_eq!(stats.height(), 7);
+    assert_eq!(stats.minimum_height(), 7);
+    assert_eq!(stats.leaf_count(), 64);
+    assert_eq!((stats.min_depth(), stats.max_depth()), (Some(6), Some(6)));
+    assert_eq!(stats.nodes_per_level(), [1, 2, 4, 8, 16, 32, 64]);
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    assert_eq!(stats.balance_factors().get(&BalanceFactor::Balanced), Some(&127));
+
+    let empty = TravlMapPersistent::<u64, u64>::new().stats();
+    assert_eq!((empty.height(), empty.minimum_height(), empty.min_depth()), (0, 0, None));
+}
+
+#[test]
+fn stats_of_relaxed_trees_stay_within_the_allowed_imbalance() {
+    for imbalance_factor in 0..4 {
+        let (map, _) = random_map(imbalance_factor, 5_000, imbalance_factor);
+        let stats = map.stats();
+
+        assert_eq!(stats.node_count(), map.len());
+        assert_eq!(stats.height() as u64, map.height());
+        assert!(stats.minimum_height() <= stats.height());
+        assert!(stats.min_depth() <= stats.max_depth());
+        #[cfg(any(feature = "std", feature = "hashbrown"))]
+        {
+            assert_eq!(stats.balance_factors().values().sum::<usize>(), map.len());
+            assert!(!stats.balance_factors().contains_key(&BalanceFactor::TooLeftHeavy));
+            assert!(!stats.balance_factors().contains_key(&BalanceFactor::TooRightHeavy));
+        }
+    }
+}
+
+#[test]
+fn structural_diffs_compare_the_shapes_of_trees() {
+    let balanced: TravlMapPersistent<_, _> = (0..3).map(|key| (key, ())).collect();
+    let chain = (0..3).fold(TravlMapPersistent::with_imbalance_factor(1), |map, key| map.insert(key, ()));
+
+    let diff = balanced.structural_diff(&chain);
+    assert_eq!(diff.depth_changes(), [(&0, 1, 0), (&1, 0, 1), (&2, 1, 2)]);
+    assert_eq!(diff.height_changes(), [(&0, 1, 3)]);
+    assert_eq!(diff.rotation_distance_estimate(), 1);
+    assert_eq!(diff.unmatched_keys(), 0);
+    assert!(!diff.is_identical());
+
+    let rotated = chain.remove(&0).0.insert(0, ());
+    assert!(balanced.structural_diff(&rotated).is_identical());
+    assert!(balanced.structural_diff(&balanced.clone()).is_identical());
+    assert_eq!(balanced.structural_diff(&balanced.insert(3, ())).unmatched_keys(), 1);
+}
+
+#[test]
+fn fallible_comparisons_propagate_their_errors_and_leave_the_map_untouched() {
+    let numerically = |a: &String, b: &String| Ok::<_, ParseIntError>(a.parse::<u32>()?.cmp(&b.parse::<u32>()?));
+
+    let map = ["10", "2", "33", "4"].into_iter().try_fold(TravlMapPersistent::new(), |map, key| {
+        map.try_insert_by(key.to_owned(), key.len(), numerically)
+    });
+    let map = map.unwrap();
+    assert_eq!(map.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), ["2", "4", "10", "33"]);
+
+    assert!(map.try_insert_by("x".to_owned(), 1, numerically).is_err());
+    assert!(map.try_get_by(&"x".to_owned(), numerically).is_err());
+    assert!(map.try_remove_by(&"x".to_owned(), numerically).is_err());
+    assert_eq!(map.len(), 4);
+
+    assert_eq!(map.try_get_by(&"10".to_owned(), numerically), Ok(Some(&2)));
+    assert_eq!(map.try_get_by(&"3".to_owned(), numerically), Ok(None));
+
+    let (map, removed) = map.try_remove_by(&"4".to_owned(), numerically).unwrap();
+    assert_eq!(removed, Some(1));
+    assert_eq!(map.try_remove_by(&"4".to_owned(), numerically).unwrap().1, None);
+    assert_eq!(map.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), ["2", "10", "33"]);
+}
+
+#[test]
+fn panicking_comparisons_leave_the_map_untouched() {
+    let map: TravlMapPersistent<u32, u32> = (0..100).map(|key| (key, key)).collect();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        map.try_insert_by(1_000, 0, |a: &u32, b: &u32| {
+            assert_ne!(*b, 99, "comparison panicked");
+            Ok::<_, ()>(a.cmp(b))
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq(0..100));
+}
+
+/// Key whose comparisons panic when it is compared to the key 99
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fragile(u32);
+
+impl PartialOrd for Fragile {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fragile {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        assert!(self.0 != 99 && other.0 != 99, "comparison panicked");
+        self.0.cmp(&other.0)
+    }
+}
+
+#[test]
+fn panicking_comparisons_leave_maps_changed_in_place_empty() {
+    let mut map = TravlMapPersistent::new();
+    for key in 0..50 {
+        map.insert_in_place(Fragile(key), key);
+    }
+    let snapshot = map.snapshot();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| map.insert_in_place(Fragile(99), 99)));
+    assert!(result.is_err());
+    assert_eq!(map.len(), 0);
+    assert_eq!(map.iter().count(), 0);
+    assert_eq!(map.validate(), []);
+
+    map.insert_in_place(Fragile(1), 1);
+    assert_eq!(map.len(), 1);
+    assert_eq!(snapshot.len(), 50);
+    assert_eq!(snapshot.validate(), []);
+}
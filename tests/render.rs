@@ -0,0 +1,50 @@
+use travl::map::TravlMap;
+
+/// Returns a map whose root is 4, with 2, 1 and 3 on its left and 6 then 5 on its right
+fn lopsided_map() -> TravlMap<'static, u32, u32> {
+    let mut map = TravlMap::new();
+    for key in [4, 2, 6, 1, 3, 5] {
+        map.insert(key, key * 10);
+    }
+    map
+}
+
+#[test]
+fn ascii_renderings_draw_each_node_under_its_parent() {
+    let expected = concat!(
+        "4: 40 (Balanced)\n",
+        "├── L: 2: 20 (Balanced)\n",
+        "│   ├── L: 1: 10 (Balanced)\n",
+        "│   └── R: 3: 30 (Balanced)\n",
+        "└── R: 6: 60 (TooLeftHeavy)\n",
+        "    └── L: 5: 50 (Balanced)\n",
+    );
+
+    assert_eq!(lopsided_map().render_ascii(), expected);
+    assert_eq!(TravlMap::<u32, u32>::new().render_ascii(), "");
+}
+
+#[test]
+fn json_renderings_nest_children_in_their_parent() {
+    let mut map = lopsided_map();
+    for key in [1, 3, 4] {
+        map.remove(&key);
+    }
+
+    assert_eq!(
+        map.render_json(),
+        concat!(
+            r#"{"key":"5","value":"50","height":1,"#,
+            r#""left":{"key":"2","value":"20","height":0,"left":null,"right":null},"#,
+            r#""right":{"key":"6","value":"60","height":0,"left":null,"right":null}}"#
+        )
+    );
+    assert_eq!(TravlMap::<u32, u32>::new().render_json(), "null");
+
+    let mut strings = TravlMap::<&str, &str>::new();
+    strings.insert("key", "\"quoted\"\tand\\escaped\n");
+    assert_eq!(
+        strings.render_json(),
+        r#"{"key":"key","value":"\"quoted\"\tand\\escaped\n","height":0,"left":null,"right":null}"#
+    );
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "rkyv")]
+
+use rkyv::rancor::Error;
+use travl::map::{ArchivedTravlMapArchive, TravlMap};
+
+#[test]
+fn archived_maps_are_queried_without_being_deserialized() {
+    let mut map = TravlMap::<u32, u32>::builder().imbalance_factor(1).build();
+    for key in 0..100 {
+        map.insert(key, 1_000 - key * 10);
+    }
+
+    let bytes = rkyv::to_bytes::<Error>(&map.to_archive()).unwrap();
+    let archived = rkyv::access::<ArchivedTravlMapArchive<u32, u32>, Error>(&bytes).unwrap();
+
+    assert_eq!((archived.len(), archived.imbalance_factor()), (100, 1));
+    assert_eq!(archived.entries().first().map(|entry| entry.key().to_native()), Some(99));
+
+    let compare_to = |prop: u32| move |value: &rkyv::Archived<u32>| value.to_native().cmp(&prop);
+    assert_eq!(archived.find(compare_to(500)).map(|entry| entry.key().to_native()), Some(50));
+    assert!(archived.find(compare_to(505)).is_none());
+    assert_eq!(archived.rank(compare_to(505)), 50);
+
+    let range = archived.range(compare_to(15), compare_to(50));
+    let keys: Vec<u32> = range.iter().map(|entry| entry.key().to_native()).collect();
+    assert_eq!(keys, [98, 97, 96, 95]);
+
+    let deserialized = rkyv::deserialize::<_, Error>(archived).unwrap();
+    assert_eq!(deserialized, map.to_archive());
+}
@@ -0,0 +1,466 @@
+#![cfg(feature = "serde")]
+
+use std::collections::HashMap;
+
+use bincode::Options;
+use serde::de::DeserializeSeed;
+use travl::core::{IndexedNode, NodeRecord, TravlNode};
+use travl::map::{Balancing, DeserializedMap, SerdeFormat, TiePolicy, TravlMap, TravlMapConfig, TravlMapSeed};
+use travl::storage::OrderedNodeMap;
+use travl::traversal::PreOrderIter;
+
+fn seed() -> TravlMapSeed<'static, u32, u32, u32> {
+    TravlMapSeed::new(Box::new(|value: &u32| value), Box::new(|a: &u32, b: &u32| a.cmp(b)))
+}
+
+/// Serializes a map with an imbalance factor of 1 and the given serialized entries, using the full format
+fn full_map(entries: &[String]) -> String {
+    format!(
+        r#"{{"version":1,"imbalance_factor":1,"tie_policy":"After","balancing":"Strict","entries":[{}]}}"#,
+        entries.join(",")
+    )
+}
+
+#[test]
+fn deserialized_maps_are_balanced_trees_of_their_entries() {
+    let entries: Vec<String> = (0..1_000_u32)
+        .map(|key| key * 7_919 % 1_000)
+        .map(|key| format!(r#"{{"key":{key},"value":{}}}"#, 999 - key))
+        .collect();
+    let json = full_map(&entries);
+
+    let mut map = seed().deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+
+    assert_eq!(map.len(), 1_000);
+    assert_eq!(map.validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq((0..1_000).rev()));
+    assert_eq!(map.stats().height(), map.stats().minimum_height());
+
+    let root = map.root().unwrap();
+    assert_eq!(root.parent(), None);
+    for key in [root.left(), root.right()].into_iter().flatten() {
+        assert_eq!(map.get(key).unwrap().parent(), Some(root.key()));
+    }
+}
+
+#[test]
+fn maps_survive_a_round_trip() {
+    let json = concat!(
+        r#"{"version":1,"imbalance_factor":0,"tie_policy":"After","balancing":"Strict","root":3,"#,
+        r#""entries":[{"key":1,"value":10},{"key":3,"value":30}]}"#
+    );
+    let map: TravlMap<'static, u32, u32, u32> =
+        seed().deserialize(&mut serde_json::Deserializer::from_str(json)).unwrap();
+
+    let serialized = serde_json::to_string(&map).unwrap();
+    let mut deserialized = seed().deserialize(&mut serde_json::Deserializer::from_str(&serialized)).unwrap();
+
+    assert_eq!(serialized, json);
+    assert_eq!(deserialized.validate(), []);
+    assert_eq!(deserialized.to_sorted_vec(), map.to_sorted_vec());
+}
+
+fn stream(values: &[u32]) -> Result<TravlMap<'static, u32, u32, u32>, serde_json::Error> {
+    let entries: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(key, value)| format!(r#"{{"key":{key},"value":{value}}}"#))
+        .collect();
+    let json = full_map(&entries);
+
+    seed().streaming().deserialize(&mut serde_json::Deserializer::from_str(&json))
+}
+
+#[test]
+fn streamed_maps_are_balanced_trees_of_their_entries() {
+    for len in [0_u32, 1, 6, 7] {
+        let values: Vec<u32> = (0..len).map(|value| value * 3).collect();
+        let mut map = stream(&values).unwrap();
+
+        assert_eq!(map.len(), values.len());
+        assert_eq!(map.imbalance_factor(), 1);
+        assert_eq!(map.validate(), []);
+        assert!(map.iter().map(|(_, value)| *value).eq(values.iter().copied()));
+        assert_eq!(map.stats().height(), map.stats().minimum_height());
+    }
+}
+
+#[test]
+fn streaming_rejects_unsorted_entries() {
+    assert!(stream(&[1, 3, 2]).is_err());
+}
+
+#[test]
+fn streaming_rejects_duplicate_keys() {
+    let json = concat!(
+        r#"{"version":1,"imbalance_factor":1,"tie_policy":"After","balancing":"Strict","#,
+        r#""entries":[{"key":1,"value":1},{"key":1,"value":2}]}"#
+    );
+
+    assert!(seed().streaming().deserialize(&mut serde_json::Deserializer::from_str(json)).is_err());
+}
+
+/// Node serialized using the structural format, as `(key, height, parent, left, right)`, holding its key as value
+type Record = (u32, u64, Option<usize>, Option<usize>, Option<usize>);
+
+/// Serializes nodes using the structural format
+fn structure(nodes: &[Record]) -> String {
+    let index = |link: Option<usize>| link.map_or_else(|| "null".to_owned(), |index| index.to_string());
+    let nodes: Vec<String> = nodes
+        .iter()
+        .map(|&(key, height, parent, left, right)| {
+            format!(
+                r#"{{"key":{key},"value":{key},"height":{height},"parent":{},"left":{},"right":{}}}"#,
+                index(parent),
+                index(left),
+                index(right)
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{"version":1,"imbalance_factor":0,"tie_policy":"After","balancing":"Strict","nodes":[{}]}}"#,
+        nodes.join(",")
+    )
+}
+
+fn deserialize_structure(json: &str) -> Result<TravlMap<'static, u32, u32, u32>, serde_json::Error> {
+    seed().with_format(SerdeFormat::Structural).deserialize(&mut serde_json::Deserializer::from_str(json))
+}
+
+#[test]
+fn structural_maps_keep_their_shape() {
+    // A tree which building a balanced tree out of the entries wouldn't give, rooted at 1 rather than 2
+    let json = structure(&[
+        (1, 2, None, Some(1), Some(2)),
+        (0, 0, Some(0), None, None),
+        (2, 1, Some(0), None, Some(3)),
+        (3, 0, Some(2), None, None),
+    ]);
+
+    let mut map = deserialize_structure(&json).unwrap();
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.root_key(), Some(&1));
+    assert_eq!(map.get(&2).unwrap().right(), Some(&3));
+    assert_eq!(map.get(&3).unwrap().parent(), Some(&2));
+    assert_eq!(serde_json::to_string(&map.with_serde_format(SerdeFormat::Structural)).unwrap(), json);
+}
+
+#[test]
+fn structural_maps_survive_a_round_trip() {
+    let map = stream(&(0..100).collect::<Vec<_>>()).unwrap();
+
+    let serialized = serde_json::to_string(&map.with_serde_format(SerdeFormat::Structural)).unwrap();
+    let deserialized = deserialize_structure(&serialized).unwrap();
+
+    assert_eq!(deserialized.root_key(), map.root_key());
+    for (key, _) in &map {
+        let (node, other) = (map.get(key).unwrap(), deserialized.get(key).unwrap());
+        assert_eq!((other.height(), other.parent(), other.left(), other.right()), (
+            node.height(),
+            node.parent(),
+            node.left(),
+            node.right()
+        ));
+    }
+}
+
+#[test]
+fn rank_balanced_structures_survive_a_round_trip() {
+    let mut map = TravlMap::<u32, u32>::builder().balancing(Balancing::Rank).build();
+    for key in 0..200 {
+        map.insert(key, key);
+    }
+    for key in (0..200).filter(|key| key % 5 != 0) {
+        map.remove(&key);
+    }
+
+    let serialized = serde_json::to_string(&map.with_serde_format(SerdeFormat::Structural)).unwrap();
+    let mut deserialized = deserialize_structure(&serialized).unwrap();
+
+    assert_eq!(deserialized.config().balancing(), Balancing::Rank);
+    assert_eq!(deserialized.validate(), []);
+    for key in (0..200).step_by(5) {
+        let (node, other) = (map.get(&key).unwrap(), deserialized.get(&key).unwrap());
+        assert_eq!((other.parent(), other.left(), other.right()), (node.parent(), node.left(), node.right()));
+    }
+
+    deserialized.remove(&0);
+    deserialized.insert(1, 1);
+    assert_eq!(deserialized.validate(), []);
+}
+
+#[test]
+fn invalid_structures_are_rejected() {
+    let invalid = [
+        // Link to a node which isn't serialized
+        structure(&[(0, 1, None, None, Some(5))]),
+        // Cycle between nodes
+        structure(&[(0, 1, None, None, Some(1)), (1, 1, Some(0), None, Some(0))]),
+        // Several roots
+        structure(&[(0, 0, None, None, None), (1, 0, None, None, None)]),
+        // Root with a parent
+        structure(&[(0, 1, Some(1), None, Some(1)), (1, 0, Some(0), None, None)]),
+        // Child which doesn't link back to its parent
+        structure(&[(0, 1, None, None, Some(1)), (1, 0, None, None, None)]),
+        // Wrong height
+        structure(&[(0, 2, None, None, Some(1)), (1, 0, Some(0), None, None)]),
+        // Imbalanced node
+        structure(&[(0, 2, None, None, Some(1)), (1, 1, Some(0), None, Some(2)), (2, 0, Some(1), None, None)]),
+        // Nodes out of order
+        structure(&[(1, 1, None, None, Some(1)), (0, 0, Some(0), None, None)]),
+        // Duplicate keys
+        structure(&[(0, 1, None, None, Some(1)), (0, 0, Some(0), None, None)]),
+    ];
+
+    for json in invalid {
+        assert!(deserialize_structure(&json).is_err(), "{json}");
+    }
+}
+
+/// Map whose values are ordered by their first element only, so that values can have equal properties
+type PairMap = TravlMap<'static, u32, (u32, u32), u32>;
+
+fn pair_seed() -> TravlMapSeed<'static, u32, (u32, u32), u32> {
+    TravlMapSeed::new(Box::new(|(first, _): &(u32, u32)| first), Box::new(|a: &u32, b: &u32| a.cmp(b)))
+}
+
+#[test]
+fn configurations_and_roots_survive_a_round_trip() {
+    let mut map: PairMap = TravlMap::builder()
+        .imbalance_factor(2)
+        .tie_policy(TiePolicy::Before)
+        .balancing(Balancing::Relaxed(1))
+        .prop(|(first, _): &(u32, u32)| first)
+        .build();
+    for key in 0..40 {
+        map.insert(key, (key % 10, key));
+    }
+    assert_ne!(map.root_key(), Some(&map.to_sorted_vec()[20].0));
+
+    let json = serde_json::to_string(&map).unwrap();
+    let mut deserialized = pair_seed().deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    let streamed = pair_seed().streaming().deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+
+    assert_eq!(deserialized.validate(), []);
+    for restored in [&deserialized, &streamed] {
+        assert_eq!(restored.config(), map.config());
+        assert_eq!(restored.root_key(), map.root_key());
+        assert_eq!(restored.to_sorted_vec(), map.to_sorted_vec());
+    }
+
+    // Entries with equal properties are inserted before the existing ones in both maps
+    map.insert(100, (5, 100));
+    deserialized.insert(100, (5, 100));
+    assert_eq!(deserialized.to_sorted_vec(), map.to_sorted_vec());
+    assert_eq!(deserialized.to_sorted_vec()[20], (100, (5, 100)));
+}
+
+#[test]
+fn roots_too_far_from_the_middle_are_not_kept() {
+    let entries: Vec<String> = (0..100).map(|key| format!(r#"{{"key":{key},"value":{key}}}"#)).collect();
+    let json = format!(
+        r#"{{"version":1,"imbalance_factor":0,"tie_policy":"After","balancing":"Strict","root":0,"entries":[{}]}}"#,
+        entries.join(",")
+    );
+
+    let mut map = seed().deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.root_key(), Some(&50));
+}
+
+#[test]
+fn deserialized_maps_are_built_through_the_hook() {
+    let json = concat!(
+        r#"{"version":1,"imbalance_factor":1,"tie_policy":"Before","balancing":"Strict","root":2,"#,
+        r#""entries":[{"key":1,"value":10},{"key":2,"value":20},{"key":3,"value":30},{"key":4,"value":40}]}"#
+    );
+    let deserialized: DeserializedMap<u32, u32> = serde_json::from_str(json).unwrap();
+
+    assert_eq!(deserialized.config(), TravlMapConfig::new(1).with_tie_policy(TiePolicy::Before));
+    assert_eq!(deserialized.root_key(), Some(&2));
+
+    let mut map = deserialized.build(|builder| {
+        builder
+            .ordering(|a: &u32, b: &u32| b.cmp(a))
+            .storage::<OrderedNodeMap<_, _>>()
+            .build()
+    });
+
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.config(), TravlMapConfig::new(1).with_tie_policy(TiePolicy::Before));
+    assert_eq!(map.root_key(), Some(&2));
+    assert!(map.iter().map(|(key, _)| *key).eq([4, 3, 2, 1]));
+}
+
+#[test]
+fn configurations_survive_a_round_trip() {
+    let config = TravlMapConfig::new(3)
+        .with_tie_policy(TiePolicy::Before)
+        .with_balancing(Balancing::Relaxed(2));
+
+    let json = serde_json::to_string(&config).unwrap();
+
+    assert_eq!(json, r#"{"imbalance_factor":3,"tie_policy":"Before","balancing":{"Relaxed":2}}"#);
+    assert_eq!(serde_json::from_str::<TravlMapConfig>(&json).unwrap(), config);
+    assert_eq!(bincode::deserialize::<TravlMapConfig>(&bincode::serialize(&config).unwrap()).unwrap(), config);
+    assert!(serde_json::from_str::<TravlMapConfig>(r#"{"imbalance_factor":3}"#).is_err());
+}
+
+/// Map `{1: 10, 2: 20}` with an imbalance factor of 1, as serialized by bincode
+const BINCODE_FIXTURE: &[u8] = &[
+    1, 0, 0, 0, // version
+    1, 0, 0, 0, 0, 0, 0, 0, // imbalance factor
+    0, 0, 0, 0, // tie policy
+    0, 0, 0, 0, // balancing strategy
+    1, 2, 0, 0, 0, // root
+    2, 0, 0, 0, 0, 0, 0, 0, // number of entries
+    1, 0, 0, 0, 10, 0, 0, 0, // entries
+    2, 0, 0, 0, 20, 0, 0, 0,
+];
+
+/// Same map as the bincode fixture, as serialized by `serde_json`
+const JSON_FIXTURE: &str = concat!(
+    r#"{"version":1,"imbalance_factor":1,"tie_policy":"After","balancing":"Strict","root":2,"#,
+    r#""entries":[{"key":1,"value":10},{"key":2,"value":20}]}"#
+);
+
+fn bincode_deserializer(
+    bytes: &[u8],
+) -> bincode::Deserializer<bincode::de::read::SliceReader<'_>, impl Options> {
+    bincode::Deserializer::from_slice(bytes, bincode::options().with_fixint_encoding())
+}
+
+fn assert_fixture(mut map: TravlMap<'static, u32, u32, u32>) {
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.config(), TravlMapConfig::new(1));
+    assert_eq!(map.root_key(), Some(&2));
+    assert_eq!(map.to_sorted_vec(), [(1, 10), (2, 20)]);
+}
+
+#[test]
+fn maps_are_serialized_along_with_their_version() {
+    let map = seed().deserialize(&mut bincode_deserializer(BINCODE_FIXTURE)).unwrap();
+    assert_eq!(bincode::serialize(&map).unwrap(), BINCODE_FIXTURE);
+    assert_eq!(serde_json::to_string(&map).unwrap(), JSON_FIXTURE);
+    assert_fixture(map);
+
+    let map = seed().streaming().deserialize(&mut bincode_deserializer(BINCODE_FIXTURE)).unwrap();
+    assert_fixture(map);
+
+    let deserialized: DeserializedMap<u32, u32> =
+        serde::Deserialize::deserialize(&mut bincode_deserializer(BINCODE_FIXTURE)).unwrap();
+    assert_eq!(deserialized.config(), TravlMapConfig::new(1));
+
+    let map = seed().deserialize(&mut serde_json::Deserializer::from_str(JSON_FIXTURE)).unwrap();
+    assert_fixture(map);
+    let map = seed().streaming().deserialize(&mut serde_json::Deserializer::from_str(JSON_FIXTURE)).unwrap();
+    assert_fixture(map);
+}
+
+#[test]
+fn unsupported_versions_are_rejected() {
+    for version in [0, 2] {
+        let mut bytes = BINCODE_FIXTURE.to_vec();
+        bytes[0] = version;
+        assert!(seed().deserialize(&mut bincode_deserializer(&bytes)).is_err());
+
+        let json = JSON_FIXTURE.replace(r#""version":1"#, &format!(r#""version":{version}"#));
+        assert!(seed().deserialize(&mut serde_json::Deserializer::from_str(&json)).is_err());
+    }
+
+    let json = r#"{"imbalance_factor":1,"tie_policy":"After","balancing":"Strict","entries":[]}"#;
+    assert!(seed().deserialize(&mut serde_json::Deserializer::from_str(json)).is_err());
+
+    let json = r#"{"version":1,"imbalance_factor":1,"tie_policy":"After","entries":[]}"#;
+    assert!(seed().deserialize(&mut serde_json::Deserializer::from_str(json)).is_err());
+}
+
+#[test]
+fn balancing_strategies_survive_a_round_trip() {
+    let strategies =
+        [Balancing::Strict, Balancing::Relaxed(3), Balancing::Scapegoat, Balancing::Weight, Balancing::Rank];
+    for balancing in strategies {
+        let json = serde_json::to_string(&balancing).unwrap();
+        let bytes = bincode::serialize(&balancing).unwrap();
+
+        assert_eq!(serde_json::from_str::<Balancing>(&json).unwrap(), balancing);
+        assert_eq!(bincode::deserialize::<Balancing>(&bytes).unwrap(), balancing);
+    }
+
+    assert_eq!(serde_json::to_string(&Balancing::Scapegoat).unwrap(), r#""Scapegoat""#);
+}
+
+#[test]
+fn flat_maps_only_keep_their_entries() {
+    let mut map = TravlMap::<u32, u32>::builder().imbalance_factor(2).build();
+    for key in 0..20 {
+        map.insert(key, 100 - key);
+    }
+
+    let json = serde_json::to_string(&map.with_serde_format(SerdeFormat::Flat)).unwrap();
+    assert!(json.starts_with(r#"[{"key":19,"value":81},{"key":18,"value":82},"#));
+
+    let mut deserialized =
+        seed().with_format(SerdeFormat::Flat).deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+
+    assert_eq!(deserialized.validate(), []);
+    assert_eq!(deserialized.config(), TravlMapConfig::default());
+    assert_eq!(deserialized.to_sorted_vec(), map.to_sorted_vec());
+}
+
+#[test]
+fn nodes_link_to_each_other_by_index() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in 1..=3 {
+        map.insert(key, key * 10);
+    }
+    let nodes: Vec<&TravlNode<u32, u32>> = map.traverse::<PreOrderIter<_, _>>().collect();
+    let indices: HashMap<&u32, usize> = nodes.iter().enumerate().map(|(index, node)| (node.key(), index)).collect();
+
+    let json = serde_json::to_string(&nodes.iter().map(|node| IndexedNode::new(node, &indices)).collect::<Vec<_>>());
+    let records: Vec<NodeRecord<u32, u32>> = serde_json::from_str(&json.unwrap()).unwrap();
+
+    let links: Vec<_> =
+        records.iter().map(|record| (*record.key(), record.parent(), record.left(), record.right())).collect();
+    assert_eq!(links, [(2, None, Some(1), Some(2)), (1, Some(0), None, None), (3, Some(0), None, None)]);
+    assert_eq!((records[0].height(), *records[1].value()), (1, 10));
+
+    // Links to nodes without an index are left out
+    let root = IndexedNode::new(nodes[0], &HashMap::from([(&2, 0)]));
+    assert_eq!(
+        serde_json::to_string(&root).unwrap(),
+        r#"{"key":2,"value":20,"height":1,"parent":null,"left":null,"right":null}"#
+    );
+}
+
+#[test]
+fn compact_formats_get_entries_as_key_value_pairs() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in 0..30 {
+        map.insert(key, 100 - key);
+    }
+
+    // Compact formats get the same maps without any field names
+    let full = bincode::serialize(&map).unwrap();
+    let flat = bincode::serialize(&map.with_serde_format(SerdeFormat::Flat)).unwrap();
+    assert_eq!(flat.len(), 8 + 30 * 8);
+    assert_eq!(flat[8..16], [29, 0, 0, 0, 71, 0, 0, 0]);
+    assert!(full.ends_with(&flat[8..]));
+
+    let mut deserialized = seed().deserialize(&mut bincode_deserializer(&full)).unwrap();
+    assert_eq!(deserialized.validate(), []);
+    assert_eq!(deserialized.to_sorted_vec(), map.to_sorted_vec());
+
+    let flat_seed = seed().with_format(SerdeFormat::Flat);
+    let deserialized = flat_seed.deserialize(&mut bincode_deserializer(&flat)).unwrap();
+    assert_eq!(deserialized.to_sorted_vec(), map.to_sorted_vec());
+
+    // Both representations of entries are accepted by human-readable formats
+    let json = r#"[[2,10],{"key":1,"value":20},[3,30]]"#;
+    let flat_seed = seed().with_format(SerdeFormat::Flat);
+    let deserialized = flat_seed.deserialize(&mut serde_json::Deserializer::from_str(json)).unwrap();
+    assert_eq!(deserialized.to_sorted_vec(), [(2, 10), (1, 20), (3, 30)]);
+}
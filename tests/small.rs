@@ -0,0 +1,100 @@
+use travl::map::{SearchType, TiePolicy, TravlMap};
+use travl::small::SmallTravlMap;
+
+#[test]
+fn small_maps_are_promoted_past_their_inline_capacity() {
+    let mut map = SmallTravlMap::<u32, u32, u32, 4>::new();
+    for key in [3, 1, 4, 2] {
+        assert_eq!(map.insert(key, key * 10), None);
+    }
+
+    assert!(map.is_inline());
+    assert!(map.as_map().is_none());
+    assert!(map.iter().eq([(&1, &10), (&2, &20), (&3, &30), (&4, &40)]));
+
+    map.insert(0, 0);
+
+    assert!(!map.is_inline());
+    assert_eq!(map.len(), 5);
+    assert_eq!(map.as_map_mut().unwrap().validate(), []);
+    assert!(map.iter().map(|(key, _)| *key).eq(0..5));
+}
+
+#[test]
+fn emptied_trees_hand_entries_back_to_the_inline_array() {
+    let mut map = SmallTravlMap::<u32, u32, u32, 2>::new();
+    for key in 0..3 {
+        map.insert(key, key);
+    }
+    assert!(!map.is_inline());
+
+    for key in 0..3 {
+        assert_eq!(map.remove(&key), Some(key));
+    }
+    assert!(map.is_empty() && map.is_inline());
+
+    map.insert(7, 7);
+    assert!(map.is_inline());
+    assert_eq!(map.get(&7), Some(&7));
+}
+
+/// Orders values by their first element only, so ties are broken by the tie policy
+fn first(value: &(u32, u32)) -> &u32 {
+    &value.0
+}
+
+#[test]
+fn inline_entries_are_ordered_like_a_tree() {
+    for tie_policy in [TiePolicy::After, TiePolicy::Before] {
+        let build = || TravlMap::<u32, (u32, u32)>::builder().prop(first).tie_policy(tie_policy).build();
+        let (mut tree, mut small) = (build(), SmallTravlMap::<u32, (u32, u32), u32>::from(build()));
+
+        let operations = [(1, (2, 0)), (2, (1, 0)), (3, (2, 1)), (4, (3, 0)), (1, (2, 2)), (2, (3, 1)), (5, (0, 0))];
+        for (key, value) in operations {
+            assert_eq!(small.insert(key, value), tree.insert(key, value));
+            assert!(small.iter().eq(tree.iter()), "{tie_policy:?}");
+        }
+
+        assert_eq!(small.remove(&3), tree.remove(&3));
+        assert!(small.iter().eq(tree.iter()), "{tie_policy:?}");
+        assert!(small.is_inline());
+    }
+}
+
+#[test]
+fn inline_entries_are_found_by_property() {
+    let mut map = SmallTravlMap::<u32, u32>::new();
+    for (key, value) in [(1, 10), (2, 20), (3, 20), (4, 30)] {
+        map.insert(key, value);
+    }
+
+    assert_eq!(map.find(&20, SearchType::Equality), Some((&2, &20)));
+    assert_eq!(map.find(&25, SearchType::Equality), None);
+    assert_eq!(map.find(&25, SearchType::NearestToBottom), Some((&3, &20)));
+    assert_eq!(map.find(&25, SearchType::NearestToTop), Some((&4, &30)));
+    assert_eq!(map.find(&5, SearchType::NearestToBottom), None);
+    assert_eq!(map.find(&35, SearchType::NearestToTop), None);
+
+    #[allow(deprecated)]
+    let nearest = [25, 35].map(|prop| map.find(&prop, SearchType::Nearest));
+    assert_eq!(nearest, [Some((&4, &30)), Some((&4, &30))]);
+}
+
+#[test]
+fn converted_maps_keep_their_configuration() {
+    let mut tree = TravlMap::<u32, u32>::builder().imbalance_factor(2).build();
+    for key in 0..3 {
+        tree.insert(key, 2 - key);
+    }
+
+    let mut map = SmallTravlMap::<u32, u32, u32, 3>::from(tree);
+    assert!(map.is_inline());
+    assert!(map.iter().eq([(&2, &0), (&1, &1), (&0, &2)]));
+
+    map.insert(3, 3);
+    assert_eq!(map.as_map().unwrap().imbalance_factor(), 2);
+
+    let tree = map.into_map();
+    assert_eq!(tree.len(), 4);
+    assert_eq!(tree.imbalance_factor(), 2);
+}
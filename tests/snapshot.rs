@@ -0,0 +1,74 @@
+#![cfg(feature = "std")]
+
+use std::collections::BTreeMap;
+use std::io;
+
+use travl::map::{TravlMap, TravlMapConfig};
+use travl::snapshot::SNAPSHOT_MAGIC;
+
+/// Returns the snapshot of a map holding the given number of entries
+fn snapshot(len: u32) -> Vec<u8> {
+    let entries: BTreeMap<u32, String> = (0..len).map(|key| (key, format!("value {key:02}"))).collect();
+    let map: TravlMap<u32, String> = entries.into();
+
+    let mut bytes = Vec::new();
+    map.write_snapshot(&mut bytes).unwrap();
+    bytes
+}
+
+fn read(bytes: &[u8]) -> io::Result<TravlMap<'static, u32, String>> {
+    TravlMap::read_snapshot(bytes, TravlMapConfig::new(2))
+}
+
+#[test]
+fn snapshots_survive_a_round_trip() {
+    for len in [0, 1, 10, 33] {
+        let mut map = read(&snapshot(len)).unwrap();
+
+        assert_eq!(map.len(), len as usize);
+        assert_eq!(map.imbalance_factor(), 2);
+        assert_eq!(map.validate(), []);
+        assert!(map.iter().map(|(key, _)| *key).eq(0..len));
+        assert_eq!(map.get(&0).map(|node| node.value().as_str()), (len > 0).then_some("value 00"));
+    }
+}
+
+#[test]
+fn truncated_snapshots_are_rejected() {
+    let bytes = snapshot(10);
+
+    for len in [0, 2, SNAPSHOT_MAGIC.len() + 2, bytes.len() / 2, bytes.len() - 1] {
+        let error = read(&bytes[..len]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "truncated to {len} bytes");
+    }
+}
+
+#[test]
+fn corrupted_snapshots_are_rejected() {
+    let mut bytes = snapshot(3);
+    bytes[0] = b'X';
+    assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    let mut bytes = snapshot(3);
+    bytes[SNAPSHOT_MAGIC.len()] = 9;
+    assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    // Entries start after the header, each with a 4 bytes key and a value prefixed by its 8 bytes length
+    let first = SNAPSHOT_MAGIC.len() + 4 + 8;
+    let entry_len = 4 + 8 + "value 00".len();
+
+    // Entries whose values are out of order
+    let mut bytes = snapshot(3);
+    bytes[first + entry_len - 1] = b'9';
+    assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    // Entries with duplicate keys
+    let mut bytes = snapshot(3);
+    bytes[first + entry_len] = 0;
+    assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    // Values whose bytes aren't valid UTF-8
+    let mut bytes = snapshot(3);
+    bytes[first + 4 + 8] = 0xFF;
+    assert_eq!(read(&bytes).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,75 @@
+use std::ops::ControlFlow;
+
+use travl::core::TravlNode;
+use travl::map::{NodeMap, TravlMap};
+use travl::stats::StatsVisitor;
+use travl::traversal::{Visitor, visit};
+
+/// Returns a map whose root is 4, with 2, 1 and 3 on its left and 5 then 6 on its right
+fn lopsided_map() -> TravlMap<'static, u32, u32> {
+    let mut map = TravlMap::new();
+    for key in 1..=6 {
+        map.insert(key, key);
+    }
+    map
+}
+
+#[test]
+fn stats_visitors_describe_the_shape_of_the_tree() {
+    let map = lopsided_map();
+    let mut visitor = StatsVisitor::new(map.imbalance_factor());
+
+    assert_eq!(visit(map.nodes(), map.root(), &mut visitor), ControlFlow::Continue(()));
+    let stats = visitor.finish();
+
+    assert_eq!(stats.node_count(), 6);
+    assert_eq!(stats.leaf_count(), 3);
+    assert_eq!(stats.height(), 3);
+    assert_eq!(stats.minimum_height(), 3);
+    assert_eq!(stats.nodes_per_level(), [1, 2, 3]);
+    assert_eq!((stats.min_depth(), stats.max_depth()), (Some(2), Some(2)));
+    assert_eq!(stats.average_depth(), Some(8.0 / 6.0));
+    assert_eq!(map.stats().node_count(), stats.node_count());
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+#[test]
+fn stats_visitors_count_balance_factors() {
+    use travl::core::BalanceFactor;
+
+    let map = lopsided_map();
+
+    let mut visitor = StatsVisitor::new(map.imbalance_factor());
+    let _ = visit(map.nodes(), map.root(), &mut visitor);
+    assert_eq!(visitor.stats().balance_factors()[&BalanceFactor::Balanced], 5);
+    assert_eq!(visitor.stats().balance_factors()[&BalanceFactor::TooRightHeavy], 1);
+
+    // Balance factors depend on the imbalance factor given to the visitor
+    let mut relaxed = StatsVisitor::new(1);
+    let _ = visit(map.nodes(), map.root(), &mut relaxed);
+    assert_eq!(relaxed.stats().balance_factors()[&BalanceFactor::RightHeavy], 1);
+    assert!(!relaxed.stats().balance_factors().contains_key(&BalanceFactor::TooRightHeavy));
+}
+
+/// Visitor stopping once it has seen the given number of nodes
+struct Take(usize, Vec<(u32, usize)>);
+
+impl Visitor<u32, u32> for Take {
+    fn visit(&mut self, _: &NodeMap<u32, u32>, node: &TravlNode<u32, u32>, depth: usize) -> ControlFlow<()> {
+        self.1.push((*node.key(), depth));
+        if self.1.len() == self.0 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    }
+}
+
+#[test]
+fn visitors_are_driven_in_pre_order_until_they_break() {
+    let map = lopsided_map();
+
+    let mut visitor = Take(4, Vec::new());
+    assert_eq!(visit(map.nodes(), map.root(), &mut visitor), ControlFlow::Break(()));
+    assert_eq!(visitor.1, [(4, 0), (2, 1), (1, 2), (3, 2)]);
+
+    let mut visitor = Take(10, Vec::new());
+    assert_eq!(visit(map.nodes(), map.root(), &mut visitor), ControlFlow::Continue(()));
+    assert_eq!(visitor.1.len(), 6);
+}
@@ -0,0 +1,159 @@
+use std::alloc::Layout;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use allocator_api2::alloc::AllocError;
+
+use travl::core::TravlNode;
+use travl::map::TravlMap;
+use travl::storage::{Allocator, Global, NodeStorage, OrderedNodeMap, SlabStorage};
+
+fn fill<S>(storage: &mut S)
+where
+    S: NodeStorage<u32, &'static str>
+{
+    for (key, value) in [(3, "three"), (1, "one"), (2, "two")] {
+        assert!(storage.insert(key, TravlNode::new(key, value)).is_none());
+    }
+}
+
+fn check_storage<S>()
+where
+    S: NodeStorage<u32, &'static str>
+{
+    let mut storage = S::with_capacity(3);
+    assert!(storage.is_empty());
+    fill(&mut storage);
+
+    assert_eq!(storage.len(), 3);
+    assert!(storage.capacity() >= 3);
+    assert_eq!(storage.get(&2).map(TravlNode::value), Some(&"two"));
+    assert!(!storage.contains_key(&4));
+
+    *storage.get_mut(&1).unwrap().value_mut() = "uno";
+    let previous = storage.insert(3, TravlNode::new(3, "tres")).unwrap();
+    assert_eq!(previous.into_entry(), (3, "three"));
+
+    assert_eq!(storage.remove(&2).map(TravlNode::into_entry), Some((2, "two")));
+    assert!(storage.remove(&2).is_none());
+
+    let mut entries = storage.drain().map(TravlNode::into_entry).collect::<Vec<_>>();
+    entries.sort_unstable();
+    assert_eq!(entries, [(1, "uno"), (3, "tres")]);
+    assert!(storage.is_empty());
+}
+
+#[test]
+fn storages_store_nodes_by_key() {
+    #[cfg(feature = "std")]
+    check_storage::<std::collections::HashMap<_, _>>();
+    check_storage::<BTreeMap<_, _>>();
+    check_storage::<SlabStorage<_, _>>();
+}
+
+#[test]
+fn btree_storages_keep_keys_sorted() {
+    let mut storage = BTreeMap::new();
+    fill(&mut storage);
+
+    assert_eq!(NodeStorage::iter(&storage).map(|(key, _)| *key).collect::<Vec<_>>(), [1, 2, 3]);
+}
+
+#[test]
+fn slab_storages_reuse_the_slots_of_removed_nodes() {
+    let mut storage = SlabStorage::new();
+    fill(&mut storage);
+    let capacity = storage.capacity();
+
+    storage.remove(&3);
+    storage.insert(4, TravlNode::new(4, "four"));
+    storage.remove(&1);
+    storage.insert(5, TravlNode::new(5, "five"));
+
+    assert_eq!(storage.len(), 3);
+    assert_eq!(storage.capacity(), capacity);
+    assert_eq!(storage.iter().map(|(key, _)| *key).collect::<Vec<_>>(), [4, 5, 2]);
+}
+
+#[test]
+fn builders_set_the_storage() {
+    let map = TravlMap::<u32, u32>::builder().storage::<SlabStorage<_, _>>().capacity(8).build();
+
+    assert!(map.is_empty());
+    assert!(map.capacity() >= 8);
+    assert!(map.nodes().is_empty());
+}
+
+#[test]
+fn slab_backed_maps_insert_find_and_remove_entries() {
+    let mut map = TravlMap::<u32, u32>::builder().storage::<SlabStorage<_, _>>().build();
+    for key in 0..2_000 {
+        assert!(map.insert(key, (key * 7) % 2_000).is_none());
+    }
+    for key in (0..2_000).step_by(2) {
+        assert_eq!(map.remove(&key), Some((key * 7) % 2_000));
+    }
+
+    assert!(map.validate().is_empty());
+    assert_eq!(map.len(), 1_000);
+    assert_eq!(map.get(&7).map(TravlNode::value), Some(&49));
+    assert!(map.get(&8).is_none());
+
+    let mut expected: Vec<(u32, u32)> = (1..2_000).step_by(2).map(|key| (key, (key * 7) % 2_000)).collect();
+    expected.sort_unstable_by_key(|(_, value)| *value);
+    assert!(map.iter().map(|(key, value)| (*key, *value)).eq(expected));
+}
+
+/// Key which can be ordered but not hashed
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version(u32, u32);
+
+#[test]
+fn ordered_storages_accept_keys_which_are_not_hashable() {
+    let mut map = TravlMap::<Version, u32>::builder().storage::<OrderedNodeMap<_, _>>().build();
+
+    map.batch().finish();
+    assert!(map.validate().is_empty());
+    assert!(map.repair().is_empty());
+    assert!(map.get(&Version(1, 0)).is_none());
+    assert_eq!(map.iter().count(), 0);
+}
+
+/// Allocator counting the allocations made through it, before handing them to the global allocator
+#[derive(Clone, Default)]
+struct CountingAllocator(Rc<Cell<usize>>);
+
+unsafe impl Allocator for CountingAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0.set(self.0.get() + 1);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { Global.deallocate(ptr, layout) }
+    }
+}
+
+#[test]
+fn slab_storages_allocate_their_slots_with_the_given_allocator() {
+    let allocator = CountingAllocator::default();
+    let storage = SlabStorage::new_in(allocator.clone());
+    let mut map = TravlMap::<u32, u32>::builder().storage_in(storage).capacity(16).build();
+    let allocations = allocator.0.get();
+    assert!(allocations > 0);
+
+    for key in 0..100 {
+        map.insert(key, 100 - key);
+    }
+    let mut batch = map.batch();
+    batch.insert(7, 7);
+    batch.finish();
+
+    assert!(map.validate().is_empty());
+    assert_eq!(map.len(), 100);
+    assert!(allocator.0.get() > allocations);
+    // Maps keep the storage they were given, along with its allocator
+    assert!(Rc::ptr_eq(&map.nodes().allocator().0, &allocator.0));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "proptest")]
+
+use std::collections::HashSet;
+
+use proptest::strategy::{Strategy, ValueTree};
+use proptest::test_runner::TestRunner;
+use travl::map::TravlMapStrategy;
+
+#[test]
+fn generated_maps_are_valid_trees_within_the_configured_ranges() {
+    let strategy = TravlMapStrategy::new(0..1_000_u32, 0..1_000_u32)
+        .with_size(20..=50)
+        .with_imbalance_factor(1..=2)
+        .with_duplicate_prop_density(0.5);
+    let mut runner = TestRunner::deterministic();
+
+    for _ in 0..20 {
+        let mut map = strategy.new_tree(&mut runner).unwrap().current();
+
+        assert_eq!(map.validate(), []);
+        assert!((20..=50).contains(&map.len()));
+        assert!((1..=2).contains(&map.imbalance_factor()));
+
+        let values: HashSet<u32> = map.iter().map(|(_, value)| *value).collect();
+        assert!(values.len() < map.len());
+    }
+}
+
+#[test]
+fn maps_shrink_by_removing_entries_down_to_the_minimum_size() {
+    let strategy = TravlMapStrategy::new(0..1_000_u32, 0..1_000_u32).with_size(5..=30);
+    let mut tree = strategy.new_tree(&mut TestRunner::deterministic()).unwrap();
+    let len = tree.current().len();
+
+    assert!(tree.simplify());
+    assert_eq!(tree.current().len(), len - 1);
+
+    // Entries needed to reproduce a failure are put back and kept
+    assert!(tree.complicate());
+    assert_eq!(tree.current().len(), len);
+
+    while tree.simplify() {
+        let mut map = tree.current();
+        assert_eq!(map.validate(), []);
+    }
+    assert_eq!(tree.current().len(), 5);
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "async")]
+
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use travl::map::IterStream;
+use travl::persistent::TravlMapPersistent;
+
+/// Polls the stream until it ends, returning its items and the number of times it yielded
+fn drain<S>(mut stream: S) -> (Vec<S::Item>, usize)
+where
+    S: Stream + Unpin
+{
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut items = Vec::new();
+    let mut yields = 0;
+
+    loop {
+        match Pin::new(&mut stream).poll_next(&mut cx) {
+            Poll::Ready(Some(item)) => items.push(item),
+            Poll::Ready(None) => return (items, yields),
+            Poll::Pending => yields += 1,
+        }
+    }
+}
+
+#[test]
+fn streams_yield_to_the_executor_at_regular_intervals() {
+    let (items, yields) = drain(IterStream::new(0..100).with_yield_interval(10));
+
+    assert!(items.into_iter().eq(0..100));
+    assert_eq!(yields, 10);
+}
+
+#[test]
+fn range_streams_produce_the_entries_within_bounds() {
+    let map: TravlMapPersistent<u32, u32> = (0..1_000).map(|key| (key, key * 2)).collect();
+
+    let (entries, _) = drain(map.range_stream(10..20));
+    assert!(entries.into_iter().map(|(key, _)| *key).eq(10..20));
+
+    let (entries, yields) = drain(map.stream());
+    assert_eq!(entries.len(), 1_000);
+    assert_eq!(yields, 1_000 / travl::map::DEFAULT_YIELD_INTERVAL);
+}
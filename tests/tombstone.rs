@@ -0,0 +1,54 @@
+#![cfg(any(feature = "std", feature = "hashbrown"))]
+
+use std::collections::BTreeMap;
+
+use travl::tombstone::TombstoneMap;
+
+#[test]
+fn tombstoned_entries_are_skipped_until_compaction() {
+    let mut map: TombstoneMap<u32, u32> = (0..10).map(|key| (key, key * 10)).collect();
+    let height = map.clone().into_persistent().height();
+
+    assert_eq!(map.remove(&3), Some(30));
+    assert_eq!(map.remove(&3), None);
+    assert_eq!(map.remove(&42), None);
+    assert_eq!(map.remove(&7), Some(70));
+
+    assert_eq!((map.len(), map.tombstone_len()), (8, 2));
+    assert_eq!(map.get(&3), None);
+    assert!(!map.contains_key(&7));
+    assert_eq!(map.iter().map(|(key, _)| *key).collect::<Vec<_>>(), [0, 1, 2, 4, 5, 6, 8, 9]);
+    assert_eq!(map.iter().len(), 8);
+
+    assert_eq!(map.insert(3, 33), None);
+    assert_eq!(map.insert(4, 44), Some(40));
+    assert_eq!((map.len(), map.tombstone_len()), (9, 1));
+
+    assert_eq!(map.compact(), 1);
+    assert_eq!(map.tombstone_len(), 0);
+
+    let map = map.into_persistent();
+    assert_eq!(map.len(), 9);
+    assert!(map.height() <= height);
+    assert_eq!(map.get(&3), Some(&33));
+}
+
+#[test]
+fn bursts_of_removals_match_a_btree_map() {
+    let mut map = TombstoneMap::new();
+    let mut model = BTreeMap::new();
+
+    for round in 0..20_u64 {
+        for key in 0..100 {
+            assert_eq!(map.insert(key, round), model.insert(key, round));
+        }
+        for key in (round % 7..100).step_by(3) {
+            assert_eq!(map.remove(&key), model.remove(&key));
+        }
+
+        assert!(map.iter().eq(model.iter()));
+        if round % 5 == 0 {
+            map.compact();
+        }
+    }
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use travl::observer::TracingObserver;
+use travl::persistent::TravlMapPersistent;
+
+/// Messages of the recorded events, along with the name of the span they were emitted in
+type Events = Arc<Mutex<Vec<(Option<&'static str>, String)>>>;
+
+/// Subscriber recording the messages of events, along with the name of the span they were emitted in
+#[derive(Default)]
+struct Recorder {
+    next_id: AtomicU64,
+    span_names: Mutex<Vec<&'static str>>,
+    entered: Mutex<Vec<&'static str>>,
+    events: Events,
+}
+
+/// Visitor extracting the message of an event
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.span_names.lock().unwrap().push(span.metadata().name());
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = Message(String::new());
+        event.record(&mut message);
+
+        let span = self.entered.lock().unwrap().last().copied();
+        self.events.lock().unwrap().push((span, message.0));
+    }
+
+    fn enter(&self, span: &Id) {
+        let index = usize::try_from(span.into_u64() - 1).unwrap();
+        let name = self.span_names.lock().unwrap()[index];
+        self.entered.lock().unwrap().push(name);
+    }
+
+    fn exit(&self, _: &Id) {
+        self.entered.lock().unwrap().pop();
+    }
+}
+
+#[test]
+fn operations_emit_events_within_their_spans() {
+    let recorder = Recorder::default();
+    let events = Arc::clone(&recorder.events);
+
+    tracing::subscriber::with_default(recorder, || {
+        let map = TravlMapPersistent::new().with_observer(Arc::new(TracingObserver::new()));
+        let map = (0..3).fold(map, |map, key| map.insert(key, ()));
+        let _ = map.insert(1, ()).remove(&0);
+    });
+
+    let events = events.lock().unwrap();
+    let events: Vec<_> = events.iter().map(|(span, message)| (*span, message.as_str())).collect();
+    assert_eq!(
+        events,
+        [
+            (Some("insert"), "inserted key"),
+            (Some("insert"), "inserted key"),
+            (Some("insert"), "inserted key"),
+            (Some("insert"), "rebalancing node"),
+            (Some("insert"), "rotated subtree"),
+            (Some("insert"), "inserted key"),
+            (Some("remove"), "removed key"),
+        ]
+    );
+}
@@ -0,0 +1,252 @@
+use std::ops::ControlFlow;
+
+use travl::core::TravlNode;
+use travl::map::{NodeMap, TravlMap};
+use travl::storage::NodeStorage;
+use travl::traversal::{
+    DepthEntry, InOrderIter, InternalIter, LeafIter, LevelOrderDepthIter, LevelOrderIter, ParentLinkedInOrderIter,
+    PostOrderIter, PreOrderDepthIter, PreOrderIter, ReverseInOrderIter, TraversalOrder, ZigZagIter,
+};
+
+const DEGENERATE_NODE_COUNT: u32 = 1_000_000;
+
+/// Builds a chain of nodes where every node is the left child of the next one,
+/// which is the deepest tree that can be formed with a given number of nodes
+fn degenerate_tree(node_count: u32) -> NodeMap<u32, ()> {
+    // Called through the storage trait, as `NodeMap` is a slab rather than a hash map without the `std` feature
+    let mut nodes: NodeMap<u32, ()> = NodeStorage::with_capacity(node_count as usize);
+
+    for key in 0..node_count {
+        let mut node = TravlNode::new(key, ());
+        if let Some(child) = key.checked_sub(1) {
+            let _ = node.link_left(child);
+        }
+        if key + 1 < node_count {
+            let _ = node.link_parent(key + 1);
+        }
+        NodeStorage::insert(&mut nodes, key, node);
+    }
+
+    nodes
+}
+
+#[test]
+fn deep_trees_do_not_overflow_the_stack() {
+    let nodes = degenerate_tree(DEGENERATE_NODE_COUNT);
+    let root = NodeStorage::get(&nodes, &(DEGENERATE_NODE_COUNT - 1));
+    let expected = DEGENERATE_NODE_COUNT as usize;
+
+    assert!(InOrderIter::new(&nodes, root).map(TravlNode::key).copied().eq(0..DEGENERATE_NODE_COUNT));
+    assert!(PreOrderIter::new(&nodes, root).map(TravlNode::key).copied().eq((0..DEGENERATE_NODE_COUNT).rev()));
+    assert!(PostOrderIter::new(&nodes, root).map(TravlNode::key).copied().eq(0..DEGENERATE_NODE_COUNT));
+    assert_eq!(LevelOrderIter::new(&nodes, root).count(), expected);
+    assert_eq!(ZigZagIter::new(&nodes, root).count(), expected);
+    assert_eq!(LeafIter::new(&nodes, root).count(), 1);
+    assert_eq!(InternalIter::new(&nodes, root).count(), expected - 1);
+    assert_eq!(PreOrderDepthIter::new(&nodes, root).last().map(|entry| entry.depth()), Some(expected - 1));
+    assert_eq!(LevelOrderDepthIter::new(&nodes, root).count(), expected);
+}
+
+/// Folds an iterator through its next method only, as the default fold does
+fn fold_by_next<I>(mut iter: I) -> Vec<u32>
+where
+    I: Iterator<Item = u32>
+{
+    std::iter::from_fn(|| iter.next()).fold(Vec::new(), |mut keys, key| {
+        keys.push(key);
+        keys
+    })
+}
+
+/// Folds an iterator through its own fold method
+fn fold<I>(iter: I) -> Vec<u32>
+where
+    I: Iterator<Item = u32>
+{
+    iter.fold(Vec::new(), |mut keys, key| {
+        keys.push(key);
+        keys
+    })
+}
+
+#[test]
+fn folds_match_folds_through_next() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in (0..500).map(|key| key * 7 % 500) {
+        map.insert(key, key);
+    }
+    let nodes = map.nodes();
+    let root = map.root();
+
+    for skipped in [0_u32, 1, 37, 499, 500] {
+        let in_order = || InOrderIter::new(nodes, root).skip(skipped as usize).map(|node| *node.key());
+        let reverse = || ReverseInOrderIter::new(nodes, root).skip(skipped as usize).map(|node| *node.key());
+        let entries = || map.iter().skip(skipped as usize).map(|(key, _)| *key);
+
+        assert_eq!(fold(in_order()), fold_by_next(in_order()));
+        assert_eq!(fold(reverse()), fold_by_next(reverse()));
+        assert_eq!(fold(entries()), fold_by_next(entries()));
+        assert_eq!(fold(entries()), (skipped..500).collect::<Vec<_>>());
+    }
+
+    let nodes = degenerate_tree(DEGENERATE_NODE_COUNT);
+    let root = NodeStorage::get(&nodes, &(DEGENERATE_NODE_COUNT - 1));
+    assert_eq!(InOrderIter::new(&nodes, root).fold(0, |count, _| count + 1), DEGENERATE_NODE_COUNT);
+}
+
+/// Map of the keys 1 to 7 inserted in order, whose tree is complete:
+///
+/// ```text
+///       4
+///    2     6
+///   1 3   5 7
+/// ```
+fn complete_map() -> TravlMap<'static, u32, u32> {
+    let mut map = TravlMap::new();
+    for key in 1..=7 {
+        map.insert(key, key);
+    }
+    map
+}
+
+/// Returns the keys of the given nodes
+fn keys<'n>(nodes: impl Iterator<Item = &'n TravlNode<u32, u32>>) -> Vec<u32> {
+    nodes.map(|node| *node.key()).collect()
+}
+
+#[test]
+fn leaves_and_internal_nodes_split_the_in_order_traversal() {
+    let mut map = complete_map();
+
+    assert_eq!(keys(map.leaves()), [1, 3, 5, 7]);
+    assert_eq!(keys(map.internal_nodes()), [2, 4, 6]);
+
+    map.remove(&5);
+    map.remove(&7);
+
+    assert_eq!(keys(map.leaves()), [1, 3, 6]);
+    assert_eq!(keys(map.internal_nodes()), [2, 4]);
+}
+
+#[test]
+fn depth_iterators_annotate_nodes_with_their_position() {
+    let map = complete_map();
+    let entries = |iter: &mut dyn Iterator<Item = DepthEntry<'_, u32, u32>>| -> Vec<(u32, usize, Option<u32>)> {
+        iter.map(|entry| (*entry.node().key(), entry.depth(), entry.parent_key().copied())).collect()
+    };
+
+    assert_eq!(entries(&mut PreOrderDepthIter::new(map.nodes(), map.root())), [
+        (4, 0, None),
+        (2, 1, Some(4)),
+        (1, 2, Some(2)),
+        (3, 2, Some(2)),
+        (6, 1, Some(4)),
+        (5, 2, Some(6)),
+        (7, 2, Some(6))
+    ]);
+    assert_eq!(entries(&mut LevelOrderDepthIter::new(map.nodes(), map.root())), [
+        (4, 0, None),
+        (2, 1, Some(4)),
+        (6, 1, Some(4)),
+        (1, 2, Some(2)),
+        (3, 2, Some(2)),
+        (5, 2, Some(6)),
+        (7, 2, Some(6))
+    ]);
+}
+
+#[test]
+fn zig_zag_traversals_alternate_the_direction_of_each_level() {
+    let map = complete_map();
+
+    assert_eq!(keys(ZigZagIter::new(map.nodes(), map.root())), [4, 6, 2, 1, 3, 5, 7]);
+    assert_eq!(keys(LevelOrderIter::new(map.nodes(), map.root())), [4, 2, 6, 1, 3, 5, 7]);
+}
+
+#[test]
+fn parent_linked_traversals_match_stack_based_ones() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in (0..300).map(|key| key * 17 % 300) {
+        map.insert(key, key);
+    }
+    for key in (0..300).step_by(3) {
+        map.remove(&key);
+    }
+    let (nodes, root) = (map.nodes(), map.root());
+
+    assert_eq!(keys(ParentLinkedInOrderIter::new(nodes, root)), keys(InOrderIter::new(nodes, root)));
+
+    // Subtrees are left once their last node is visited
+    let subtree = map.get(root.unwrap().left().unwrap());
+    assert_eq!(keys(ParentLinkedInOrderIter::new(nodes, subtree)), keys(InOrderIter::new(nodes, subtree)));
+}
+
+#[test]
+fn in_order_iterations_resume_from_any_key() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in 0..50 {
+        map.insert(key, 100 - key * 2);
+    }
+
+    assert_eq!(keys(map.iter_from(&30)), (0..=30).rev().collect::<Vec<_>>());
+    assert_eq!(map.iter_from(&50).count(), 0);
+
+    // Values go down as keys go up, so the properties from 41 onwards belong to the keys up to 29
+    assert_eq!(keys(map.range_from(&41)), (0..=29).rev().collect::<Vec<_>>());
+    assert_eq!(keys(map.range_from(&42)), (0..=29).rev().collect::<Vec<_>>());
+    assert_eq!(map.range_from(&101).count(), 0);
+}
+
+#[test]
+fn boundary_traversals_go_around_the_tree() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in 1..=15 {
+        map.insert(key, key);
+    }
+
+    assert_eq!(keys(map.boundary()), [8, 4, 2, 1, 3, 5, 7, 9, 11, 13, 15, 14, 12]);
+    assert_eq!(keys(complete_map().boundary()), [4, 2, 1, 3, 5, 7, 6]);
+
+    let mut single = TravlMap::<u32, u32>::new();
+    single.insert(1, 1);
+    assert_eq!(keys(single.boundary()), [1]);
+}
+
+#[test]
+fn traversals_can_be_rooted_at_any_key() {
+    let mut map = complete_map();
+
+    assert_eq!(keys(map.traverse::<PreOrderIter<_, _>>()), [4, 2, 1, 3, 6, 5, 7]);
+    assert_eq!(keys(map.traverse_subtree::<PreOrderIter<_, _>>(&6)), [6, 5, 7]);
+    assert_eq!(keys(map.traverse_subtree::<PostOrderIter<_, _>>(&2)), [1, 3, 2]);
+    assert_eq!(map.traverse_subtree::<InOrderIter<_, _>>(&8).count(), 0);
+
+    assert_eq!(keys(map.range_from_in_subtree(&2, &2)), [2, 3]);
+    assert_eq!(keys(map.range_from_in_subtree(&6, &1)), [5, 6, 7]);
+    assert_eq!(map.range_from_in_subtree(&2, &4).count(), 0);
+    assert_eq!(map.range_from_in_subtree(&8, &1).count(), 0);
+}
+
+#[test]
+fn walks_follow_the_requested_order_until_they_break() {
+    let map = complete_map();
+    let walk = |order| {
+        let mut keys = Vec::new();
+        let _ = map.walk_with(order, |node| {
+            keys.push(*node.key());
+            ControlFlow::<()>::Continue(())
+        });
+        keys
+    };
+
+    assert_eq!(walk(TraversalOrder::InOrder), [1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(walk(TraversalOrder::PreOrder), [4, 2, 1, 3, 6, 5, 7]);
+    assert_eq!(walk(TraversalOrder::PostOrder), [1, 3, 2, 5, 7, 6, 4]);
+    assert_eq!(walk(TraversalOrder::LevelOrder), [4, 2, 6, 1, 3, 5, 7]);
+    assert_eq!(walk(TraversalOrder::ReverseInOrder), [7, 6, 5, 4, 3, 2, 1]);
+
+    let first_leaf = map.walk_with(TraversalOrder::PostOrder, |node| {
+        if node.is_internal() { ControlFlow::Continue(()) } else { ControlFlow::Break(*node.key()) }
+    });
+    assert_eq!(first_leaf, ControlFlow::Break(1));
+}
@@ -0,0 +1,110 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use travl::map::TravlMap;
+use travl::persistent::TravlMapPersistent;
+use travl::validation::Violation;
+
+#[test]
+fn maps_built_by_their_operations_are_valid() {
+    for imbalance_factor in 0..3 {
+        let mut map = TravlMapPersistent::with_imbalance_factor(imbalance_factor);
+        for key in (0..2_000_u64).map(|key| key * 7_919 % 2_003) {
+            map = map.insert(key, key);
+            if key % 3 == 0 {
+                map = map.remove(&(key / 2)).0;
+            }
+        }
+
+        let (rest, taken) = map.take_range(500..1_500);
+        for map in [&map, &rest, &taken, &rest.copy_range_from(&taken, 700..900)] {
+            assert_eq!(map.validate(), []);
+        }
+    }
+}
+
+#[test]
+fn empty_maps_are_valid() {
+    assert_eq!(TravlMapPersistent::<u32, u32>::new().validate(), []);
+    assert_eq!(TravlMap::<u32, u32>::new().validate(), []);
+    assert_eq!(TravlMap::<u32, u32>::new().repair(), []);
+    assert_eq!(TravlMap::<u32, u32>::new().audit_ordering(), []);
+}
+
+#[test]
+fn panics_while_rebuilding_poison_the_map_until_it_is_repaired() {
+    let mut map = TravlMap::<u32, u32>::new_with_ordering(Box::new(|_, _| panic!("ordering panicked")));
+    assert!(!map.is_poisoned());
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut batch = map.batch();
+        batch.insert(1, 1);
+        batch.insert(2, 2);
+        batch.finish();
+    }));
+    assert!(result.is_err());
+    assert!(map.is_poisoned());
+    assert!(map.is_empty());
+    assert_eq!(map.validate(), []);
+
+    assert_eq!(map.repair(), []);
+    assert!(!map.is_poisoned());
+}
+
+#[test]
+fn maps_broken_by_mutated_values_are_repaired() {
+    let mut map = TravlMap::<u32, u32>::new();
+    for key in 1..=7 {
+        map.insert(key, key);
+    }
+    assert_eq!(map.repair(), []);
+
+    *map.get_mut(&1).unwrap().value_mut() = 10;
+
+    let violations = map.repair();
+    assert!(violations.contains(&Violation::OutOfOrder { previous: 1, key: 2 }));
+    assert_eq!(map.validate(), []);
+    assert_eq!(map.len(), 7);
+    assert!(map.iter().map(|(key, _)| *key).eq([2, 3, 4, 5, 6, 7, 1]));
+    assert_eq!(map.stats().height(), 3);
+}
+
+#[test]
+fn applying_closures_to_missing_keys_leaves_the_map_untouched() {
+    let mut map = TravlMap::<u32, u32>::new();
+
+    assert_eq!(map.apply(&1, |_| unreachable!("no value to apply the closure to")), None);
+    assert!(!map.is_poisoned());
+}
+
+#[test]
+fn violations_name_the_nodes_breaking_invariants() {
+    let violation = Violation::WrongHeight { key: "b", height: 3, expected: 2 };
+    assert_eq!(violation.to_string(), r#"node "b" has height 3 instead of 2"#);
+
+    let violation = Violation::<u32>::WrongSize { key: None, size: 4, expected: 3 };
+    assert_eq!(violation.to_string(), "the map has length 4 instead of 3");
+}
+
+#[test]
+fn validated_maps_check_every_derived_map() {
+    let mut map = TravlMapPersistent::with_imbalance_factor(1).with_validation();
+    for key in 0..500_u32 {
+        map = map.insert(key * 37 % 503, key);
+    }
+    for key in 0..250 {
+        map = map.remove(&key).0;
+    }
+
+    let (rest, taken) = map.take_range(300..);
+    let copied = rest.copy_range_from(&taken, 400..);
+    assert_eq!(copied.len(), rest.len() + taken.range(400..).count());
+}
+
+#[test]
+#[should_panic(expected = "node 1 is ordered before node 2")]
+fn validated_maps_catch_inconsistent_comparisons() {
+    let reversed = |a: &u32, b: &u32| Ok::<_, ()>(b.cmp(a));
+    let map = TravlMapPersistent::new().with_validation().insert(1, "one");
+
+    let _ = map.try_insert_by(2, "two", reversed);
+}
@@ -0,0 +1,47 @@
+use std::thread;
+
+use travl::versioned::VersionedMap;
+
+#[test]
+fn snapshots_read_earlier_versions_within_the_retention() {
+    let mut map = VersionedMap::new(3);
+    assert_eq!((map.version(), map.oldest_version()), (0, 0));
+
+    for key in 0..5 {
+        map.insert(key, key * 10);
+    }
+    assert_eq!(map.remove(&42), None);
+    assert_eq!(map.remove(&0), Some(0));
+
+    assert_eq!((map.version(), map.oldest_version()), (6, 3));
+    assert_eq!(map.snapshot_at(2), None);
+    assert_eq!(map.snapshot_at(7), None);
+    assert_eq!(map.snapshot_at(3).unwrap().len(), 3);
+    assert_eq!(map.snapshot_at(5).unwrap().get(&0), Some(&0));
+    assert_eq!(map.snapshot_at(6).unwrap().get(&0), None);
+
+    map.set_retention(1);
+    assert_eq!(map.oldest_version(), 5);
+    assert_eq!(map.snapshot_at(4), None);
+}
+
+#[test]
+fn scans_see_the_version_they_started_from_while_writes_go_on() {
+    let mut map = VersionedMap::new(16);
+    for key in 0..1_000 {
+        map.insert(key, 0);
+    }
+
+    let version = map.version();
+    let snapshot = map.snapshot_at(version).unwrap();
+    let scan = thread::spawn(move || snapshot.iter().map(|(_, value)| *value).sum::<u64>());
+
+    for key in 0..1_000 {
+        map.insert(key, 1);
+    }
+
+    assert_eq!(scan.join().unwrap(), 0);
+    assert_eq!(map.iter().map(|(_, value)| *value).sum::<u64>(), 1_000);
+    assert_eq!(map.version(), version + 1_000);
+    assert_eq!(map.snapshot_at(version), None);
+}